@@ -0,0 +1,52 @@
+use proc_macro2::{Span, TokenStream, TokenTree};
+
+/// Splits the tokens by the top-level commas.
+pub fn split_commas(tokens: TokenStream) -> Vec<TokenStream> {
+    let mut segments = vec![];
+    let mut segment = vec![];
+
+    for tt in tokens {
+        match tt {
+            TokenTree::Punct(ref punct) if punct.as_char() == ',' => {
+                segments.push(segment.drain(..).collect());
+            }
+            tt => segment.push(tt),
+        }
+    }
+
+    if !segment.is_empty() {
+        segments.push(segment.into_iter().collect());
+    }
+
+    segments
+}
+
+/// Returns the name and the value of an option like `name = <expr>`,
+/// if the segment starts with one of the names.
+pub fn named_option(segment: &TokenStream, names: &[&str]) -> Option<(String, TokenStream)> {
+    let mut tokens = segment.clone().into_iter();
+
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(ident)), Some(TokenTree::Punct(ref punct)))
+            if punct.as_char() == '=' && names.iter().any(|name| ident == name) =>
+        {
+            let value: TokenStream = tokens.collect();
+
+            if value.is_empty() {
+                None
+            } else {
+                Some((ident.to_string(), value))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns the span of the first token, or the call site.
+pub fn span_of(tokens: &TokenStream) -> Span {
+    tokens
+        .clone()
+        .into_iter()
+        .next()
+        .map_or_else(Span::call_site, |tt| tt.span())
+}
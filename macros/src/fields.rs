@@ -0,0 +1,136 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Fields, Meta, NestedMeta};
+
+/// Expands `#[derive(RuntimeCfg)]`.
+pub fn derive(input: TokenStream) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse2(input)?;
+
+    Ok(implement(&input))
+}
+
+/// Expands `#[cfg_fields]`, which keeps the item and implements `RuntimeCfg` for it.
+pub fn attribute(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    if !args.is_empty() {
+        return Err(syn::Error::new(
+            crate::args::span_of(&args),
+            "#[cfg_fields] doesn't accept any argument",
+        ));
+    }
+
+    let input: DeriveInput = syn::parse2(item.clone())?;
+    let imp = implement(&input);
+
+    Ok(quote! {
+        #item
+        #imp
+    })
+}
+
+fn implement(input: &DeriveInput) -> TokenStream {
+    let mut entries = vec![];
+
+    match input.data {
+        Data::Struct(ref data) => collect_fields(&mut entries, None, &data.fields),
+        Data::Enum(ref data) => {
+            for variant in &data.variants {
+                let name = variant.ident.to_string();
+
+                if let Some(predicate) = predicate_of(&variant.attrs) {
+                    entries.push((name.clone(), predicate));
+                }
+
+                collect_fields(&mut entries, Some(&name), &variant.fields);
+            }
+        }
+        Data::Union(ref data) => {
+            collect_fields(&mut entries, None, &Fields::Named(data.fields.clone()))
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let entries = entries.into_iter().map(|(name, predicate)| {
+        quote! {
+            (#name, ::runtime_cfg::Cfg::from(#predicate))
+        }
+    });
+
+    quote! {
+        impl #impl_generics ::runtime_cfg::RuntimeCfg for #ident #ty_generics #where_clause {
+            fn field_cfgs() -> &'static [(&'static str, ::runtime_cfg::Cfg)] {
+                static CFGS: ::runtime_cfg::__private::OnceLock<
+                    ::runtime_cfg::__private::Vec<(&'static str, ::runtime_cfg::Cfg)>,
+                > = ::runtime_cfg::__private::OnceLock::new();
+
+                CFGS.get_or_init(|| ::runtime_cfg::__private::vec![#(#entries),*])
+            }
+        }
+    }
+}
+
+fn collect_fields(entries: &mut Vec<(String, TokenStream)>, prefix: Option<&str>, fields: &Fields) {
+    for (idx, field) in fields.iter().enumerate() {
+        if let Some(predicate) = predicate_of(&field.attrs) {
+            let name = field
+                .ident
+                .as_ref()
+                .map_or_else(|| idx.to_string(), ToString::to_string);
+            let name = match prefix {
+                Some(prefix) => format!("{}::{}", prefix, name),
+                None => name,
+            };
+
+            entries.push((name, predicate));
+        }
+    }
+}
+
+/// Combines the `#[cfg(..)]` and `#[cfg_attr(.., cfg(..))]` attributes into a predicate expression.
+pub fn predicate_of(attrs: &[Attribute]) -> Option<TokenStream> {
+    let predicates = attrs
+        .iter()
+        .filter_map(|attr| attr.parse_meta().ok())
+        .filter_map(|meta| match meta {
+            Meta::List(ref list) if list.ident == "cfg" => {
+                let nested = &list.nested;
+
+                Some(quote! { ::runtime_cfg::cfg_pred!(#nested) })
+            }
+            Meta::List(ref list) if list.ident == "cfg_attr" => {
+                let mut nested = list.nested.iter();
+                let condition = nested.next()?;
+                let cfgs = nested
+                    .filter_map(|nested| match nested {
+                        NestedMeta::Meta(Meta::List(ref list)) if list.ident == "cfg" => {
+                            let nested = &list.nested;
+
+                            Some(quote! { ::runtime_cfg::cfg_pred!(#nested) })
+                        }
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                if cfgs.is_empty() {
+                    None
+                } else {
+                    Some(quote! {
+                        ::runtime_cfg::any(::runtime_cfg::__private::vec![
+                            ::runtime_cfg::not(::runtime_cfg::cfg_pred!(#condition)),
+                            ::runtime_cfg::all(::runtime_cfg::__private::vec![#(#cfgs),*]),
+                        ])
+                    })
+                }
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    match predicates.len() {
+        0 => None,
+        1 => predicates.into_iter().next(),
+        _ => Some(quote! {
+            ::runtime_cfg::all(::runtime_cfg::__private::vec![#(#predicates),*])
+        }),
+    }
+}
@@ -0,0 +1,51 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, ItemFn};
+
+use crate::args::{named_option, span_of, split_commas};
+
+pub fn expand(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let mut item: ItemFn = syn::parse2(item)?;
+    let mut predicate = None;
+    let mut flags = None;
+    let mut default = None;
+
+    for segment in split_commas(args) {
+        match named_option(&segment, &["flags", "default"]) {
+            Some((ref name, value)) if name == "flags" => flags = Some(value),
+            Some((_, value)) => default = Some(value),
+            None if predicate.is_none() => predicate = Some(segment),
+            None => {
+                return Err(syn::Error::new(
+                    span_of(&segment),
+                    "#[runtime_cfg(..)] only support one predicate",
+                ))
+            }
+        }
+    }
+
+    let predicate = predicate.ok_or_else(|| {
+        syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "#[runtime_cfg(..)] predicate can't be empty",
+        )
+    })?;
+    let flags = flags.ok_or_else(|| {
+        syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "#[runtime_cfg(..)] expect `flags = <expr>`",
+        )
+    })?;
+    let default = default.unwrap_or_else(|| quote! { ::core::default::Default::default() });
+    let block = item.block.clone();
+
+    *item.block = parse_quote! {{
+        if !::runtime_cfg::cfg_pred!(#predicate).matches(&#flags) {
+            return #default;
+        }
+
+        #block
+    }};
+
+    Ok(quote! { #item })
+}
@@ -0,0 +1,153 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse::Parser, punctuated::Punctuated, Attribute, Item, ItemMod, LitStr, Token};
+
+use crate::fields::predicate_of;
+
+/// Expands `include_cfg!("path/to/file.rs", "module::Item")`.
+pub fn expand(input: TokenStream) -> syn::Result<TokenStream> {
+    let args = Punctuated::<LitStr, Token![,]>::parse_terminated.parse2(input)?;
+    let mut args = args.into_iter();
+    let (file, item) = match (args.next(), args.next(), args.next()) {
+        (Some(file), Some(item), None) => (file, item),
+        _ => {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "include_cfg!(..) expect a file path and an item path",
+            ))
+        }
+    };
+
+    let path = env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(file.value());
+    let source = fs::read_to_string(&path).map_err(|err| {
+        syn::Error::new(
+            file.span(),
+            format!("failed to read `{}`, {}", path.display(), err),
+        )
+    })?;
+    let ast = syn::parse_file(&source).map_err(|err| {
+        syn::Error::new(
+            file.span(),
+            format!("failed to parse `{}`, {}", path.display(), err),
+        )
+    })?;
+    let segments = item.value();
+    let segments = segments.split("::").collect::<Vec<_>>();
+    let found =
+        find_items(&ast.items, &segments, &[]).map_err(|err| syn::Error::new(item.span(), err))?;
+    let mut predicates = found.iter().map(|attrs| {
+        predicate_of(attrs).unwrap_or_else(|| quote! { ::runtime_cfg::Predicate::True })
+    });
+    let predicate = match found.len() {
+        0 => {
+            return Err(syn::Error::new(
+                item.span(),
+                format!("item `{}` not found", item.value()),
+            ))
+        }
+        1 => predicates.next().unwrap(),
+        _ => quote! { ::runtime_cfg::any(::runtime_cfg::__private::vec![#(#predicates),*]) },
+    };
+    let path = path.to_string_lossy();
+
+    // the included file is a dependency of the invoking crate, so it is rebuilt once the file changes
+    Ok(quote! {{
+        const _: &[u8] = include_bytes!(#path);
+
+        ::runtime_cfg::Cfg::from(#predicate)
+    }})
+}
+
+/// Finds the items of the path in the items,
+/// returns the attributes of each item and its enclosing modules.
+///
+/// The items of the same name, like `#[cfg(unix)] mod sys;` and `#[cfg(windows)] mod sys;`,
+/// are all returned, since they are declared under the different configurations.
+fn find_items(
+    items: &[Item],
+    segments: &[&str],
+    enclosing: &[Attribute],
+) -> Result<Vec<Vec<Attribute>>, String> {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => return Ok(vec![]),
+    };
+    let mut found = vec![];
+
+    for item in items
+        .iter()
+        .filter(|item| ident_of(item).is_some_and(|ident| ident == segment))
+    {
+        let mut attrs = enclosing.to_vec();
+
+        attrs.extend(attrs_of(item).iter().cloned());
+
+        if rest.is_empty() {
+            found.push(attrs);
+            continue;
+        }
+
+        match item {
+            Item::Mod(ItemMod {
+                content: Some((_, ref items)),
+                ..
+            }) => found.extend(find_items(items, rest, &attrs)?),
+            Item::Mod(_) => {
+                return Err(format!(
+                    "module `{}` is declared out of line, include the file of the module instead",
+                    segment
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    Ok(found)
+}
+
+fn ident_of(item: &Item) -> Option<&syn::Ident> {
+    Some(match item {
+        Item::ExternCrate(ref item) => &item.ident,
+        Item::Static(ref item) => &item.ident,
+        Item::Const(ref item) => &item.ident,
+        Item::Fn(ref item) => &item.ident,
+        Item::Mod(ref item) => &item.ident,
+        Item::Type(ref item) => &item.ident,
+        Item::Existential(ref item) => &item.ident,
+        Item::Struct(ref item) => &item.ident,
+        Item::Enum(ref item) => &item.ident,
+        Item::Union(ref item) => &item.ident,
+        Item::Trait(ref item) => &item.ident,
+        Item::TraitAlias(ref item) => &item.ident,
+        Item::Macro(ref item) => item.ident.as_ref()?,
+        Item::Macro2(ref item) => &item.ident,
+        _ => return None,
+    })
+}
+
+fn attrs_of(item: &Item) -> &[Attribute] {
+    match item {
+        Item::ExternCrate(ref item) => &item.attrs,
+        Item::Static(ref item) => &item.attrs,
+        Item::Const(ref item) => &item.attrs,
+        Item::Fn(ref item) => &item.attrs,
+        Item::Mod(ref item) => &item.attrs,
+        Item::Type(ref item) => &item.attrs,
+        Item::Existential(ref item) => &item.attrs,
+        Item::Struct(ref item) => &item.attrs,
+        Item::Enum(ref item) => &item.attrs,
+        Item::Union(ref item) => &item.attrs,
+        Item::Trait(ref item) => &item.attrs,
+        Item::TraitAlias(ref item) => &item.attrs,
+        Item::Macro(ref item) => &item.attrs,
+        Item::Macro2(ref item) => &item.attrs,
+        _ => &[],
+    }
+}
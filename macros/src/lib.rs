@@ -0,0 +1,99 @@
+//! Procedural macros of `runtime_cfg`, re-exported by `runtime_cfg` with the `macros` feature.
+
+extern crate proc_macro;
+
+mod args;
+mod fields;
+mod guard;
+mod include;
+
+use proc_macro::TokenStream;
+
+/// Guards a function with a configuration predicate evaluated at runtime.
+///
+/// The function returns early with the default value when the predicate doesn't match the flags.
+///
+/// ```ignore
+/// #[runtime_cfg(feature = "beta", flags = my_flags())]
+/// fn beta() -> Option<u32> {
+///     Some(42)
+/// }
+/// ```
+///
+/// The attribute accepts a predicate, and the following options:
+///
+/// - `flags = <expr>`: the flags to match the predicate, required.
+/// - `default = <expr>`: the value returned when the predicate doesn't match, `Default::default()` by default.
+#[proc_macro_attribute]
+pub fn runtime_cfg(args: TokenStream, item: TokenStream) -> TokenStream {
+    guard::expand(args.into(), item.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Implements `RuntimeCfg`, recording the `#[cfg(..)]` of each field or enum variant.
+///
+/// The compiler strips the inactive fields and variants before expanding a derive macro,
+/// so only the active ones are recorded; use `#[cfg_fields]` to record all of them.
+///
+/// ```ignore
+/// #[derive(RuntimeCfg)]
+/// struct Config {
+///     #[cfg(unix)]
+///     socket: String,
+///     port: u16,
+/// }
+///
+/// assert_eq!(Config::field_cfgs()[0].0, "socket");
+/// ```
+#[proc_macro_derive(RuntimeCfg)]
+pub fn derive_runtime_cfg(input: TokenStream) -> TokenStream {
+    fields::derive(input.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Implements `RuntimeCfg` for an item, recording the `#[cfg(..)]` of each field or enum variant.
+///
+/// Unlike `#[derive(RuntimeCfg)]`, the attribute is expanded before the configurations,
+/// so the fields and variants inactive for the current target are recorded as well.
+///
+/// ```ignore
+/// #[cfg_fields]
+/// struct Config {
+///     #[cfg(unix)]
+///     socket: String,
+///     #[cfg(windows)]
+///     pipe: String,
+/// }
+///
+/// assert_eq!(Config::field_cfgs().len(), 2);
+/// ```
+#[proc_macro_attribute]
+pub fn cfg_fields(args: TokenStream, item: TokenStream) -> TokenStream {
+    fields::attribute(args.into(), item.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Embeds the configuration of an item in a source file, parsed at compile time.
+///
+/// The file path is relative to the directory of the invoking crate's manifest,
+/// and the item path is relative to the file, like `module::Item`.
+/// The configuration combines the `#[cfg(..)]` of the item and its enclosing modules,
+/// or always matches if there isn't any.
+/// The items of the same name, like `#[cfg(unix)] mod sys { .. }` and `#[cfg(windows)] mod sys { .. }`,
+/// are combined with `any(..)`; a path through an out-of-line `mod sys;` is an error,
+/// include the file of the module instead.
+///
+/// ```ignore
+/// let cfg = include_cfg!("src/lib.rs", "sys::unix::Socket");
+///
+/// assert_eq!(cfg.matches(&flags), cfg!(unix));
+/// ```
+#[proc_macro]
+pub fn include_cfg(input: TokenStream) -> TokenStream {
+    include::expand(input.into())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
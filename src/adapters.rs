@@ -0,0 +1,336 @@
+//! Adapters wrapping a pattern to change or observe how it is queried.
+
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::borrow::Cow;
+        use std::collections::{BTreeMap, BTreeSet};
+    } else {
+        use alloc::borrow::Cow;
+        use alloc::collections::{BTreeMap, BTreeSet};
+        use alloc::string::{String, ToString};
+    }
+}
+
+use core::cell::RefCell;
+
+use crate::{FlagSet, Pattern};
+
+/// A pattern recording the queries performed on the wrapped pattern, and their answers.
+///
+/// Only the queries performed are recorded, the sub-predicates skipped by the short-circuit evaluation are not,
+/// so the recorded keys are exactly the flags the decision depends on.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let flags = Recording::new(vec![("target_os", Some("linux")), ("feature", Some("ssl"))]);
+/// let predicate = all(vec![
+///     name_value("target_os", "linux"),
+///     any(vec![feature("ssl"), feature("tls")]),
+/// ]);
+///
+/// assert!(predicate.matches(&flags));
+/// assert_eq!(flags.keys().into_iter().collect::<Vec<_>>(), vec!["feature", "target_os"]);
+/// assert_eq!(
+///     flags.queries().into_iter().collect::<Vec<_>>(),
+///     vec![
+///         (("feature".to_owned(), Some("ssl".to_owned())), true),
+///         (("target_os".to_owned(), Some("linux".to_owned())), true),
+///     ]
+/// );
+/// ```
+#[derive(Debug)]
+pub struct Recording<P> {
+    pattern: P,
+    queries: RefCell<BTreeMap<(String, Option<String>), bool>>,
+}
+
+impl<P> Recording<P> {
+    /// Creates a pattern recording the queries on the wrapped pattern.
+    pub fn new(pattern: P) -> Self {
+        Recording {
+            pattern,
+            queries: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the wrapped pattern.
+    pub fn get_ref(&self) -> &P {
+        &self.pattern
+    }
+
+    /// Consumes the recording, returns the wrapped pattern.
+    pub fn into_inner(self) -> P {
+        self.pattern
+    }
+
+    /// Returns the recorded queries as `(key, value)`, and their answers.
+    pub fn queries(&self) -> BTreeMap<(String, Option<String>), bool> {
+        self.queries.borrow().clone()
+    }
+
+    /// Returns the keys queried.
+    pub fn keys(&self) -> BTreeSet<String> {
+        self.queries
+            .borrow()
+            .keys()
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// Clears the recorded queries.
+    pub fn clear(&self) {
+        self.queries.borrow_mut().clear()
+    }
+}
+
+impl<P: Pattern> Pattern for Recording<P> {
+    fn matches(&self, key: &str, value: Option<&str>) -> bool {
+        let matched = self.pattern.matches(key, value);
+
+        self.queries
+            .borrow_mut()
+            .insert((key.to_string(), value.map(ToString::to_string)), matched);
+
+        matched
+    }
+}
+
+/// A pattern rewriting the queried keys before delegating to the wrapped pattern.
+///
+/// # Example
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// use runtime_cfg::*;
+///
+/// let flags = Mapped::new(vec![("os", Some("linux"))], |key| match key {
+///     "target_os" => Cow::Borrowed("os"),
+///     _ => Cow::Borrowed(key),
+/// });
+///
+/// assert!(name_value("target_os", "linux").matches(&flags));
+/// assert!(!name_value("os", "linux").matches(&Mapped::new(flags, |key| Cow::Owned(key.to_uppercase()))));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Mapped<P, F> {
+    pattern: P,
+    f: F,
+}
+
+impl<P, F> Mapped<P, F>
+where
+    F: for<'k> Fn(&'k str) -> Cow<'k, str>,
+{
+    /// Creates a pattern rewriting the queried keys with the function.
+    pub fn new(pattern: P, f: F) -> Self {
+        Mapped { pattern, f }
+    }
+}
+
+impl<P, F> Mapped<P, F> {
+    /// Consumes the adapter, returns the wrapped pattern.
+    pub fn into_inner(self) -> P {
+        self.pattern
+    }
+}
+
+impl<P, F> Pattern for Mapped<P, F>
+where
+    P: Pattern,
+    F: for<'k> Fn(&'k str) -> Cow<'k, str>,
+{
+    fn matches(&self, key: &str, value: Option<&str>) -> bool {
+        self.pattern.matches(&(self.f)(key), value)
+    }
+}
+
+/// A pattern for the keys in a namespace, the prefix is stripped before delegating to the wrapped pattern.
+///
+/// The keys without the prefix never match.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let flags = Prefixed::new("my_app_", vec![("debug", None), ("log", Some("trace"))]);
+///
+/// assert!(all(vec![name("my_app_debug"), name_value("my_app_log", "trace")]).matches(&flags));
+/// assert!(!name("debug").matches(&flags));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Prefixed<'a, P> {
+    prefix: &'a str,
+    pattern: P,
+}
+
+impl<'a, P> Prefixed<'a, P> {
+    /// Creates a pattern for the keys starting with the prefix.
+    pub fn new(prefix: &'a str, pattern: P) -> Self {
+        Prefixed { prefix, pattern }
+    }
+
+    /// Returns the prefix.
+    pub fn prefix(&self) -> &'a str {
+        self.prefix
+    }
+
+    /// Consumes the adapter, returns the wrapped pattern.
+    pub fn into_inner(self) -> P {
+        self.pattern
+    }
+}
+
+impl<P: Pattern> Pattern for Prefixed<'_, P> {
+    fn matches(&self, key: &str, value: Option<&str>) -> bool {
+        key.strip_prefix(self.prefix)
+            .is_some_and(|key| self.pattern.matches(key, value))
+    }
+}
+
+/// A pattern answering from the wrapped pattern first, falling back to the defaults for the keys it doesn't know.
+///
+/// A key is known by the wrapped pattern if it is set, with or without value,
+/// then all the default values of the key are overridden.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let defaults: FlagSet = vec![("log", Some("info")), ("feature", Some("ssl")), ("feature", Some("gzip"))]
+///     .into_iter()
+///     .collect();
+/// let flags = WithDefaults::new(vec![("feature", Some("brotli"))], defaults);
+///
+/// assert!(name_value("log", "info").matches(&flags));
+/// assert!(feature("brotli").matches(&flags));
+/// assert!(!feature("ssl").matches(&flags));
+/// ```
+#[derive(Debug, Clone)]
+pub struct WithDefaults<P> {
+    pattern: P,
+    defaults: FlagSet,
+}
+
+impl<P> WithDefaults<P> {
+    /// Creates a pattern falling back to the defaults.
+    pub fn new(pattern: P, defaults: FlagSet) -> Self {
+        WithDefaults { pattern, defaults }
+    }
+
+    /// Returns the defaults.
+    pub fn defaults(&self) -> &FlagSet {
+        &self.defaults
+    }
+
+    /// Consumes the adapter, returns the wrapped pattern and the defaults.
+    pub fn into_inner(self) -> (P, FlagSet) {
+        (self.pattern, self.defaults)
+    }
+}
+
+impl<P: Pattern> Pattern for WithDefaults<P> {
+    fn matches(&self, key: &str, value: Option<&str>) -> bool {
+        if self.pattern.matches(key, None) {
+            value.is_none() || self.pattern.matches(key, value)
+        } else {
+            self.defaults.matches(key, value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::borrow::ToOwned;
+            use alloc::vec;
+            use alloc::vec::Vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_recording() {
+        let flags = Recording::new(vec![("unix", None), ("feature", Some("ssl"))]);
+        let predicate = any(vec![
+            name("windows"),
+            all(vec![name("unix"), not(feature("ssl"))]),
+            name("wasm"),
+        ]);
+
+        assert!(!predicate.matches(&flags));
+        assert_eq!(
+            flags.queries().into_iter().collect::<Vec<_>>(),
+            vec![
+                (("feature".to_owned(), Some("ssl".to_owned())), true),
+                (("unix".to_owned(), None), true),
+                (("wasm".to_owned(), None), false),
+                (("windows".to_owned(), None), false),
+            ]
+        );
+
+        flags.clear();
+
+        assert!(any(vec![name("unix"), name("windows")]).matches(&flags));
+        assert_eq!(
+            flags.keys().into_iter().collect::<Vec<_>>(),
+            vec!["unix".to_owned()]
+        );
+        assert_eq!(flags.into_inner().len(), 2);
+    }
+
+    #[test]
+    fn test_mapped() {
+        let flags = Mapped::new(
+            Prefixed::new(
+                "app_",
+                vec![("os", Some("linux")), ("arch", Some("x86_64"))],
+            ),
+            |key| match key {
+                "target_os" => "app_os".into(),
+                "target_arch" => "app_arch".into(),
+                _ => key.into(),
+            },
+        );
+
+        assert!(all(vec![
+            name_value("target_os", "linux"),
+            name_value("target_arch", "x86_64")
+        ])
+        .matches(&flags));
+        assert!(name_value("app_os", "linux").matches(&flags));
+        assert!(!name_value("os", "linux").matches(&flags));
+
+        let flags = flags.into_inner();
+
+        assert_eq!(flags.prefix(), "app_");
+        assert_eq!(flags.into_inner()[0].0, "os");
+    }
+
+    #[test]
+    fn test_with_defaults() {
+        let mut defaults = FlagSet::new();
+
+        defaults.insert_name("debug_assertions");
+        defaults.insert("target_os", "linux");
+        defaults.insert("feature", "ssl");
+
+        let flags = WithDefaults::new(vec![("target_os", Some("macos")), ("unix", None)], defaults);
+
+        assert!(name("debug_assertions").matches(&flags));
+        assert!(name("unix").matches(&flags));
+        assert!(name("target_os").matches(&flags));
+        assert!(name_value("target_os", "macos").matches(&flags));
+        assert!(!name_value("target_os", "linux").matches(&flags));
+        assert!(feature("ssl").matches(&flags));
+        assert!(!name("windows").matches(&flags));
+        assert_eq!(flags.defaults().len(), 3);
+    }
+}
@@ -0,0 +1,197 @@
+//! Shorthand names for configuration predicates, in the spirit of `cfg_aliases`.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::string::String;
+        use alloc::vec::Vec;
+    }
+}
+
+use crate::{
+    fold::{self, Fold},
+    NamedPredicates, Predicate,
+};
+
+/// A registry of aliases, mapping a bare name to a full predicate.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let mut aliases = Aliases::new();
+/// aliases.insert(
+///     "wasm",
+///     all(vec![
+///         name_value("target_arch", "wasm32"),
+///         not(name_value("target_os", "wasi")),
+///     ]),
+/// );
+///
+/// let predicate = any(vec![name("wasm"), name("unix")]).expand_aliases(&aliases);
+///
+/// assert!(predicate.matches(&vec![("target_arch", Some("wasm32"))]));
+/// assert_eq!(
+///     predicate.contract_aliases(&aliases),
+///     any(vec![name("wasm"), name("unix")])
+/// );
+/// ```
+pub type Aliases = NamedPredicates;
+
+impl Predicate {
+    /// Returns a predicate with the aliases replaced by their definitions.
+    ///
+    /// Aliases referenced by the definitions are expanded too,
+    /// a recursive alias is left as a bare name when it is encountered again.
+    pub fn expand_aliases(&self, aliases: &Aliases) -> Predicate {
+        self.clone().fold(&mut Expand {
+            aliases,
+            expanding: Vec::new(),
+        })
+    }
+
+    /// Returns a predicate with the definitions of aliases replaced by their names.
+    ///
+    /// The sub-predicates are contracted before their parents, so the aliases defined with other aliases
+    /// are contracted too, and a contracted node is contracted again until it matches no definition.
+    /// When several aliases share the same definition, the first one by name is used.
+    pub fn contract_aliases(&self, aliases: &Aliases) -> Predicate {
+        self.clone().fold(&mut Contract { aliases })
+    }
+}
+
+struct Expand<'a> {
+    aliases: &'a Aliases,
+    expanding: Vec<&'a str>,
+}
+
+impl Fold for Expand<'_> {
+    fn fold_name(&mut self, name: String) -> Predicate {
+        match self.aliases.0.get_key_value(name.as_str()) {
+            Some((alias, predicate)) if !self.expanding.contains(&alias.as_str()) => {
+                self.expanding.push(alias);
+                let predicate = self.fold_predicate(predicate.clone());
+                self.expanding.pop();
+
+                predicate
+            }
+            _ => Predicate::Name(name),
+        }
+    }
+}
+
+struct Contract<'a> {
+    aliases: &'a Aliases,
+}
+
+impl Fold for Contract<'_> {
+    fn fold_predicate(&mut self, predicate: Predicate) -> Predicate {
+        let mut predicate = fold::fold_predicate(self, predicate);
+
+        // bounds the chains of aliases, like `b = a` and `a = b`
+        for _ in 0..self.aliases.len() {
+            match self
+                .aliases
+                .iter()
+                .find(|(_, definition)| **definition == predicate)
+            {
+                Some((alias, _)) if predicate != Predicate::Name(alias.clone()) => {
+                    predicate = Predicate::Name(alias.clone())
+                }
+                _ => break,
+            }
+        }
+
+        predicate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_expand_aliases() {
+        let aliases: Aliases = vec![
+            ("apple", any(vec![name("macos"), name("ios")])),
+            ("macos", name_value("target_os", "macos")),
+            ("ios", name_value("target_os", "ios")),
+            ("recursive", not(name("recursive"))),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            all(vec![name("apple"), name("unix")]).expand_aliases(&aliases),
+            all(vec![
+                any(vec![
+                    name_value("target_os", "macos"),
+                    name_value("target_os", "ios")
+                ]),
+                name("unix")
+            ])
+        );
+        assert_eq!(
+            name("recursive").expand_aliases(&aliases),
+            not(name("recursive"))
+        );
+    }
+
+    #[test]
+    fn test_contract_aliases() {
+        let aliases: Aliases = vec![
+            ("apple", any(vec![name("macos"), name("ios")])),
+            ("macos", name_value("target_os", "macos")),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            all(vec![
+                any(vec![name("macos"), name("ios")]),
+                not(name_value("target_os", "macos"))
+            ])
+            .contract_aliases(&aliases),
+            all(vec![name("apple"), not(name("macos"))])
+        );
+
+        let aliases: Aliases = vec![
+            ("apple", any(vec![name("macos"), name("ios")])),
+            ("macos", name_value("target_os", "macos")),
+            ("ios", name_value("target_os", "ios")),
+            ("darwin", name("apple")),
+            ("cycle_a", name("cycle_b")),
+            ("cycle_b", name("cycle_a")),
+        ]
+        .into_iter()
+        .collect();
+        let predicate = all(vec![
+            any(vec![
+                name_value("target_os", "macos"),
+                name_value("target_os", "ios"),
+            ]),
+            not(name_value("target_os", "ios")),
+        ]);
+
+        assert_eq!(
+            predicate.contract_aliases(&aliases),
+            all(vec![name("darwin"), not(name("ios"))])
+        );
+        assert_eq!(
+            predicate
+                .contract_aliases(&aliases)
+                .expand_aliases(&aliases),
+            predicate
+        );
+        assert!(matches!(
+            name("cycle_a").contract_aliases(&aliases),
+            Predicate::Name(_)
+        ));
+    }
+}
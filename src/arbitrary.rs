@@ -0,0 +1,129 @@
+//! Generating well-formed predicates from unstructured data, for fuzzing.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::boxed::Box;
+        use alloc::string::String;
+        use alloc::vec::Vec;
+    }
+}
+
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::keywords::KEYWORDS;
+use crate::{Cfg, Predicate};
+
+/// The default maximum depth of the generated predicate trees.
+pub const DEFAULT_MAX_DEPTH: usize = 8;
+
+const MAX_CHILDREN: usize = 4;
+const MAX_NAME_LEN: usize = 16;
+
+const NAME_HEAD: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const NAME_TAIL: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789_";
+
+impl<'a> Arbitrary<'a> for Predicate {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Predicate::arbitrary_with_depth(u, DEFAULT_MAX_DEPTH)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Cfg {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Predicate::arbitrary(u).map(Cfg)
+    }
+}
+
+impl Predicate {
+    /// Generates a well-formed predicate tree with the maximum depth.
+    ///
+    /// The names of generated flags are valid identifiers and never keywords,
+    /// so the printed predicate could be parsed again.
+    pub fn arbitrary_with_depth(u: &mut Unstructured, max_depth: usize) -> Result<Self> {
+        let leaf = max_depth <= 1;
+
+        Ok(match u.int_in_range(if leaf { 3 } else { 0 }..=6)? {
+            0 => Predicate::Any(arbitrary_children(u, max_depth - 1)?),
+            1 => Predicate::All(arbitrary_children(u, max_depth - 1)?),
+            2 => Predicate::Not(Box::new(Predicate::arbitrary_with_depth(u, max_depth - 1)?)),
+            3 | 4 => Predicate::Name(arbitrary_name(u)?),
+            5 => Predicate::NameValue(arbitrary_name(u)?, String::arbitrary(u)?),
+            _ => {
+                if bool::arbitrary(u)? {
+                    Predicate::True
+                } else {
+                    Predicate::False
+                }
+            }
+        })
+    }
+}
+
+#[allow(clippy::vec_box)]
+fn arbitrary_children(u: &mut Unstructured, max_depth: usize) -> Result<Vec<Box<Predicate>>> {
+    let len = u.int_in_range(0..=MAX_CHILDREN)?;
+
+    (0..len)
+        .map(|_| Predicate::arbitrary_with_depth(u, max_depth).map(Box::new))
+        .collect()
+}
+
+fn arbitrary_name(u: &mut Unstructured) -> Result<String> {
+    let len = u.int_in_range(1..=MAX_NAME_LEN)?;
+    let mut name = String::with_capacity(len + 1);
+
+    name.push(char::from(*u.choose(NAME_HEAD)?));
+
+    for _ in 1..len {
+        name.push(char::from(*u.choose(NAME_TAIL)?));
+    }
+
+    if KEYWORDS.contains(&name.as_str()) {
+        name.push('_');
+    }
+
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arbitrary() {
+        let data = (0..=255).cycle().take(4096).collect::<Vec<u8>>();
+        let mut u = Unstructured::new(&data);
+
+        while !u.is_empty() {
+            let predicate = Predicate::arbitrary_with_depth(&mut u, 3).unwrap();
+
+            assert!(predicate.metrics().depth <= 3);
+
+            for leaf in predicate.leaves() {
+                match leaf {
+                    Predicate::Name(name) | Predicate::NameValue(name, _) => {
+                        assert!(!KEYWORDS.contains(&name.as_str()));
+                        assert!(name.starts_with(|c: char| c.is_ascii_lowercase()));
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+
+    #[cfg(all(feature = "parsing", feature = "printing"))]
+    #[test]
+    fn test_roundtrip() {
+        use std::string::ToString;
+
+        let data = (0..=255).rev().cycle().take(4096).collect::<Vec<u8>>();
+        let mut u = Unstructured::new(&data);
+
+        while !u.is_empty() {
+            let cfg = Cfg::arbitrary(&mut u).unwrap();
+            let s = cfg.to_string();
+
+            assert_eq!(Cfg::parse(&s).unwrap().to_string(), s);
+        }
+    }
+}
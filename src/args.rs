@@ -0,0 +1,111 @@
+//! Command line arguments to collect the flags with `clap`.
+
+use crate::FlagSet;
+
+/// The repeated `--cfg name` or `--cfg name=value` options, to be flattened into a `clap` parser.
+///
+/// The value could be quoted as `rustc --cfg 'feature="serde"'` does.
+///
+/// # Example
+///
+/// ```
+/// use clap::Parser;
+/// use runtime_cfg::*;
+///
+/// #[derive(Parser)]
+/// struct Cli {
+///     #[command(flatten)]
+///     cfg: CfgArgs,
+/// }
+///
+/// let cli = Cli::parse_from(["app", "--cfg", "unix", "--cfg", "feature=\"serde\""]);
+///
+/// assert!(cli.cfg.flags().has("unix"));
+/// assert!(cli.cfg.flags().has_value("feature", "serde"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, ::clap::Args)]
+pub struct CfgArgs {
+    /// Sets a configuration flag, like `unix`, `target_os=linux` or `feature="serde"`
+    #[arg(long = "cfg", value_name = "NAME[=VALUE]", value_parser = parse_cfg)]
+    pub cfgs: Vec<(String, Option<String>)>,
+}
+
+impl CfgArgs {
+    /// Returns the flags set by the options.
+    pub fn flags(&self) -> FlagSet {
+        self.cfgs.iter().cloned().collect()
+    }
+}
+
+impl From<CfgArgs> for FlagSet {
+    fn from(args: CfgArgs) -> Self {
+        args.cfgs.into_iter().collect()
+    }
+}
+
+fn parse_cfg(s: &str) -> Result<(String, Option<String>), String> {
+    let (name, value) = match s.split_once('=') {
+        Some((name, value)) => {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+
+            (name.trim(), Some(value.to_owned()))
+        }
+        None => (s.trim(), None),
+    };
+
+    if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '"') {
+        Err(format!("invalid cfg name `{}`", name))
+    } else {
+        Ok((name.to_owned(), value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::clap::Parser;
+
+    use super::*;
+
+    #[derive(Debug, Parser)]
+    struct Cli {
+        #[command(flatten)]
+        cfg: CfgArgs,
+    }
+
+    #[test]
+    fn test_cfg_args() {
+        let cli = Cli::try_parse_from([
+            "app",
+            "--cfg",
+            "unix",
+            "--cfg=target_os=linux",
+            "--cfg",
+            "feature=\"a\"",
+            "--cfg",
+            "feature = \"b\"",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            FlagSet::from(cli.cfg),
+            vec![
+                ("unix", None),
+                ("target_os", Some("linux")),
+                ("feature", Some("a")),
+                ("feature", Some("b")),
+            ]
+            .into_iter()
+            .collect()
+        );
+        assert_eq!(
+            Cli::try_parse_from(["app"]).unwrap().cfg,
+            CfgArgs::default()
+        );
+        assert!(Cli::try_parse_from(["app", "--cfg", "=x"]).is_err());
+        assert!(Cli::try_parse_from(["app", "--cfg", "a b"]).is_err());
+    }
+}
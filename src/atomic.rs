@@ -0,0 +1,331 @@
+//! The `target_has_atomic` flags, and the flags of the target specs.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::string::ToString;
+        use alloc::vec::Vec;
+
+        #[cfg(feature = "serde")]
+        use alloc::string::String;
+    }
+}
+
+use crate::{FlagSet, Predicate};
+
+/// The name of the flag for the supported atomic widths.
+pub const TARGET_HAS_ATOMIC: &str = "target_has_atomic";
+
+/// The atomic widths in bits known by rustc.
+const ATOMIC_WIDTHS: &[u16] = &[8, 16, 32, 64, 128];
+
+/// A configuration predicate for the atomic integers of the width in bits, as `target_has_atomic = "64"`.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let mut flags = FlagSet::new();
+///
+/// flags.insert_max_atomic_width(64, 32);
+///
+/// assert!(target_has_atomic(64).matches(&flags));
+/// assert!(!target_has_atomic(128).matches(&flags));
+/// assert!(target_has_atomic_ptr().matches(&flags));
+/// ```
+pub fn target_has_atomic(width: u16) -> Predicate {
+    Predicate::NameValue(TARGET_HAS_ATOMIC.into(), width.to_string())
+}
+
+/// A configuration predicate for the atomic pointers, as `target_has_atomic = "ptr"`.
+pub fn target_has_atomic_ptr() -> Predicate {
+    Predicate::NameValue(TARGET_HAS_ATOMIC.into(), "ptr".into())
+}
+
+impl FlagSet {
+    /// Sets the flag for the atomic integers of the width in bits.
+    pub fn insert_atomic(&mut self, width: u16) -> bool {
+        self.insert(TARGET_HAS_ATOMIC, width.to_string())
+    }
+
+    /// Sets the flag for the atomic pointers.
+    pub fn insert_atomic_ptr(&mut self) -> bool {
+        self.insert(TARGET_HAS_ATOMIC, "ptr")
+    }
+
+    /// Sets the flags for the atomic widths up to the maximum width in bits, like the `max-atomic-width` of rustc,
+    /// and for the atomic pointers if the pointer width doesn't exceed it.
+    pub fn insert_max_atomic_width(&mut self, max_width: u16, pointer_width: u16) {
+        self.insert_atomic_widths(8, max_width, pointer_width)
+    }
+
+    fn insert_atomic_widths(&mut self, min_width: u16, max_width: u16, pointer_width: u16) {
+        for &width in ATOMIC_WIDTHS {
+            if min_width <= width && width <= max_width {
+                self.insert_atomic(width);
+            }
+        }
+
+        if min_width <= pointer_width && pointer_width <= max_width {
+            self.insert_atomic_ptr();
+        }
+    }
+
+    /// Returns `true` if the flag for the atomic integers of the width in bits is set.
+    pub fn has_atomic(&self, width: u16) -> bool {
+        self.has_value(TARGET_HAS_ATOMIC, &width.to_string())
+    }
+
+    /// Returns `true` if the flag for the atomic pointers is set.
+    pub fn has_atomic_ptr(&self) -> bool {
+        self.has_value(TARGET_HAS_ATOMIC, "ptr")
+    }
+
+    /// Returns the widths in bits of the supported atomic integers, in ascending order.
+    pub fn atomic_widths(&self) -> Vec<u16> {
+        let mut widths = self
+            .values(TARGET_HAS_ATOMIC)
+            .filter_map(|width| width.parse().ok())
+            .collect::<Vec<_>>();
+
+        widths.sort_unstable();
+        widths
+    }
+}
+
+/// The fields of a target spec JSON, as printed by `rustc --print target-spec-json`, which determine the flags.
+///
+/// The missing fields have the defaults of rustc.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let spec: TargetSpec = serde_json::from_str(r#"{
+///     "arch": "riscv32",
+///     "llvm-target": "riscv32",
+///     "target-pointer-width": "32",
+///     "max-atomic-width": 32,
+///     "panic-strategy": "abort"
+/// }"#).unwrap();
+/// let flags = FlagSet::from_target_spec(&spec);
+///
+/// assert!(all(vec![
+///     name_value("target_arch", "riscv32"),
+///     name_value("target_os", "none"),
+///     name_value("panic", "abort"),
+///     target_has_atomic(32),
+///     target_has_atomic_ptr(),
+///     not(target_has_atomic(64)),
+/// ])
+/// .matches(&flags));
+/// ```
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ::serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TargetSpec {
+    /// The `target_arch`.
+    pub arch: String,
+    /// The `target_os`, `none` by default.
+    #[serde(default = "TargetSpec::default_os")]
+    pub os: String,
+    /// The `target_env`, empty by default.
+    #[serde(default)]
+    pub env: String,
+    /// The `target_vendor`, `unknown` by default.
+    #[serde(default = "TargetSpec::default_vendor")]
+    pub vendor: String,
+    /// The `target_abi`, empty by default.
+    #[serde(default)]
+    pub abi: String,
+    /// The `target_endian`, `little` by default.
+    #[serde(default = "TargetSpec::default_endian")]
+    pub target_endian: String,
+    /// The `target_pointer_width`, which is a string or a number.
+    #[serde(deserialize_with = "deserialize_width")]
+    pub target_pointer_width: u16,
+    /// The `target_family` values, which set the `unix` and `windows` names too.
+    #[serde(default)]
+    pub target_family: Vec<String>,
+    /// The `panic` strategy, `unwind` by default.
+    #[serde(default = "TargetSpec::default_panic_strategy")]
+    pub panic_strategy: String,
+    /// The maximum atomic width in bits, the pointer width by default.
+    #[serde(default)]
+    pub max_atomic_width: Option<u16>,
+    /// The minimum atomic width in bits, `8` by default.
+    #[serde(default)]
+    pub min_atomic_width: Option<u16>,
+    /// Whether the target supports the atomic compare-and-swap, `true` by default.
+    ///
+    /// The `target_has_atomic` flags are set only with the atomic compare-and-swap.
+    #[serde(default = "TargetSpec::default_atomic_cas")]
+    pub atomic_cas: bool,
+}
+
+#[cfg(feature = "serde")]
+impl TargetSpec {
+    fn default_os() -> String {
+        "none".into()
+    }
+
+    fn default_vendor() -> String {
+        "unknown".into()
+    }
+
+    fn default_endian() -> String {
+        "little".into()
+    }
+
+    fn default_panic_strategy() -> String {
+        "unwind".into()
+    }
+
+    fn default_atomic_cas() -> bool {
+        true
+    }
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_width<'de, D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<u16, D::Error> {
+    #[derive(::serde::Deserialize)]
+    #[serde(untagged)]
+    enum Width {
+        Number(u16),
+        String(String),
+    }
+
+    match ::serde::Deserialize::deserialize(deserializer)? {
+        Width::Number(width) => Ok(width),
+        Width::String(width) => width.parse().map_err(::serde::de::Error::custom),
+    }
+}
+
+#[cfg(feature = "serde")]
+impl FlagSet {
+    /// Creates the flags of a target spec.
+    pub fn from_target_spec(spec: &TargetSpec) -> FlagSet {
+        let mut flags = FlagSet::new();
+
+        flags.insert("target_arch", spec.arch.as_str());
+        flags.insert("target_os", spec.os.as_str());
+        flags.insert("target_env", spec.env.as_str());
+        flags.insert("target_vendor", spec.vendor.as_str());
+        flags.insert("target_abi", spec.abi.as_str());
+        flags.insert("target_endian", spec.target_endian.as_str());
+        flags.insert(
+            "target_pointer_width",
+            spec.target_pointer_width.to_string(),
+        );
+        flags.insert("panic", spec.panic_strategy.as_str());
+
+        for family in &spec.target_family {
+            flags.insert("target_family", family.as_str());
+
+            if family == "unix" || family == "windows" {
+                flags.insert_name(family.as_str());
+            }
+        }
+
+        if spec.atomic_cas {
+            flags.insert_atomic_widths(
+                spec.min_atomic_width.unwrap_or(8),
+                spec.max_atomic_width.unwrap_or(spec.target_pointer_width),
+                spec.target_pointer_width,
+            );
+        }
+
+        flags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_atomic() {
+        let mut flags = FlagSet::new();
+
+        flags.insert_max_atomic_width(64, 64);
+
+        assert_eq!(flags.atomic_widths(), vec![8, 16, 32, 64]);
+        assert!(flags.has_atomic(64));
+        assert!(!flags.has_atomic(128));
+        assert!(flags.has_atomic_ptr());
+        assert!(all(vec![target_has_atomic(8), target_has_atomic_ptr()]).matches(&flags));
+
+        let mut flags = FlagSet::new();
+
+        flags.insert_max_atomic_width(32, 64);
+
+        assert_eq!(flags.atomic_widths(), vec![8, 16, 32]);
+        assert!(!flags.has_atomic_ptr());
+
+        let mut flags = FlagSet::new();
+
+        flags.insert_max_atomic_width(0, 16);
+
+        assert!(flags.is_empty());
+        assert!(flags.insert_atomic(128));
+        assert!(!flags.insert_atomic(128));
+        assert_eq!(
+            target_has_atomic(128),
+            name_value("target_has_atomic", "128")
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_from_target_spec() {
+        let spec: TargetSpec = serde_json::from_str(
+            r#"{
+                "arch": "x86_64",
+                "os": "linux",
+                "env": "gnu",
+                "target-family": ["unix"],
+                "target-pointer-width": 64,
+                "max-atomic-width": 64
+            }"#,
+        )
+        .unwrap();
+        let flags = FlagSet::from_target_spec(&spec);
+
+        assert!(all(vec![
+            name("unix"),
+            name_value("target_family", "unix"),
+            name_value("target_os", "linux"),
+            name_value("target_env", "gnu"),
+            name_value("target_vendor", "unknown"),
+            name_value("target_abi", ""),
+            name_value("target_endian", "little"),
+            name_value("target_pointer_width", "64"),
+            name_value("panic", "unwind"),
+            target_has_atomic_ptr(),
+        ])
+        .matches(&flags));
+        assert_eq!(flags.atomic_widths(), vec![8, 16, 32, 64]);
+
+        let spec: TargetSpec = serde_json::from_str(
+            r#"{
+                "arch": "arm",
+                "target-pointer-width": "32",
+                "atomic-cas": false
+            }"#,
+        )
+        .unwrap();
+
+        assert!(FlagSet::from_target_spec(&spec).atomic_widths().is_empty());
+        assert!(serde_json::from_str::<TargetSpec>(
+            r#"{"arch": "arm", "target-pointer-width": "wide"}"#
+        )
+        .is_err());
+    }
+}
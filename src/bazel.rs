@@ -0,0 +1,380 @@
+//! Conversions between predicates and the Bazel `config_setting` and `select()` constraints, as structured data.
+//!
+//! Bazel has no negation, and a `config_setting` is a conjunction,
+//! so a predicate is exported in the disjunctive normal form, as a group of settings matching any of them,
+//! like `selects.config_setting_group(match_any = [..])` of the bazel-skylib.
+//!
+//! The leaves are mapped by the [`BazelMapping`]:
+//!
+//! - `target_os = "linux"` to the constraint value `@platforms//os:linux`;
+//! - `target_arch = "x86_64"` to the constraint value `@platforms//cpu:x86_64`;
+//! - `feature = "ssl"` to the flag value `//cfg:feature_ssl` of `"true"`;
+//! - `unix` to the flag value `//cfg:unix` of `"true"`;
+//! - `key = "value"` to the flag value `//cfg:key` of `"value"`.
+
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::collections::BTreeMap;
+    } else {
+        use alloc::collections::BTreeMap;
+        use alloc::format;
+        use alloc::string::{String, ToString};
+        use alloc::vec;
+        use alloc::vec::Vec;
+    }
+}
+
+use core::fmt;
+
+use crate::{all, any, feature, name, name_value, not, Predicate};
+
+/// The mapping between the predicate leaves and the Bazel labels.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BazelMapping {
+    /// The package of the operating system constraint values, for `target_os`.
+    pub os: String,
+    /// The package of the CPU constraint values, for `target_arch`.
+    pub cpu: String,
+    /// The prefix of the flag labels, for the other leaves.
+    pub flags: String,
+}
+
+impl Default for BazelMapping {
+    fn default() -> Self {
+        BazelMapping {
+            os: "@platforms//os:".to_string(),
+            cpu: "@platforms//cpu:".to_string(),
+            flags: "//cfg:".to_string(),
+        }
+    }
+}
+
+/// A Bazel `config_setting`, matching when all the constraint values and flag values match.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ConfigSetting {
+    /// The labels of the constraint values, like `@platforms//os:linux`.
+    pub constraint_values: Vec<String>,
+    /// The labels of the flags, and their values.
+    pub flag_values: BTreeMap<String, String>,
+}
+
+impl ConfigSetting {
+    /// Converts the setting to a predicate, with the mapping.
+    pub fn to_predicate(&self, mapping: &BazelMapping) -> Result<Predicate, BazelError> {
+        let constraints = self.constraint_values.iter().map(|label| {
+            if let Some(os) = label.strip_prefix(mapping.os.as_str()) {
+                Ok(name_value("target_os", os))
+            } else if let Some(cpu) = label.strip_prefix(mapping.cpu.as_str()) {
+                Ok(name_value("target_arch", cpu))
+            } else {
+                Err(BazelError::Label(label.clone()))
+            }
+        });
+        let flags = self.flag_values.iter().map(|(label, value)| {
+            let key = label
+                .strip_prefix(mapping.flags.as_str())
+                .ok_or_else(|| BazelError::Label(label.clone()))?;
+
+            Ok(match (key.strip_prefix("feature_"), value.as_str()) {
+                (Some(name), "true") => feature(name),
+                (None, "true") => name(key),
+                _ => name_value(key, value.as_str()),
+            })
+        });
+
+        Ok(all(constraints
+            .chain(flags)
+            .collect::<Result<Vec<_>, _>>()?))
+    }
+
+    /// Merges the settings, returns `None` if a flag has conflicting values.
+    fn merge(&self, other: &ConfigSetting) -> Option<ConfigSetting> {
+        let mut merged = self.clone();
+
+        for label in &other.constraint_values {
+            if !merged.constraint_values.contains(label) {
+                merged.constraint_values.push(label.clone());
+            }
+        }
+
+        for (label, value) in &other.flag_values {
+            match merged.flag_values.get(label) {
+                Some(merged) if merged != value => return None,
+                Some(_) => {}
+                None => {
+                    merged.flag_values.insert(label.clone(), value.clone());
+                }
+            }
+        }
+
+        Some(merged)
+    }
+}
+
+/// A Bazel `select()`, choosing the value of the first matched branch, or the default one.
+///
+/// Each branch matches any of its settings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Select<T> {
+    /// The branches with their settings.
+    pub branches: Vec<(Vec<ConfigSetting>, T)>,
+    /// The value of `//conditions:default`.
+    pub default: Option<T>,
+}
+
+impl<T> Select<T> {
+    /// Converts the predicates and their values to a `select()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    /// use runtime_cfg::bazel::{BazelMapping, Select};
+    ///
+    /// let mapping = BazelMapping::default();
+    /// let select = Select::from_cfgs(
+    ///     vec![
+    ///         (name_value("target_os", "linux"), "epoll"),
+    ///         (any(vec![name_value("target_os", "macos"), name_value("target_os", "ios")]), "kqueue"),
+    ///     ],
+    ///     Some("poll"),
+    ///     &mapping,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(select.branches[1].0.len(), 2);
+    /// assert_eq!(select.branches[1].0[0].constraint_values, vec!["@platforms//os:macos"]);
+    ///
+    /// let cfgs = select.to_cfgs(&mapping).unwrap();
+    ///
+    /// assert_eq!(cfgs[0], (any(vec![all(vec![name_value("target_os", "linux")])]), "epoll"));
+    /// assert_eq!(cfgs.len(), 3);
+    /// ```
+    pub fn from_cfgs<I>(
+        cfgs: I,
+        default: Option<T>,
+        mapping: &BazelMapping,
+    ) -> Result<Self, BazelError>
+    where
+        I: IntoIterator<Item = (Predicate, T)>,
+    {
+        let branches = cfgs
+            .into_iter()
+            .map(|(predicate, value)| Ok((predicate.to_bazel(mapping)?, value)))
+            .collect::<Result<_, BazelError>>()?;
+
+        Ok(Select { branches, default })
+    }
+
+    /// Converts the `select()` to the predicates and their values,
+    /// the default value is converted with a predicate matching when none of the branches matches.
+    pub fn to_cfgs(&self, mapping: &BazelMapping) -> Result<Vec<(Predicate, T)>, BazelError>
+    where
+        T: Clone,
+    {
+        let mut cfgs = self
+            .branches
+            .iter()
+            .map(|(settings, value)| Ok((from_bazel(settings, mapping)?, value.clone())))
+            .collect::<Result<Vec<_>, BazelError>>()?;
+
+        if let Some(ref default) = self.default {
+            let others = cfgs
+                .iter()
+                .map(|(predicate, _)| predicate.clone())
+                .collect::<Vec<_>>();
+
+            cfgs.push((not(any(others)), default.clone()));
+        }
+
+        Ok(cfgs)
+    }
+}
+
+/// Converts the settings to a predicate matching any of them.
+pub fn from_bazel(
+    settings: &[ConfigSetting],
+    mapping: &BazelMapping,
+) -> Result<Predicate, BazelError> {
+    Ok(any(settings
+        .iter()
+        .map(|setting| setting.to_predicate(mapping))
+        .collect::<Result<Vec<_>, _>>()?))
+}
+
+impl Predicate {
+    /// Converts the predicate to the Bazel settings, matching when any of them matches.
+    ///
+    /// The conjunctions with conflicting flag values never match, and are left out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    /// use runtime_cfg::bazel::BazelMapping;
+    ///
+    /// let predicate = all(vec![
+    ///     name_value("target_os", "linux"),
+    ///     any(vec![feature("ssl"), name("debug_assertions")]),
+    /// ]);
+    /// let settings = predicate.to_bazel(&BazelMapping::default()).unwrap();
+    ///
+    /// assert_eq!(settings.len(), 2);
+    /// assert_eq!(settings[0].constraint_values, vec!["@platforms//os:linux"]);
+    /// assert_eq!(settings[0].flag_values["//cfg:feature_ssl"], "true");
+    /// assert_eq!(settings[1].flag_values["//cfg:debug_assertions"], "true");
+    /// ```
+    pub fn to_bazel(&self, mapping: &BazelMapping) -> Result<Vec<ConfigSetting>, BazelError> {
+        match self {
+            Predicate::Any(predicates) => {
+                let mut settings = Vec::new();
+
+                for predicate in predicates {
+                    settings.extend(predicate.to_bazel(mapping)?);
+                }
+
+                Ok(settings)
+            }
+            Predicate::All(predicates) => {
+                let mut settings = vec![ConfigSetting::default()];
+
+                for predicate in predicates {
+                    let terms = predicate.to_bazel(mapping)?;
+
+                    settings = settings
+                        .iter()
+                        .flat_map(|setting| {
+                            terms.iter().filter_map(move |term| setting.merge(term))
+                        })
+                        .collect();
+                }
+
+                Ok(settings)
+            }
+            Predicate::Not(predicate) => match **predicate {
+                Predicate::True => Ok(vec![]),
+                Predicate::False => Ok(vec![ConfigSetting::default()]),
+                _ => Err(BazelError::Negation),
+            },
+            Predicate::Name(name) => Ok(vec![flag(mapping, name, "true")]),
+            Predicate::NameValue(name, value) => Ok(vec![match name.as_str() {
+                "target_os" => constraint(&mapping.os, value),
+                "target_arch" => constraint(&mapping.cpu, value),
+                "feature" => flag(mapping, &format!("feature_{}", value), "true"),
+                _ => flag(mapping, name, value),
+            }]),
+            Predicate::True => Ok(vec![ConfigSetting::default()]),
+            Predicate::False => Ok(vec![]),
+            Predicate::Param(name) => Err(BazelError::Param(name.clone())),
+        }
+    }
+}
+
+fn constraint(package: &str, value: &str) -> ConfigSetting {
+    ConfigSetting {
+        constraint_values: vec![format!("{}{}", package, value)],
+        ..Default::default()
+    }
+}
+
+fn flag(mapping: &BazelMapping, key: &str, value: &str) -> ConfigSetting {
+    let mut setting = ConfigSetting::default();
+
+    setting
+        .flag_values
+        .insert(format!("{}{}", mapping.flags, key), value.to_string());
+
+    setting
+}
+
+/// The errors of the conversions between predicates and Bazel settings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BazelError {
+    /// A negation, which has no Bazel equivalent.
+    Negation,
+    /// An unbound parameter.
+    Param(String),
+    /// A label out of the mapping.
+    Label(String),
+}
+
+impl fmt::Display for BazelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BazelError::Negation => f.write_str("negation is not supported by Bazel"),
+            BazelError::Param(name) => write!(f, "unbound parameter `{}`", name),
+            BazelError::Label(label) => write!(f, "label `{}` is out of the mapping", label),
+        }
+    }
+}
+
+impl core::error::Error for BazelError {}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::borrow::ToOwned;
+            use alloc::vec;
+        }
+    }
+
+    use crate::bazel::*;
+    use crate::*;
+
+    #[test]
+    fn test_bazel() {
+        let mapping = BazelMapping::default();
+        let predicate = all(vec![
+            any(vec![
+                name_value("target_os", "linux"),
+                name_value("target_arch", "aarch64"),
+            ]),
+            name_value("panic", "abort"),
+            any(vec![name_value("panic", "unwind"), feature("std")]),
+        ]);
+        let settings = predicate.to_bazel(&mapping).unwrap();
+
+        assert_eq!(settings.len(), 2);
+        assert_eq!(
+            settings[1].constraint_values,
+            vec!["@platforms//cpu:aarch64".to_owned()]
+        );
+        assert_eq!(
+            from_bazel(&settings, &mapping).unwrap(),
+            any(vec![
+                all(vec![
+                    name_value("target_os", "linux"),
+                    feature("std"),
+                    name_value("panic", "abort"),
+                ]),
+                all(vec![
+                    name_value("target_arch", "aarch64"),
+                    feature("std"),
+                    name_value("panic", "abort"),
+                ]),
+            ])
+        );
+
+        assert_eq!(Predicate::True.to_bazel(&mapping).unwrap().len(), 1);
+        assert!(Predicate::False.to_bazel(&mapping).unwrap().is_empty());
+        assert_eq!(
+            not(name("unix")).to_bazel(&mapping),
+            Err(BazelError::Negation)
+        );
+        assert_eq!(
+            param("x").to_bazel(&mapping),
+            Err(BazelError::Param("x".to_owned()))
+        );
+
+        let setting = ConfigSetting {
+            constraint_values: vec!["//my:constraint".to_owned()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            setting.to_predicate(&mapping),
+            Err(BazelError::Label("//my:constraint".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! Evaluates and analyzes the configuration predicates from the command line.
+
+use std::convert::TryFrom;
+use std::env;
+use std::process;
+
+use cfg_expr::{targets, Expression};
+
+use runtime_cfg::{Cfg, FlagSet};
+
+const USAGE: &str = r#"Usage: runtime-cfg <COMMAND> <CFG> [OPTIONS]
+
+Commands:
+    eval        Evaluates the configuration with the flags, exits with 1 if it doesn't match
+    explain     Shows the evaluation result of each node with the flags
+    simplify    Prints the simplified configuration
+    targets     Lists the built-in targets matching the configuration
+    lint        Reports the suspicious constructs, exits with 1 if any
+
+Options:
+    -f, --flag <NAME[=VALUE]>   Sets a flag, like `unix` or `target_pointer_width=32`
+        --color                 Renders the explanation with ANSI colors
+    -h, --help                  Prints this help
+
+The configuration could be written as `#[cfg(..)]`, `cfg(..)` or a bare predicate."#;
+
+struct Args {
+    command: String,
+    cfg: Cfg,
+    flags: FlagSet,
+    color: bool,
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("error: {}\n\n{}", msg, USAGE);
+            process::exit(2)
+        }
+    };
+
+    process::exit(run(&args))
+}
+
+fn run(args: &Args) -> i32 {
+    match args.command.as_str() {
+        "eval" => {
+            let matched = args.cfg.matches(&args.flags);
+
+            println!("{}", matched);
+
+            if matched {
+                0
+            } else {
+                1
+            }
+        }
+        "explain" => {
+            let explanation = args.cfg.explain(&args.flags);
+
+            if args.color {
+                print!("{}", explanation.colored());
+            } else {
+                print!("{}", explanation);
+            }
+
+            if explanation.matched {
+                0
+            } else {
+                1
+            }
+        }
+        "simplify" => {
+            println!("{}", Cfg::from(args.cfg.simplify()));
+
+            0
+        }
+        "targets" => match Expression::try_from(&args.cfg) {
+            Ok(expr) => {
+                for target in targets::ALL_BUILTINS {
+                    if expr.eval(|predicate| match predicate {
+                        cfg_expr::Predicate::Target(predicate) => predicate.matches(target),
+                        _ => false,
+                    }) {
+                        println!("{}", target.triple);
+                    }
+                }
+
+                0
+            }
+            Err(err) => {
+                eprintln!("error: {}", err);
+
+                2
+            }
+        },
+        "lint" => {
+            let lints = args.cfg.lint();
+
+            for lint in &lints {
+                println!("warning: {}", lint);
+            }
+
+            if lints.is_empty() {
+                0
+            } else {
+                1
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Args, String> {
+    let mut command = None;
+    let mut cfg = None;
+    let mut flags = FlagSet::new();
+    let mut color = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0)
+            }
+            "-f" | "--flag" => {
+                let flag = args
+                    .next()
+                    .ok_or_else(|| format!("`{}` expect a flag", arg))?;
+
+                parse_flag(&mut flags, &flag)?
+            }
+            "--color" => color = true,
+            _ if arg.starts_with("--flag=") => parse_flag(&mut flags, &arg["--flag=".len()..])?,
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                return Err(format!("unknown option `{}`", arg))
+            }
+            _ if command.is_none() => match arg.as_str() {
+                "eval" | "explain" | "simplify" | "targets" | "lint" => command = Some(arg),
+                _ => return Err(format!("unknown command `{}`", arg)),
+            },
+            _ if cfg.is_none() => cfg = Some(parse_cfg(&arg)?),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+
+    Ok(Args {
+        command: command.ok_or("missing command")?,
+        cfg: cfg.ok_or("missing configuration")?,
+        flags,
+        color,
+    })
+}
+
+fn parse_cfg(s: &str) -> Result<Cfg, String> {
+    let s = s.trim();
+    let res = if s.starts_with('#') || s.starts_with("cfg") && s[3..].trim_start().starts_with('(')
+    {
+        Cfg::parse(s)
+    } else {
+        Cfg::parse(format!("cfg({})", s))
+    };
+
+    res.map_err(|err| format!("invalid configuration `{}`, {}", s, err))
+}
+
+fn parse_flag(flags: &mut FlagSet, flag: &str) -> Result<(), String> {
+    match flag.find('=') {
+        Some(idx) => {
+            let name = flag[..idx].trim();
+            let value = flag[idx + 1..].trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+
+            if name.is_empty() {
+                return Err(format!("invalid flag `{}`, name can't be empty", flag));
+            }
+
+            flags.insert(name, value);
+        }
+        None if flag.trim().is_empty() => return Err("flag can't be empty".to_owned()),
+        None => flags.insert_name(flag.trim()),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use runtime_cfg::*;
+
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = args(&[
+            "eval",
+            "all(unix, target_os = \"linux\")",
+            "-f",
+            "unix",
+            "--flag=target_os=\"linux\"",
+            "--color",
+        ])
+        .unwrap();
+
+        assert_eq!(parsed.command, "eval");
+        assert_eq!(
+            parsed.cfg,
+            all(vec![name("unix"), name_value("target_os", "linux")]).into()
+        );
+        assert!(parsed.flags.has("unix"));
+        assert!(parsed.flags.has_value("target_os", "linux"));
+        assert!(parsed.color);
+
+        assert_eq!(
+            args(&["build", "unix"]).err().unwrap(),
+            "unknown command `build`"
+        );
+        assert_eq!(
+            args(&["eval", "unix", "--verbose"]).err().unwrap(),
+            "unknown option `--verbose`"
+        );
+        assert_eq!(
+            args(&["eval", "unix", "--flag"]).err().unwrap(),
+            "`--flag` expect a flag"
+        );
+        assert_eq!(
+            args(&["eval", "unix", "--flag="]).err().unwrap(),
+            "flag can't be empty"
+        );
+        assert_eq!(
+            args(&["eval", "unix", "windows"]).err().unwrap(),
+            "unexpected argument `windows`"
+        );
+        assert_eq!(args(&["eval"]).err().unwrap(), "missing configuration");
+        assert_eq!(args(&[]).err().unwrap(), "missing command");
+    }
+
+    #[test]
+    fn test_parse_flag() {
+        let mut flags = FlagSet::new();
+
+        parse_flag(&mut flags, " unix ").unwrap();
+        parse_flag(&mut flags, "target_os = \"linux\"").unwrap();
+        parse_flag(&mut flags, "target_env=gnu").unwrap();
+        parse_flag(&mut flags, "feature=\"a=b\"").unwrap();
+        parse_flag(&mut flags, "quoted=\"").unwrap();
+        parse_flag(&mut flags, "empty=").unwrap();
+
+        assert!(flags.has("unix"));
+        assert!(flags.has_value("target_os", "linux"));
+        assert!(flags.has_value("target_env", "gnu"));
+        assert!(flags.has_value("feature", "a=b"));
+        assert!(flags.has_value("quoted", "\""));
+        assert!(flags.has_value("empty", ""));
+
+        assert_eq!(
+            parse_flag(&mut flags, "=linux").unwrap_err(),
+            "invalid flag `=linux`, name can't be empty"
+        );
+        assert_eq!(
+            parse_flag(&mut flags, "  ").unwrap_err(),
+            "flag can't be empty"
+        );
+    }
+
+    #[test]
+    fn test_parse_cfg() {
+        let expected = Cfg::from(all(vec![name("unix"), not(name("test"))]));
+
+        for s in &[
+            "#[cfg(all(unix, not(test)))]",
+            "cfg(all(unix, not(test)))",
+            "cfg (all(unix, not(test)))",
+            " all(unix, not(test)) ",
+        ] {
+            assert_eq!(parse_cfg(s).unwrap(), expected, "{}", s);
+        }
+
+        assert_eq!(
+            parse_cfg("target_os = \"linux\"").unwrap(),
+            name_value("target_os", "linux").into()
+        );
+        assert_eq!(parse_cfg("cfg_test").unwrap(), name("cfg_test").into());
+        assert!(parse_cfg("all(unix,")
+            .unwrap_err()
+            .starts_with("invalid configuration `all(unix,`"));
+    }
+}
@@ -0,0 +1,239 @@
+//! Borrowed predicates, constructed without heap allocation.
+
+cfg_if! {
+    if #[cfg(all(feature = "alloc", not(feature = "std")))] {
+        use alloc::boxed::Box;
+        use alloc::string::ToString;
+    }
+}
+
+use crate::Pattern;
+#[cfg(feature = "alloc")]
+use crate::{Cfg, Predicate};
+
+/// A borrowed predicate, which doesn't need any heap allocation.
+///
+/// It could be built from string literals as a constant,
+/// and converted to an owned `Predicate` when needed.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// const LINUX: PredicateRef = PredicateRef::All(&[
+///     PredicateRef::Name("unix"),
+///     PredicateRef::NameValue("target_os", "linux"),
+/// ]);
+///
+/// assert!(LINUX.matches(&[("unix", None), ("target_os", Some("linux"))]));
+///
+/// static STABLE: PredicateRef = PredicateRef::not(&PredicateRef::any(&[
+///     PredicateRef::feature("beta"),
+///     PredicateRef::feature("nightly"),
+/// ]));
+///
+/// assert!(STABLE.matches(&[("feature", Some("stable"))]));
+/// # #[cfg(feature = "alloc")]
+/// assert_eq!(
+///     Predicate::from(LINUX),
+///     all(vec![name("unix"), name_value("target_os", "linux")])
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PredicateRef<'a> {
+    /// `any()` with a comma separated list of configuration predicates.
+    Any(&'a [PredicateRef<'a>]),
+    /// `all()` with a comma separated list of configuration predicates.
+    All(&'a [PredicateRef<'a>]),
+    /// `not()` with a configuration predicate.
+    Not(&'a PredicateRef<'a>),
+    /// A configuration option name.
+    Name(&'a str),
+    /// A configuration option name and value.
+    NameValue(&'a str, &'a str),
+    /// A predicate that is always `true`.
+    True,
+    /// A predicate that is always `false`.
+    False,
+}
+
+impl<'a> PredicateRef<'a> {
+    /// A predicate success when `any` of sub-predicates success.
+    pub const fn any(predicates: &'a [PredicateRef<'a>]) -> Self {
+        PredicateRef::Any(predicates)
+    }
+
+    /// A predicate success when `all` of sub-predicates success.
+    pub const fn all(predicates: &'a [PredicateRef<'a>]) -> Self {
+        PredicateRef::All(predicates)
+    }
+
+    /// A predicate apply `not` operator to a sub-predicate.
+    pub const fn not(predicate: &'a PredicateRef<'a>) -> Self {
+        PredicateRef::Not(predicate)
+    }
+
+    /// A predicate with name.
+    pub const fn name(name: &'a str) -> Self {
+        PredicateRef::Name(name)
+    }
+
+    /// A predicate with name and value.
+    pub const fn name_value(name: &'a str, value: &'a str) -> Self {
+        PredicateRef::NameValue(name, value)
+    }
+
+    /// A predicate for the Cargo feature, as `feature = "name"`.
+    pub const fn feature(name: &'a str) -> Self {
+        PredicateRef::NameValue("feature", name)
+    }
+
+    /// Returns the number of sub-predicates, or `0` for a leaf.
+    pub const fn arity(&self) -> usize {
+        match self {
+            PredicateRef::Any(predicates) | PredicateRef::All(predicates) => predicates.len(),
+            PredicateRef::Not(_) => 1,
+            _ => 0,
+        }
+    }
+
+    /// Returns `true` if configuration matches the predicate
+    pub fn matches<P: Pattern + ?Sized>(&self, pattern: &P) -> bool {
+        use PredicateRef::*;
+
+        match self {
+            Any(predicates) => predicates
+                .iter()
+                .any(|predicate| predicate.matches(pattern)),
+            All(predicates) => predicates
+                .iter()
+                .all(|predicate| predicate.matches(pattern)),
+            Not(predicate) => !predicate.matches(pattern),
+            Name(name) => pattern.matches(name, None),
+            NameValue(name, value) => pattern.matches(name, Some(value)),
+            True => true,
+            False => false,
+        }
+    }
+
+    /// Converts to an owned predicate.
+    #[cfg(feature = "alloc")]
+    pub fn to_predicate(&self) -> Predicate {
+        match *self {
+            PredicateRef::Any(predicates) => Predicate::Any(
+                predicates
+                    .iter()
+                    .map(|predicate| Box::new(predicate.to_predicate()))
+                    .collect(),
+            ),
+            PredicateRef::All(predicates) => Predicate::All(
+                predicates
+                    .iter()
+                    .map(|predicate| Box::new(predicate.to_predicate()))
+                    .collect(),
+            ),
+            PredicateRef::Not(predicate) => Predicate::Not(Box::new(predicate.to_predicate())),
+            PredicateRef::Name(name) => Predicate::Name(name.to_string()),
+            PredicateRef::NameValue(name, value) => {
+                Predicate::NameValue(name.to_string(), value.to_string())
+            }
+            PredicateRef::True => Predicate::True,
+            PredicateRef::False => Predicate::False,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<PredicateRef<'_>> for Predicate {
+    fn from(predicate: PredicateRef) -> Self {
+        predicate.to_predicate()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<PredicateRef<'_>> for Cfg {
+    fn from(predicate: PredicateRef) -> Self {
+        Cfg(predicate.to_predicate())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(all(feature = "alloc", not(feature = "std")))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    static UNIX: PredicateRef<'static> = PredicateRef::Name("unix");
+    static NOT_MUSL: PredicateRef<'static> =
+        PredicateRef::Not(&PredicateRef::NameValue("target_env", "musl"));
+    static PREDICATE: PredicateRef<'static> =
+        PredicateRef::Any(&[PredicateRef::All(&[UNIX, NOT_MUSL]), PredicateRef::False]);
+
+    #[test]
+    fn test_matches() {
+        assert!(PREDICATE.matches(&[("unix", None), ("target_env", Some("gnu"))]));
+        assert!(!PREDICATE.matches(&[("unix", None), ("target_env", Some("musl"))]));
+        assert!(!PREDICATE.matches(&[("windows", None::<&str>)]));
+        assert!(PredicateRef::True.matches(&[("windows", None::<&str>)]));
+    }
+
+    static TABLE: &[(&str, PredicateRef<'static>)] = &[
+        (
+            "linux",
+            PredicateRef::all(&[
+                PredicateRef::name("unix"),
+                PredicateRef::name_value("target_os", "linux"),
+            ]),
+        ),
+        (
+            "stable",
+            PredicateRef::not(&PredicateRef::any(&[
+                PredicateRef::feature("beta"),
+                PredicateRef::feature("nightly"),
+            ])),
+        ),
+    ];
+
+    #[test]
+    fn test_const() {
+        const UNIX_REF: PredicateRef<'static> = PredicateRef::name("unix");
+
+        assert_eq!(UNIX_REF, UNIX);
+        assert_eq!(TABLE[0].1.arity(), 2);
+        assert_eq!(TABLE[1].1.arity(), 1);
+        assert_eq!(UNIX_REF.arity(), 0);
+
+        let flags = [
+            ("unix", None),
+            ("target_os", Some("linux")),
+            ("feature", Some("beta")),
+        ];
+
+        assert_eq!(
+            TABLE
+                .iter()
+                .filter(|(_, predicate)| predicate.matches(&flags))
+                .map(|(name, _)| *name)
+                .next(),
+            Some("linux")
+        );
+        assert!(TABLE[1].1.matches(&[("feature", Some("stable"))]));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_to_predicate() {
+        assert_eq!(
+            Predicate::from(PREDICATE),
+            any(vec![
+                all(vec![name("unix"), not(name_value("target_env", "musl"))]),
+                Predicate::False
+            ])
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Helpers for build scripts to emit the `cargo::rustc-cfg` directives.
+//!
+//! The directives use the `cargo::` prefix, which requires Cargo 1.77 or later.
+//!
+//! # Example
+//!
+//! ```no_run
+//! // build.rs
+//! use runtime_cfg::{build_helper, *};
+//!
+//! let decls = vec![
+//!     ("linux_musl", Cfg::from(all(vec![
+//!         name_value("target_os", "linux"),
+//!         name_value("target_env", "musl"),
+//!     ]))),
+//!     ("apple", Cfg::from(any(vec![
+//!         name_value("target_os", "macos"),
+//!         name_value("target_os", "ios"),
+//!     ]))),
+//! ];
+//!
+//! build_helper::emit_cfgs(&decls, &build_helper::target_flags());
+//! ```
+
+use std::env;
+use std::io::{self, Write};
+
+use crate::{Cfg, FlagSet, Pattern};
+
+/// Prints the `cargo::rustc-check-cfg` directive for each declared name,
+/// and the `cargo::rustc-cfg` directive for the names whose configurations match the pattern.
+pub fn emit_cfgs<S: AsRef<str>, P: Pattern>(decls: &[(S, Cfg)], pattern: &P) {
+    write_cfgs(&mut io::stdout().lock(), decls, pattern).expect("write to stdout")
+}
+
+/// Writes the `cargo::rustc-check-cfg` directive for each declared name,
+/// and the `cargo::rustc-cfg` directive for the names whose configurations match the pattern.
+pub fn write_cfgs<W: Write, S: AsRef<str>, P: Pattern>(
+    w: &mut W,
+    decls: &[(S, Cfg)],
+    pattern: &P,
+) -> io::Result<()> {
+    for (name, _) in decls {
+        writeln!(w, "cargo::rustc-check-cfg=cfg({})", name.as_ref())?;
+    }
+
+    for (name, cfg) in decls {
+        if cfg.matches(pattern) {
+            writeln!(w, "cargo::rustc-cfg={}", name.as_ref())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the flags of the build target, from the environment variables set by Cargo for build scripts.
+///
+/// The `CARGO_CFG_<NAME>` variables are read as the configuration flags,
+/// the comma separated values are split, like `target_feature`.
+///
+/// The enabled features are read from `CARGO_CFG_FEATURE`, set by Cargo 1.80 or later,
+/// which keeps their exact names, unlike the `CARGO_FEATURE_<NAME>` variables
+/// that are uppercased with `-` replaced by `_`.
+pub fn target_flags() -> FlagSet {
+    flags_from_vars(env::vars())
+}
+
+fn flags_from_vars<I: IntoIterator<Item = (String, String)>>(vars: I) -> FlagSet {
+    let mut flags = FlagSet::new();
+
+    for (key, value) in vars {
+        if let Some(name) = key.strip_prefix("CARGO_CFG_") {
+            let name = name.to_lowercase();
+
+            if value.is_empty() {
+                if name != "feature" {
+                    flags.insert_name(name);
+                }
+            } else {
+                for value in value.split(',') {
+                    flags.insert(name.as_str(), value);
+                }
+            }
+        }
+    }
+
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    use super::*;
+
+    #[test]
+    fn test_write_cfgs() {
+        let decls = vec![
+            ("linux", Cfg::from(name_value("target_os", "linux"))),
+            ("sse2", Cfg::from(name_value("target_feature", "sse2"))),
+            ("std", Cfg::from(feature("std"))),
+            ("ext", Cfg::from(feature("with_ext"))),
+            ("dash", Cfg::from(feature("with-dash"))),
+        ];
+        let flags = flags_from_vars(
+            vec![
+                ("CARGO_CFG_UNIX", ""),
+                ("CARGO_CFG_TARGET_OS", "macos"),
+                ("CARGO_CFG_TARGET_FEATURE", "sse,sse2"),
+                ("CARGO_CFG_FEATURE", "std,with_ext"),
+                ("CARGO_FEATURE_STD", "1"),
+                ("CARGO_FEATURE_WITH_EXT", "1"),
+                ("CARGO_PKG_NAME", "foo"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v.to_owned())),
+        );
+
+        assert!(flags.has("unix"));
+        assert!(!flags.has("cargo_pkg_name"));
+        assert!(!flags.has_value("feature", "with-ext"));
+        assert!(flags_from_vars(vec![("CARGO_CFG_FEATURE".to_owned(), String::new())]).is_empty());
+
+        let mut out = Vec::new();
+
+        write_cfgs(&mut out, &decls, &flags).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "cargo::rustc-check-cfg=cfg(linux)\n\
+             cargo::rustc-check-cfg=cfg(sse2)\n\
+             cargo::rustc-check-cfg=cfg(std)\n\
+             cargo::rustc-check-cfg=cfg(ext)\n\
+             cargo::rustc-check-cfg=cfg(dash)\n\
+             cargo::rustc-cfg=sse2\n\
+             cargo::rustc-cfg=std\n\
+             cargo::rustc-cfg=ext\n"
+        );
+    }
+}
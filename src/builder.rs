@@ -0,0 +1,170 @@
+//! A fluent builder of configurations with scoped nesting.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::boxed::Box;
+        use alloc::string::String;
+        use alloc::vec::Vec;
+    }
+}
+
+use crate::{Cfg, Predicate};
+
+/// A fluent builder of configurations with scoped nesting.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let cfg = CfgBuilder::all(|b| {
+///     b.name("unix");
+///     b.not(|b| b.value("target_env", "musl"));
+/// });
+///
+/// assert_eq!(
+///     cfg,
+///     all(vec![name("unix"), not(name_value("target_env", "musl"))]).into()
+/// );
+/// ```
+#[derive(Debug)]
+pub struct CfgBuilder;
+
+impl CfgBuilder {
+    /// Builds a `#[cfg(any(..))]` configuration with the sub-predicates added in the scope.
+    pub fn any<F>(f: F) -> Cfg
+    where
+        F: FnOnce(&mut CfgScope),
+    {
+        Cfg(Predicate::Any(CfgScope::build(f)))
+    }
+
+    /// Builds a `#[cfg(all(..))]` configuration with the sub-predicates added in the scope.
+    pub fn all<F>(f: F) -> Cfg
+    where
+        F: FnOnce(&mut CfgScope),
+    {
+        Cfg(Predicate::All(CfgScope::build(f)))
+    }
+
+    /// Builds a `#[cfg(not(..))]` configuration with the sub-predicates added in the scope.
+    ///
+    /// Multiple sub-predicates are combined with `all`.
+    pub fn not<F>(f: F) -> Cfg
+    where
+        F: FnOnce(&mut CfgScope),
+    {
+        Cfg(CfgScope::build_not(f))
+    }
+}
+
+/// A scope to add the sub-predicates of a predicate.
+#[derive(Debug, Default)]
+pub struct CfgScope {
+    predicates: Vec<Predicate>,
+}
+
+impl CfgScope {
+    #[allow(clippy::vec_box)]
+    fn build<F>(f: F) -> Vec<Box<Predicate>>
+    where
+        F: FnOnce(&mut CfgScope),
+    {
+        let mut scope = CfgScope::default();
+
+        f(&mut scope);
+
+        scope.predicates.into_iter().map(Box::new).collect()
+    }
+
+    fn build_not<F>(f: F) -> Predicate
+    where
+        F: FnOnce(&mut CfgScope),
+    {
+        let mut predicates = CfgScope::build(f);
+
+        Predicate::Not(if predicates.len() == 1 {
+            predicates.pop().unwrap()
+        } else {
+            Box::new(Predicate::All(predicates))
+        })
+    }
+
+    /// Adds an `any` sub-predicate with the sub-predicates added in the nested scope.
+    pub fn any<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut CfgScope),
+    {
+        self.predicate(Predicate::Any(CfgScope::build(f)))
+    }
+
+    /// Adds an `all` sub-predicate with the sub-predicates added in the nested scope.
+    pub fn all<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut CfgScope),
+    {
+        self.predicate(Predicate::All(CfgScope::build(f)))
+    }
+
+    /// Adds a `not` sub-predicate with the sub-predicates added in the nested scope.
+    ///
+    /// Multiple sub-predicates are combined with `all`.
+    pub fn not<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut CfgScope),
+    {
+        self.predicate(CfgScope::build_not(f))
+    }
+
+    /// Adds a sub-predicate with name.
+    pub fn name<S: Into<String>>(&mut self, name: S) {
+        self.predicate(Predicate::Name(name.into()))
+    }
+
+    /// Adds a sub-predicate with name and value.
+    pub fn value<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) {
+        self.predicate(Predicate::NameValue(name.into(), value.into()))
+    }
+
+    /// Adds a sub-predicate.
+    pub fn predicate(&mut self, predicate: Predicate) {
+        self.predicates.push(predicate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_builder() {
+        assert_eq!(
+            CfgBuilder::any(|b| {
+                b.name("foo");
+                b.all(|b| {
+                    b.value("target_os", "macos");
+                    b.not(|b| {
+                        b.name("bar");
+                        b.name("baz");
+                    })
+                });
+            }),
+            any(vec![
+                name("foo"),
+                all(vec![
+                    name_value("target_os", "macos"),
+                    not(all(vec![name("bar"), name("baz")]))
+                ])
+            ])
+            .into()
+        );
+        assert_eq!(CfgBuilder::not(|b| b.name("foo")), not(name("foo")).into());
+        assert_eq!(CfgBuilder::all(|_| ()), Cfg::from(all(vec![])));
+    }
+}
@@ -0,0 +1,157 @@
+//! Cache of the evaluation results, for the configurations evaluated repeatedly against the rarely changed flags.
+
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::collections::BTreeMap;
+    } else {
+        use alloc::collections::BTreeMap;
+    }
+}
+
+use crate::{FlagSet, Predicate};
+
+/// A cache of the evaluation results, keyed by the [fingerprints](Predicate::fingerprint) of the predicates
+/// and the [generation](FlagSet::generation) of the flags.
+///
+/// The cached results are invalidated once the flags are evaluated in another generation,
+/// so a cache is expected to serve a single `FlagSet` as it changes over time.
+///
+/// Computing a fingerprint costs more than evaluating a small predicate,
+/// compute it once with [`Predicate::fingerprint`] and use [`EvalCache::matches_fingerprint`] on the hot path.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let mut flags = FlagSet::new();
+/// let mut cache = EvalCache::new();
+/// let cfg = all(vec![name("unix"), feature("ssl")]);
+///
+/// flags.insert_name("unix");
+///
+/// assert!(!cache.matches(&cfg, &flags));
+/// assert!(!cache.matches(&cfg, &flags));
+/// assert_eq!((cache.hits(), cache.misses()), (1, 1));
+///
+/// flags.insert("feature", "ssl");
+///
+/// assert!(cache.matches(&cfg, &flags));
+/// assert_eq!((cache.hits(), cache.misses()), (1, 2));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EvalCache {
+    generation: Option<u64>,
+    results: BTreeMap<u128, bool>,
+    hits: u64,
+    misses: u64,
+}
+
+impl EvalCache {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Evaluates the predicate against the flags, or returns the cached result.
+    pub fn matches(&mut self, predicate: &Predicate, flags: &FlagSet) -> bool {
+        self.matches_fingerprint(predicate.fingerprint(), predicate, flags)
+    }
+
+    /// Evaluates the predicate with the precomputed fingerprint against the flags, or returns the cached result.
+    ///
+    /// The fingerprint must be the one of the predicate, otherwise a wrong result may be returned.
+    pub fn matches_fingerprint(
+        &mut self,
+        fingerprint: u128,
+        predicate: &Predicate,
+        flags: &FlagSet,
+    ) -> bool {
+        if self.generation != Some(flags.generation()) {
+            self.generation = Some(flags.generation());
+            self.results.clear();
+        }
+
+        if let Some(&matched) = self.results.get(&fingerprint) {
+            self.hits += 1;
+
+            return matched;
+        }
+
+        let matched = predicate.matches(flags);
+
+        self.misses += 1;
+        self.results.insert(fingerprint, matched);
+
+        matched
+    }
+
+    /// Returns the number of the cached results.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Returns `true` if no result was cached.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Returns the number of the evaluations answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns the number of the evaluations performed.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Removes all the cached results, and resets the statistics.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_eval_cache() {
+        let mut flags: FlagSet = vec![("unix", None), ("target_os", Some("linux"))]
+            .into_iter()
+            .collect();
+        let mut cache = EvalCache::new();
+        let linux = name_value("target_os", "linux");
+        let ssl = any(vec![feature("ssl"), feature("tls")]);
+        let fingerprint = linux.fingerprint();
+
+        assert!(cache.matches_fingerprint(fingerprint, &linux, &flags));
+        assert!(!cache.matches(&ssl, &flags));
+        assert!(cache.matches(&any(vec![name_value("target_os", "linux")]), &flags));
+        assert!(!cache.matches(&any(vec![feature("tls"), feature("ssl")]), &flags));
+        assert_eq!(cache.len(), 3);
+        assert_eq!((cache.hits(), cache.misses()), (1, 3));
+
+        let cloned = flags.clone();
+
+        assert!(cache.matches_fingerprint(fingerprint, &linux, &cloned));
+        assert_eq!(cache.hits(), 2);
+
+        flags.insert("feature", "tls");
+
+        assert!(cache.matches(&ssl, &flags));
+        assert_eq!(cache.len(), 1);
+
+        cache.clear();
+
+        assert!(cache.is_empty());
+        assert_eq!((cache.hits(), cache.misses()), (0, 0));
+    }
+}
@@ -0,0 +1,287 @@
+//! Canonical form of a predicate tree.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::boxed::Box;
+        use alloc::vec::Vec;
+    }
+}
+
+use core::ops::Deref;
+
+use crate::{fold::Fold, Predicate};
+
+impl Predicate {
+    /// Returns the canonical form of the predicate.
+    ///
+    /// The nested `any` or `all` are flattened into their parent of the same kind,
+    /// then the sub-predicates are sorted in a deterministic order and the duplicates are removed.
+    /// The empty `any()` and `all()` are replaced with [`Predicate::False`] and [`Predicate::True`],
+    /// and dropped from the parent of the same kind.
+    /// The logically-identical predicates written in different orders have the same canonical form,
+    /// so the same `Display` output and hash.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let lhs = any(vec![name("foo"), any(vec![name("bar"), name("foo")])]);
+    /// let rhs = any(vec![name("bar"), name("foo")]);
+    ///
+    /// assert_eq!(lhs.canonicalize(), rhs.canonicalize());
+    /// ```
+    pub fn canonicalize(&self) -> Predicate {
+        self.clone().fold(&mut Canonicalize)
+    }
+
+    /// Returns `true` if the predicates are equal, regardless the order and duplicates of sub-predicates.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let lhs = any(vec![name("foo"), name("bar")]);
+    /// let rhs = any(vec![name("bar"), name("foo"), name("bar")]);
+    ///
+    /// assert_ne!(lhs, rhs);
+    /// assert!(lhs.eq_unordered(&rhs));
+    /// ```
+    pub fn eq_unordered(&self, other: &Predicate) -> bool {
+        self.canonicalize() == other.canonicalize()
+    }
+}
+
+/// A predicate kept in the canonical form,
+/// whose `Eq` and `Hash` are insensitive to the order and duplicates of sub-predicates.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use runtime_cfg::*;
+///
+/// let mut cache = HashMap::new();
+///
+/// cache.insert(Canonical::new(Cfg::from(any(vec![name("foo"), name("bar")]))), true);
+///
+/// assert_eq!(
+///     cache.get(&Canonical::new(Cfg::from(any(vec![name("bar"), name("foo")])))),
+///     Some(&true)
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Canonical<T>(T);
+
+impl<T> Canonical<T>
+where
+    T: Into<Predicate> + From<Predicate>,
+{
+    /// Wraps the predicate in the canonical form.
+    pub fn new(value: T) -> Self {
+        Canonical(T::from(value.into().fold(&mut Canonicalize)))
+    }
+}
+
+impl<T> Canonical<T> {
+    /// Unwraps the predicate in the canonical form.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Canonical<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> AsRef<T> for Canonical<T> {
+    fn as_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<T> for Canonical<T>
+where
+    T: Into<Predicate> + From<Predicate>,
+{
+    fn from(value: T) -> Self {
+        Canonical::new(value)
+    }
+}
+
+struct Canonicalize;
+
+impl Fold for Canonicalize {
+    fn fold_any(&mut self, predicates: Vec<Box<Predicate>>) -> Predicate {
+        let children = self.fold_children(predicates, |predicate| match predicate {
+            Predicate::Any(predicates) => Ok(predicates),
+            Predicate::False => Ok(Vec::new()),
+            _ => Err(predicate),
+        });
+
+        if children.is_empty() {
+            Predicate::False
+        } else {
+            Predicate::Any(children)
+        }
+    }
+
+    fn fold_all(&mut self, predicates: Vec<Box<Predicate>>) -> Predicate {
+        let children = self.fold_children(predicates, |predicate| match predicate {
+            Predicate::All(predicates) => Ok(predicates),
+            Predicate::True => Ok(Vec::new()),
+            _ => Err(predicate),
+        });
+
+        if children.is_empty() {
+            Predicate::True
+        } else {
+            Predicate::All(children)
+        }
+    }
+}
+
+impl Canonicalize {
+    #[allow(clippy::vec_box)]
+    fn fold_children<F>(
+        &mut self,
+        predicates: Vec<Box<Predicate>>,
+        nested: F,
+    ) -> Vec<Box<Predicate>>
+    where
+        F: Fn(Predicate) -> Result<Vec<Box<Predicate>>, Predicate>,
+    {
+        let mut children = Vec::with_capacity(predicates.len());
+
+        for predicate in predicates {
+            match nested(self.fold_predicate(*predicate)) {
+                Ok(predicates) => children.extend(predicates),
+                Err(predicate) => children.push(Box::new(predicate)),
+            }
+        }
+
+        children.sort();
+        children.dedup();
+        children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_canonicalize() {
+        let testcases = vec![
+            (name("foo"), name("foo")),
+            (
+                all(vec![name("foo"), name("bar"), name("foo")]),
+                all(vec![name("bar"), name("foo")]),
+            ),
+            (
+                any(vec![
+                    name_value("target_os", "macos"),
+                    not(name("foo")),
+                    any(vec![name("bar"), name_value("target_os", "linux")]),
+                    all(vec![name("foo"), all(vec![name("bar")])]),
+                ]),
+                any(vec![
+                    all(vec![name("bar"), name("foo")]),
+                    not(name("foo")),
+                    name("bar"),
+                    name_value("target_os", "linux"),
+                    name_value("target_os", "macos"),
+                ]),
+            ),
+            (any(vec![]), Predicate::False),
+            (all(vec![]), Predicate::True),
+            (
+                all(vec![name("foo"), all(vec![]), Predicate::True]),
+                all(vec![name("foo")]),
+            ),
+            (
+                any(vec![any(vec![]), not(all(vec![any(vec![])]))]),
+                any(vec![not(all(vec![Predicate::False]))]),
+            ),
+        ];
+
+        for (predicate, canonical) in testcases {
+            assert_eq!(predicate.canonicalize(), canonical);
+        }
+    }
+
+    #[test]
+    fn test_eq_unordered() {
+        let lhs = all(vec![name("foo"), any(vec![name("bar"), name("baz")])]);
+        let rhs = all(vec![any(vec![name("baz"), name("bar")]), name("foo")]);
+
+        assert!(lhs.eq_unordered(&rhs));
+        assert!(!lhs.eq_unordered(&any(vec![name("foo"), name("bar")])));
+        assert_eq!(Canonical::new(lhs), Canonical::new(rhs));
+    }
+
+    #[test]
+    fn test_ordering() {
+        let mut predicates = vec![
+            Predicate::False,
+            Predicate::True,
+            name_value("target_os", "macos"),
+            name_value("target_os", "linux"),
+            name("foo"),
+            not(name("foo")),
+            all(vec![name("foo"), name("bar")]),
+            all(vec![name("foo")]),
+            any(vec![]),
+        ];
+
+        predicates.sort();
+
+        assert_eq!(
+            predicates,
+            vec![
+                any(vec![]),
+                all(vec![name("foo")]),
+                all(vec![name("foo"), name("bar")]),
+                not(name("foo")),
+                name("foo"),
+                name_value("target_os", "linux"),
+                name_value("target_os", "macos"),
+                Predicate::True,
+                Predicate::False,
+            ]
+        );
+    }
+
+    #[cfg(feature = "printing")]
+    #[test]
+    fn test_canonical_display() {
+        #[cfg(not(feature = "std"))]
+        use alloc::string::ToString;
+
+        let lhs = all(vec![
+            any(vec![name("b"), name("a")]),
+            name_value("target_os", "linux"),
+        ]);
+        let rhs = all(vec![
+            name_value("target_os", "linux"),
+            any(vec![name("a"), name("b"), name("a")]),
+        ]);
+
+        assert_eq!(
+            lhs.canonicalize().to_string(),
+            rhs.canonicalize().to_string()
+        );
+    }
+}
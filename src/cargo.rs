@@ -0,0 +1,248 @@
+//! The Cargo features of the packages in a workspace build, from the output of `cargo metadata`.
+//!
+//! Only the fields needed to resolve the features are deserialized,
+//! from the JSON printed by `cargo metadata --format-version 1`.
+//! The features of a package in the `resolve` graph are the features enabled after the feature unification,
+//! so they depend on the `--features`, `--all-features` or `--no-default-features` of the `cargo metadata` command.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::process::Command;
+//!
+//! use runtime_cfg::cargo::Metadata;
+//! use runtime_cfg::*;
+//!
+//! let output = Command::new("cargo")
+//!     .args(["metadata", "--format-version", "1"])
+//!     .output()
+//!     .unwrap();
+//! let metadata: Metadata = serde_json::from_slice(&output.stdout).unwrap();
+//! let serde = metadata.find_package("serde").unwrap();
+//!
+//! let predicate = all(vec![feature("std"), name("unix")]);
+//! let resolved = predicate.resolve_features(&metadata, &serde.id).unwrap();
+//!
+//! println!("{}", resolved.simplify());
+//! ```
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::string::String;
+        use alloc::vec::Vec;
+    }
+}
+
+use core::fmt;
+
+use crate::{fold::Fold, FlagSet, Predicate};
+
+/// The output of `cargo metadata`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ::serde::Deserialize)]
+pub struct Metadata {
+    /// The packages in the dependency graph.
+    pub packages: Vec<Package>,
+    /// The resolved dependency graph, which is missing with `--no-deps`.
+    #[serde(default)]
+    pub resolve: Option<Resolve>,
+}
+
+/// A package in the dependency graph.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ::serde::Deserialize)]
+pub struct Package {
+    /// The package ID.
+    pub id: String,
+    /// The package name.
+    pub name: String,
+    /// The package version.
+    pub version: String,
+}
+
+/// The resolved dependency graph.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ::serde::Deserialize)]
+pub struct Resolve {
+    /// The nodes of the packages.
+    pub nodes: Vec<Node>,
+}
+
+/// A node of a package in the resolved dependency graph.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ::serde::Deserialize)]
+pub struct Node {
+    /// The package ID.
+    pub id: String,
+    /// The features enabled for the package.
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+/// An error when resolving the features of a package.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MetadataError {
+    /// The metadata has no resolved dependency graph, like the output of `cargo metadata --no-deps`.
+    Unresolved,
+    /// The package ID isn't in the resolved dependency graph.
+    UnknownPackage(String),
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MetadataError::Unresolved => f.write_str("the dependency graph isn't resolved"),
+            MetadataError::UnknownPackage(id) => write!(f, "unknown package `{}`", id),
+        }
+    }
+}
+
+impl core::error::Error for MetadataError {}
+
+impl Metadata {
+    /// Returns the first package of the name.
+    ///
+    /// A package may have more than one version in the dependency graph, use [`Metadata::packages`] to choose one.
+    pub fn find_package(&self, name: &str) -> Option<&Package> {
+        self.packages.iter().find(|package| package.name == name)
+    }
+
+    /// Returns the features enabled for the package.
+    pub fn enabled_features(&self, package_id: &str) -> Result<&[String], MetadataError> {
+        self.resolve
+            .as_ref()
+            .ok_or(MetadataError::Unresolved)?
+            .nodes
+            .iter()
+            .find(|node| node.id == package_id)
+            .map(|node| node.features.as_slice())
+            .ok_or_else(|| MetadataError::UnknownPackage(package_id.into()))
+    }
+
+    /// Returns the flags of the features enabled for the package, as `feature = "name"`.
+    pub fn features(&self, package_id: &str) -> Result<FlagSet, MetadataError> {
+        let mut flags = FlagSet::new();
+
+        for feature in self.enabled_features(package_id)? {
+            flags.insert("feature", feature.as_str());
+        }
+
+        Ok(flags)
+    }
+}
+
+impl Predicate {
+    /// Returns a predicate with the `feature = "name"` leaves replaced by `True` or `False`,
+    /// whether the feature is enabled for the package in the metadata.
+    ///
+    /// The other leaves are left as is, and could be evaluated against the target flags later.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::cargo::Metadata;
+    /// use runtime_cfg::*;
+    ///
+    /// let metadata: Metadata = serde_json::from_str(r#"{
+    ///     "packages": [{"id": "serde 1.0.0", "name": "serde", "version": "1.0.0"}],
+    ///     "resolve": {"nodes": [{"id": "serde 1.0.0", "features": ["default", "std"]}]}
+    /// }"#).unwrap();
+    ///
+    /// let predicate = all(vec![feature("std"), any(vec![feature("alloc"), name("unix")])]);
+    /// let resolved = predicate.resolve_features(&metadata, "serde 1.0.0").unwrap();
+    ///
+    /// assert_eq!(resolved.simplify(), name("unix"));
+    /// ```
+    pub fn resolve_features(
+        &self,
+        metadata: &Metadata,
+        package_id: &str,
+    ) -> Result<Predicate, MetadataError> {
+        let features = metadata.enabled_features(package_id)?;
+
+        Ok(self.clone().fold(&mut ResolveFeatures { features }))
+    }
+}
+
+struct ResolveFeatures<'a> {
+    features: &'a [String],
+}
+
+impl Fold for ResolveFeatures<'_> {
+    fn fold_name_value(&mut self, name: String, value: String) -> Predicate {
+        if name != "feature" {
+            Predicate::NameValue(name, value)
+        } else if self.features.contains(&value) {
+            Predicate::True
+        } else {
+            Predicate::False
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::string::ToString;
+            use alloc::vec;
+        }
+    }
+
+    use crate::cargo::*;
+    use crate::*;
+
+    const METADATA: &str = r#"{
+        "packages": [
+            {"id": "app 0.1.0 (path+file:///app)", "name": "app", "version": "0.1.0", "features": {}},
+            {"id": "log 0.4.0 (registry+https://github.com/rust-lang/crates.io-index)", "name": "log", "version": "0.4.0"}
+        ],
+        "resolve": {
+            "nodes": [
+                {"id": "app 0.1.0 (path+file:///app)", "dependencies": [], "features": []},
+                {"id": "log 0.4.0 (registry+https://github.com/rust-lang/crates.io-index)", "features": ["std", "kv"]}
+            ],
+            "root": "app 0.1.0 (path+file:///app)"
+        },
+        "workspace_members": ["app 0.1.0 (path+file:///app)"]
+    }"#;
+
+    #[test]
+    fn test_resolve_features() {
+        let metadata: Metadata = serde_json::from_str(METADATA).unwrap();
+        let log = metadata.find_package("log").unwrap();
+
+        assert_eq!(log.version, "0.4.0");
+        assert_eq!(
+            metadata.enabled_features(&log.id).unwrap(),
+            ["std".to_string(), "kv".to_string()]
+        );
+        assert!(
+            all(vec![feature("std"), feature("kv")]).matches(&metadata.features(&log.id).unwrap())
+        );
+
+        let predicate = any(vec![
+            all(vec![feature("std"), name_value("target_os", "linux")]),
+            feature("serde"),
+        ]);
+
+        assert_eq!(
+            predicate.resolve_features(&metadata, &log.id).unwrap(),
+            any(vec![
+                all(vec![Predicate::True, name_value("target_os", "linux")]),
+                Predicate::False,
+            ])
+        );
+        assert_eq!(
+            predicate
+                .resolve_features(&metadata, "app 0.1.0 (path+file:///app)")
+                .unwrap()
+                .simplify(),
+            Predicate::False
+        );
+        assert_eq!(
+            predicate.resolve_features(&metadata, "serde"),
+            Err(MetadataError::UnknownPackage("serde".to_string()))
+        );
+
+        let metadata: Metadata = serde_json::from_str(r#"{"packages": []}"#).unwrap();
+
+        assert_eq!(metadata.features("log"), Err(MetadataError::Unresolved));
+    }
+}
@@ -0,0 +1,158 @@
+//! Collecting iterators of predicates into `any` or `all`.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::boxed::Box;
+        use alloc::vec::Vec;
+    }
+}
+
+use core::iter::{Extend, FromIterator};
+
+use crate::{Cfg, Predicate};
+
+/// A builder to collect predicates into `Predicate::Any`.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let predicate: AnyOf = vec!["foo", "bar"].into_iter().map(name).collect();
+///
+/// assert_eq!(Predicate::from(predicate), any(vec![name("foo"), name("bar")]));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct AnyOf(Vec<Predicate>);
+
+/// A builder to collect predicates into `Predicate::All`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct AllOf(Vec<Predicate>);
+
+macro_rules! impl_builder {
+    ($builder:ident, $variant:ident) => {
+        impl $builder {
+            /// Creates an empty builder.
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            /// Appends a sub-predicate.
+            pub fn push(&mut self, predicate: Predicate) -> &mut Self {
+                self.0.push(predicate);
+                self
+            }
+
+            /// Builds the predicate.
+            pub fn build(self) -> Predicate {
+                Predicate::$variant(self.0.into_iter().map(Box::new).collect())
+            }
+        }
+
+        impl FromIterator<Predicate> for $builder {
+            fn from_iter<I: IntoIterator<Item = Predicate>>(iter: I) -> Self {
+                $builder(iter.into_iter().collect())
+            }
+        }
+
+        impl Extend<Predicate> for $builder {
+            fn extend<I: IntoIterator<Item = Predicate>>(&mut self, iter: I) {
+                self.0.extend(iter)
+            }
+        }
+
+        impl From<$builder> for Predicate {
+            fn from(builder: $builder) -> Self {
+                builder.build()
+            }
+        }
+
+        impl From<$builder> for Cfg {
+            fn from(builder: $builder) -> Self {
+                Cfg(builder.build())
+            }
+        }
+    };
+}
+
+impl_builder!(AnyOf, Any);
+impl_builder!(AllOf, All);
+
+/// Collects predicates into `Predicate::All`.
+impl FromIterator<Predicate> for Predicate {
+    fn from_iter<I: IntoIterator<Item = Predicate>>(iter: I) -> Self {
+        Predicate::All(iter.into_iter().map(Box::new).collect())
+    }
+}
+
+/// Collects predicates into `#[cfg(all(..))]`.
+impl FromIterator<Predicate> for Cfg {
+    fn from_iter<I: IntoIterator<Item = Predicate>>(iter: I) -> Self {
+        Cfg(iter.into_iter().collect())
+    }
+}
+
+/// Appends sub-predicates to `any` or `all`.
+///
+/// Any other predicate is first wrapped into an `all` with itself as the only sub-predicate.
+impl Extend<Predicate> for Predicate {
+    fn extend<I: IntoIterator<Item = Predicate>>(&mut self, iter: I) {
+        match self {
+            Predicate::Any(predicates) | Predicate::All(predicates) => {
+                predicates.extend(iter.into_iter().map(Box::new))
+            }
+            _ => {
+                let predicate = core::mem::replace(self, Predicate::All(Vec::new()));
+
+                *self = Some(predicate).into_iter().chain(iter).collect();
+            }
+        }
+    }
+}
+
+impl Extend<Predicate> for Cfg {
+    fn extend<I: IntoIterator<Item = Predicate>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_collect() {
+        let predicate: Predicate = vec![name("foo"), name("bar")].into_iter().collect();
+        assert_eq!(predicate, all(vec![name("foo"), name("bar")]));
+
+        let cfg: Cfg = vec![name("foo")].into_iter().collect();
+        assert_eq!(cfg, all(vec![name("foo")]).into());
+
+        let predicate: AnyOf = vec![name("foo"), name("bar")].into_iter().collect();
+        assert_eq!(predicate.build(), any(vec![name("foo"), name("bar")]));
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut predicate = any(vec![name("foo")]);
+        predicate.extend(vec![name("bar")]);
+        assert_eq!(predicate, any(vec![name("foo"), name("bar")]));
+
+        let mut predicate = name("foo");
+        predicate.extend(vec![name("bar")]);
+        assert_eq!(predicate, all(vec![name("foo"), name("bar")]));
+
+        let mut builder = AllOf::new();
+        builder.push(name("foo")).extend(vec![name("bar")]);
+        assert_eq!(
+            Predicate::from(builder),
+            all(vec![name("foo"), name("bar")])
+        );
+    }
+}
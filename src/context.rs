@@ -0,0 +1,207 @@
+//! The context passed through an evaluation, for the extension predicates and tracing.
+
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::collections::BTreeMap;
+    } else {
+        use alloc::boxed::Box;
+        use alloc::collections::BTreeMap;
+        use alloc::string::String;
+        use alloc::vec::Vec;
+    }
+}
+
+use core::fmt;
+
+use crate::{rollout::in_rollout, Pattern, Predicate, ROLLOUT_KEY};
+
+type Extension = Box<dyn Fn(Option<&str>, &EvalContext) -> bool + Send + Sync>;
+type Tracer = Box<dyn Fn(&str, Option<&str>, bool) + Send + Sync>;
+
+/// The context of an evaluation, carrying the stable ID, the clock, the locale, the extension predicates and the tracer.
+///
+/// The leaves are evaluated by the extension of their key if any, then the rollout leaves by the stable ID,
+/// and then the other leaves by the pattern. The context is passed explicitly,
+/// so the same pattern could be evaluated in different contexts at the same time.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+///
+/// use runtime_cfg::*;
+///
+/// let traced = Arc::new(Mutex::new(Vec::new()));
+/// let ctx = EvalContext::new()
+///     .with_stable_id("user-42")
+///     .with_locale("fr-FR")
+///     .with_now(1_700_000_000)
+///     .with_extension("locale", |value, ctx| value == ctx.locale())
+///     .with_extension("after", |value, ctx| {
+///         matches!((value.and_then(|v| v.parse::<u64>().ok()), ctx.now()), (Some(t), Some(now)) if now >= t)
+///     })
+///     .with_tracer({
+///         let traced = traced.clone();
+///
+///         move |key, _, matched| traced.lock().unwrap().push((key.to_owned(), matched))
+///     });
+///
+/// let predicate = all(vec![
+///     name("unix"),
+///     name_value("locale", "fr-FR"),
+///     name_value("after", "1600000000"),
+///     rollout("new-ui", 100),
+/// ]);
+///
+/// assert!(predicate.matches_in(&vec![("unix", None::<&str>)], &ctx));
+/// assert_eq!(traced.lock().unwrap().len(), 4);
+/// ```
+#[derive(Default)]
+pub struct EvalContext {
+    stable_id: Option<String>,
+    now: Option<u64>,
+    locale: Option<String>,
+    extensions: BTreeMap<String, Extension>,
+    tracer: Option<Tracer>,
+}
+
+impl fmt::Debug for EvalContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EvalContext")
+            .field("stable_id", &self.stable_id)
+            .field("now", &self.now)
+            .field("locale", &self.locale)
+            .field("extensions", &self.extensions.keys().collect::<Vec<_>>())
+            .field("tracer", &self.tracer.is_some())
+            .finish()
+    }
+}
+
+impl EvalContext {
+    /// Creates an empty context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the stable ID, like the user or request ID, for the rollouts.
+    pub fn with_stable_id<S: Into<String>>(mut self, id: S) -> Self {
+        self.stable_id = Some(id.into());
+        self
+    }
+
+    /// Sets the current time, as the seconds since the Unix epoch.
+    pub fn with_now(mut self, now: u64) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    /// Sets the locale, like `en-US`.
+    pub fn with_locale<S: Into<String>>(mut self, locale: S) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
+    /// Registers an extension predicate evaluating the leaves of the key, with the value and the context.
+    pub fn with_extension<S, F>(mut self, key: S, f: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(Option<&str>, &EvalContext) -> bool + Send + Sync + 'static,
+    {
+        self.extensions.insert(key.into(), Box::new(f));
+        self
+    }
+
+    /// Sets the tracer, called with the key, the value and the result of each evaluated leaf.
+    pub fn with_tracer<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str, Option<&str>, bool) + Send + Sync + 'static,
+    {
+        self.tracer = Some(Box::new(f));
+        self
+    }
+
+    /// Returns the stable ID.
+    pub fn stable_id(&self) -> Option<&str> {
+        self.stable_id.as_deref()
+    }
+
+    /// Returns the current time, as the seconds since the Unix epoch.
+    pub fn now(&self) -> Option<u64> {
+        self.now
+    }
+
+    /// Returns the locale.
+    pub fn locale(&self) -> Option<&str> {
+        self.locale.as_deref()
+    }
+}
+
+/// A pattern evaluating the leaves in the context.
+struct InContext<'a, P: ?Sized> {
+    pattern: &'a P,
+    ctx: &'a EvalContext,
+}
+
+impl<P: Pattern + ?Sized> Pattern for InContext<'_, P> {
+    fn matches(&self, key: &str, value: Option<&str>) -> bool {
+        let matched = match (self.ctx.extensions.get(key), value) {
+            (Some(extension), _) => extension(value, self.ctx),
+            (None, Some(value)) if key == ROLLOUT_KEY => {
+                self.ctx.stable_id().is_some_and(|id| in_rollout(value, id))
+            }
+            _ => self.pattern.matches(key, value),
+        };
+
+        if let Some(ref tracer) = self.ctx.tracer {
+            tracer(key, value, matched);
+        }
+
+        matched
+    }
+}
+
+impl Predicate {
+    /// Returns `true` if configuration matches the predicate, evaluated in the context.
+    pub fn matches_in<P: Pattern + ?Sized>(&self, pattern: &P, ctx: &EvalContext) -> bool {
+        self.matches(&InContext { pattern, ctx })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::*;
+
+    #[test]
+    fn test_matches_in() {
+        static TRACED: AtomicUsize = AtomicUsize::new(0);
+
+        let flags = vec![("unix", None), ("rollout", Some("beta:0"))];
+        let predicate = any(vec![rollout("beta", 0), name("windows")]);
+
+        assert!(predicate.matches(&flags));
+        assert!(!predicate.matches_in(&flags, &EvalContext::new()));
+        assert!(!predicate.matches_in(&flags, &EvalContext::new().with_stable_id("user-1")));
+        assert!(
+            rollout("beta", 100).matches_in(&flags, &EvalContext::new().with_stable_id("user-1"))
+        );
+
+        let ctx = EvalContext::new()
+            .with_extension("windows", |_, ctx| ctx.stable_id() == Some("admin"))
+            .with_tracer(|_, _, _| {
+                TRACED.fetch_add(1, Ordering::Relaxed);
+            });
+
+        assert!(!predicate.matches_in(&flags, &ctx));
+        assert_eq!(TRACED.load(Ordering::Relaxed), 2);
+        assert!(name("unix").matches_in(&flags, &ctx));
+        assert!(predicate.matches_in(&flags, &ctx.with_stable_id("admin")));
+    }
+}
@@ -0,0 +1,148 @@
+//! Evaluation costs of the flags, to evaluate the cheap sub-predicates first.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::boxed::Box;
+        use alloc::vec::Vec;
+    }
+}
+
+use crate::{fold::Fold, Predicate};
+
+/// A cost model estimating the cost of querying a flag from a pattern.
+pub trait CostModel {
+    /// Returns the cost of querying the flag, with the value if any.
+    fn cost(&self, key: &str, value: Option<&str>) -> u32;
+}
+
+impl<F> CostModel for F
+where
+    F: Fn(&str, Option<&str>) -> u32,
+{
+    fn cost(&self, key: &str, value: Option<&str>) -> u32 {
+        self(key, value)
+    }
+}
+
+impl Predicate {
+    /// Returns the total cost of querying all the flags of the predicate.
+    pub fn cost<M: CostModel + ?Sized>(&self, model: &M) -> u64 {
+        match self {
+            Predicate::Any(predicates) | Predicate::All(predicates) => predicates
+                .iter()
+                .map(|predicate| predicate.cost(model))
+                .fold(0, u64::saturating_add),
+            Predicate::Not(predicate) => predicate.cost(model),
+            Predicate::Name(name) => model.cost(name, None).into(),
+            Predicate::NameValue(name, value) => model.cost(name, Some(value)).into(),
+            Predicate::True | Predicate::False | Predicate::Param(_) => 0,
+        }
+    }
+
+    /// Returns a logically equivalent predicate, whose sub-predicates of `any` and `all`
+    /// are sorted by their costs, so the cheap ones are evaluated first and the expensive ones may be short-circuited.
+    ///
+    /// The sub-predicates of the same cost keep their order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// // the remote flags pay a network call per query.
+    /// let model = |key: &str, _: Option<&str>| if key.starts_with("remote_") { 100 } else { 1 };
+    ///
+    /// let predicate = all(vec![
+    ///     name("remote_rollout"),
+    ///     any(vec![name("remote_beta"), feature("beta")]),
+    ///     name("unix"),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     predicate.reorder_for(&model),
+    ///     all(vec![
+    ///         name("unix"),
+    ///         name("remote_rollout"),
+    ///         any(vec![feature("beta"), name("remote_beta")]),
+    ///     ])
+    /// );
+    /// ```
+    pub fn reorder_for<M: CostModel + ?Sized>(&self, model: &M) -> Predicate {
+        self.clone().fold(&mut Reorder(model))
+    }
+}
+
+struct Reorder<'a, M: ?Sized>(&'a M);
+
+impl<M: CostModel + ?Sized> Fold for Reorder<'_, M> {
+    fn fold_any(&mut self, predicates: Vec<Box<Predicate>>) -> Predicate {
+        Predicate::Any(self.reorder(predicates))
+    }
+
+    fn fold_all(&mut self, predicates: Vec<Box<Predicate>>) -> Predicate {
+        Predicate::All(self.reorder(predicates))
+    }
+}
+
+impl<M: CostModel + ?Sized> Reorder<'_, M> {
+    #[allow(clippy::vec_box)]
+    fn reorder(&mut self, predicates: Vec<Box<Predicate>>) -> Vec<Box<Predicate>> {
+        let mut predicates = predicates
+            .into_iter()
+            .map(|predicate| Box::new(self.fold_predicate(*predicate)))
+            .collect::<Vec<_>>();
+
+        predicates.sort_by_cached_key(|predicate| predicate.cost(self.0));
+        predicates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+            use alloc::vec::Vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_reorder_for() {
+        let model = |key: &str, value: Option<&str>| match (key, value) {
+            ("remote", _) => 100,
+            ("feature", Some(_)) => 10,
+            _ => 1,
+        };
+
+        let predicate = any(vec![
+            name("remote"),
+            not(all(vec![feature("beta"), name("unix"), name("remote")])),
+            name("windows"),
+            Predicate::False,
+        ]);
+
+        assert_eq!(predicate.cost(&model), 212);
+
+        let reordered = predicate.reorder_for(&model);
+
+        assert_eq!(
+            reordered,
+            any(vec![
+                Predicate::False,
+                name("windows"),
+                name("remote"),
+                not(all(vec![name("unix"), feature("beta"), name("remote")])),
+            ])
+        );
+
+        let flags = Recording::new(vec![("windows", None::<&str>)]);
+
+        assert!(reordered.matches(&flags));
+        assert_eq!(
+            flags.keys().into_iter().collect::<Vec<_>>(),
+            vec!["windows"]
+        );
+    }
+}
@@ -0,0 +1,730 @@
+//! Annotated diagnostics of the parse errors and lints, reported with `miette`.
+//!
+//! The diagnostics carry the source, the byte span of the offending predicate,
+//! a label and an optional help text.
+//!
+//! # Example
+//!
+//! ```
+//! use runtime_cfg::diagnostics;
+//!
+//! let err = diagnostics::parse("cfg(target_os = linux)").unwrap_err();
+//!
+//! assert_eq!(err.to_string(), "expected a literal value");
+//! assert_eq!(err.span(), 16..21);
+//! assert_eq!(err.help(), Some(r#"quote this value: `target_os = "linux"`"#));
+//!
+//! let lints = diagnostics::lint("cfg(all(unix, not(not(windows))))").unwrap();
+//!
+//! assert_eq!(lints.len(), 1);
+//! assert_eq!(lints[0].to_string(), "double negation is redundant");
+//! assert_eq!(lints[0].span(), 14..31);
+//! assert_eq!(lints[0].help(), Some("replace with `windows`"));
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use std::ptr;
+
+use miette::{LabeledSpan, Severity, SourceCode};
+
+use crate::{Cfg, LintKind, Predicate};
+
+/// A diagnostic with the source, the span of the offending predicate, a label and an optional help text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    severity: Severity,
+    message: String,
+    source: String,
+    span: Range<usize>,
+    label: &'static str,
+    help: Option<String>,
+}
+
+impl Diagnostic {
+    /// Returns the description of the diagnostic.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the source of the configuration.
+    pub fn source_code(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the byte span of the offending predicate in the source.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Returns the label of the span.
+    pub fn label(&self) -> &str {
+        self.label
+    }
+
+    /// Returns the help text, if any.
+    pub fn help(&self) -> Option<&str> {
+        self.help.as_deref()
+    }
+
+    /// Returns `true` if the diagnostic is an error, otherwise it is a warning.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for Diagnostic {}
+
+impl miette::Diagnostic for Diagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(if self.is_error() {
+            "runtime_cfg::parse"
+        } else {
+            "runtime_cfg::lint"
+        }))
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        Some(self.severity)
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.help
+            .as_ref()
+            .map(|help| Box::new(help) as Box<dyn fmt::Display>)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(std::iter::once(LabeledSpan::new(
+            Some(self.label.to_owned()),
+            self.span.start,
+            self.span.len(),
+        ))))
+    }
+}
+
+/// Parses the configuration, or returns a diagnostic locating the error.
+pub fn parse(source: &str) -> Result<Cfg, Diagnostic> {
+    parse_with_spans(source).map(|(cfg, _)| cfg)
+}
+
+/// Parses the configuration and returns a diagnostic for each lint, or a diagnostic locating the parse error.
+pub fn lint(source: &str) -> Result<Vec<Diagnostic>, Diagnostic> {
+    let (cfg, spans) = parse_with_spans(source)?;
+
+    let mut nodes = Vec::new();
+
+    preorder(&cfg, &mut nodes);
+
+    Ok(cfg
+        .lint()
+        .into_iter()
+        .map(|lint| {
+            let span = nodes
+                .iter()
+                .position(|node| ptr::eq(*node, lint.predicate))
+                .and_then(|idx| spans.as_ref().and_then(|spans| spans.get(idx)))
+                .cloned()
+                .unwrap_or(0..source.len());
+
+            Diagnostic {
+                severity: Severity::Warning,
+                message: lint.kind.message().to_owned(),
+                source: source.to_owned(),
+                span,
+                label: "this predicate",
+                help: Some(match lint.kind {
+                    LintKind::Nested => "move its predicates into the parent".to_owned(),
+                    LintKind::Duplicate => "remove this predicate".to_owned(),
+                    LintKind::Contradiction => "replace the parent with `any()`".to_owned(),
+                    LintKind::Tautology => "replace the parent with `all()`".to_owned(),
+                    _ => format!("replace with `{}`", lint.predicate.simplify()),
+                }),
+            }
+        })
+        .collect())
+}
+
+/// Parses the configuration with the spans of the predicates in the pre-order,
+/// the spans are unavailable if the light scanner can't follow the syntax.
+fn parse_with_spans(source: &str) -> Result<(Cfg, Option<Spans>), Diagnostic> {
+    let scanned = Scanner::new(source).scan();
+
+    match (Cfg::parse(source), scanned) {
+        (Ok(cfg), Ok(spans)) => Ok((cfg, Some(spans))),
+        (Ok(cfg), Err(_)) => Ok((cfg, None)),
+        (Err(_), Err(located)) => Err(Diagnostic {
+            severity: Severity::Error,
+            message: located.message,
+            source: source.to_owned(),
+            span: located.span,
+            label: located.label,
+            help: located.help,
+        }),
+        (Err(err), Ok(_)) => Err(Diagnostic {
+            severity: Severity::Error,
+            message: err.to_string(),
+            source: source.to_owned(),
+            span: 0..source.len(),
+            label: "invalid configuration",
+            help: None,
+        }),
+    }
+}
+
+type Spans = Vec<Range<usize>>;
+
+fn preorder<'a>(predicate: &'a Predicate, nodes: &mut Vec<&'a Predicate>) {
+    nodes.push(predicate);
+
+    match predicate {
+        Predicate::Any(predicates) | Predicate::All(predicates) => {
+            for predicate in predicates {
+                preorder(predicate, nodes);
+            }
+        }
+        Predicate::Not(predicate) => preorder(predicate, nodes),
+        _ => {}
+    }
+}
+
+/// A located error of the light scanner.
+struct Located {
+    span: Range<usize>,
+    message: String,
+    label: &'static str,
+    help: Option<String>,
+}
+
+impl Located {
+    fn new<S: Into<String>>(span: Range<usize>, message: S, label: &'static str) -> Self {
+        Located {
+            span,
+            message: message.into(),
+            label,
+            help: None,
+        }
+    }
+
+    fn help<S: Into<String>>(mut self, help: S) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+/// A light scanner following the configuration syntax, to locate the predicates and errors.
+struct Scanner<'a> {
+    src: &'a str,
+    pos: usize,
+    depth: usize,
+    spans: Vec<Range<usize>>,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(src: &'a str) -> Self {
+        Scanner {
+            src,
+            pos: 0,
+            depth: 0,
+            spans: Vec::new(),
+        }
+    }
+
+    fn scan(mut self) -> Result<Spans, Located> {
+        self.skip_ws();
+
+        if self.eat('#') {
+            self.skip_ws();
+            self.eat('!');
+            self.skip_ws();
+            self.expect('[')?;
+            self.cfg()?;
+            self.skip_ws();
+            self.expect(']')?;
+        } else {
+            self.cfg()?;
+        }
+
+        self.skip_ws();
+
+        if self.pos < self.src.len() {
+            return Err(Located::new(
+                self.pos..self.src.len(),
+                "unexpected trailing input",
+                "remove this",
+            ));
+        }
+
+        Ok(self.spans)
+    }
+
+    fn cfg(&mut self) -> Result<(), Located> {
+        self.skip_ws();
+
+        let start = self.pos;
+
+        match self.ident() {
+            Some("cfg") => {}
+            _ => {
+                return Err(Located::new(
+                    start..self.src.len(),
+                    "expected `cfg(..)`",
+                    "not a configuration",
+                )
+                .help(format!(
+                    "wrap the predicate as `cfg({})`",
+                    self.src[start..].trim()
+                )))
+            }
+        }
+
+        self.skip_ws();
+
+        let open = self.pos;
+
+        self.expect('(')?;
+        self.single(start, "cfg")?;
+        self.close(open)
+    }
+
+    /// Scans a predicate, and records its span.
+    fn predicate(&mut self) -> Result<(), Located> {
+        self.skip_ws();
+
+        #[cfg(feature = "no-panic")]
+        {
+            if self.depth >= crate::MAX_NESTING {
+                return Err(Located::new(
+                    self.pos..self.next_boundary(),
+                    format!("predicate nested deeper than {}", crate::MAX_NESTING),
+                    "nested too deep",
+                ));
+            }
+        }
+
+        self.depth += 1;
+
+        let scanned = self.operand();
+
+        self.depth -= 1;
+
+        scanned
+    }
+
+    fn operand(&mut self) -> Result<(), Located> {
+        let start = self.pos;
+
+        if self.peek_literal() {
+            let literal = self.literal()?;
+
+            return Err(
+                Located::new(literal, "unexpected literal", "expected a predicate").help(
+                    "a predicate is a name, `name = \"value\"` or an operator like `all(..)`",
+                ),
+            );
+        }
+
+        let name = match self.ident() {
+            Some(name) => name,
+            None => {
+                return Err(Located::new(
+                    start..self.next_boundary(),
+                    "expected a predicate",
+                    "expected a predicate",
+                ))
+            }
+        };
+
+        if name == "true" || name == "false" {
+            self.spans.push(start..self.pos);
+            return Ok(());
+        }
+
+        let ident_end = self.pos;
+
+        self.skip_ws();
+
+        if self.peek('(') {
+            let open = self.pos;
+
+            self.pos += 1;
+
+            match name {
+                "cfg" => {
+                    self.single(start, name)?;
+                }
+                "any" | "all" | "not" => {
+                    let idx = self.spans.len();
+
+                    self.spans.push(start..start);
+
+                    let count = self.list()?;
+
+                    self.close(open)?;
+                    self.spans[idx] = start..self.pos;
+
+                    if name == "not" && count != 1 {
+                        return Err(Located::new(
+                            start..self.pos,
+                            "`not(..)` expects exactly one predicate",
+                            "expected one predicate",
+                        ));
+                    }
+
+                    return Ok(());
+                }
+                "param" => {
+                    self.skip_ws();
+
+                    if self.ident().is_none() {
+                        return Err(Located::new(
+                            start..self.next_boundary(),
+                            "`param(..)` expects a parameter name",
+                            "expected a name",
+                        ));
+                    }
+                }
+                _ => {
+                    return Err(Located::new(
+                        start..ident_end,
+                        format!("unexpected operator `{}`", name),
+                        "unknown operator",
+                    )
+                    .help("expected `any`, `all` or `not`"))
+                }
+            }
+
+            self.close(open)?;
+
+            if name == "param" {
+                self.spans.push(start..self.pos);
+            }
+        } else if self.eat('=') {
+            self.skip_ws();
+
+            let value_start = self.pos;
+
+            if self.peek_literal() {
+                self.literal()?;
+            } else if let Some(value) = self.ident() {
+                return Err(Located::new(
+                    value_start..self.pos,
+                    "expected a literal value",
+                    "not a literal",
+                )
+                .help(format!("quote this value: `{} = \"{}\"`", name, value)));
+            } else {
+                return Err(Located::new(
+                    start..self.pos,
+                    "expected a value",
+                    "missing value",
+                ));
+            }
+
+            self.spans.push(start..self.pos);
+        } else {
+            self.spans.push(start..ident_end);
+        }
+
+        Ok(())
+    }
+
+    /// Scans a single predicate of `cfg(..)`, which is not a node itself.
+    fn single(&mut self, start: usize, name: &str) -> Result<(), Located> {
+        let count = self.list()?;
+
+        if count != 1 {
+            return Err(Located::new(
+                start..self.pos,
+                format!("`{}(..)` expects exactly one predicate", name),
+                "expected one predicate",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Scans a comma separated list of predicates, returns the number of predicates.
+    fn list(&mut self) -> Result<usize, Located> {
+        let mut count = 0;
+
+        loop {
+            self.skip_ws();
+
+            if self.peek(')') || self.pos >= self.src.len() {
+                return Ok(count);
+            }
+
+            self.predicate()?;
+            count += 1;
+            self.skip_ws();
+
+            if !self.eat(',') {
+                return Ok(count);
+            }
+        }
+    }
+
+    fn close(&mut self, open: usize) -> Result<(), Located> {
+        self.skip_ws();
+
+        if self.eat(')') {
+            Ok(())
+        } else if self.pos >= self.src.len() {
+            Err(Located::new(
+                open..open + 1,
+                "unclosed delimiter",
+                "unclosed",
+            ))
+        } else {
+            Err(Located::new(
+                self.pos..self.next_boundary(),
+                "expected `,` or `)`",
+                "unexpected token",
+            ))
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = &self.src[self.pos..];
+
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self, c: char) -> bool {
+        self.src[self.pos..].starts_with(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Located> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(Located::new(
+                self.pos..self.next_boundary(),
+                format!("expected `{}`", c),
+                "unexpected token",
+            ))
+        }
+    }
+
+    fn ident(&mut self) -> Option<&'a str> {
+        let src = self.src;
+        let rest = &src[self.pos..];
+        let rest = rest.strip_prefix("r#").unwrap_or(rest);
+        let prefix = src.len() - self.pos - rest.len();
+
+        if !rest.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+            return None;
+        }
+
+        let len = rest
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(rest.len());
+        let start = self.pos + prefix;
+
+        self.pos = start + len;
+
+        Some(&src[start..self.pos])
+    }
+
+    fn peek_literal(&self) -> bool {
+        let rest = &self.src[self.pos..];
+
+        rest.starts_with(|c: char| c == '"' || c == '\'' || c.is_ascii_digit() || c == '-')
+            || rest.starts_with("r\"")
+            || rest.starts_with("r#\"")
+            || rest.starts_with("r##")
+            || rest.starts_with("b\"")
+            || rest.starts_with("b'")
+            || rest.starts_with("br")
+    }
+
+    fn literal(&mut self) -> Result<Range<usize>, Located> {
+        let start = self.pos;
+        let rest = &self.src[self.pos..];
+        let rest = rest.strip_prefix('b').unwrap_or(rest);
+
+        let len = if let Some(raw) = rest.strip_prefix('r') {
+            let hashes = raw.len() - raw.trim_start_matches('#').len();
+            let terminator = format!("\"{}", "#".repeat(hashes));
+
+            raw[hashes + 1..]
+                .find(&terminator)
+                .map(|end| 1 + hashes + 1 + end + terminator.len())
+        } else if let Some(quote) = rest.chars().next().filter(|&c| c == '"' || c == '\'') {
+            let mut escaped = false;
+
+            rest[1..]
+                .char_indices()
+                .find(|&(_, c)| {
+                    let end = !escaped && c == quote;
+
+                    escaped = !escaped && c == '\\';
+                    end
+                })
+                .map(|(end, _)| 1 + end + 1)
+        } else {
+            Some(
+                rest.find(|c: char| !c.is_alphanumeric() && c != '_' && c != '.' && c != '-')
+                    .unwrap_or(rest.len()),
+            )
+        };
+
+        match len {
+            Some(len) => {
+                self.pos = self.src.len() - rest.len() + len;
+
+                Ok(start..self.pos)
+            }
+            None => Err(Located::new(
+                start..self.src.len(),
+                "unterminated literal",
+                "unterminated",
+            )),
+        }
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.src[self.pos..]
+            .char_indices()
+            .nth(1)
+            .map_or(self.src.len(), |(idx, _)| self.pos + idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> (String, Range<usize>, Option<String>) {
+        let err = parse(source).unwrap_err();
+
+        assert!(err.is_error());
+
+        (err.to_string(), err.span(), err.help().map(str::to_owned))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse(r#"#[cfg(all(unix, target_pointer_width = 32, param(x)))]"#).unwrap(),
+            Cfg::from(crate::all(vec![
+                crate::name("unix"),
+                crate::name_value("target_pointer_width", "32"),
+                crate::param("x"),
+            ]))
+        );
+        assert_eq!(
+            error("unix"),
+            (
+                "expected `cfg(..)`".to_owned(),
+                0..4,
+                Some("wrap the predicate as `cfg(unix)`".to_owned())
+            )
+        );
+        assert_eq!(
+            error("cfg(any(unix, windows)"),
+            ("unclosed delimiter".to_owned(), 3..4, None)
+        );
+        assert_eq!(
+            error("cfg(not(unix, windows))"),
+            (
+                "`not(..)` expects exactly one predicate".to_owned(),
+                4..22,
+                None
+            )
+        );
+        assert_eq!(
+            error("cfg(either(unix))"),
+            (
+                "unexpected operator `either`".to_owned(),
+                4..10,
+                Some("expected `any`, `all` or `not`".to_owned())
+            )
+        );
+        assert_eq!(
+            error(r#"cfg(all("unix"))"#),
+            (
+                "unexpected literal".to_owned(),
+                8..14,
+                Some(
+                    "a predicate is a name, `name = \"value\"` or an operator like `all(..)`"
+                        .to_owned()
+                )
+            )
+        );
+        assert_eq!(
+            error(r#"cfg(feature = "a) "#),
+            ("unterminated literal".to_owned(), 14..18, None)
+        );
+        assert_eq!(
+            error("cfg(unix) extra"),
+            ("unexpected trailing input".to_owned(), 10..15, None)
+        );
+    }
+
+    #[test]
+    fn test_lint() {
+        let source = r#"cfg(any(unix, any(windows), unix, not(unix)))"#;
+        let lints = lint(source).unwrap();
+
+        assert_eq!(
+            lints
+                .iter()
+                .map(|lint| (&source[lint.span()], lint.message()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "any(windows)",
+                    "nested `any` or `all` of the same kind could be flattened"
+                ),
+                (&source[28..32], "duplicated predicate is redundant"),
+                (
+                    "not(unix)",
+                    "`any` with a predicate and its negation always matches"
+                ),
+                (
+                    "any(windows)",
+                    "`any` or `all` with a single predicate is redundant"
+                ),
+            ]
+        );
+        assert!(lints.iter().all(|lint| !lint.is_error()));
+        assert_eq!(lints[1].help(), Some("remove this predicate"));
+        assert_eq!(lints[2].help(), Some("replace the parent with `all()`"));
+        assert_eq!(lints[3].help(), Some("replace with `windows`"));
+    }
+
+    #[cfg(feature = "no-panic")]
+    #[test]
+    fn test_parse_too_deep() {
+        let nested = |n| "cfg(".to_owned() + &"not(".repeat(n) + "unix" + &")".repeat(n + 1);
+
+        assert!(parse(&nested(crate::MAX_NESTING - 1)).is_ok());
+        assert_eq!(
+            error(&nested(crate::MAX_NESTING)).0,
+            format!("predicate nested deeper than {}", crate::MAX_NESTING)
+        );
+        assert!(parse(&nested(200_000)).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+//! Graphviz DOT export of the predicate tree.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::string::{String, ToString};
+        use alloc::vec;
+    }
+}
+
+use core::fmt::Write;
+
+use crate::Predicate;
+
+impl Predicate {
+    /// Returns a Graphviz DOT graph of the predicate tree.
+    ///
+    /// The `all` nodes are drawn as boxes, the `any` nodes as ellipses,
+    /// the `not` nodes as diamonds, and the leaves as plain text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let predicate = all(vec![name("unix"), not(name_value("target_env", "musl"))]);
+    ///
+    /// assert_eq!(
+    ///     predicate.to_dot(),
+    ///     r#"digraph cfg {
+    ///     n0 [label="all", shape=box];
+    ///     n1 [label="unix", shape=plaintext];
+    ///     n0 -> n1;
+    ///     n2 [label="not", shape=diamond];
+    ///     n3 [label="target_env = \"musl\"", shape=plaintext];
+    ///     n2 -> n3;
+    ///     n0 -> n2;
+    /// }
+    /// "#
+    /// );
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cfg {\n");
+        let mut next = 0;
+
+        write_node(self, &mut dot, &mut next);
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn write_node(predicate: &Predicate, dot: &mut String, next: &mut usize) -> usize {
+    use Predicate::*;
+
+    let id = *next;
+
+    *next += 1;
+
+    let (label, shape) = match predicate {
+        Any(_) => ("any".to_string(), "ellipse"),
+        All(_) => ("all".to_string(), "box"),
+        Not(_) => ("not".to_string(), "diamond"),
+        _ => (predicate.to_string(), "plaintext"),
+    };
+
+    let _ = writeln!(
+        dot,
+        "    n{} [label=\"{}\", shape={}];",
+        id,
+        label.replace('\\', "\\\\").replace('"', "\\\""),
+        shape
+    );
+
+    let children = match predicate {
+        Any(predicates) | All(predicates) => predicates.iter().map(|p| &**p).collect(),
+        Not(predicate) => vec![&**predicate],
+        _ => vec![],
+    };
+
+    for child in children {
+        let child = write_node(child, dot, next);
+
+        let _ = writeln!(dot, "    n{} -> n{};", id, child);
+    }
+
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_to_dot() {
+        assert_eq!(
+            any(vec![Predicate::True, name_value("foo", r#"a"b\c"#)]).to_dot(),
+            r#"digraph cfg {
+    n0 [label="any", shape=ellipse];
+    n1 [label="all()", shape=plaintext];
+    n0 -> n1;
+    n2 [label="foo = \"a\\\"b\\\\c\"", shape=plaintext];
+    n0 -> n2;
+}
+"#
+        );
+    }
+}
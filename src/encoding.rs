@@ -0,0 +1,375 @@
+//! Compact binary encoding of predicates.
+//!
+//! Each predicate is encoded as a tag byte followed by its contents:
+//!
+//! | Tag | Predicate   | Contents                                  |
+//! |-----|-------------|-------------------------------------------|
+//! | 0   | `any`       | the number of sub-predicates, and them    |
+//! | 1   | `all`       | the number of sub-predicates, and them    |
+//! | 2   | `not`       | the sub-predicate                         |
+//! | 3   | `Name`      | the name                                  |
+//! | 4   | `NameValue` | the name and value                        |
+//! | 5   | `True`      |                                           |
+//! | 6   | `False`     |                                           |
+//! | 7   | `Param`     | the parameter name                        |
+//!
+//! The numbers are encoded as unsigned LEB128,
+//! and the strings are encoded as their lengths in bytes followed by the UTF-8 bytes.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::boxed::Box;
+        use alloc::string::String;
+        use alloc::vec::Vec;
+    }
+}
+
+use core::fmt;
+use core::str;
+
+use crate::Predicate;
+
+/// An error when decoding a predicate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecodeError {
+    /// The input ends in the middle of a predicate.
+    UnexpectedEof,
+    /// An unknown tag byte at the offset.
+    InvalidTag(usize, u8),
+    /// A number overflows at the offset.
+    Overflow(usize),
+    /// A string is not valid UTF-8 at the offset.
+    InvalidUtf8(usize),
+    /// The input has extra bytes after the predicate at the offset.
+    TrailingBytes(usize),
+    /// The predicate at the offset is nested deeper than [`MAX_NESTING`](crate::MAX_NESTING),
+    /// only checked with the `no-panic` feature.
+    TooDeep(usize),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use DecodeError::*;
+
+        match self {
+            UnexpectedEof => f.write_str("unexpected end of input"),
+            InvalidTag(off, tag) => write!(f, "invalid tag {} at offset {}", tag, off),
+            Overflow(off) => write!(f, "number overflow at offset {}", off),
+            InvalidUtf8(off) => write!(f, "invalid UTF-8 string at offset {}", off),
+            TrailingBytes(off) => write!(f, "trailing bytes at offset {}", off),
+            TooDeep(off) => write!(f, "nesting too deep at offset {}", off),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+impl Predicate {
+    /// Encodes the predicate in the compact binary form.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let predicate = all(vec![name("unix"), not(name_value("target_env", "musl"))]);
+    /// let bytes = predicate.encode();
+    ///
+    /// assert_eq!(bytes.len(), 26);
+    /// assert_eq!(Predicate::decode(&bytes), Ok(predicate));
+    /// ```
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        self.encode_to(&mut buf);
+
+        buf
+    }
+
+    /// Appends the compact binary form of the predicate to the buffer.
+    pub fn encode_to(&self, buf: &mut Vec<u8>) {
+        encode(self, buf)
+    }
+
+    /// Decodes a predicate from the compact binary form.
+    pub fn decode(bytes: &[u8]) -> Result<Predicate, DecodeError> {
+        let (predicate, len) = Predicate::decode_prefix(bytes)?;
+
+        if len < bytes.len() {
+            Err(DecodeError::TrailingBytes(len))
+        } else {
+            Ok(predicate)
+        }
+    }
+
+    /// Decodes a predicate from the beginning of the bytes,
+    /// returns it with the number of bytes consumed.
+    pub fn decode_prefix(bytes: &[u8]) -> Result<(Predicate, usize), DecodeError> {
+        let mut decoder = Decoder {
+            bytes,
+            off: 0,
+            depth: 0,
+        };
+        let predicate = decoder.predicate()?;
+
+        Ok((predicate, decoder.off))
+    }
+}
+
+/// A sink of the encoded bytes.
+pub(crate) trait Sink {
+    fn write(&mut self, bytes: &[u8]);
+
+    fn write_len(&mut self, mut n: usize) {
+        loop {
+            let b = (n & 0x7f) as u8;
+
+            n >>= 7;
+
+            if n == 0 {
+                self.write(&[b]);
+                break;
+            } else {
+                self.write(&[b | 0x80]);
+            }
+        }
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write_len(s.len());
+        self.write(s.as_bytes());
+    }
+}
+
+impl Sink for Vec<u8> {
+    fn write(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes)
+    }
+}
+
+/// Encodes the predicate to the sink.
+pub(crate) fn encode<S: Sink + ?Sized>(predicate: &Predicate, sink: &mut S) {
+    use Predicate::*;
+
+    match predicate {
+        Any(predicates) | All(predicates) => {
+            sink.write(&[if let Any(_) = predicate { 0 } else { 1 }]);
+            sink.write_len(predicates.len());
+
+            for predicate in predicates {
+                encode(predicate, sink);
+            }
+        }
+        Not(predicate) => {
+            sink.write(&[2]);
+            encode(predicate, sink);
+        }
+        Name(name) => {
+            sink.write(&[3]);
+            sink.write_str(name);
+        }
+        NameValue(name, value) => {
+            sink.write(&[4]);
+            sink.write_str(name);
+            sink.write_str(value);
+        }
+        True => sink.write(&[5]),
+        False => sink.write(&[6]),
+        Param(name) => {
+            sink.write(&[7]);
+            sink.write_str(name);
+        }
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    off: usize,
+    depth: usize,
+}
+
+impl Decoder<'_> {
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let b = *self.bytes.get(self.off).ok_or(DecodeError::UnexpectedEof)?;
+
+        self.off += 1;
+
+        Ok(b)
+    }
+
+    fn len(&mut self) -> Result<usize, DecodeError> {
+        let off = self.off;
+        let mut n = 0usize;
+        let mut shift = 0;
+
+        loop {
+            let b = self.byte()?;
+            let bits = usize::from(b & 0x7f);
+
+            if shift >= usize::BITS || (bits << shift) >> shift != bits {
+                return Err(DecodeError::Overflow(off));
+            }
+
+            n |= bits << shift;
+            shift += 7;
+
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, DecodeError> {
+        let len = self.len()?;
+        let off = self.off;
+        let bytes = off
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(off..end))
+            .ok_or(DecodeError::UnexpectedEof)?;
+        let s = str::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8(off))?;
+
+        self.off += len;
+
+        Ok(s.into())
+    }
+
+    fn predicate(&mut self) -> Result<Predicate, DecodeError> {
+        #[cfg(feature = "no-panic")]
+        {
+            if self.depth >= crate::MAX_NESTING {
+                return Err(DecodeError::TooDeep(self.off));
+            }
+        }
+
+        self.depth += 1;
+
+        let predicate = self.operand();
+
+        self.depth -= 1;
+
+        predicate
+    }
+
+    fn operand(&mut self) -> Result<Predicate, DecodeError> {
+        let off = self.off;
+
+        Ok(match self.byte()? {
+            tag @ 0 | tag @ 1 => {
+                let len = self.len()?;
+                // Each sub-predicate takes one byte at least.
+                let mut predicates = Vec::with_capacity(len.min(self.bytes.len() - self.off));
+
+                for _ in 0..len {
+                    predicates.push(Box::new(self.predicate()?));
+                }
+
+                if tag == 0 {
+                    Predicate::Any(predicates)
+                } else {
+                    Predicate::All(predicates)
+                }
+            }
+            2 => Predicate::Not(Box::new(self.predicate()?)),
+            3 => Predicate::Name(self.string()?),
+            4 => Predicate::NameValue(self.string()?, self.string()?),
+            5 => Predicate::True,
+            6 => Predicate::False,
+            7 => Predicate::Param(self.string()?),
+            tag => return Err(DecodeError::InvalidTag(off, tag)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(name("unix").encode(), b"\x03\x04unix");
+        assert_eq!(
+            any(vec![Predicate::True, not(param("p"))]).encode(),
+            b"\x00\x02\x05\x02\x07\x01p"
+        );
+        assert_eq!(name("x".repeat(200)).encode()[..3], [3, 0xc8, 0x01]);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let predicates = vec![
+            any(vec![]),
+            all(vec![
+                name("unix"),
+                name_value("target_os", "中文"),
+                not(any(vec![Predicate::False, param("region")])),
+            ]),
+            name("x".repeat(1000)),
+        ];
+
+        for predicate in predicates {
+            let bytes = predicate.encode();
+
+            assert_eq!(Predicate::decode(&bytes), Ok(predicate.clone()));
+            assert_eq!(
+                Predicate::decode_prefix(&[&bytes[..], b"\x05"].concat()),
+                Ok((predicate, bytes.len()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_error() {
+        assert_eq!(Predicate::decode(b""), Err(DecodeError::UnexpectedEof));
+        assert_eq!(
+            Predicate::decode(b"\x03\x05unix"),
+            Err(DecodeError::UnexpectedEof)
+        );
+        assert_eq!(
+            Predicate::decode(b"\x02\x08"),
+            Err(DecodeError::InvalidTag(1, 8))
+        );
+        assert_eq!(
+            Predicate::decode(b"\x03\x01\xff"),
+            Err(DecodeError::InvalidUtf8(2))
+        );
+        assert_eq!(
+            Predicate::decode(b"\x05\x06"),
+            Err(DecodeError::TrailingBytes(1))
+        );
+        assert_eq!(
+            Predicate::decode(b"\x00\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01"),
+            Err(DecodeError::Overflow(1))
+        );
+        assert_eq!(
+            Predicate::decode(b"\x00\xff\xff\xff\xff\x0f"),
+            Err(DecodeError::UnexpectedEof)
+        );
+    }
+
+    #[cfg(feature = "no-panic")]
+    #[test]
+    fn test_decode_too_deep() {
+        let mut predicate = name("unix");
+
+        for _ in 0..MAX_NESTING {
+            predicate = not(predicate);
+        }
+
+        assert_eq!(
+            Predicate::decode(&predicate.encode()),
+            Err(DecodeError::TooDeep(MAX_NESTING))
+        );
+        assert_eq!(
+            Predicate::decode(&[2; 100_000]),
+            Err(DecodeError::TooDeep(MAX_NESTING))
+        );
+    }
+}
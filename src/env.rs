@@ -0,0 +1,286 @@
+//! Flags sourced from the environment variables, with a declarative mapping of the keys.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+
+use crate::{FlagSet, Pattern};
+
+/// How the value of an environment variable is parsed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(::serde::Serialize, ::serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum EnvKind {
+    /// The value as is, the key is set with the value.
+    #[default]
+    String,
+    /// A boolean, like `1`, `true`, `yes` or `on`, the key is set without value when it is true.
+    Bool,
+    /// The comma separated values, the key is set with each trimmed non-empty value.
+    List,
+    /// An integer, the key is set with the value in decimal.
+    Integer,
+}
+
+/// The source of a key, an environment variable with its kind and default value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct EnvVar {
+    /// The name of the environment variable.
+    pub var: String,
+    /// How the value is parsed.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: EnvKind,
+    /// The value when the environment variable is unset, parsed the same way.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub default: Option<String>,
+}
+
+impl EnvVar {
+    /// Creates a source of the environment variable, parsed as a string without default.
+    pub fn new<S: Into<String>>(var: S) -> Self {
+        EnvVar {
+            var: var.into(),
+            kind: EnvKind::default(),
+            default: None,
+        }
+    }
+
+    /// Sets how the value is parsed.
+    pub fn kind(mut self, kind: EnvKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Sets the value when the environment variable is unset.
+    pub fn default_value<S: Into<String>>(mut self, value: S) -> Self {
+        self.default = Some(value.into());
+        self
+    }
+}
+
+/// The mapping from the keys to their environment variables.
+///
+/// It is built with the builder, or deserialized from a map of the keys to their sources with the `serde` feature,
+/// like `{"log": {"var": "APP_LOG", "default": "info"}, "debug": {"var": "APP_DEBUG", "kind": "bool"}}`.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let env = EnvFlags::new()
+///     .key("log", EnvVar::new("APP_LOG").default_value("info"))
+///     .key("debug", EnvVar::new("APP_DEBUG").kind(EnvKind::Bool))
+///     .key("feature", EnvVar::new("APP_FEATURES").kind(EnvKind::List));
+///
+/// let vars = [("APP_DEBUG", "yes"), ("APP_FEATURES", "ssl, gzip")];
+/// let flags = env
+///     .flags_from(|var| vars.iter().find(|(k, _)| *k == var).map(|(_, v)| v.to_string()))
+///     .unwrap();
+///
+/// assert!(all(vec![
+///     name_value("log", "info"),
+///     name("debug"),
+///     feature("ssl"),
+///     feature("gzip"),
+/// ])
+/// .matches(&flags));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(::serde::Serialize, ::serde::Deserialize),
+    serde(transparent)
+)]
+pub struct EnvFlags(BTreeMap<String, EnvVar>);
+
+impl EnvFlags {
+    /// Creates an empty mapping.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps the key to the environment variable.
+    pub fn key<S: Into<String>>(mut self, key: S, var: EnvVar) -> Self {
+        self.0.insert(key.into(), var);
+        self
+    }
+
+    /// Returns the source of the key.
+    pub fn get(&self, key: &str) -> Option<&EnvVar> {
+        self.0.get(key)
+    }
+
+    /// Reads the flags from the environment variables.
+    pub fn flags(&self) -> Result<FlagSet, EnvError> {
+        self.flags_from(|var| env::var(var).ok())
+    }
+
+    /// Reads the flags from the variables looked up by the function.
+    pub fn flags_from<F>(&self, lookup: F) -> Result<FlagSet, EnvError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut flags = FlagSet::new();
+
+        for (key, var) in &self.0 {
+            if let Some(value) = lookup(&var.var).or_else(|| var.default.clone()) {
+                parse(key, var, &value, &mut flags)?;
+            }
+        }
+
+        Ok(flags)
+    }
+}
+
+/// Reads the environment variable of the key on each query, the malformed values never match.
+impl Pattern for EnvFlags {
+    fn matches(&self, key: &str, value: Option<&str>) -> bool {
+        let var = match self.0.get(key) {
+            Some(var) => var,
+            None => return false,
+        };
+        let mut flags = FlagSet::new();
+
+        match env::var(&var.var).ok().or_else(|| var.default.clone()) {
+            Some(raw) if parse(key, var, &raw, &mut flags).is_ok() => flags.matches(key, value),
+            _ => false,
+        }
+    }
+}
+
+fn parse(key: &str, var: &EnvVar, value: &str, flags: &mut FlagSet) -> Result<(), EnvError> {
+    let error = |message| EnvError {
+        key: key.to_string(),
+        var: var.var.clone(),
+        value: value.to_string(),
+        message,
+    };
+
+    match var.kind {
+        EnvKind::String => {
+            flags.insert(key, value);
+        }
+        EnvKind::Bool => match value.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => flags.insert_name(key),
+            "0" | "false" | "no" | "off" | "" => {}
+            _ => return Err(error("expect a boolean")),
+        },
+        EnvKind::List => {
+            for value in value.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+                flags.insert(key, value);
+            }
+        }
+        EnvKind::Integer => {
+            let n = value
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| error("expect an integer"))?;
+
+            flags.insert(key, n.to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// An error when parsing the value of an environment variable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EnvError {
+    /// The key sourced from the variable.
+    pub key: String,
+    /// The name of the environment variable.
+    pub var: String,
+    /// The malformed value.
+    pub value: String,
+    /// The description of the error.
+    pub message: &'static str,
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}, got `{}` from `{}` for `{}`",
+            self.message, self.value, self.var, self.key
+        )
+    }
+}
+
+impl std::error::Error for EnvError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_env_flags() {
+        let env = EnvFlags::new()
+            .key("workers", EnvVar::new("APP_WORKERS").kind(EnvKind::Integer))
+            .key(
+                "debug",
+                EnvVar::new("APP_DEBUG")
+                    .kind(EnvKind::Bool)
+                    .default_value("on"),
+            )
+            .key("region", EnvVar::new("APP_REGION"));
+        let lookup = |vars: &'static [(&'static str, &'static str)]| {
+            move |var: &str| {
+                vars.iter()
+                    .find(|(k, _)| *k == var)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+
+        assert_eq!(
+            env.flags_from(lookup(&[("APP_WORKERS", " 08 "), ("APP_DEBUG", "Off")])),
+            Ok(vec![("workers", Some("8"))].into_iter().collect())
+        );
+        assert_eq!(
+            env.flags_from(lookup(&[("APP_REGION", "eu")])),
+            Ok(vec![("debug", None), ("region", Some("eu"))]
+                .into_iter()
+                .collect())
+        );
+
+        let err = env
+            .flags_from(lookup(&[("APP_WORKERS", "many")]))
+            .unwrap_err();
+
+        assert_eq!(err.var, "APP_WORKERS");
+        assert_eq!(
+            err.to_string(),
+            "expect an integer, got `many` from `APP_WORKERS` for `workers`"
+        );
+
+        let env = env.key("path", EnvVar::new("PATH"));
+
+        assert!(name("path").matches(&env));
+        assert!(name("debug").matches(&env));
+        assert!(!name("unknown").matches(&env));
+        assert_eq!(env.get("region").unwrap().var, "APP_REGION");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize() {
+        let env: EnvFlags = serde_json::from_str(
+            r#"{"log": {"var": "APP_LOG", "default": "info"}, "debug": {"var": "APP_DEBUG", "kind": "bool"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            env,
+            EnvFlags::new()
+                .key("log", EnvVar::new("APP_LOG").default_value("info"))
+                .key("debug", EnvVar::new("APP_DEBUG").kind(EnvKind::Bool))
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! The error type of the crate.
+
+use core::fmt;
+
+use crate::{rust_analyzer::InvalidCfgError, DecodeError, Metrics, Predicate, SexprError};
+
+/// The maximum depth of the predicates, as [`Metrics::depth`],
+/// accepted by the parsers and decoders with the `no-panic` feature.
+///
+/// The parsing and matching recurse into the sub-predicates,
+/// so bounding the depth bounds the stack they use.
+/// It is always defined, so enabling the feature doesn't change the API.
+pub const MAX_NESTING: usize = 64;
+
+/// The error type of the crate, independent of the parser in use.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let budget = Metrics { depth: 2, ..Metrics::UNLIMITED };
+/// let err = all(vec![not(name("unix"))]).check_metrics(&budget).unwrap_err();
+///
+/// assert!(matches!(err, Error::LimitExceeded { .. }));
+/// ```
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Error {
+    /// The configuration can't be parsed.
+    #[cfg(feature = "parsing")]
+    Parse(syn::Error),
+    /// The s-expression can't be parsed.
+    Sexpr(SexprError),
+    /// The binary form can't be decoded.
+    Decode(DecodeError),
+    /// The flag can't be parsed.
+    InvalidFlag(InvalidCfgError),
+    /// The predicate exceeds the complexity budget.
+    LimitExceeded {
+        /// The complexity metrics of the predicate.
+        metrics: Metrics,
+        /// The complexity budget.
+        budget: Metrics,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "parsing")]
+            Error::Parse(err) => write!(f, "failed to parse cfg, {}", err),
+            Error::Sexpr(err) => write!(f, "failed to parse s-expression, {}", err),
+            Error::Decode(err) => write!(f, "failed to decode predicate, {}", err),
+            Error::InvalidFlag(err) => err.fmt(f),
+            Error::LimitExceeded { metrics, budget } => write!(
+                f,
+                "predicate exceeds the limits, depth {} (limit {}), nodes {} (limit {}), leaves {} (limit {})",
+                metrics.depth, budget.depth, metrics.nodes, budget.nodes, metrics.leaves, budget.leaves
+            ),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "parsing")]
+            Error::Parse(err) => Some(err),
+            Error::Sexpr(err) => Some(err),
+            Error::Decode(err) => Some(err),
+            Error::InvalidFlag(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "parsing")]
+impl From<syn::Error> for Error {
+    fn from(err: syn::Error) -> Self {
+        Error::Parse(err)
+    }
+}
+
+impl From<SexprError> for Error {
+    fn from(err: SexprError) -> Self {
+        Error::Sexpr(err)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(err: DecodeError) -> Self {
+        Error::Decode(err)
+    }
+}
+
+impl From<InvalidCfgError> for Error {
+    fn from(err: InvalidCfgError) -> Self {
+        Error::InvalidFlag(err)
+    }
+}
+
+impl Predicate {
+    /// Returns the complexity metrics of the predicate, or an error if it exceeds the budget.
+    pub fn check_metrics(&self, budget: &Metrics) -> Result<Metrics, Error> {
+        let metrics = self.metrics();
+
+        if metrics.exceeds(budget) {
+            Err(Error::LimitExceeded {
+                metrics,
+                budget: *budget,
+            })
+        } else {
+            Ok(metrics)
+        }
+    }
+
+    /// Returns the complexity metrics of the predicate,
+    /// or an error if it is nested deeper than [`MAX_NESTING`].
+    ///
+    /// Only the matching and printing walk a predicate with an explicit stack,
+    /// the other operations, like cloning, comparing, hashing, dropping, folding or explaining,
+    /// recurse into the sub-predicates; check a generated or untrusted predicate before using them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// assert!(not(name("unix")).check_nesting().is_ok());
+    ///
+    /// let deep = (0..MAX_NESTING).fold(name("unix"), |predicate, _| not(predicate));
+    ///
+    /// assert!(matches!(deep.check_nesting(), Err(Error::LimitExceeded { .. })));
+    /// ```
+    pub fn check_nesting(&self) -> Result<Metrics, Error> {
+        self.check_metrics(&Metrics {
+            depth: MAX_NESTING,
+            ..Metrics::UNLIMITED
+        })
+    }
+}
@@ -0,0 +1,55 @@
+//! Quoting the values as string literals for the printers.
+
+use core::fmt::{self, Write};
+
+/// Displays a value as a quoted string literal, which the parsers unescape back to the same value.
+///
+/// Only `"`, `\` and the control characters are escaped, as `\"`, `\\`, `\n`, `\r`, `\t`, `\0`
+/// or `\u{..}`, so the output doesn't depend on the `Debug` format of `str`.
+pub(crate) struct Quoted<'a>(pub(crate) &'a str);
+
+impl fmt::Display for Quoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_char('"')?;
+
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                '\0' => f.write_str("\\0")?,
+                c if c.is_control() => write!(f, "\\u{{{:x}}}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+
+        f.write_char('"')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_quoted() {
+        cfg_if! {
+            if #[cfg(not(feature = "std"))] {
+                use alloc::string::ToString;
+            }
+        }
+
+        use super::*;
+
+        assert_eq!(Quoted("linux").to_string(), r#""linux""#);
+        assert_eq!(
+            Quoted("a\"b\\c\nd\re\tf\0g\u{7f}h\u{1b}").to_string(),
+            r#""a\"b\\c\nd\re\tf\0g\u{7f}h\u{1b}""#
+        );
+        assert_eq!(
+            Quoted("é \u{301} 中文 '").to_string(),
+            "\"é \u{301} 中文 '\""
+        );
+    }
+}
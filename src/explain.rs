@@ -0,0 +1,168 @@
+//! Explanation of the evaluation of a predicate, with the result of each node.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::vec;
+        use alloc::vec::Vec;
+    }
+}
+
+use crate::{Pattern, Predicate};
+
+/// The evaluation result of a predicate node, with the results of its sub-predicates.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let predicate = all(vec![name("unix"), not(name_value("target_env", "musl"))]);
+/// let explanation = predicate.explain(&vec![("unix", None), ("target_env", Some("musl"))]);
+///
+/// assert!(!explanation.matched);
+/// assert!(explanation.children[0].matched);
+/// assert!(!explanation.children[1].matched);
+/// assert_eq!(
+///     explanation.failed_leaves().collect::<Vec<_>>(),
+///     vec![&name_value("target_env", "musl")]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct Explanation<'a> {
+    /// The predicate of the node.
+    pub predicate: &'a Predicate,
+    /// Whether the predicate matches.
+    pub matched: bool,
+    /// The explanations of the sub-predicates, all of them are evaluated without short-circuit.
+    pub children: Vec<Explanation<'a>>,
+}
+
+impl<'a> Explanation<'a> {
+    /// Returns an iterator over the leaves which decide the predicate doesn't match,
+    /// the leaves under `not` are flipped, so a matched leaf under `not` is failed.
+    pub fn failed_leaves(&self) -> impl Iterator<Item = &'a Predicate> + '_ {
+        let mut stack = vec![(self, true)];
+
+        core::iter::from_fn(move || {
+            while let Some((explanation, want)) = stack.pop() {
+                if explanation.children.is_empty() {
+                    if explanation.matched != want {
+                        return Some(explanation.predicate);
+                    }
+                } else {
+                    let want = if let Predicate::Not(_) = explanation.predicate {
+                        !want
+                    } else {
+                        want
+                    };
+
+                    stack.extend(explanation.children.iter().rev().map(|child| (child, want)));
+                }
+            }
+
+            None
+        })
+    }
+}
+
+impl Predicate {
+    /// Evaluates the predicate, returns the result of each node.
+    pub fn explain<P: Pattern>(&self, pattern: &P) -> Explanation<'_> {
+        use Predicate::*;
+
+        let children = match self {
+            Any(predicates) | All(predicates) => predicates
+                .iter()
+                .map(|predicate| predicate.explain(pattern))
+                .collect(),
+            Not(predicate) => vec![predicate.explain(pattern)],
+            _ => Vec::new(),
+        };
+        let matched = match self {
+            Any(_) => children.iter().any(|child| child.matched),
+            All(_) => children.iter().all(|child| child.matched),
+            Not(_) => !children[0].matched,
+            _ => self.matches(pattern),
+        };
+
+        Explanation {
+            predicate: self,
+            matched,
+            children,
+        }
+    }
+}
+
+/// Panics with the explanation if the predicate doesn't match the pattern as expected,
+/// used by [`assert_cfg_matches!`] and [`assert_cfg_not_matches!`].
+#[doc(hidden)]
+#[cfg(feature = "printing")]
+#[track_caller]
+pub fn assert_cfg<P: Pattern>(
+    predicate: &Predicate,
+    pattern: &P,
+    expected: bool,
+    exprs: (&str, &str),
+    msg: Option<core::fmt::Arguments>,
+) {
+    let explanation = predicate.explain(pattern);
+
+    if explanation.matched == expected {
+        return;
+    }
+
+    let (cfg, flags) = exprs;
+    let op = if expected { "matches" } else { "doesn't match" };
+
+    match msg {
+        Some(msg) => panic!(
+            "assertion failed: `{}` {} `{}`: {}\n  cfg: {}\n{}",
+            cfg, op, flags, msg, predicate, explanation
+        ),
+        None => panic!(
+            "assertion failed: `{}` {} `{}`\n  cfg: {}\n{}",
+            cfg, op, flags, predicate, explanation
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+            use alloc::vec::Vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_explain() {
+        let predicate = any(vec![
+            all(vec![name("unix"), name("test")]),
+            not(name("unix")),
+            Predicate::False,
+        ]);
+        let explanation = predicate.explain(&vec![("unix", None::<&str>)]);
+
+        assert!(!explanation.matched);
+        assert_eq!(
+            explanation
+                .children
+                .iter()
+                .map(|child| child.matched)
+                .collect::<Vec<_>>(),
+            vec![false, false, false]
+        );
+        assert_eq!(
+            explanation.failed_leaves().collect::<Vec<_>>(),
+            vec![&name("test"), &name("unix"), &Predicate::False]
+        );
+        assert_eq!(
+            predicate.explain(&vec![("test", None::<&str>)]).matched,
+            predicate.matches(&vec![("test", None::<&str>)])
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Conversions between predicates and `cfg_expr::Expression`.
+
+use core::convert::TryFrom;
+
+use ::cfg_expr::{
+    expr::{Logic, TargetPredicate},
+    targets::{self, TargetInfo},
+    Expression, ParseError,
+};
+
+use crate::{Cfg, FlagSet, Predicate};
+
+/// Converts a typed target predicate to a name-value predicate.
+impl From<&TargetPredicate> for Predicate {
+    fn from(predicate: &TargetPredicate) -> Self {
+        use TargetPredicate::*;
+
+        let (name, value) = match predicate {
+            Abi(abi) => ("target_abi", abi.as_str().to_owned()),
+            Arch(arch) => ("target_arch", arch.as_str().to_owned()),
+            Endian(endian) => (
+                "target_endian",
+                match endian {
+                    targets::Endian::big => "big",
+                    targets::Endian::little => "little",
+                }
+                .to_owned(),
+            ),
+            Env(env) => ("target_env", env.as_str().to_owned()),
+            Family(family) => ("target_family", family.as_str().to_owned()),
+            HasAtomic(atomic) => ("target_has_atomic", atomic.to_string()),
+            Os(os) => ("target_os", os.as_str().to_owned()),
+            Panic(panic) => ("panic", panic.as_str().to_owned()),
+            PointerWidth(width) => ("target_pointer_width", width.to_string()),
+            Vendor(vendor) => ("target_vendor", vendor.as_str().to_owned()),
+        };
+
+        Predicate::NameValue(name.to_owned(), value)
+    }
+}
+
+impl From<&::cfg_expr::Predicate<'_>> for Predicate {
+    fn from(predicate: &::cfg_expr::Predicate) -> Self {
+        use ::cfg_expr::Predicate::*;
+
+        match predicate {
+            Target(predicate) => predicate.into(),
+            Test => crate::name("test"),
+            DebugAssertions => crate::name("debug_assertions"),
+            ProcMacro => crate::name("proc_macro"),
+            Feature(name) => crate::feature(*name),
+            TargetFeature(name) => crate::name_value("target_feature", *name),
+            Flag(name) => crate::name(*name),
+            KeyValue { key, val } => crate::name_value(*key, *val),
+        }
+    }
+}
+
+/// Converts an expression to a predicate.
+///
+/// The bare `unix` and `windows` are converted to `target_family = "unix"` and `target_family = "windows"`,
+/// since `cfg_expr` doesn't distinguish them; and the order of sub-predicates may differ from the source,
+/// since `cfg_expr` doesn't preserve it.
+impl From<&Expression> for Predicate {
+    fn from(expr: &Expression) -> Self {
+        expr.eval(|predicate| Node(predicate.into())).0
+    }
+}
+
+impl From<Expression> for Predicate {
+    fn from(expr: Expression) -> Self {
+        Predicate::from(&expr)
+    }
+}
+
+impl From<&Expression> for Cfg {
+    fn from(expr: &Expression) -> Self {
+        Cfg(expr.into())
+    }
+}
+
+impl From<Expression> for Cfg {
+    fn from(expr: Expression) -> Self {
+        Cfg(expr.into())
+    }
+}
+
+/// Converts a predicate to an expression, fails if the predicate has any parameter.
+impl TryFrom<&Predicate> for Expression {
+    type Error = ParseError;
+
+    fn try_from(predicate: &Predicate) -> Result<Self, Self::Error> {
+        Expression::parse(&predicate.to_string())
+    }
+}
+
+impl TryFrom<Predicate> for Expression {
+    type Error = ParseError;
+
+    fn try_from(predicate: Predicate) -> Result<Self, Self::Error> {
+        Expression::try_from(&predicate)
+    }
+}
+
+impl TryFrom<&Cfg> for Expression {
+    type Error = ParseError;
+
+    fn try_from(cfg: &Cfg) -> Result<Self, Self::Error> {
+        Expression::try_from(&cfg.0)
+    }
+}
+
+impl TryFrom<Cfg> for Expression {
+    type Error = ParseError;
+
+    fn try_from(cfg: Cfg) -> Result<Self, Self::Error> {
+        Expression::try_from(&cfg.0)
+    }
+}
+
+impl FlagSet {
+    /// Creates the flags of a target in the builtin target database of `cfg_expr`.
+    ///
+    /// Returns `None` if the target triple is unknown.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let flags = FlagSet::from_builtin_target("thumbv6m-none-eabi").unwrap();
+    ///
+    /// assert!(name_value("target_arch", "arm").matches(&flags));
+    /// assert!(!target_has_atomic(32).matches(&flags));
+    /// assert!(flags.atomic_widths().is_empty());
+    /// ```
+    pub fn from_builtin_target(triple: &str) -> Option<FlagSet> {
+        targets::get_builtin_target_by_triple(triple).map(FlagSet::from_target_info)
+    }
+
+    /// Creates the flags of a target of `cfg_expr`.
+    pub fn from_target_info(target: &TargetInfo) -> FlagSet {
+        let mut flags = FlagSet::new();
+        let mut insert = |predicate: &TargetPredicate| {
+            if let Predicate::NameValue(name, value) = Predicate::from(predicate) {
+                flags.insert(name, value);
+            }
+        };
+
+        insert(&TargetPredicate::Arch(target.arch.clone()));
+        insert(&TargetPredicate::Endian(target.endian));
+        insert(&TargetPredicate::PointerWidth(target.pointer_width));
+        insert(&TargetPredicate::Panic(target.panic.clone()));
+
+        if let Some(os) = &target.os {
+            insert(&TargetPredicate::Os(os.clone()));
+        }
+        if let Some(env) = &target.env {
+            insert(&TargetPredicate::Env(env.clone()));
+        }
+        if let Some(vendor) = &target.vendor {
+            insert(&TargetPredicate::Vendor(vendor.clone()));
+        }
+        if let Some(abi) = &target.abi {
+            insert(&TargetPredicate::Abi(abi.clone()));
+        }
+        for family in target.families.iter() {
+            insert(&TargetPredicate::Family(family.clone()));
+        }
+
+        for atomic in target.has_atomics.iter() {
+            insert(&TargetPredicate::HasAtomic(*atomic));
+        }
+
+        for family in target.families.iter() {
+            if family.as_str() == "unix" || family.as_str() == "windows" {
+                flags.insert_name(family.as_str());
+            }
+        }
+
+        flags
+    }
+}
+
+/// Rebuilds the predicate tree when evaluating the expression.
+///
+/// The `all` and `any` are evaluated by folding the sub-predicates in the reverse order,
+/// into the `top` or `bottom` accumulator.
+#[derive(Debug)]
+struct Node(Predicate);
+
+impl Logic for Node {
+    fn top() -> Self {
+        Node(Predicate::All(vec![]))
+    }
+
+    fn bottom() -> Self {
+        Node(Predicate::Any(vec![]))
+    }
+
+    fn and(mut self, other: Self) -> Self {
+        if let Predicate::All(ref mut predicates) = self.0 {
+            predicates.insert(0, Box::new(other.0));
+        }
+
+        self
+    }
+
+    fn or(mut self, other: Self) -> Self {
+        if let Predicate::Any(ref mut predicates) = self.0 {
+            predicates.insert(0, Box::new(other.0));
+        }
+
+        self
+    }
+
+    fn not(self) -> Self {
+        Node(crate::not(self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::*;
+
+    #[test]
+    fn test_from_expression() {
+        let expr = Expression::parse(
+            r#"all(unix, target_os = "linux", any(all(), not(feature = "std"), target_has_atomic = "ptr"), target_endian = "little", foo = "bar")"#,
+        )
+        .unwrap();
+
+        assert!(Predicate::from(&expr).eq_unordered(&all(vec![
+            name_value("target_family", "unix"),
+            name_value("target_os", "linux"),
+            any(vec![
+                all(vec![]),
+                not(feature("std")),
+                name_value("target_has_atomic", "ptr"),
+            ]),
+            name_value("target_endian", "little"),
+            name_value("foo", "bar"),
+        ])));
+        assert_eq!(
+            Predicate::from(Expression::parse("any()").unwrap()),
+            any(vec![])
+        );
+    }
+
+    #[test]
+    fn test_from_builtin_target() {
+        let flags = FlagSet::from_builtin_target("x86_64-unknown-linux-gnu").unwrap();
+
+        assert!(all(vec![
+            name("unix"),
+            name_value("target_family", "unix"),
+            name_value("target_arch", "x86_64"),
+            name_value("target_os", "linux"),
+            name_value("target_env", "gnu"),
+            name_value("target_vendor", "unknown"),
+            name_value("target_endian", "little"),
+            name_value("target_pointer_width", "64"),
+            name_value("panic", "unwind"),
+            target_has_atomic_ptr(),
+        ])
+        .matches(&flags));
+        assert_eq!(flags.atomic_widths(), vec![8, 16, 32, 64]);
+
+        let flags = FlagSet::from_builtin_target("thumbv6m-none-eabi").unwrap();
+
+        assert!(flags.atomic_widths().is_empty());
+        assert!(!flags.has("unix"));
+        assert!(FlagSet::from_builtin_target("x86_64-unknown-nowhere").is_none());
+    }
+
+    #[test]
+    fn test_to_expression() {
+        let predicate = all(vec![
+            name("test"),
+            name_value("target_pointer_width", "64"),
+            not(name_value("target_feature", "sse2")),
+        ]);
+        let expr = Expression::try_from(&predicate).unwrap();
+
+        assert!(Predicate::from(&expr).eq_unordered(&predicate));
+        assert!(Expression::try_from(param("region")).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+//! Stable fingerprint and hashing of a predicate.
+
+use core::hash::Hasher;
+
+use crate::{
+    encoding::{encode, Sink},
+    Cfg, Predicate,
+};
+
+/// The version of the byte encoding fed by [`Cfg::hash_stable`].
+///
+/// It is bumped whenever the encoding changes, so the hashes of different versions never collide.
+pub const STABLE_HASH_VERSION: u8 = 1;
+
+const FNV_OFFSET_BASIS: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+const FNV_PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+impl Predicate {
+    /// Returns a stable 128-bit fingerprint of the predicate.
+    ///
+    /// The fingerprint is computed over the compact binary encoding of the canonical form,
+    /// with the 128-bit FNV-1a algorithm,
+    /// it doesn't depend on the process, the platform or the order of sub-predicates,
+    /// so it could be persisted as the identity of the predicate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let lhs = any(vec![name("foo"), name("bar")]);
+    /// let rhs = any(vec![name("bar"), name("foo")]);
+    ///
+    /// assert_eq!(lhs.fingerprint(), rhs.fingerprint());
+    /// assert_ne!(lhs.fingerprint(), all(vec![name("foo"), name("bar")]).fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u128 {
+        let mut hasher = Fnv1a(FNV_OFFSET_BASIS);
+
+        encode(&self.canonicalize(), &mut hasher);
+
+        hasher.0
+    }
+}
+
+impl Cfg {
+    /// Feeds a portable byte encoding of the configuration into the hasher.
+    ///
+    /// Unlike the derived `Hash`, which feeds the platform-dependent lengths and enum discriminants,
+    /// the hasher only receives bytes through [`Hasher::write`]:
+    /// the [`STABLE_HASH_VERSION`] byte, followed by the compact binary encoding of the predicate
+    /// (see [`Predicate::encode`]).
+    /// So the hash only depends on the choice of hasher,
+    /// and could be used as the key of content-addressed build caches.
+    ///
+    /// The predicate is hashed as-is, call [`Predicate::canonicalize`] first
+    /// to hash the equivalent configurations in the same way, or use [`Predicate::fingerprint`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::hash::Hasher;
+    ///
+    /// use runtime_cfg::*;
+    ///
+    /// #[derive(Default)]
+    /// struct Bytes(Vec<u8>);
+    ///
+    /// impl Hasher for Bytes {
+    ///     fn finish(&self) -> u64 {
+    ///         0
+    ///     }
+    ///
+    ///     fn write(&mut self, bytes: &[u8]) {
+    ///         self.0.extend_from_slice(bytes)
+    ///     }
+    /// }
+    ///
+    /// let cfg = Cfg::from(name("unix"));
+    /// let mut hasher = Bytes::default();
+    ///
+    /// cfg.hash_stable(&mut hasher);
+    ///
+    /// assert_eq!(hasher.0, b"\x01\x03\x04unix");
+    /// ```
+    pub fn hash_stable<H: Hasher>(&self, state: &mut H) {
+        let mut sink = HasherSink(state);
+
+        sink.write(&[STABLE_HASH_VERSION]);
+
+        encode(&self.0, &mut sink);
+    }
+}
+
+struct HasherSink<'a, H>(&'a mut H);
+
+impl<H: Hasher> Sink for HasherSink<'_, H> {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.write(bytes)
+    }
+}
+
+struct Fnv1a(u128);
+
+impl Sink for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= u128::from(b);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+            use alloc::vec::Vec;
+        }
+    }
+
+    use core::hash::Hasher;
+
+    use crate::*;
+
+    #[test]
+    fn test_fingerprint() {
+        assert_eq!(
+            name("unix").fingerprint(),
+            0x2472_a172_1c3c_64bf_6dca_26f0_c903_34e8
+        );
+        assert_eq!(
+            all(vec![name("unix"), name_value("target_pointer_width", "32")]).fingerprint(),
+            all(vec![name_value("target_pointer_width", "32"), name("unix")]).fingerprint()
+        );
+        assert_ne!(
+            name_value("ab", "c").fingerprint(),
+            name_value("a", "bc").fingerprint()
+        );
+        assert_ne!(
+            Predicate::True.fingerprint(),
+            Predicate::False.fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_hash_stable() {
+        #[derive(Default)]
+        struct Bytes(Vec<u8>);
+
+        impl Hasher for Bytes {
+            fn finish(&self) -> u64 {
+                0
+            }
+
+            fn write(&mut self, bytes: &[u8]) {
+                self.0.extend_from_slice(bytes)
+            }
+        }
+
+        let cfg = Cfg::from(all(vec![
+            name("unix"),
+            not(name_value("target_env", "musl")),
+        ]));
+        let mut hasher = Bytes::default();
+
+        cfg.hash_stable(&mut hasher);
+
+        let mut expected = vec![STABLE_HASH_VERSION];
+
+        cfg.encode_to(&mut expected);
+
+        assert_eq!(hasher.0, expected);
+
+        let mut lhs = Bytes::default();
+        let mut rhs = Bytes::default();
+
+        Cfg::from(name_value("ab", "c")).hash_stable(&mut lhs);
+        Cfg::from(name_value("a", "bc")).hash_stable(&mut rhs);
+
+        assert_ne!(lhs.0, rhs.0);
+    }
+}
@@ -0,0 +1,662 @@
+//! Predicates of a fixed capacity, parsed and matched without heap allocation.
+
+cfg_if! {
+    if #[cfg(all(feature = "alloc", not(feature = "std")))] {
+        use alloc::boxed::Box;
+        use alloc::string::ToString;
+        use alloc::vec::Vec;
+    }
+}
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+use crate::escape::Quoted;
+#[cfg(feature = "alloc")]
+use crate::{Cfg, Predicate};
+use crate::{Pattern, PredicateRef};
+
+/// A predicate with at most `N` nodes, stored inline without heap allocation.
+///
+/// The names and values are borrowed from the source,
+/// so it could be parsed and matched on the targets without an allocator,
+/// sharing the same semantic with the host tools.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let cfg = StaticPredicate::<8>::parse(r#"cfg(all(unix, not(target_env = "musl")))"#).unwrap();
+///
+/// assert_eq!(cfg.len(), 4);
+/// assert!(cfg.matches(&[("unix", None), ("target_env", Some("gnu"))]));
+/// assert!(!cfg.matches(&[("unix", None), ("target_env", Some("musl"))]));
+///
+/// assert_eq!(
+///     StaticPredicate::<2>::parse(r#"cfg(all(unix, not(target_env = "musl")))"#),
+///     Err(StaticError::CapacityExceeded)
+/// );
+/// ```
+#[derive(Clone)]
+pub struct StaticPredicate<'a, const N: usize> {
+    nodes: [Node<'a>; N],
+    len: usize,
+}
+
+/// A node of the predicate in the pre-order, a list node knows the number of nodes in its subtree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Node<'a> {
+    Any(usize),
+    All(usize),
+    Not(usize),
+    Name(&'a str),
+    NameValue(&'a str, &'a str),
+    True,
+    False,
+}
+
+impl Node<'_> {
+    fn size(&self) -> usize {
+        match *self {
+            Node::Any(size) | Node::All(size) | Node::Not(size) => size,
+            _ => 1,
+        }
+    }
+}
+
+/// An error of building a `StaticPredicate`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StaticError {
+    /// The predicate has more nodes than the capacity.
+    CapacityExceeded,
+    /// The predicate is not supported without heap allocation, like `param(..)`.
+    Unsupported,
+    /// The source is malformed at the byte offset.
+    Syntax {
+        /// The byte offset of the error in the source.
+        offset: usize,
+        /// The description of the error.
+        message: &'static str,
+    },
+}
+
+impl fmt::Display for StaticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StaticError::CapacityExceeded => f.write_str("too many predicates for the capacity"),
+            StaticError::Unsupported => f.write_str("unsupported predicate without allocation"),
+            StaticError::Syntax { offset, message } => {
+                write!(f, "{} at offset {}", message, offset)
+            }
+        }
+    }
+}
+
+impl core::error::Error for StaticError {}
+
+impl<'a, const N: usize> StaticPredicate<'a, N> {
+    /// Parses a `#[cfg(..)]` attribute or a bare `cfg(..)` predicate.
+    ///
+    /// The values must be string literals without escapes, or integers.
+    ///
+    /// The nesting is bounded by the capacity, since each level of the nesting takes a node,
+    /// so the parser never recurses deeper than `N`.
+    pub fn parse(s: &'a str) -> Result<Self, StaticError> {
+        let mut parser = Parser {
+            src: s,
+            pos: 0,
+            predicate: StaticPredicate {
+                nodes: [Node::False; N],
+                len: 0,
+            },
+        };
+
+        parser.parse()?;
+
+        Ok(parser.predicate)
+    }
+
+    /// Returns the number of nodes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there is no node, which never happens for a built predicate.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the maximum number of nodes.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns `true` if configuration matches the predicate
+    pub fn matches<P: Pattern + ?Sized>(&self, pattern: &P) -> bool {
+        self.len > 0 && self.matches_at(0, pattern)
+    }
+
+    fn matches_at<P: Pattern + ?Sized>(&self, idx: usize, pattern: &P) -> bool {
+        match self.nodes[idx] {
+            Node::Any(size) => self
+                .children(idx, size)
+                .any(|child| self.matches_at(child, pattern)),
+            Node::All(size) => self
+                .children(idx, size)
+                .all(|child| self.matches_at(child, pattern)),
+            Node::Not(_) => !self.matches_at(idx + 1, pattern),
+            Node::Name(name) => pattern.matches(name, None),
+            Node::NameValue(name, value) => pattern.matches(name, Some(value)),
+            Node::True => true,
+            Node::False => false,
+        }
+    }
+
+    fn children(&self, idx: usize, size: usize) -> impl Iterator<Item = usize> + '_ {
+        let end = idx + size;
+
+        core::iter::successors(Some(idx + 1).filter(|&child| child < end), move |&child| {
+            Some(child + self.nodes[child].size()).filter(|&next| next < end)
+        })
+    }
+
+    fn nodes(&self) -> &[Node<'a>] {
+        &self.nodes[..self.len]
+    }
+
+    fn push(&mut self, node: Node<'a>) -> Result<usize, StaticError> {
+        if self.len == N {
+            return Err(StaticError::CapacityExceeded);
+        }
+
+        self.nodes[self.len] = node;
+        self.len += 1;
+
+        Ok(self.len - 1)
+    }
+
+    fn push_ref(&mut self, predicate: &PredicateRef<'a>) -> Result<(), StaticError> {
+        let (idx, predicates) = match *predicate {
+            PredicateRef::Any(predicates) => (self.push(Node::Any(0))?, predicates),
+            PredicateRef::All(predicates) => (self.push(Node::All(0))?, predicates),
+            PredicateRef::Not(predicate) => {
+                let idx = self.push(Node::Not(0))?;
+
+                self.push_ref(predicate)?;
+                self.nodes[idx] = Node::Not(self.len - idx);
+
+                return Ok(());
+            }
+            PredicateRef::Name(name) => return self.push(Node::Name(name)).map(drop),
+            PredicateRef::NameValue(name, value) => {
+                return self.push(Node::NameValue(name, value)).map(drop)
+            }
+            PredicateRef::True => return self.push(Node::True).map(drop),
+            PredicateRef::False => return self.push(Node::False).map(drop),
+        };
+
+        for predicate in predicates {
+            self.push_ref(predicate)?;
+        }
+
+        self.nodes[idx] = match self.nodes[idx] {
+            Node::Any(_) => Node::Any(self.len - idx),
+            _ => Node::All(self.len - idx),
+        };
+
+        Ok(())
+    }
+
+    fn fmt_at(&self, idx: usize, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.nodes[idx] {
+            Node::Any(size) | Node::All(size) => {
+                f.write_str(if let Node::Any(_) = self.nodes[idx] {
+                    "any("
+                } else {
+                    "all("
+                })?;
+                for (i, child) in self.children(idx, size).enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    self.fmt_at(child, f)?;
+                }
+                f.write_str(")")
+            }
+            Node::Not(_) => {
+                f.write_str("not(")?;
+                self.fmt_at(idx + 1, f)?;
+                f.write_str(")")
+            }
+            Node::Name(name) => f.write_str(name),
+            Node::NameValue(name, value) => write!(f, "{} = {}", name, Quoted(value)),
+            Node::True => f.write_str("all()"),
+            Node::False => f.write_str("any()"),
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    fn to_predicate_at(&self, idx: usize) -> Predicate {
+        match self.nodes[idx] {
+            Node::Any(size) => Predicate::Any(
+                self.children(idx, size)
+                    .map(|child| Box::new(self.to_predicate_at(child)))
+                    .collect::<Vec<_>>(),
+            ),
+            Node::All(size) => Predicate::All(
+                self.children(idx, size)
+                    .map(|child| Box::new(self.to_predicate_at(child)))
+                    .collect::<Vec<_>>(),
+            ),
+            Node::Not(_) => Predicate::Not(Box::new(self.to_predicate_at(idx + 1))),
+            Node::Name(name) => Predicate::Name(name.to_string()),
+            Node::NameValue(name, value) => {
+                Predicate::NameValue(name.to_string(), value.to_string())
+            }
+            Node::True => Predicate::True,
+            Node::False => Predicate::False,
+        }
+    }
+
+    /// Converts to an owned predicate.
+    #[cfg(feature = "alloc")]
+    pub fn to_predicate(&self) -> Predicate {
+        if self.len == 0 {
+            Predicate::False
+        } else {
+            self.to_predicate_at(0)
+        }
+    }
+}
+
+impl<'a, const N: usize> TryFrom<PredicateRef<'a>> for StaticPredicate<'a, N> {
+    type Error = StaticError;
+
+    fn try_from(predicate: PredicateRef<'a>) -> Result<Self, Self::Error> {
+        let mut static_predicate = StaticPredicate {
+            nodes: [Node::False; N],
+            len: 0,
+        };
+
+        static_predicate.push_ref(&predicate)?;
+
+        Ok(static_predicate)
+    }
+}
+
+impl<const N: usize> fmt::Debug for StaticPredicate<'_, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StaticPredicate")
+            .field("nodes", &self.nodes())
+            .finish()
+    }
+}
+
+/// Displays the predicate as the `Predicate` does.
+impl<const N: usize> fmt::Display for StaticPredicate<'_, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.len == 0 {
+            f.write_str("any()")
+        } else {
+            self.fmt_at(0, f)
+        }
+    }
+}
+
+impl<const N: usize, const M: usize> PartialEq<StaticPredicate<'_, M>> for StaticPredicate<'_, N> {
+    fn eq(&self, other: &StaticPredicate<'_, M>) -> bool {
+        self.nodes() == other.nodes()
+    }
+}
+
+impl<const N: usize> Eq for StaticPredicate<'_, N> {}
+
+impl<const N: usize> Hash for StaticPredicate<'_, N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.nodes().hash(state)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const N: usize> From<&StaticPredicate<'_, N>> for Predicate {
+    fn from(predicate: &StaticPredicate<'_, N>) -> Self {
+        predicate.to_predicate()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const N: usize> From<&StaticPredicate<'_, N>> for Cfg {
+    fn from(predicate: &StaticPredicate<'_, N>) -> Self {
+        Cfg(predicate.to_predicate())
+    }
+}
+
+/// A parser of the `cfg` syntax, writing the nodes into a `StaticPredicate`.
+struct Parser<'a, const N: usize> {
+    src: &'a str,
+    pos: usize,
+    predicate: StaticPredicate<'a, N>,
+}
+
+impl<'a, const N: usize> Parser<'a, N> {
+    fn parse(&mut self) -> Result<(), StaticError> {
+        self.skip_ws();
+
+        if self.eat('#') {
+            self.skip_ws();
+            self.expect('[')?;
+            self.skip_ws();
+            self.cfg()?;
+            self.skip_ws();
+            self.expect(']')?;
+        } else {
+            self.cfg()?;
+        }
+
+        self.skip_ws();
+
+        if self.pos < self.src.len() {
+            return Err(self.error("unexpected trailing input"));
+        }
+
+        Ok(())
+    }
+
+    fn cfg(&mut self) -> Result<(), StaticError> {
+        if self.ident() != Some("cfg") {
+            return Err(self.error("expect cfg(..)"));
+        }
+
+        self.skip_ws();
+        self.expect('(')?;
+
+        if self.list()? != 1 {
+            return Err(self.error("cfg(..) only support one predicate"));
+        }
+
+        self.expect(')')
+    }
+
+    fn predicate(&mut self) -> Result<(), StaticError> {
+        self.skip_ws();
+
+        let name = self
+            .ident()
+            .ok_or_else(|| self.error("expected a predicate"))?;
+
+        self.skip_ws();
+
+        if self.eat('(') {
+            let idx = match name {
+                "any" => self.predicate.push(Node::Any(0))?,
+                "all" => self.predicate.push(Node::All(0))?,
+                "not" => self.predicate.push(Node::Not(0))?,
+                "param" => return Err(StaticError::Unsupported),
+                _ => return Err(self.error("unexpected operator")),
+            };
+
+            let count = self.list()?;
+
+            self.expect(')')?;
+
+            let size = self.predicate.len - idx;
+
+            self.predicate.nodes[idx] = match name {
+                "any" => Node::Any(size),
+                "all" => Node::All(size),
+                _ if count == 1 => Node::Not(size),
+                _ => return Err(self.error("not(..) only support one predicate")),
+            };
+        } else if self.eat('=') {
+            self.skip_ws();
+
+            let value = self.value()?;
+
+            self.predicate.push(Node::NameValue(name, value))?;
+        } else {
+            self.predicate.push(match name {
+                "true" => Node::True,
+                "false" => Node::False,
+                _ => Node::Name(name),
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Parses a comma separated list of predicates, returns the number of predicates.
+    fn list(&mut self) -> Result<usize, StaticError> {
+        let mut count = 0;
+
+        loop {
+            self.skip_ws();
+
+            if self.peek(')') {
+                return Ok(count);
+            }
+
+            self.predicate()?;
+            count += 1;
+            self.skip_ws();
+
+            if !self.eat(',') {
+                return Ok(count);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<&'a str, StaticError> {
+        let src = self.src;
+        let rest = &src[self.pos..];
+
+        if let Some(rest) = rest.strip_prefix('"') {
+            let len = rest
+                .find(['"', '\\'])
+                .ok_or_else(|| self.error("unterminated string"))?;
+
+            if rest[len..].starts_with('\\') {
+                return Err(self.error("escaped string is not supported"));
+            }
+
+            self.pos += 1 + len + 1;
+
+            Ok(&rest[..len])
+        } else if rest.starts_with(|c: char| c.is_ascii_digit()) {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+
+            self.pos += len;
+
+            Ok(&rest[..len])
+        } else {
+            Err(self.error("expected a string literal"))
+        }
+    }
+
+    fn ident(&mut self) -> Option<&'a str> {
+        let src = self.src;
+        let rest = &src[self.pos..];
+
+        if !rest.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+            return None;
+        }
+
+        let len = rest
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(rest.len());
+
+        self.pos += len;
+
+        Some(&rest[..len])
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = &self.src[self.pos..];
+
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self, c: char) -> bool {
+        self.src[self.pos..].starts_with(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), StaticError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(match c {
+                '(' => "expected `(`",
+                ')' => "expected `)`",
+                '[' => "expected `[`",
+                _ => "expected `]`",
+            }))
+        }
+    }
+
+    fn error(&self, message: &'static str) -> StaticError {
+        StaticError::Syntax {
+            offset: self.pos,
+            message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::TryFrom;
+
+    use crate::*;
+
+    const LINUX: PredicateRef = PredicateRef::All(&[
+        PredicateRef::Name("unix"),
+        PredicateRef::NameValue("target_os", "linux"),
+    ]);
+
+    #[test]
+    fn test_parse() {
+        let cfg = StaticPredicate::<8>::parse(
+            r#"#[cfg(any(all(unix, target_os = "linux"), not(windows), target_pointer_width = 64, false))]"#,
+        )
+        .unwrap();
+
+        assert_eq!(cfg.len(), 8);
+        assert_eq!(cfg.capacity(), 8);
+        assert!(cfg.matches(&[("unix", None), ("target_os", Some("linux"))]));
+        assert!(cfg.matches(&[("target_pointer_width", Some("64"))]));
+        assert!(!cfg.matches(&[("windows", None::<&str>)]));
+        assert!(cfg.matches::<[(&str, Option<&str>)]>(&[]));
+
+        assert_eq!(
+            StaticPredicate::<4>::parse(r#"cfg(all(unix, target_os = "linux"))"#).unwrap(),
+            StaticPredicate::<3>::try_from(LINUX).unwrap()
+        );
+        assert!(StaticPredicate::<1>::parse("cfg(all())")
+            .unwrap()
+            .matches(&[("unix", None::<&str>)]));
+
+        let errors = [
+            ("cfg(all(unix, windows))", StaticError::CapacityExceeded),
+            ("cfg(param(x))", StaticError::Unsupported),
+            (
+                "unix",
+                StaticError::Syntax {
+                    offset: 4,
+                    message: "expect cfg(..)",
+                },
+            ),
+            (
+                "cfg(foo(bar))",
+                StaticError::Syntax {
+                    offset: 8,
+                    message: "unexpected operator",
+                },
+            ),
+            (
+                r#"cfg(foo = "a\"b")"#,
+                StaticError::Syntax {
+                    offset: 10,
+                    message: "escaped string is not supported",
+                },
+            ),
+            (
+                "cfg(not())",
+                StaticError::Syntax {
+                    offset: 9,
+                    message: "not(..) only support one predicate",
+                },
+            ),
+            (
+                "cfg(unix, windows)",
+                StaticError::Syntax {
+                    offset: 17,
+                    message: "cfg(..) only support one predicate",
+                },
+            ),
+        ];
+
+        for (s, err) in errors.iter() {
+            assert_eq!(StaticPredicate::<2>::parse(s), Err(*err), "parse {}", s);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_to_predicate() {
+        cfg_if! {
+            if #[cfg(not(feature = "std"))] {
+                use alloc::format;
+                use alloc::string::ToString;
+                use alloc::vec;
+            }
+        }
+
+        let s = r#"any(all(unix, target_os = "linux"), not(windows), all(), any())"#;
+        let source = format!("cfg({})", s);
+        let cfg = StaticPredicate::<8>::parse(&source).unwrap();
+
+        assert_eq!(cfg.to_string(), s);
+        assert_eq!(
+            Predicate::from(&cfg),
+            any(vec![
+                all(vec![name("unix"), name_value("target_os", "linux")]),
+                not(name("windows")),
+                all(vec![]),
+                any(vec![]),
+            ])
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_parse_too_deep() {
+        cfg_if! {
+            if #[cfg(not(feature = "std"))] {
+                use alloc::borrow::ToOwned;
+            }
+        }
+
+        let nested = |n| "cfg(".to_owned() + &"not(".repeat(n) + "unix" + &")".repeat(n + 1);
+
+        assert!(StaticPredicate::<8>::parse(&nested(7)).is_ok());
+        assert_eq!(
+            StaticPredicate::<8>::parse(&nested(8)),
+            Err(StaticError::CapacityExceeded)
+        );
+        assert_eq!(
+            StaticPredicate::<8>::parse(&nested(100_000)),
+            Err(StaticError::CapacityExceeded)
+        );
+    }
+}
@@ -0,0 +1,320 @@
+//! A set of configuration flags.
+
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::collections::{btree_map::{self, BTreeMap}, BTreeSet};
+    } else {
+        use alloc::collections::{btree_map::{self, BTreeMap}, BTreeSet};
+        use alloc::string::String;
+    }
+}
+
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::iter::FromIterator;
+
+use crate::{Lookup, Pattern, PatternExt};
+
+/// A set of configuration flags, each flag has a name and optional values.
+///
+/// A flag may be set without any value, like `unix`,
+/// or with multiple values, like `feature = "std"` and `feature = "serde"`.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let flags: FlagSet = vec![("unix", None), ("feature", Some("std"))]
+///     .into_iter()
+///     .collect();
+///
+/// assert!(all(vec![name("unix"), feature("std")]).matches(&flags));
+/// assert!(!feature("serde").matches(&flags));
+/// ```
+///
+/// The flag sets are compared, ordered and hashed by their flags only, regardless of their generations.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(::serde::Serialize, ::serde::Deserialize),
+    serde(transparent)
+)]
+#[cfg_attr(feature = "schemars", derive(::schemars::JsonSchema))]
+pub struct FlagSet(
+    BTreeMap<String, BTreeSet<String>>,
+    #[cfg_attr(feature = "serde", serde(skip, default = "next_generation"))]
+    #[cfg_attr(feature = "schemars", schemars(skip))]
+    u64,
+);
+
+cfg_if! {
+    if #[cfg(target_has_atomic = "64")] {
+        use core::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+        /// The last generation taken by a flag set, shared by all the sets so a generation identifies a version of flags.
+        static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+        fn next_generation() -> u64 {
+            GENERATION.fetch_add(1, Relaxed) + 1
+        }
+    } else if #[cfg(target_has_atomic = "ptr")] {
+        use core::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+        /// The last generation taken by a flag set, which wraps around on the targets without 64-bit atomics.
+        static GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+        fn next_generation() -> u64 {
+            GENERATION.fetch_add(1, Relaxed).wrapping_add(1) as u64
+        }
+    } else {
+        use core::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+        /// The last generation taken by a flag set, the targets without the atomic read-modify-write,
+        /// like `thumbv6m`, are single-core so a load and a store are enough.
+        static GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+        fn next_generation() -> u64 {
+            let generation = GENERATION.load(Relaxed).wrapping_add(1);
+
+            GENERATION.store(generation, Relaxed);
+
+            generation as u64
+        }
+    }
+}
+
+impl FlagSet {
+    /// Creates an empty set.
+    pub const fn new() -> Self {
+        FlagSet(BTreeMap::new(), 0)
+    }
+
+    /// Returns the generation of the flags, which changes on every mutation.
+    ///
+    /// The generations are taken from a process-wide counter,
+    /// so two sets have the same generation only if one is a clone of the other, or both are new.
+    pub fn generation(&self) -> u64 {
+        self.1
+    }
+
+    /// Returns `true` if the flags were mutated since the generation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let mut flags = FlagSet::new();
+    /// let generation = flags.generation();
+    ///
+    /// flags.insert_name("unix");
+    ///
+    /// assert!(flags.has_changed_since(generation));
+    /// assert!(!flags.has_changed_since(flags.generation()));
+    /// ```
+    pub fn has_changed_since(&self, generation: u64) -> bool {
+        self.1 != generation
+    }
+
+    /// Sets a flag without value.
+    pub fn insert_name<S: Into<String>>(&mut self, name: S) {
+        if let btree_map::Entry::Vacant(entry) = self.0.entry(name.into()) {
+            entry.insert(BTreeSet::new());
+            self.1 = next_generation();
+        }
+    }
+
+    /// Sets a flag with value, returns `false` if the value was already set.
+    pub fn insert<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) -> bool {
+        let inserted = self.0.entry(name.into()).or_default().insert(value.into());
+
+        if inserted {
+            self.1 = next_generation();
+        }
+
+        inserted
+    }
+
+    /// Removes a flag with all its values, returns `true` if the flag was set.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let removed = self.0.remove(name).is_some();
+
+        if removed {
+            self.1 = next_generation();
+        }
+
+        removed
+    }
+
+    /// Returns `true` if the flag was set, with or without value.
+    pub fn has(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// Returns `true` if the flag was set with the value.
+    pub fn has_value(&self, name: &str, value: &str) -> bool {
+        self.0
+            .get(name)
+            .is_some_and(|values| values.contains(value))
+    }
+
+    /// Returns the values of the flag, ordered as strings.
+    pub fn values(&self, name: &str) -> impl Iterator<Item = &str> {
+        self.0
+            .get(name)
+            .into_iter()
+            .flat_map(|values| values.iter().map(String::as_str))
+    }
+
+    /// Returns the number of flags.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if no flag was set.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns an iterator over the flags and their values, ordered by name.
+    pub fn iter(&self) -> btree_map::Iter<'_, String, BTreeSet<String>> {
+        self.0.iter()
+    }
+}
+
+impl PartialEq for FlagSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for FlagSet {}
+
+impl PartialOrd for FlagSet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FlagSet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Hash for FlagSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl Pattern for FlagSet {
+    fn matches(&self, key: &str, value: Option<&str>) -> bool {
+        match value {
+            Some(value) => self.has_value(key, value),
+            None => self.has(key),
+        }
+    }
+}
+
+impl PatternExt for FlagSet {
+    fn lookup(&self, key: &str) -> Lookup<'_> {
+        match self.0.get(key) {
+            Some(values) if values.is_empty() => Lookup::Name,
+            Some(values) => Lookup::Values(values),
+            None => Lookup::Absent,
+        }
+    }
+}
+
+impl<N: Into<String>, V: Into<String>> FromIterator<(N, Option<V>)> for FlagSet {
+    fn from_iter<I: IntoIterator<Item = (N, Option<V>)>>(iter: I) -> Self {
+        let mut flags = FlagSet::new();
+
+        flags.extend(iter);
+
+        flags
+    }
+}
+
+impl<N: Into<String>, V: Into<String>> Extend<(N, Option<V>)> for FlagSet {
+    fn extend<I: IntoIterator<Item = (N, Option<V>)>>(&mut self, iter: I) {
+        for (name, value) in iter {
+            match value {
+                Some(value) => {
+                    self.insert(name, value);
+                }
+                None => self.insert_name(name),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+            use alloc::vec::Vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_flag_set() {
+        let mut flags = FlagSet::new();
+
+        flags.insert_name("unix");
+        assert!(flags.insert("feature", "std"));
+        assert!(flags.insert("feature", "serde"));
+        assert!(!flags.insert("feature", "std"));
+
+        assert_eq!(flags.len(), 2);
+        assert!(flags.has("unix"));
+        assert!(flags.has("feature"));
+        assert!(flags.has_value("feature", "std"));
+        assert!(!flags.has_value("unix", "std"));
+        assert_eq!(
+            flags.values("feature").collect::<Vec<_>>(),
+            vec!["serde", "std"]
+        );
+
+        assert!(any_features(vec!["alloc", "serde"]).matches(&flags));
+        assert!(matches!(flags.lookup("unix"), Lookup::Name));
+        assert!(flags.lookup("feature").matches(Some("serde")));
+        assert!(!flags.lookup("windows").is_present());
+        assert!(flags.remove("feature"));
+        assert!(!feature("serde").matches(&flags));
+        assert!(name("unix").matches(&flags));
+    }
+
+    #[test]
+    fn test_generation() {
+        let mut flags = FlagSet::new();
+        let generation = flags.generation();
+
+        flags.insert_name("unix");
+        assert!(flags.generation() > generation);
+
+        let generation = flags.generation();
+        let cloned = flags.clone();
+
+        flags.insert_name("unix");
+        assert!(!flags.remove("windows"));
+        assert_eq!(flags.generation(), generation);
+
+        assert!(flags.insert("feature", "std"));
+        assert!(flags.generation() > generation);
+        assert_eq!(cloned.generation(), generation);
+
+        let mut other = cloned.clone();
+
+        other.insert("feature", "std");
+        assert_eq!(other, flags);
+        assert_ne!(other.generation(), flags.generation());
+        assert!(other.has_changed_since(generation));
+        assert!(!cloned.has_changed_since(generation));
+    }
+}
@@ -0,0 +1,289 @@
+//! Syntax tree traversal to transform the nodes of an owned predicate tree.
+//!
+//! Each method of the [`Fold`] trait is a hook that can be overridden to customize the behavior
+//! when transforming the corresponding type of node. By default, every method recursively folds
+//! the sub-predicates of the node and rebuilds it by calling the free function of the same name
+//! in this module.
+//!
+//! # Example
+//!
+//! ```
+//! use runtime_cfg::{fold::Fold, *};
+//!
+//! struct Rename;
+//!
+//! impl Fold for Rename {
+//!     fn fold_name(&mut self, name: String) -> Predicate {
+//!         if name == "my_old_flag" {
+//!             Predicate::Name("my_flag".to_owned())
+//!         } else {
+//!             Predicate::Name(name)
+//!         }
+//!     }
+//! }
+//!
+//! let predicate = all(vec![name("unix"), not(name("my_old_flag"))]);
+//!
+//! assert_eq!(
+//!     predicate.fold(&mut Rename),
+//!     all(vec![name("unix"), not(name("my_flag"))])
+//! );
+//! ```
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::boxed::Box;
+        use alloc::string::String;
+        use alloc::vec::Vec;
+    }
+}
+
+use crate::Predicate;
+
+/// Syntax tree traversal to transform the nodes of an owned predicate tree.
+pub trait Fold {
+    fn fold_predicate(&mut self, predicate: Predicate) -> Predicate {
+        fold_predicate(self, predicate)
+    }
+
+    fn fold_any(&mut self, predicates: Vec<Box<Predicate>>) -> Predicate {
+        fold_any(self, predicates)
+    }
+
+    fn fold_all(&mut self, predicates: Vec<Box<Predicate>>) -> Predicate {
+        fold_all(self, predicates)
+    }
+
+    fn fold_not(&mut self, predicate: Predicate) -> Predicate {
+        fold_not(self, predicate)
+    }
+
+    fn fold_name(&mut self, name: String) -> Predicate {
+        fold_name(self, name)
+    }
+
+    fn fold_name_value(&mut self, name: String, value: String) -> Predicate {
+        fold_name_value(self, name, value)
+    }
+
+    fn fold_true(&mut self) -> Predicate {
+        Predicate::True
+    }
+
+    fn fold_false(&mut self) -> Predicate {
+        Predicate::False
+    }
+
+    fn fold_param(&mut self, name: String) -> Predicate {
+        fold_param(self, name)
+    }
+}
+
+/// Fold the predicate by dispatching on its variant.
+pub fn fold_predicate<F>(f: &mut F, predicate: Predicate) -> Predicate
+where
+    F: Fold + ?Sized,
+{
+    use Predicate::*;
+
+    match predicate {
+        Any(predicates) => f.fold_any(predicates),
+        All(predicates) => f.fold_all(predicates),
+        Not(predicate) => f.fold_not(*predicate),
+        Name(name) => f.fold_name(name),
+        NameValue(name, value) => f.fold_name_value(name, value),
+        True => f.fold_true(),
+        False => f.fold_false(),
+        Param(name) => f.fold_param(name),
+    }
+}
+
+/// Fold the sub-predicates of `any`.
+pub fn fold_any<F>(f: &mut F, predicates: Vec<Box<Predicate>>) -> Predicate
+where
+    F: Fold + ?Sized,
+{
+    Predicate::Any(
+        predicates
+            .into_iter()
+            .map(|predicate| Box::new(f.fold_predicate(*predicate)))
+            .collect(),
+    )
+}
+
+/// Fold the sub-predicates of `all`.
+pub fn fold_all<F>(f: &mut F, predicates: Vec<Box<Predicate>>) -> Predicate
+where
+    F: Fold + ?Sized,
+{
+    Predicate::All(
+        predicates
+            .into_iter()
+            .map(|predicate| Box::new(f.fold_predicate(*predicate)))
+            .collect(),
+    )
+}
+
+/// Fold the sub-predicate of `not`.
+pub fn fold_not<F>(f: &mut F, predicate: Predicate) -> Predicate
+where
+    F: Fold + ?Sized,
+{
+    Predicate::Not(Box::new(f.fold_predicate(predicate)))
+}
+
+/// Fold the predicate with name.
+pub fn fold_name<F>(_f: &mut F, name: String) -> Predicate
+where
+    F: Fold + ?Sized,
+{
+    Predicate::Name(name)
+}
+
+/// Fold the predicate with name and value.
+pub fn fold_name_value<F>(_f: &mut F, name: String, value: String) -> Predicate
+where
+    F: Fold + ?Sized,
+{
+    Predicate::NameValue(name, value)
+}
+
+/// Fold the placeholder with name.
+pub fn fold_param<F>(_f: &mut F, name: String) -> Predicate
+where
+    F: Fold + ?Sized,
+{
+    Predicate::Param(name)
+}
+
+impl Predicate {
+    /// Transform the predicate tree with the folder.
+    pub fn fold<F: Fold>(self, folder: &mut F) -> Predicate {
+        folder.fold_predicate(self)
+    }
+
+    /// Returns a predicate with the names of all flags rewritten.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let predicate = all(vec![name("my_old_flag"), name_value("my_old_flag", "1")]);
+    ///
+    /// assert_eq!(
+    ///     predicate.map_names(|name| name.replace("my_old_", "my_")),
+    ///     all(vec![name("my_flag"), name_value("my_flag", "1")])
+    /// );
+    /// ```
+    pub fn map_names<F: FnMut(&str) -> String>(&self, f: F) -> Predicate {
+        self.clone().fold(&mut MapNames(f))
+    }
+
+    /// Returns a predicate with the values of all flags rewritten.
+    ///
+    /// The closure is called with the name and value of each flag.
+    pub fn map_values<F: FnMut(&str, &str) -> String>(&self, f: F) -> Predicate {
+        self.clone().fold(&mut MapValues(f))
+    }
+}
+
+struct MapNames<F>(F);
+
+impl<F: FnMut(&str) -> String> Fold for MapNames<F> {
+    fn fold_name(&mut self, name: String) -> Predicate {
+        Predicate::Name((self.0)(&name))
+    }
+
+    fn fold_name_value(&mut self, name: String, value: String) -> Predicate {
+        Predicate::NameValue((self.0)(&name), value)
+    }
+}
+
+struct MapValues<F>(F);
+
+impl<F: FnMut(&str, &str) -> String> Fold for MapValues<F> {
+    fn fold_name_value(&mut self, name: String, value: String) -> Predicate {
+        let value = (self.0)(&name, &value);
+
+        Predicate::NameValue(name, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::borrow::ToOwned;
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    use super::*;
+
+    struct PruneTest;
+
+    impl Fold for PruneTest {
+        fn fold_all(&mut self, predicates: Vec<Box<Predicate>>) -> Predicate {
+            fold_all(
+                self,
+                predicates
+                    .into_iter()
+                    .filter(|predicate| **predicate != name("test"))
+                    .collect(),
+            )
+        }
+
+        fn fold_name_value(&mut self, name: String, value: String) -> Predicate {
+            if name == "os" {
+                name_value("target_os", value.as_str())
+            } else {
+                Predicate::NameValue(name, value)
+            }
+        }
+    }
+
+    #[test]
+    fn test_fold() {
+        let predicate = any(vec![
+            all(vec![name("test"), name_value("os", "macos")]),
+            not(all(vec![name("unix"), name("test")])),
+        ]);
+
+        assert_eq!(
+            predicate.fold(&mut PruneTest),
+            any(vec![
+                all(vec![name_value("target_os", "macos")]),
+                not(all(vec![name("unix")])),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_map() {
+        let predicate = any(vec![
+            name("old_foo"),
+            not(name_value("old_bar", "old_value")),
+        ]);
+
+        assert_eq!(
+            predicate.map_names(|name| name.trim_start_matches("old_").to_owned()),
+            any(vec![name("foo"), not(name_value("bar", "old_value"))])
+        );
+        assert_eq!(
+            predicate.map_values(|name, value| {
+                if name == "old_bar" {
+                    value.to_uppercase()
+                } else {
+                    value.to_owned()
+                }
+            }),
+            any(vec![
+                name("old_foo"),
+                not(name_value("old_bar", "OLD_VALUE"))
+            ])
+        );
+    }
+}
@@ -0,0 +1,308 @@
+//! Configurable formatting of predicates.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::string::String;
+    }
+}
+
+use core::fmt::Write;
+
+use crate::escape::Quoted;
+use crate::{Cfg, Predicate};
+
+/// The style to quote the values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum QuoteStyle {
+    /// Quotes the values as string literals with escapes, like `"a\"b"`.
+    #[default]
+    Escaped,
+    /// Quotes the values containing `"` or `\` as raw string literals, like `r#"a"b"#`.
+    Raw,
+}
+
+/// The options to format the predicates.
+///
+/// The default options produce the same output as `Display`.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let cfg = Cfg::from(all(vec![name("unix"), name_value("target_os", "linux")]));
+/// let opts = FormatOptions {
+///     max_width: Some(24),
+///     trailing_comma: true,
+///     ..Default::default()
+/// };
+///
+/// assert_eq!(
+///     cfg.format_with(&opts),
+///     r#"#[cfg(all(
+///     unix,
+///     target_os = "linux",
+/// ))]"#
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FormatOptions {
+    /// The style to quote the values.
+    pub quote_style: QuoteStyle,
+    /// Whether to put spaces around `=`.
+    pub space_around_eq: bool,
+    /// The maximum width of a line, the `any` and `all` exceeding it are wrapped one sub-predicate per line.
+    pub max_width: Option<usize>,
+    /// The number of spaces of an indentation level.
+    pub tab_spaces: usize,
+    /// Whether to put a comma after the last sub-predicate of a wrapped `any` or `all`.
+    pub trailing_comma: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            quote_style: QuoteStyle::default(),
+            space_around_eq: true,
+            max_width: None,
+            tab_spaces: 4,
+            trailing_comma: false,
+        }
+    }
+}
+
+impl Cfg {
+    /// Formats the configuration as a `#[cfg(..)]` attribute with the options.
+    pub fn format_with(&self, opts: &FormatOptions) -> String {
+        let mut s = String::from("#[cfg(");
+
+        Formatter { opts, out: &mut s }.predicate(&self.0, 0, "#[cfg(".len(), ")]".len());
+
+        s.push_str(")]");
+        s
+    }
+}
+
+impl Predicate {
+    /// Formats the predicate with the options.
+    pub fn format_with(&self, opts: &FormatOptions) -> String {
+        let mut s = String::new();
+
+        Formatter { opts, out: &mut s }.predicate(self, 0, 0, 0);
+
+        s
+    }
+}
+
+struct Formatter<'a> {
+    opts: &'a FormatOptions,
+    out: &'a mut String,
+}
+
+impl Formatter<'_> {
+    /// Writes the predicate at the indentation level and the column,
+    /// followed by `trailing` characters in the same line.
+    fn predicate(&mut self, predicate: &Predicate, indent: usize, column: usize, trailing: usize) {
+        use Predicate::*;
+
+        let start = self.out.len();
+
+        self.flat(predicate);
+
+        let fits = self
+            .opts
+            .max_width
+            .is_none_or(|width| column + self.out.len() - start + trailing <= width);
+
+        if fits {
+            return;
+        }
+
+        match predicate {
+            Any(predicates) | All(predicates) if !predicates.is_empty() => {
+                self.out.truncate(start);
+                self.out.push_str(if let Any(_) = predicate {
+                    "any(\n"
+                } else {
+                    "all(\n"
+                });
+
+                let inner = (indent + 1) * self.opts.tab_spaces;
+
+                for (i, predicate) in predicates.iter().enumerate() {
+                    let last = i + 1 == predicates.len();
+                    let comma = !last || self.opts.trailing_comma;
+
+                    self.indent(inner);
+                    self.predicate(predicate, indent + 1, inner, comma as usize);
+
+                    if comma {
+                        self.out.push(',');
+                    }
+                    self.out.push('\n');
+                }
+
+                self.indent(indent * self.opts.tab_spaces);
+                self.out.push(')');
+            }
+            Not(predicate) => {
+                self.out.truncate(start);
+                self.out.push_str("not(");
+                self.predicate(predicate, indent, column + "not(".len(), trailing + 1);
+                self.out.push(')');
+            }
+            _ => {}
+        }
+    }
+
+    fn flat(&mut self, predicate: &Predicate) {
+        use Predicate::*;
+
+        match predicate {
+            Any(predicates) | All(predicates) => {
+                self.out.push_str(if let Any(_) = predicate {
+                    "any("
+                } else {
+                    "all("
+                });
+                for (i, predicate) in predicates.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.flat(predicate);
+                }
+                self.out.push(')');
+            }
+            Not(predicate) => {
+                self.out.push_str("not(");
+                self.flat(predicate);
+                self.out.push(')');
+            }
+            NameValue(name, value) => {
+                self.out.push_str(name);
+                self.out.push_str(if self.opts.space_around_eq {
+                    " = "
+                } else {
+                    "="
+                });
+                self.value(value);
+            }
+            _ => {
+                let _ = write!(self.out, "{}", predicate);
+            }
+        }
+    }
+
+    fn value(&mut self, value: &str) {
+        let raw = self.opts.quote_style == QuoteStyle::Raw
+            && value.contains(['"', '\\'])
+            && !value.contains(|c: char| c.is_control());
+
+        if raw {
+            let mut hashes = 0;
+            let mut run = None;
+
+            for c in value.chars() {
+                run = match (c, run) {
+                    ('"', _) => Some(0),
+                    ('#', Some(n)) => Some(n + 1),
+                    _ => None,
+                };
+                if let Some(n) = run {
+                    hashes = hashes.max(n + 1);
+                }
+            }
+
+            self.out.push('r');
+            self.hashes(hashes);
+            self.out.push('"');
+            self.out.push_str(value);
+            self.out.push('"');
+            self.hashes(hashes);
+        } else {
+            let _ = write!(self.out, "{}", Quoted(value));
+        }
+    }
+
+    fn hashes(&mut self, n: usize) {
+        for _ in 0..n {
+            self.out.push('#');
+        }
+    }
+
+    fn indent(&mut self, n: usize) {
+        for _ in 0..n {
+            self.out.push(' ');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::string::ToString;
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_default() {
+        let cfg = Cfg::from(any(vec![
+            all(vec![name("unix"), name_value("target_os", r#"a"b"#)]),
+            not(Predicate::True),
+        ]));
+
+        assert_eq!(cfg.format_with(&FormatOptions::default()), cfg.to_string());
+    }
+
+    #[test]
+    fn test_format_with() {
+        let predicate = any(vec![
+            name_value("foo", r#"a"b\c"#),
+            name_value("bar", r##"x"#y"##),
+            name_value("baz", "plain"),
+        ]);
+        let opts = FormatOptions {
+            quote_style: QuoteStyle::Raw,
+            space_around_eq: false,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            predicate.format_with(&opts),
+            r###"any(foo=r#"a"b\c"#, bar=r##"x"#y"##, baz="plain")"###
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        let cfg = Cfg::from(not(all(vec![
+            name("unix"),
+            any(vec![name("foo"), name("bar")]),
+            name_value("target_os", "linux"),
+        ])));
+        let opts = FormatOptions {
+            max_width: Some(30),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            cfg.format_with(&opts),
+            r#"#[cfg(not(all(
+    unix,
+    any(foo, bar),
+    target_os = "linux"
+)))]"#
+        );
+        assert_eq!(
+            cfg.format_with(&FormatOptions {
+                max_width: Some(100),
+                ..opts
+            }),
+            cfg.to_string()
+        );
+    }
+}
@@ -0,0 +1,369 @@
+//! The process-wide flags, with the scoped overrides for the current thread.
+//!
+//! # Example
+//!
+//! ```
+//! use runtime_cfg::*;
+//!
+//! global::update(|flags| flags.insert("target_os", "linux"));
+//!
+//! assert!(name_value("target_os", "linux").matches_global());
+//!
+//! global::with_overrides(flags![target_os = "macos"], || {
+//!     assert!(name_value("target_os", "macos").matches_global());
+//!     assert!(!name_value("target_os", "linux").matches_global());
+//! });
+//!
+//! assert!(name_value("target_os", "linux").matches_global());
+//! ```
+//!
+//! The thread-local overrides don't follow a future moving across the threads of an async runtime,
+//! with the `task-local` feature, wrap the future with [`Instrumented`] to carry its flags across `.await` points.
+
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::sync::{PoisonError, RwLock};
+
+use crate::{FlagSet, Pattern, Predicate};
+
+static FLAGS: RwLock<FlagSet> = RwLock::new(FlagSet::new());
+
+thread_local! {
+    static OVERRIDES: RefCell<Vec<FlagSet>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Replaces the process-wide flags, returns the previous ones.
+pub fn set(flags: FlagSet) -> FlagSet {
+    std::mem::replace(
+        &mut *FLAGS.write().unwrap_or_else(PoisonError::into_inner),
+        flags,
+    )
+}
+
+/// Returns a copy of the process-wide flags, without the overrides.
+pub fn get() -> FlagSet {
+    FLAGS.read().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+/// Returns the generation of the process-wide flags, without the overrides.
+pub fn generation() -> u64 {
+    FLAGS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .generation()
+}
+
+/// Updates the process-wide flags in place.
+pub fn update<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut FlagSet) -> R,
+{
+    f(&mut FLAGS.write().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// Pushes a layer of flags over the process-wide ones for the current thread.
+///
+/// The thread-local layers are consulted from the last pushed one, before the process-wide flags,
+/// without locking them when the flag is found.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// // a canary request
+/// global::push(flags![feature = "canary"]);
+///
+/// assert!(feature("canary").matches_global());
+/// assert_eq!(global::pop(), Some(flags![feature = "canary"]));
+/// assert!(!feature("canary").matches_global());
+/// ```
+pub fn push(flags: FlagSet) {
+    OVERRIDES.with(|overrides| overrides.borrow_mut().push(flags))
+}
+
+/// Pops the last layer of flags pushed for the current thread.
+pub fn pop() -> Option<FlagSet> {
+    OVERRIDES.with(|overrides| overrides.borrow_mut().pop())
+}
+
+/// Returns the number of the layers of flags for the current thread.
+pub fn depth() -> usize {
+    OVERRIDES.with(|overrides| overrides.borrow().len())
+}
+
+/// Evaluates the closure with the flags layered over the process-wide ones, for the current thread.
+pub fn with_overrides<F, R>(flags: FlagSet, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let _guard = OverrideGuard::new(flags);
+
+    f()
+}
+
+/// A guard layering the flags over the process-wide ones for the current thread, until it is dropped.
+///
+/// A flag set by the overrides, with or without value, overrides all the values of the flag in the lower layers.
+///
+/// The guards should be dropped in the reverse order of their creation,
+/// dropping a guard also removes the overrides layered after it.
+#[derive(Debug)]
+#[must_use = "the overrides are removed when the guard is dropped"]
+pub struct OverrideGuard {
+    depth: usize,
+    // the overrides belong to the current thread
+    _marker: PhantomData<*const ()>,
+}
+
+impl OverrideGuard {
+    /// Layers the flags over the process-wide ones for the current thread.
+    pub fn new(flags: FlagSet) -> Self {
+        let depth = depth();
+
+        push(flags);
+
+        OverrideGuard {
+            depth,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl Drop for OverrideGuard {
+    fn drop(&mut self) {
+        OVERRIDES.with(|overrides| overrides.borrow_mut().truncate(self.depth))
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "task-local")] {
+        use std::future::Future;
+        use std::pin::Pin;
+        use std::task::{Context, Poll};
+
+        pin_project_lite::pin_project! {
+            /// A future layering its flags over the process-wide ones whenever it is polled,
+            /// so the flags follow the task across the threads of an async runtime.
+            ///
+            /// # Example
+            ///
+            /// ```
+            /// use runtime_cfg::*;
+            /// use runtime_cfg::global::WithFlags;
+            ///
+            /// async fn handle() -> bool {
+            ///     feature("canary").matches_global()
+            /// }
+            ///
+            /// let request = handle().with_flags(flags![feature = "canary"]);
+            /// # let _ = request;
+            /// ```
+            #[derive(Debug)]
+            #[must_use = "futures do nothing unless polled"]
+            pub struct Instrumented<F> {
+                #[pin]
+                inner: F,
+                flags: FlagSet,
+            }
+        }
+
+        impl<F> Instrumented<F> {
+            /// Wraps the future with the flags.
+            pub fn new(inner: F, flags: FlagSet) -> Self {
+                Instrumented { inner, flags }
+            }
+
+            /// Returns the flags of the future.
+            pub fn flags(&self) -> &FlagSet {
+                &self.flags
+            }
+
+            /// Consumes the wrapper, returns the wrapped future.
+            pub fn into_inner(self) -> F {
+                self.inner
+            }
+        }
+
+        impl<F: Future> Future for Instrumented<F> {
+            type Output = F::Output;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                let this = self.project();
+                let depth = depth();
+
+                push(std::mem::take(this.flags));
+
+                let _restore = Restore {
+                    flags: this.flags,
+                    depth,
+                };
+
+                this.inner.poll(cx)
+            }
+        }
+
+        /// Takes back the flags of the future after polling, even if it panics.
+        struct Restore<'a> {
+            flags: &'a mut FlagSet,
+            depth: usize,
+        }
+
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                OVERRIDES.with(|overrides| {
+                    let mut overrides = overrides.borrow_mut();
+
+                    overrides.truncate(self.depth + 1);
+
+                    if overrides.len() > self.depth {
+                        *self.flags = overrides.pop().unwrap_or_default();
+                    }
+                })
+            }
+        }
+
+        /// An extension of the futures, to carry the flags across `.await` points.
+        pub trait WithFlags: Future + Sized {
+            /// Layers the flags over the process-wide ones whenever the future is polled.
+            fn with_flags(self, flags: FlagSet) -> Instrumented<Self> {
+                Instrumented::new(self, flags)
+            }
+        }
+
+        impl<F: Future> WithFlags for F {}
+    }
+}
+
+/// The pattern of the process-wide flags, with the overrides of the current thread.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Global;
+
+impl Pattern for Global {
+    fn matches(&self, key: &str, value: Option<&str>) -> bool {
+        let overridden = OVERRIDES.with(|overrides| {
+            overrides
+                .borrow()
+                .iter()
+                .rev()
+                .find(|flags| flags.has(key))
+                .map(|flags| flags.matches(key, value))
+        });
+
+        overridden.unwrap_or_else(|| {
+            FLAGS
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .matches(key, value)
+        })
+    }
+}
+
+impl Predicate {
+    /// Evaluates the predicate against the process-wide flags, with the overrides of the current thread.
+    pub fn matches_global(&self) -> bool {
+        self.matches(&Global)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::*;
+
+    #[test]
+    fn test_overrides() {
+        global::update(|flags| flags.insert_name("test_overrides"));
+
+        let predicate = all(vec![name("test_overrides"), feature("beta")]);
+
+        assert!(!predicate.matches_global());
+
+        let guard = global::OverrideGuard::new(flags![feature = "beta"]);
+
+        assert!(predicate.matches_global());
+        assert!(!thread::spawn(move || predicate.matches_global())
+            .join()
+            .unwrap());
+
+        global::with_overrides(flags![feature = "gamma"], || {
+            assert!(feature("gamma").matches_global());
+            assert!(!feature("beta").matches_global());
+        });
+
+        assert!(feature("beta").matches_global());
+
+        drop(guard);
+
+        assert!(!feature("beta").matches_global());
+        assert!(global::get().has("test_overrides"));
+    }
+
+    #[test]
+    fn test_push_pop() {
+        assert_eq!(global::depth(), 0);
+        assert_eq!(global::pop(), None);
+
+        global::push(flags![unix, target_os = "linux"]);
+        global::push(flags![target_os = "macos"]);
+
+        assert_eq!(global::depth(), 2);
+        assert!(all(vec![name("unix"), name_value("target_os", "macos")]).matches_global());
+        assert!(!name_value("target_os", "linux").matches_global());
+
+        assert_eq!(global::pop(), Some(flags![target_os = "macos"]));
+        assert!(name_value("target_os", "linux").matches_global());
+
+        global::with_overrides(flags![feature = "canary"], || {
+            global::push(flags![feature = "beta"]);
+
+            assert_eq!(global::depth(), 3);
+        });
+
+        assert_eq!(global::depth(), 1);
+        assert!(global::pop().is_some());
+    }
+
+    #[cfg(feature = "task-local")]
+    #[test]
+    fn test_instrumented() {
+        use std::future::Future;
+        use std::pin::Pin;
+        use std::task::{Context, Poll, Waker};
+
+        use crate::global::WithFlags;
+
+        /// Yields once, then answers whether the canary feature is set.
+        struct Canary(bool);
+
+        impl Future for Canary {
+            type Output = bool;
+
+            fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<bool> {
+                if self.0 {
+                    Poll::Ready(feature("canary").matches_global())
+                } else {
+                    self.0 = true;
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                }
+            }
+        }
+
+        let mut task = Box::pin(Canary(false).with_flags(flags![feature = "canary"]));
+        let mut cx = Context::from_waker(Waker::noop());
+
+        assert!(task.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(global::depth(), 0);
+        assert!(!feature("canary").matches_global());
+
+        let resumed = thread::spawn(move || {
+            let mut cx = Context::from_waker(Waker::noop());
+
+            task.as_mut().poll(&mut cx)
+        });
+
+        assert_eq!(resumed.join().unwrap(), Poll::Ready(true));
+    }
+}
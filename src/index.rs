@@ -0,0 +1,829 @@
+//! An index of the `#[cfg(..)]` attributes in the source files, with the regions of code they guard.
+//!
+//! The source files are scanned lexically, the comments and literals are skipped,
+//! so the files with syntax errors or macros could be indexed too.
+//!
+//! The region guarded by an outer `#[cfg(..)]` attribute starts at the attribute,
+//! and ends after the item, field, variant, match arm or statement it is attached to.
+//! The region guarded by an inner `#![cfg(..)]` attribute ends at the end of the enclosing module, block or file.
+
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::{Cfg, Error, Pattern};
+
+/// A `#[cfg(..)]` attribute found in a source file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IndexedCfg {
+    /// The configuration of the attribute.
+    pub cfg: Cfg,
+    /// Whether it is an inner `#![cfg(..)]` attribute.
+    pub inner: bool,
+    /// The byte range of the attribute.
+    pub attr: Range<usize>,
+    /// The byte range of the guarded region, which starts at the attribute.
+    pub region: Range<usize>,
+    /// The 1-based line of the start of the region.
+    pub start_line: usize,
+    /// The 1-based line of the end of the region.
+    pub end_line: usize,
+    /// The positions of the attribute.
+    pub attr_span: Range<Position>,
+    /// The positions of the guarded region.
+    pub span: Range<Position>,
+}
+
+/// A position in a source file, like `proc_macro2::LineColumn`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    /// The 1-based line.
+    pub line: usize,
+    /// The 0-based column in characters.
+    pub column: usize,
+}
+
+impl Position {
+    /// Creates a position of the 1-based line and the 0-based column.
+    pub fn new(line: usize, column: usize) -> Self {
+        Position { line, column }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column + 1)
+    }
+}
+
+/// An error when indexing an attribute, which isn't a valid `#[cfg(..)]`.
+#[derive(Debug, Clone)]
+pub struct IndexError {
+    /// The path of the source file.
+    pub path: PathBuf,
+    /// The 1-based line of the attribute.
+    pub line: usize,
+    /// The error when parsing the attribute.
+    pub error: Error,
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.path.display(), self.line, self.error)
+    }
+}
+
+impl StdError for IndexError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct FileIndex {
+    hash: u64,
+    cfgs: Vec<IndexedCfg>,
+    errors: Vec<IndexError>,
+}
+
+/// An index of the `#[cfg(..)]` attributes in the source files.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let mut index = CfgIndex::new();
+///
+/// index.add_file("src/lib.rs", r#"
+/// #[cfg(unix)]
+/// mod unix {
+///     #[cfg(target_os = "linux")]
+///     pub fn linux() {}
+/// }
+/// "#);
+///
+/// let cfgs = index.cfgs("src/lib.rs");
+///
+/// assert_eq!(cfgs.len(), 2);
+/// assert_eq!((cfgs[0].start_line, cfgs[0].end_line), (2, 6));
+/// assert_eq!((cfgs[1].start_line, cfgs[1].end_line), (4, 5));
+/// assert!(cfgs[1].cfg.is(r#"cfg(target_os = "linux")"#));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CfgIndex {
+    files: BTreeMap<PathBuf, FileIndex>,
+}
+
+impl CfgIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads and indexes the source file.
+    pub fn add_path<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let source = fs::read_to_string(path.as_ref())?;
+
+        self.add_file(path, &source);
+
+        Ok(())
+    }
+
+    /// Indexes the source of a file, replacing the previous index of the file if any.
+    pub fn add_file<P: AsRef<Path>>(&mut self, path: P, source: &str) {
+        let path = path.as_ref().to_path_buf();
+        let file = index_file(&path, source);
+
+        self.files.insert(path, file);
+    }
+
+    /// Re-indexes the changed source of a file, returns `true` if the attributes or their regions changed.
+    ///
+    /// Only the file is scanned, and not even it if the source is unchanged,
+    /// so the index could be kept up to date on every edit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let mut index = CfgIndex::new();
+    ///
+    /// assert!(index.update_file("src/lib.rs", "#[cfg(unix)] fn f() {}"));
+    /// assert!(!index.update_file("src/lib.rs", "#[cfg(unix)] fn f() {}"));
+    /// assert!(!index.update_file("src/lib.rs", "#[cfg(unix)] fn g() {}"));
+    /// assert!(index.update_file("src/lib.rs", "#[cfg(unix)] fn f() { g() }"));
+    /// assert!(index.update_file("src/lib.rs", "#[cfg(windows)] fn f() { g() }"));
+    /// ```
+    pub fn update_file<P: AsRef<Path>>(&mut self, path: P, source: &str) -> bool {
+        let path = path.as_ref();
+
+        if let Some(file) = self.files.get(path) {
+            if file.hash == hash(source) {
+                return false;
+            }
+        }
+
+        let file = index_file(path, source);
+        let changed = self
+            .files
+            .get(path)
+            .is_none_or(|old| old.cfgs != file.cfgs || old.errors.len() != file.errors.len());
+
+        self.files.insert(path.to_path_buf(), file);
+
+        changed
+    }
+
+    /// Removes the index of a file, returns `true` if the file was indexed.
+    pub fn remove_file<P: AsRef<Path>>(&mut self, path: P) -> bool {
+        self.files.remove(path.as_ref()).is_some()
+    }
+
+    /// Returns the indexed files, in the order of their paths.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// Returns the attributes of a file, in the source order.
+    pub fn cfgs<P: AsRef<Path>>(&self, path: P) -> &[IndexedCfg] {
+        self.files
+            .get(path.as_ref())
+            .map_or(&[], |file| file.cfgs.as_slice())
+    }
+
+    /// Returns the attributes of a file whose regions contain the byte offset, the outermost first.
+    pub fn guards<P: AsRef<Path>>(
+        &self,
+        path: P,
+        offset: usize,
+    ) -> impl Iterator<Item = &IndexedCfg> {
+        self.cfgs(path)
+            .iter()
+            .filter(move |cfg| cfg.region.contains(&offset))
+    }
+
+    /// Returns the attributes of a file whose regions contain the position, the outermost first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let mut index = CfgIndex::new();
+    ///
+    /// index.add_file("src/lib.rs", "#[cfg(unix)]\nmod unix {\n    #[cfg(test)] fn test() {}\n}\n");
+    ///
+    /// let unix = vec![("unix", None::<&str>)];
+    ///
+    /// assert_eq!(index.cfgs_at("src/lib.rs", Position::new(3, 20)).count(), 2);
+    /// assert_eq!(index.cfgs_at("src/lib.rs", Position::new(3, 2)).count(), 1);
+    /// assert!(!index.is_active_at("src/lib.rs", Position::new(3, 20), &unix));
+    /// assert!(index.is_active_at("src/lib.rs", Position::new(2, 0), &unix));
+    /// ```
+    pub fn cfgs_at<P: AsRef<Path>>(
+        &self,
+        path: P,
+        position: Position,
+    ) -> impl Iterator<Item = &IndexedCfg> {
+        self.cfgs(path)
+            .iter()
+            .filter(move |cfg| cfg.span.contains(&position))
+    }
+
+    /// Returns `true` if the code at the position is active for the pattern,
+    /// that is, the configurations of all the regions containing the position match.
+    pub fn is_active_at<P: AsRef<Path>, T: Pattern>(
+        &self,
+        path: P,
+        position: Position,
+        pattern: &T,
+    ) -> bool {
+        self.cfgs_at(path, position)
+            .all(|cfg| cfg.cfg.matches(pattern))
+    }
+
+    /// Returns the attributes of all the files, with their paths.
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &IndexedCfg)> {
+        self.files
+            .iter()
+            .flat_map(|(path, file)| file.cfgs.iter().map(move |cfg| (path.as_path(), cfg)))
+    }
+
+    /// Returns the attributes which can't be parsed.
+    pub fn errors(&self) -> impl Iterator<Item = &IndexError> {
+        self.files.values().flat_map(|file| file.errors.iter())
+    }
+
+    /// Returns the number of the attributes in all the files.
+    pub fn len(&self) -> usize {
+        self.files.values().map(|file| file.cfgs.len()).sum()
+    }
+
+    /// Returns `true` if there is no attribute.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn index_file(path: &Path, source: &str) -> FileIndex {
+    let lines = line_starts(source);
+    let line_of = |offset: usize| lines.partition_point(|&start| start <= offset);
+    let position = |offset: usize| {
+        let line = line_of(offset);
+
+        Position {
+            line,
+            column: source[lines[line - 1]..offset].chars().count(),
+        }
+    };
+    let mut file = FileIndex {
+        hash: hash(source),
+        ..FileIndex::default()
+    };
+
+    for (attr, inner) in scan(source) {
+        let text = &source[attr.clone()];
+        let parsed = if inner {
+            Cfg::parse(format!(
+                "#{}",
+                text[1..].trim_start().trim_start_matches('!')
+            ))
+        } else {
+            Cfg::parse(text)
+        };
+
+        match parsed {
+            Ok(cfg) => {
+                let end = if inner {
+                    enclosing_end(source, attr.end)
+                } else {
+                    item_end(source, attr.end)
+                };
+                let region = attr.start..end;
+
+                file.cfgs.push(IndexedCfg {
+                    cfg,
+                    inner,
+                    start_line: line_of(region.start),
+                    end_line: line_of(region.end.saturating_sub(1).max(region.start)),
+                    attr_span: position(attr.start)..position(attr.end),
+                    span: position(region.start)..position(region.end),
+                    attr,
+                    region,
+                });
+            }
+            Err(error) => file.errors.push(IndexError {
+                path: path.to_path_buf(),
+                line: line_of(attr.start),
+                error,
+            }),
+        }
+    }
+
+    file
+}
+
+fn hash(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn line_starts(source: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+
+    starts.extend(source.match_indices('\n').map(|(idx, _)| idx + 1));
+    starts
+}
+
+fn is_ident(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Returns the byte ranges of the `#[cfg(..)]` and `#![cfg(..)]` attributes, and whether they are inner.
+fn scan(source: &str) -> Vec<(Range<usize>, bool)> {
+    let bytes = source.as_bytes();
+    let mut attrs = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if let Some(end) = skip_literal(source, i) {
+            i = end;
+        } else if bytes[i] == b'#' {
+            match attribute(source, i) {
+                Some((end, inner, true)) => {
+                    attrs.push((i..end, inner));
+                    i = end;
+                }
+                Some((end, _, false)) => i = end,
+                None => i += 1,
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    attrs
+}
+
+/// Parses an attribute at the `#`, returns its end, whether it is inner and whether it is `cfg`.
+fn attribute(source: &str, start: usize) -> Option<(usize, bool, bool)> {
+    let bytes = source.as_bytes();
+    let mut i = skip_whitespace(source, start + 1);
+    let inner = bytes.get(i) == Some(&b'!');
+
+    if inner {
+        i = skip_whitespace(source, i + 1);
+    }
+
+    if bytes.get(i) != Some(&b'[') {
+        return None;
+    }
+
+    let end = skip_group(source, i)?;
+    let content = source[i + 1..end - 1].trim_start();
+    let is_cfg = content.strip_prefix("cfg").is_some_and(|rest| {
+        !rest.bytes().next().is_some_and(is_ident) && rest.trim_start().starts_with('(')
+    });
+
+    Some((end, inner, is_cfg))
+}
+
+fn skip_whitespace(source: &str, mut i: usize) -> usize {
+    let bytes = source.as_bytes();
+
+    while i < bytes.len() {
+        if bytes[i].is_ascii_whitespace() {
+            i += 1;
+        } else if let Some(end) = skip_comment(source, i) {
+            i = end;
+        } else {
+            break;
+        }
+    }
+
+    i
+}
+
+fn skip_comment(source: &str, i: usize) -> Option<usize> {
+    let rest = &source[i..];
+
+    if rest.starts_with("//") {
+        Some(rest.find('\n').map_or(source.len(), |idx| i + idx + 1))
+    } else if rest.starts_with("/*") {
+        let bytes = source.as_bytes();
+        let mut depth = 0;
+        let mut j = i;
+
+        while j + 1 < bytes.len() {
+            match &bytes[j..j + 2] {
+                b"/*" => {
+                    depth += 1;
+                    j += 2;
+                }
+                b"*/" => {
+                    depth -= 1;
+                    j += 2;
+
+                    if depth == 0 {
+                        return Some(j);
+                    }
+                }
+                _ => j += 1,
+            }
+        }
+
+        Some(source.len())
+    } else {
+        None
+    }
+}
+
+/// Skips a comment, a string, a raw string or a character literal, returns its end.
+fn skip_literal(source: &str, i: usize) -> Option<usize> {
+    let bytes = source.as_bytes();
+
+    if let Some(end) = skip_comment(source, i) {
+        return Some(end);
+    }
+
+    match bytes[i] {
+        b'"' => {
+            let mut j = i + 1;
+
+            while j < bytes.len() {
+                match bytes[j] {
+                    b'\\' => j += 2,
+                    b'"' => return Some(j + 1),
+                    _ => j += 1,
+                }
+            }
+
+            Some(bytes.len())
+        }
+        b'r' if i == 0
+            || !is_ident(bytes[i - 1])
+            || (bytes[i - 1] == b'b' && (i == 1 || !is_ident(bytes[i - 2]))) =>
+        {
+            let hashes = bytes[i + 1..].iter().take_while(|&&b| b == b'#').count();
+
+            if bytes.get(i + 1 + hashes) != Some(&b'"') {
+                return None;
+            }
+
+            let closing = format!("\"{}", "#".repeat(hashes));
+            let body = i + 2 + hashes;
+
+            Some(
+                source[body..]
+                    .find(&closing)
+                    .map_or(source.len(), |idx| body + idx + closing.len()),
+            )
+        }
+        b'\'' => {
+            let mut chars = source[i + 1..].char_indices();
+
+            match chars.next() {
+                Some((_, '\\')) => {
+                    chars.next();
+                    chars
+                        .find(|&(_, c)| c == '\'')
+                        .map(|(idx, _)| i + 1 + idx + 1)
+                }
+                Some(_) => match chars.next() {
+                    Some((idx, '\'')) => Some(i + 1 + idx + 1),
+                    _ => None,
+                },
+                None => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Skips a delimited group at the opening delimiter, returns the end after the closing delimiter.
+fn skip_group(source: &str, start: usize) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut depth = 0;
+    let mut i = start;
+
+    while i < bytes.len() {
+        if let Some(end) = skip_literal(source, i) {
+            i = end;
+            continue;
+        }
+
+        match bytes[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    None
+}
+
+/// Returns the end of the item after its outer attribute.
+fn item_end(source: &str, mut i: usize) -> usize {
+    let bytes = source.as_bytes();
+
+    // skip the other attributes of the item
+    loop {
+        i = skip_whitespace(source, i);
+
+        match bytes.get(i) {
+            Some(b'#') => match attribute(source, i) {
+                Some((end, false, _)) => i = end,
+                _ => break,
+            },
+            _ => break,
+        }
+    }
+
+    let mut angles = 0usize;
+
+    while i < bytes.len() {
+        if let Some(end) = skip_literal(source, i) {
+            i = end;
+            continue;
+        }
+
+        match bytes[i] {
+            b'(' | b'[' | b'{' => {
+                let Some(end) = skip_group(source, i) else {
+                    return bytes.len();
+                };
+
+                if bytes[i] == b'{' && angles == 0 {
+                    let next = skip_whitespace(source, end);
+
+                    return if bytes.get(next) == Some(&b';') {
+                        next + 1
+                    } else {
+                        end
+                    };
+                }
+
+                i = end;
+                continue;
+            }
+            b';' => return i + 1,
+            b',' if angles == 0 => return i,
+            b')' | b']' | b'}' => return i,
+            b'<' => angles += 1,
+            b'>' if i > 0 && (bytes[i - 1] == b'-' || bytes[i - 1] == b'=') => {}
+            b'>' => angles = angles.saturating_sub(1),
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    bytes.len()
+}
+
+/// Returns the end of the enclosing module, block or file, at its closing delimiter.
+fn enclosing_end(source: &str, mut i: usize) -> usize {
+    let bytes = source.as_bytes();
+
+    while i < bytes.len() {
+        if let Some(end) = skip_literal(source, i) {
+            i = end;
+            continue;
+        }
+
+        match bytes[i] {
+            b'(' | b'[' | b'{' => match skip_group(source, i) {
+                Some(end) => i = end,
+                None => return bytes.len(),
+            },
+            b')' | b']' | b'}' => return i,
+            _ => i += 1,
+        }
+    }
+
+    bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    const SOURCE: &str = r##"#![cfg(feature = "std")]
+
+// #[cfg(commented)]
+/* #[cfg(block /* nested */ comment)] */
+const S: &str = "#[cfg(string)]";
+const R: &str = r#"#[cfg(raw)]"#;
+const C: char = '#';
+
+#[cfg(unix)]
+#[derive(Debug)]
+struct Handle<'a> {
+    #[cfg(target_os = "linux")]
+    fd: HashMap<i32, &'a str>,
+    #[cfg_attr(windows, allow(unused))]
+    name: &'a str,
+}
+
+#[cfg(windows)]
+fn f() -> Result<(), ()> {
+    match 1 {
+        #[cfg(test)]
+        1 => {}
+        _ => {}
+    }
+}
+
+#[cfg(all(unix, not(test)))] use std::{io, fs};
+
+#[cfg(invalid(test))]
+fn g() {}
+"##;
+
+    #[test]
+    fn test_index() {
+        let cfg = |s: &str| Cfg::parse(s).unwrap();
+        let mut index = CfgIndex::new();
+
+        index.add_file("lib.rs", SOURCE);
+
+        let cfgs = index
+            .cfgs("lib.rs")
+            .iter()
+            .map(|cfg| {
+                (
+                    cfg.cfg.clone(),
+                    cfg.inner,
+                    &SOURCE[cfg.region.clone()],
+                    cfg.start_line,
+                    cfg.end_line,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            cfgs,
+            vec![
+                (cfg(r#"#[cfg(feature = "std")]"#), true, SOURCE, 1, 30),
+                (
+                    cfg("#[cfg(unix)]"),
+                    false,
+                    &SOURCE[SOURCE.find("#[cfg(unix)]").unwrap()
+                        ..SOURCE.find("}\n\n#[cfg(windows)]").unwrap() + 1],
+                    9,
+                    16
+                ),
+                (
+                    cfg(r#"#[cfg(target_os = "linux")]"#),
+                    false,
+                    "#[cfg(target_os = \"linux\")]\n    fd: HashMap<i32, &'a str>",
+                    12,
+                    13
+                ),
+                (
+                    cfg("#[cfg(windows)]"),
+                    false,
+                    &SOURCE[SOURCE.find("#[cfg(windows)]").unwrap()
+                        ..SOURCE.find("}\n\n#[cfg(all").unwrap() + 1],
+                    18,
+                    25
+                ),
+                (
+                    cfg("#[cfg(test)]"),
+                    false,
+                    "#[cfg(test)]\n        1 => {}",
+                    21,
+                    22
+                ),
+                (
+                    cfg("#[cfg(all(unix, not(test)))]"),
+                    false,
+                    "#[cfg(all(unix, not(test)))] use std::{io, fs};",
+                    27,
+                    27
+                ),
+            ]
+        );
+
+        let errors = index.errors().collect::<Vec<_>>();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 29);
+        assert!(errors[0].to_string().starts_with("lib.rs:29: "));
+
+        assert_eq!(
+            index
+                .guards("lib.rs", SOURCE.find("fd:").unwrap())
+                .map(|cfg| cfg.start_line)
+                .collect::<Vec<_>>(),
+            vec![1, 9, 12]
+        );
+        assert_eq!(index.len(), 6);
+        assert_eq!(index.iter().count(), 6);
+        assert_eq!(
+            index.files().collect::<Vec<_>>(),
+            vec![std::path::Path::new("lib.rs")]
+        );
+        assert!(index.remove_file("lib.rs"));
+        assert!(index.is_empty());
+        assert!(index.cfgs("lib.rs").is_empty());
+    }
+
+    #[test]
+    fn test_update_file() {
+        let mut index = CfgIndex::new();
+
+        index.add_file("lib.rs", SOURCE);
+        index.add_file("main.rs", "#[cfg(unix)] fn main() {}");
+
+        assert!(!index.update_file("lib.rs", SOURCE));
+        assert!(!index.update_file("lib.rs", &SOURCE.replace("HashMap", "HashSet")));
+
+        let source = SOURCE.replace("#[cfg(test)]", "");
+
+        assert!(index.update_file("lib.rs", &source));
+        assert_eq!(index.len(), 6);
+        assert_eq!(index.cfgs("main.rs").len(), 1);
+
+        assert!(index.update_file("lib.rs", &source.replace("invalid(test)", "test")));
+        assert_eq!(index.errors().count(), 0);
+        assert_eq!(index.len(), 7);
+
+        assert!(index.update_file("new.rs", ""));
+        assert_eq!(index.files().count(), 3);
+    }
+
+    #[test]
+    fn test_positions() {
+        let mut index = CfgIndex::new();
+
+        index.add_file("lib.rs", SOURCE);
+
+        let cfgs = index.cfgs("lib.rs");
+
+        assert_eq!(
+            cfgs[2].attr_span,
+            Position::new(12, 4)..Position::new(12, 31)
+        );
+        assert_eq!(cfgs[2].span, Position::new(12, 4)..Position::new(13, 29));
+        assert_eq!(cfgs[0].span, Position::new(1, 0)..Position::new(31, 0));
+
+        let field = Position::new(13, 8);
+        let linux = vec![
+            ("unix", None),
+            ("target_os", Some("linux")),
+            ("feature", Some("std")),
+        ];
+        let macos = vec![
+            ("unix", None),
+            ("target_os", Some("macos")),
+            ("feature", Some("std")),
+        ];
+
+        assert_eq!(
+            index
+                .cfgs_at("lib.rs", field)
+                .map(|cfg| cfg.start_line)
+                .collect::<Vec<_>>(),
+            vec![1, 9, 12]
+        );
+        assert!(index.is_active_at("lib.rs", field, &linux));
+        assert!(!index.is_active_at("lib.rs", field, &macos));
+        assert!(index.is_active_at("lib.rs", Position::new(15, 4), &macos));
+        assert!(index.is_active_at("main.rs", field, &macos));
+        assert_eq!(Position::new(13, 8).to_string(), "13:9");
+    }
+
+    #[cfg(feature = "no-panic")]
+    #[test]
+    fn test_add_file_too_deep() {
+        let mut index = CfgIndex::new();
+        let source = "#[cfg(".to_owned()
+            + &"not(".repeat(100_000)
+            + "unix"
+            + &")".repeat(100_001)
+            + "]\nfn f() {}\n";
+
+        index.add_file("src/lib.rs", &source);
+
+        assert!(index.is_empty());
+        assert_eq!(index.errors().count(), 1);
+    }
+}
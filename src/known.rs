@@ -0,0 +1,536 @@
+//! The well-known configuration names of rustc and their value domains, like `--check-cfg`.
+
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::collections::{BTreeMap, BTreeSet};
+    } else {
+        use alloc::collections::{BTreeMap, BTreeSet};
+        use alloc::string::{String, ToString};
+        use alloc::vec::Vec;
+    }
+}
+
+use core::fmt;
+
+use crate::Predicate;
+
+/// The names set without value.
+const NAMES: &[&str] = &[
+    "debug_assertions",
+    "doc",
+    "doctest",
+    "miri",
+    "overflow_checks",
+    "proc_macro",
+    "sanitizer_cfi_generalize_pointers",
+    "sanitizer_cfi_normalize_integers",
+    "test",
+    "ub_checks",
+    "unix",
+    "windows",
+];
+
+/// The names set with any value.
+const OPEN_NAMES: &[&str] = &["feature", "target_feature"];
+
+/// The names set with the values of a closed domain.
+const VALUES: &[(&str, &[&str])] = &[
+    ("panic", &["abort", "unwind"]),
+    (
+        "relocation_model",
+        &[
+            "dynamic-no-pic",
+            "pic",
+            "pie",
+            "ropi",
+            "ropi-rwpi",
+            "rwpi",
+            "static",
+        ],
+    ),
+    (
+        "sanitize",
+        &[
+            "address",
+            "cfi",
+            "dataflow",
+            "hwaddress",
+            "kcfi",
+            "kernel-address",
+            "leak",
+            "memory",
+            "memtag",
+            "safestack",
+            "shadow-call-stack",
+            "thread",
+        ],
+    ),
+    (
+        "target_abi",
+        &[
+            "",
+            "abi64",
+            "abiv2",
+            "abiv2hf",
+            "eabi",
+            "eabihf",
+            "elf",
+            "fortanix",
+            "ilp32",
+            "ilp32e",
+            "llvm",
+            "macabi",
+            "sim",
+            "softfloat",
+            "spe",
+            "uwp",
+            "vec-extabi",
+            "x32",
+        ],
+    ),
+    (
+        "target_arch",
+        &[
+            "aarch64",
+            "amdgpu",
+            "arm",
+            "arm64ec",
+            "avr",
+            "bpf",
+            "csky",
+            "hexagon",
+            "loongarch32",
+            "loongarch64",
+            "m68k",
+            "mips",
+            "mips32r6",
+            "mips64",
+            "mips64r6",
+            "msp430",
+            "nvptx64",
+            "powerpc",
+            "powerpc64",
+            "riscv32",
+            "riscv64",
+            "s390x",
+            "sparc",
+            "sparc64",
+            "wasm32",
+            "wasm64",
+            "x86",
+            "x86_64",
+            "xtensa",
+        ],
+    ),
+    ("target_endian", &["big", "little"]),
+    (
+        "target_env",
+        &[
+            "",
+            "gnu",
+            "msvc",
+            "musl",
+            "newlib",
+            "nto70",
+            "nto71",
+            "nto71_iosock",
+            "nto80",
+            "ohos",
+            "p1",
+            "p2",
+            "psx",
+            "relibc",
+            "sgx",
+            "uclibc",
+            "v5",
+        ],
+    ),
+    ("target_family", &["unix", "wasm", "windows"]),
+    ("target_has_atomic", &["8", "16", "32", "64", "128", "ptr"]),
+    (
+        "target_os",
+        &[
+            "aix",
+            "amdhsa",
+            "android",
+            "cuda",
+            "cygwin",
+            "dragonfly",
+            "emscripten",
+            "espidf",
+            "freebsd",
+            "fuchsia",
+            "haiku",
+            "hermit",
+            "horizon",
+            "hurd",
+            "illumos",
+            "ios",
+            "l4re",
+            "linux",
+            "lynxos178",
+            "macos",
+            "managarm",
+            "motor",
+            "netbsd",
+            "none",
+            "nto",
+            "nuttx",
+            "openbsd",
+            "psp",
+            "qurt",
+            "redox",
+            "rtems",
+            "solaris",
+            "solid_asp3",
+            "teeos",
+            "trusty",
+            "tvos",
+            "uefi",
+            "unknown",
+            "visionos",
+            "vita",
+            "vxworks",
+            "wasi",
+            "watchos",
+            "windows",
+            "xous",
+            "zkvm",
+        ],
+    ),
+    ("target_pointer_width", &["16", "32", "64"]),
+    (
+        "target_vendor",
+        &[
+            "amd",
+            "apple",
+            "espressif",
+            "fortanix",
+            "ibm",
+            "kmc",
+            "mti",
+            "nintendo",
+            "nvidia",
+            "openwrt",
+            "pc",
+            "risc0",
+            "sony",
+            "sun",
+            "unikraft",
+            "unknown",
+            "uwp",
+            "vex",
+            "win7",
+            "wrs",
+        ],
+    ),
+];
+
+/// The domain of a known configuration name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Domain {
+    /// The name is set without value, like `unix`.
+    Name,
+    /// The name is set with any value, like `feature`.
+    AnyValue,
+    /// The name is set with one of the values, like `panic`.
+    Values(BTreeSet<String>),
+}
+
+impl Domain {
+    /// Returns `true` if the flag is in the domain.
+    pub fn contains(&self, value: Option<&str>) -> bool {
+        match (self, value) {
+            (Domain::Name, None) => true,
+            (Domain::AnyValue, Some(_)) => true,
+            (Domain::Values(values), Some(value)) => values.contains(value),
+            _ => false,
+        }
+    }
+}
+
+/// A table of the known configuration names and their value domains.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let mut known = KnownCfgs::rustc();
+///
+/// assert!(known.contains("panic", Some("unwind")));
+/// assert!(!known.contains("panic", Some("crash")));
+///
+/// known.add_values("panic", vec!["crash"]).add_name("tokio_unstable");
+///
+/// assert!(known.contains("panic", Some("crash")));
+/// assert!(known.contains("tokio_unstable", None));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct KnownCfgs(BTreeMap<String, Domain>);
+
+impl KnownCfgs {
+    /// Creates an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a table of the well-known configuration names of rustc.
+    ///
+    /// Like `--check-cfg`, the `feature` and `target_feature` accept any value.
+    pub fn rustc() -> Self {
+        let mut known = KnownCfgs::new();
+
+        for name in NAMES {
+            known.add_name(*name);
+        }
+
+        for name in OPEN_NAMES {
+            known.add_any_value(*name);
+        }
+
+        for (name, values) in VALUES {
+            known.add_values(*name, values.iter().copied());
+        }
+
+        known
+    }
+
+    /// Adds a name set without value.
+    pub fn add_name<S: Into<String>>(&mut self, name: S) -> &mut Self {
+        self.0.insert(name.into(), Domain::Name);
+        self
+    }
+
+    /// Adds a name set with any value.
+    pub fn add_any_value<S: Into<String>>(&mut self, name: S) -> &mut Self {
+        self.0.insert(name.into(), Domain::AnyValue);
+        self
+    }
+
+    /// Adds the values to the domain of a name, which is created if it isn't known.
+    ///
+    /// The values are ignored if the name accepts any value,
+    /// and replace the domain if the name is set without value.
+    pub fn add_values<S, I, V>(&mut self, name: S, values: I) -> &mut Self
+    where
+        S: Into<String>,
+        I: IntoIterator<Item = V>,
+        V: ToString,
+    {
+        let domain = self
+            .0
+            .entry(name.into())
+            .or_insert_with(|| Domain::Values(BTreeSet::new()));
+
+        if *domain == Domain::Name {
+            *domain = Domain::Values(BTreeSet::new());
+        }
+
+        if let Domain::Values(ref mut known) = domain {
+            known.extend(values.into_iter().map(|value| value.to_string()));
+        }
+
+        self
+    }
+
+    /// Removes a name, returns its domain if it is known.
+    pub fn remove(&mut self, name: &str) -> Option<Domain> {
+        self.0.remove(name)
+    }
+
+    /// Returns the domain of a name.
+    pub fn get(&self, name: &str) -> Option<&Domain> {
+        self.0.get(name)
+    }
+
+    /// Returns `true` if the flag is known.
+    pub fn contains(&self, name: &str, value: Option<&str>) -> bool {
+        self.get(name).is_some_and(|domain| domain.contains(value))
+    }
+
+    /// Returns an iterator over the known names and their domains.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Domain)> {
+        self.0.iter().map(|(name, domain)| (name.as_str(), domain))
+    }
+}
+
+/// The kind of an unknown flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(::serde::Serialize, ::serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum UnknownKind {
+    /// The name is unknown.
+    Name,
+    /// The value isn't in the domain of the name.
+    Value,
+    /// The name is set without value, but compared with a value.
+    UnexpectedValue,
+    /// The name is set with values, but used without value.
+    MissingValue,
+}
+
+impl UnknownKind {
+    /// Returns the description of the unknown flag.
+    pub fn message(&self) -> &'static str {
+        match self {
+            UnknownKind::Name => "unknown configuration name",
+            UnknownKind::Value => "unknown configuration value",
+            UnknownKind::UnexpectedValue => "configuration name doesn't take a value",
+            UnknownKind::MissingValue => "configuration name expects a value",
+        }
+    }
+}
+
+impl fmt::Display for UnknownKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+/// An unknown flag found in a predicate, with the offending leaf.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct UnknownCfg<'a> {
+    /// The kind of the unknown flag.
+    pub kind: UnknownKind,
+    /// The offending `Name` or `NameValue` leaf.
+    pub predicate: &'a Predicate,
+}
+
+impl Predicate {
+    /// Returns the leaves of the predicate which aren't known, in the source order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let predicate = all(vec![
+    ///     name("unix"),
+    ///     name_value("panic", "unwind"),
+    ///     name_value("target_os", "linx"),
+    ///     name("sanitize"),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     predicate.check_known(&KnownCfgs::rustc()),
+    ///     vec![
+    ///         UnknownCfg {
+    ///             kind: UnknownKind::Value,
+    ///             predicate: &name_value("target_os", "linx"),
+    ///         },
+    ///         UnknownCfg {
+    ///             kind: UnknownKind::MissingValue,
+    ///             predicate: &name("sanitize"),
+    ///         },
+    ///     ]
+    /// );
+    /// ```
+    pub fn check_known(&self, known: &KnownCfgs) -> Vec<UnknownCfg<'_>> {
+        self.leaves()
+            .filter_map(|predicate| {
+                let (name, value) = match predicate {
+                    Predicate::Name(name) => (name, None),
+                    Predicate::NameValue(name, value) => (name, Some(value.as_str())),
+                    _ => return None,
+                };
+
+                let kind = match (known.get(name), value) {
+                    (None, _) => UnknownKind::Name,
+                    (Some(domain), value) if domain.contains(value) => return None,
+                    (Some(Domain::Name), Some(_)) => UnknownKind::UnexpectedValue,
+                    (Some(_), None) => UnknownKind::MissingValue,
+                    (Some(_), Some(_)) => UnknownKind::Value,
+                };
+
+                Some(UnknownCfg { kind, predicate })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::string::ToString;
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_rustc() {
+        let known = KnownCfgs::rustc();
+
+        assert!(known.contains("unix", None));
+        assert!(!known.contains("unix", Some("yes")));
+        assert!(known.contains("feature", Some("serde")));
+        assert!(!known.contains("feature", None));
+        assert!(known.contains("target_has_atomic", Some("ptr")));
+        assert!(!known.contains("target_has_atomic", Some("256")));
+        assert!(known.contains("relocation_model", Some("pic")));
+        assert!(known.contains("sanitize", Some("address")));
+        assert!(known.contains("target_abi", Some("")));
+        assert!(!known.contains("tokio_unstable", None));
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut known = KnownCfgs::new();
+
+        known
+            .add_name("unix")
+            .add_values("unix", vec!["yes"])
+            .add_any_value("feature")
+            .add_values("feature", vec!["std"])
+            .add_values("panic", vec!["abort"])
+            .add_values("panic", vec!["unwind"]);
+
+        assert_eq!(
+            known.get("unix"),
+            Some(&Domain::Values(
+                vec!["yes".to_string()].into_iter().collect()
+            ))
+        );
+        assert_eq!(known.get("feature"), Some(&Domain::AnyValue));
+        assert!(known.contains("panic", Some("abort")));
+        assert!(known.contains("panic", Some("unwind")));
+        assert_eq!(known.iter().count(), 3);
+        assert!(known.remove("panic").is_some());
+        assert!(!known.contains("panic", Some("abort")));
+    }
+
+    #[test]
+    fn test_check_known() {
+        let predicate = any(vec![
+            name_value("unix", "yes"),
+            not(name("tokio_unstable")),
+            name_value("target_has_atomic", "64"),
+            name_value("target_has_atomic", "256"),
+        ]);
+
+        assert_eq!(
+            predicate.check_known(&KnownCfgs::rustc()),
+            vec![
+                UnknownCfg {
+                    kind: UnknownKind::UnexpectedValue,
+                    predicate: &name_value("unix", "yes"),
+                },
+                UnknownCfg {
+                    kind: UnknownKind::Name,
+                    predicate: &name("tokio_unstable"),
+                },
+                UnknownCfg {
+                    kind: UnknownKind::Value,
+                    predicate: &name_value("target_has_atomic", "256"),
+                },
+            ]
+        );
+    }
+}
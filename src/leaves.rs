@@ -0,0 +1,114 @@
+//! Iterating the leaves of a predicate tree.
+
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::collections::BTreeSet;
+    } else {
+        use alloc::collections::BTreeSet;
+        use alloc::vec;
+        use alloc::vec::Vec;
+    }
+}
+
+use crate::Predicate;
+
+/// An iterator over the `Name` and `NameValue` leaves of a predicate, in the source order.
+#[derive(Debug, Clone)]
+pub struct Leaves<'a> {
+    stack: Vec<&'a Predicate>,
+}
+
+impl<'a> Iterator for Leaves<'a> {
+    type Item = &'a Predicate;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use Predicate::*;
+
+        while let Some(predicate) = self.stack.pop() {
+            match predicate {
+                Any(predicates) | All(predicates) => self
+                    .stack
+                    .extend(predicates.iter().rev().map(|predicate| &**predicate)),
+                Not(predicate) => self.stack.push(predicate),
+                Name(_) | NameValue(_, _) => return Some(predicate),
+                True | False | Param(_) => {}
+            }
+        }
+
+        None
+    }
+}
+
+impl Predicate {
+    /// Returns an iterator over the `Name` and `NameValue` leaves of the predicate.
+    pub fn leaves(&self) -> Leaves<'_> {
+        Leaves { stack: vec![self] }
+    }
+
+    /// Returns the names of the configuration flags referenced by the predicate.
+    pub fn referenced_names(&self) -> BTreeSet<&str> {
+        self.referenced_flags()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Returns the configuration flags referenced by the predicate, with their values if any.
+    pub fn referenced_flags(&self) -> BTreeSet<(&str, Option<&str>)> {
+        use Predicate::*;
+
+        self.leaves()
+            .filter_map(|predicate| match predicate {
+                Name(name) => Some((name.as_str(), None)),
+                NameValue(name, value) => Some((name.as_str(), Some(value.as_str()))),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+            use alloc::vec::Vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_leaves() {
+        let predicate = any(vec![
+            name("foo"),
+            all(vec![
+                not(name_value("target_os", "macos")),
+                name_value("target_os", "linux"),
+            ]),
+            name("foo"),
+        ]);
+
+        assert_eq!(
+            predicate.leaves().collect::<Vec<_>>(),
+            vec![
+                &name("foo"),
+                &name_value("target_os", "macos"),
+                &name_value("target_os", "linux"),
+                &name("foo"),
+            ]
+        );
+        assert_eq!(
+            predicate.referenced_names().into_iter().collect::<Vec<_>>(),
+            vec!["foo", "target_os"]
+        );
+        assert_eq!(
+            predicate.referenced_flags().into_iter().collect::<Vec<_>>(),
+            vec![
+                ("foo", None),
+                ("target_os", Some("linux")),
+                ("target_os", Some("macos"))
+            ]
+        );
+    }
+}
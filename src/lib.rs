@@ -20,117 +20,248 @@
 //! assert!(cfg.matches(&flags));
 //! # }
 //! ```
+//!
+//! # Panics
+//!
+//! Parsing and matching never panic on any input,
+//! the malformed predicates, like a byte string value that isn't valid UTF-8, are returned as errors.
+//!
+//! With the `no-panic` feature, the parsers and decoders also reject the predicates nested deeper than
+//! `MAX_NESTING`, so the recursion of parsing and matching the parsed predicates can't overflow the stack.
+//! It applies to `Cfg::parse` and the conversions from the tokens, attributes and metas, `Lossless::parse`,
+//! `CfgIndex`, `evaluate_attrs`, `diagnostics`, the deserializers, `Predicate::from_sexpr`, `Predicate::decode`
+//! and `FlagSet::from_nix`, while `StaticPredicate::parse` is always bounded by its capacity.
+//! The predicates built in code aren't limited, check them with [`Predicate::check_nesting`] if needed.
+//!
+//! # Determinism
+//!
+//! The flags, tables and indexes are kept in ordered collections,
+//! so every output, like the printed predicates, the lints, the reports or the matching targets,
+//! has the same order from run to run and on every platform.
 #![cfg_attr(not(feature = "std"), no_std)]
 
 #[macro_use]
 extern crate cfg_if;
 
+#[cfg(feature = "macros")]
+extern crate self as runtime_cfg;
+
+#[cfg(feature = "alloc")]
+#[macro_use]
+mod macros;
+
+mod borrowed;
+mod escape;
+mod fixed;
 mod matches;
 
-pub use matches::{Matcher, Pattern};
+pub use borrowed::PredicateRef;
+pub use fixed::{StaticError, StaticPredicate};
+pub use matches::{Lookup, Matcher, Pattern, PatternExt};
+
+cfg_if! {
+    if #[cfg(feature = "alloc")] {
+        mod adapters;
+        mod aliases;
+        mod atomic;
+        pub mod bazel;
+        mod builder;
+        mod cache;
+        mod canonical;
+        mod collect;
+        mod context;
+        mod cost;
+        mod encoding;
+        mod error;
+        mod explain;
+        mod fingerprint;
+        mod flags;
+        pub mod fold;
+        mod known;
+        mod leaves;
+        mod lint;
+        mod map;
+        mod metadata;
+        mod metrics;
+        mod named;
+        mod nix;
+        mod params;
+        mod partition;
+        mod path;
+        mod predicate;
+        mod prepared;
+        mod rewrite;
+        mod rollout;
+        mod router;
+        pub mod rust_analyzer;
+        mod sexpr;
+        mod shared;
+        mod simplify;
+        mod specificity;
+        pub mod visit;
+
+        pub use adapters::{Mapped, Prefixed, Recording, WithDefaults};
+        pub use aliases::Aliases;
+        pub use atomic::{target_has_atomic, target_has_atomic_ptr, TARGET_HAS_ATOMIC};
+        #[cfg(feature = "serde")]
+        pub use atomic::TargetSpec;
+        pub use builder::{CfgBuilder, CfgScope};
+        pub use cache::EvalCache;
+        pub use canonical::Canonical;
+        pub use collect::{AllOf, AnyOf};
+        pub use context::EvalContext;
+        pub use cost::CostModel;
+        pub use encoding::DecodeError;
+        pub use error::Error;
+        pub use error::MAX_NESTING;
+        pub use explain::Explanation;
+        pub use fingerprint::STABLE_HASH_VERSION;
+        pub use flags::FlagSet;
+        pub use known::{Domain, KnownCfgs, UnknownCfg, UnknownKind};
+        pub use leaves::Leaves;
+        pub use lint::{CfgLint, LintKind};
+        pub use map::{CfgMap, CfgMapIter};
+        pub use metadata::RuntimeCfg;
+        pub use metrics::Metrics;
+        pub use named::NamedPredicates;
+        pub use nix::NixError;
+        pub use params::Bindings;
+        pub use partition::PartitionError;
+        pub use path::{Path, PathError};
+        pub use predicate::*;
+        pub use prepared::PreparedEval;
+        pub use rewrite::{Rule, Rules};
+        pub use rollout::{rollout, Rollout, ROLLOUT_KEY};
+        pub use router::{CfgRouter, RouteExplanation};
+        pub use sexpr::{Sexpr, SexprError};
+        pub use shared::SharedCfg;
+        pub use specificity::Specificity;
+    }
+}
 
 cfg_if! {
     if #[cfg(feature = "parsing")] {
+        mod index;
+        mod lossless;
         mod parsing;
+        pub mod strip;
+
+        #[cfg(feature = "diagnostics")]
+        pub mod diagnostics;
 
+        pub use index::{CfgIndex, IndexError, IndexedCfg, Position};
+        pub use lossless::Lossless;
         pub use parsing::cfg;
+        pub use strip::evaluate_attrs;
     }
 }
 
-#[cfg(feature = "printing")]
-mod printing;
-
 cfg_if! {
-    if #[cfg(not(feature = "std"))] {
-        extern crate alloc;
+    if #[cfg(feature = "printing")] {
+        mod dot;
+        mod format;
+        mod printing;
+        mod term;
 
-        use alloc::boxed::Box;
-        use alloc::string::String;
-        use alloc::vec::Vec;
+        #[doc(hidden)]
+        pub use explain::assert_cfg as __assert_cfg;
+        pub use format::{FormatOptions, QuoteStyle};
+        pub use printing::{CargoKey, CfgMacro};
+        #[cfg(feature = "term")]
+        pub use term::Colored;
     }
 }
 
-use core::convert::{AsMut, AsRef};
-use core::ops::{Deref, DerefMut};
+#[cfg(all(feature = "parsing", feature = "printing"))]
+pub mod report;
 
-/// Boolean evaluation of configuration flags, at runtime-time.
-#[derive(Debug, Clone, PartialEq, Hash)]
-pub struct Cfg(Predicate);
+#[cfg(all(feature = "parsing", feature = "printing"))]
+pub mod testing;
 
-impl Deref for Cfg {
-    type Target = Predicate;
+#[cfg(all(feature = "parsing", feature = "printing"))]
+mod tokens;
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
+#[cfg(feature = "std")]
+pub mod build_helper;
 
-impl DerefMut for Cfg {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
-    }
-}
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        mod env;
 
-impl AsRef<Predicate> for Cfg {
-    fn as_ref(&self) -> &Predicate {
-        &self.0
+        pub use env::{EnvError, EnvFlags, EnvKind, EnvVar};
     }
 }
 
-impl AsMut<Predicate> for Cfg {
-    fn as_mut(&mut self) -> &mut Predicate {
-        &mut self.0
-    }
-}
+#[cfg(feature = "std")]
+pub mod global;
 
-impl From<Predicate> for Cfg {
-    fn from(predicate: Predicate) -> Self {
-        Cfg(predicate)
+cfg_if! {
+    if #[cfg(feature = "macros")] {
+        pub use runtime_cfg_macros::{cfg_fields, include_cfg, runtime_cfg, RuntimeCfg};
+
+        #[doc(hidden)]
+        pub mod __private {
+            pub use std::sync::OnceLock;
+            pub use std::vec;
+            pub use std::vec::Vec;
+        }
     }
 }
 
-impl From<Cfg> for Predicate {
-    fn from(cfg: Cfg) -> Self {
-        cfg.0
+#[cfg(feature = "cfg-expr")]
+mod expr;
+
+cfg_if! {
+    if #[cfg(feature = "clap")] {
+        mod args;
+
+        pub use args::CfgArgs;
     }
 }
 
-/// A configuration predicate.
-#[derive(Debug, Clone, PartialEq, Hash)]
-pub enum Predicate {
-    /// A configuration predicate success when `any` of sub-predicates success.
-    Any(Vec<Box<Predicate>>),
-    /// A configuration predicate success when `all` of sub-predicates success.
-    All(Vec<Box<Predicate>>),
-    /// A configuration predicate apply `not` operator to a predicate.
-    Not(Box<Predicate>),
-    /// A configuration predicate with name.
-    Name(String),
-    /// A configuration predicate with name and value.
-    NameValue(String, String),
-}
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
 
-/// A configuration predicate success when `any` of sub-predicates success.
-pub fn any<I: IntoIterator<Item = Predicate>>(predicates: I) -> Predicate {
-    Predicate::Any(predicates.into_iter().map(Box::new).collect())
-}
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+mod keywords;
 
-/// A configuration predicate success when `all` of sub-predicates success.
-pub fn all<I: IntoIterator<Item = Predicate>>(predicates: I) -> Predicate {
-    Predicate::All(predicates.into_iter().map(Box::new).collect())
-}
+#[cfg(feature = "proptest")]
+pub mod strategy;
 
-/// A configuration predicate apply `not` operator to a sub-predicate.
-pub fn not(predicate: Predicate) -> Predicate {
-    Predicate::Not(Box::new(predicate))
-}
+#[cfg(feature = "rand")]
+mod sample;
+
+#[cfg(feature = "serde")]
+pub mod cargo;
+
+#[cfg(feature = "serde")]
+pub mod serde;
 
-/// A configuration predicate with name.
-pub fn name<S: Into<String>>(name: S) -> Predicate {
-    Predicate::Name(name.into())
+#[cfg(feature = "serde")]
+pub mod serde_loose;
+
+#[cfg(all(feature = "serde", feature = "printing", feature = "parsing"))]
+pub mod serde_str;
+
+#[cfg(feature = "schemars")]
+mod schema;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "remote")]
+pub mod remote;
+
+cfg_if! {
+    if #[cfg(feature = "notify")] {
+        mod watch;
+
+        pub use watch::{WatchError, WatchedFlags};
+    }
 }
 
-/// A configuration predicate with name and value.
-pub fn name_value<S: Into<String>>(name: S, value: S) -> Predicate {
-    Predicate::NameValue(name.into(), value.into())
+cfg_if! {
+    if #[cfg(all(feature = "alloc", not(feature = "std")))] {
+        extern crate alloc;
+    }
 }
@@ -0,0 +1,218 @@
+//! Lints of the suspicious constructs in a predicate tree.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::vec::Vec;
+    }
+}
+
+use crate::Predicate;
+
+/// The kind of a lint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(::serde::Serialize, ::serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum LintKind {
+    /// An `any()` without sub-predicate, which never matches.
+    EmptyAny,
+    /// An `all()` without sub-predicate, which always matches.
+    EmptyAll,
+    /// An `any` or `all` with a single sub-predicate.
+    SingleChild,
+    /// A `not(not(..))`, which is the sub-predicate itself.
+    DoubleNegation,
+    /// An `any` or `all` nested in its parent of the same kind.
+    Nested,
+    /// A sub-predicate appears more than once in its parent.
+    Duplicate,
+    /// An `all` with a sub-predicate and its negation, which never matches.
+    Contradiction,
+    /// An `any` with a sub-predicate and its negation, which always matches.
+    Tautology,
+}
+
+impl LintKind {
+    /// Returns the description of the lint.
+    pub fn message(&self) -> &'static str {
+        use LintKind::*;
+
+        match self {
+            EmptyAny => "empty `any()` never matches",
+            EmptyAll => "empty `all()` always matches",
+            SingleChild => "`any` or `all` with a single predicate is redundant",
+            DoubleNegation => "double negation is redundant",
+            Nested => "nested `any` or `all` of the same kind could be flattened",
+            Duplicate => "duplicated predicate is redundant",
+            Contradiction => "`all` with a predicate and its negation never matches",
+            Tautology => "`any` with a predicate and its negation always matches",
+        }
+    }
+}
+
+/// A lint found in a predicate, with the offending sub-predicate.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let predicate = all(vec![name("unix"), not(not(name("unix")))]);
+///
+/// assert_eq!(
+///     predicate.lint(),
+///     vec![CfgLint {
+///         kind: LintKind::DoubleNegation,
+///         predicate: &not(not(name("unix"))),
+///     }]
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CfgLint<'a> {
+    /// The kind of the lint.
+    pub kind: LintKind,
+    /// The offending sub-predicate.
+    pub predicate: &'a Predicate,
+}
+
+impl Predicate {
+    /// Returns the lints found in the predicate, in the pre-order of the offending sub-predicates.
+    ///
+    /// All of them could be fixed by [`Predicate::simplify`].
+    pub fn lint(&self) -> Vec<CfgLint<'_>> {
+        let mut lints = Vec::new();
+
+        lint(self, &mut lints);
+
+        lints
+    }
+}
+
+fn lint<'a>(predicate: &'a Predicate, lints: &mut Vec<CfgLint<'a>>) {
+    let mut push = |kind, predicate| lints.push(CfgLint { kind, predicate });
+
+    match predicate {
+        Predicate::Any(predicates) | Predicate::All(predicates) => {
+            let conjunction = matches!(predicate, Predicate::All(_));
+
+            match predicates.len() {
+                0 if conjunction => push(LintKind::EmptyAll, predicate),
+                0 => push(LintKind::EmptyAny, predicate),
+                1 => push(LintKind::SingleChild, predicate),
+                _ => {}
+            }
+
+            for (idx, child) in predicates.iter().enumerate() {
+                let siblings = &predicates[..idx];
+
+                match **child {
+                    Predicate::All(_) if conjunction => push(LintKind::Nested, child),
+                    Predicate::Any(_) if !conjunction => push(LintKind::Nested, child),
+                    _ => {}
+                }
+
+                if siblings.contains(child) {
+                    push(LintKind::Duplicate, child);
+                } else if siblings.iter().any(|sibling| is_negation(sibling, child)) {
+                    let kind = if conjunction {
+                        LintKind::Contradiction
+                    } else {
+                        LintKind::Tautology
+                    };
+
+                    push(kind, child);
+                }
+            }
+
+            for child in predicates {
+                lint(child, lints);
+            }
+        }
+        Predicate::Not(inner) => {
+            if let Predicate::Not(_) = **inner {
+                push(LintKind::DoubleNegation, predicate);
+            }
+
+            lint(inner, lints);
+        }
+        _ => {}
+    }
+}
+
+fn is_negation(lhs: &Predicate, rhs: &Predicate) -> bool {
+    match (lhs, rhs) {
+        (Predicate::Not(lhs), rhs) | (rhs, Predicate::Not(lhs)) => **lhs == *rhs,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+            use alloc::vec::Vec;
+        }
+    }
+
+    use crate::*;
+
+    fn kinds(predicate: &Predicate) -> Vec<LintKind> {
+        predicate.lint().into_iter().map(|lint| lint.kind).collect()
+    }
+
+    #[test]
+    fn test_lint() {
+        assert_eq!(kinds(&name("unix")), vec![]);
+        assert_eq!(kinds(&any(vec![])), vec![LintKind::EmptyAny]);
+        assert_eq!(kinds(&all(vec![])), vec![LintKind::EmptyAll]);
+        assert_eq!(
+            kinds(&any(vec![
+                name("foo"),
+                any(vec![name("bar")]),
+                name("foo"),
+                not(name("foo")),
+            ])),
+            vec![
+                LintKind::Nested,
+                LintKind::Duplicate,
+                LintKind::Tautology,
+                LintKind::SingleChild,
+            ]
+        );
+        assert_eq!(
+            kinds(&all(vec![not(name("foo")), name("foo")])),
+            vec![LintKind::Contradiction]
+        );
+
+        let predicate = all(vec![
+            name("foo"),
+            all(vec![name("bar"), name("baz")]),
+            name("foo"),
+            not(name("foo")),
+        ]);
+
+        assert_eq!(
+            predicate
+                .lint()
+                .into_iter()
+                .map(|lint| (lint.kind, lint.predicate))
+                .collect::<Vec<_>>(),
+            vec![
+                (LintKind::Nested, &all(vec![name("bar"), name("baz")])),
+                (LintKind::Duplicate, &name("foo")),
+                (LintKind::Contradiction, &not(name("foo"))),
+            ]
+        );
+
+        for predicate in &[
+            any(vec![name("foo"), any(vec![name("bar")]), name("foo")]),
+            all(vec![not(not(name("foo"))), all(vec![])]),
+        ] {
+            assert!(!predicate.lint().is_empty());
+            assert!(predicate.simplify().lint().is_empty());
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Parsed configurations preserving the original source.
+//!
+//! Only the whole source is kept, not the trivia of each node,
+//! so the formatting survives an unmodified round trip only.
+
+#[cfg(feature = "printing")]
+use core::fmt;
+use core::ops::Deref;
+
+use crate::{Cfg, Error};
+
+/// A configuration parsed from source, which preserves the original source text.
+///
+/// It is printed as the original source byte-for-byte, including the spacing,
+/// the literal suffixes and quoting, until the configuration is modified.
+///
+/// The formatting isn't tracked per node, so any modification, even of a single leaf,
+/// drops the whole source, and the configuration is printed in the canonical form.
+/// Borrowing it with [`Lossless::cfg_mut`] counts as a modification.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let mut cfg = Lossless::parse(r##"#[cfg(all( unix,target_os=r#"linux"# ))]"##).unwrap();
+///
+/// assert_eq!(*cfg, Cfg::from(all(vec![name("unix"), name_value("target_os", "linux")])));
+/// assert_eq!(cfg.to_string(), r##"#[cfg(all( unix,target_os=r#"linux"# ))]"##);
+///
+/// *cfg.cfg_mut() = Cfg::from(name("unix"));
+///
+/// assert_eq!(cfg.to_string(), "#[cfg(unix)]");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Lossless {
+    cfg: Cfg,
+    source: Option<String>,
+}
+
+impl Lossless {
+    /// Parses the configuration, and preserves the source.
+    pub fn parse<S: Into<String>>(s: S) -> Result<Self, Error> {
+        let source = s.into();
+        let cfg = Cfg::parse(&source)?;
+
+        Ok(Lossless {
+            cfg,
+            source: Some(source),
+        })
+    }
+
+    /// Returns the original source, or `None` if the configuration was modified.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Returns the configuration.
+    pub fn cfg(&self) -> &Cfg {
+        &self.cfg
+    }
+
+    /// Returns the configuration to modify, the original source is dropped, even if it isn't modified.
+    pub fn cfg_mut(&mut self) -> &mut Cfg {
+        self.source = None;
+
+        &mut self.cfg
+    }
+
+    /// Returns the configuration.
+    pub fn into_inner(self) -> Cfg {
+        self.cfg
+    }
+}
+
+impl Deref for Lossless {
+    type Target = Cfg;
+
+    fn deref(&self) -> &Self::Target {
+        &self.cfg
+    }
+}
+
+impl From<Cfg> for Lossless {
+    fn from(cfg: Cfg) -> Self {
+        Lossless { cfg, source: None }
+    }
+}
+
+impl From<Lossless> for Cfg {
+    fn from(lossless: Lossless) -> Self {
+        lossless.cfg
+    }
+}
+
+#[cfg(feature = "printing")]
+impl fmt::Display for Lossless {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.source {
+            Some(ref source) => f.write_str(source),
+            None => self.cfg.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_lossless() {
+        let sources = vec![
+            "#[cfg(any(foo,bar))]",
+            "#[cfg(  not( test ) )]",
+            "#[cfg(target_pointer_width = \"3\\x32\")]",
+            "#[cfg(all(unix,\n    target_os = \"linux\",\n))]",
+            "cfg(unix)",
+        ];
+
+        for source in sources {
+            let lossless = Lossless::parse(source).unwrap();
+
+            assert_eq!(lossless.source(), Some(source));
+            assert_eq!(lossless.cfg(), &Cfg::parse(source).unwrap());
+            #[cfg(feature = "printing")]
+            assert_eq!(lossless.to_string(), source);
+        }
+
+        let mut lossless = Lossless::parse("#[cfg(all( unix ,\n test ))]").unwrap();
+
+        lossless.cfg_mut();
+
+        assert_eq!(lossless.source(), None);
+        #[cfg(feature = "printing")]
+        assert_eq!(lossless.to_string(), "#[cfg(all(unix, test))]");
+        assert_eq!(Lossless::from(Cfg::always()).source(), None);
+    }
+
+    #[cfg(feature = "no-panic")]
+    #[test]
+    fn test_parse_too_deep() {
+        let nested = |n| "cfg(".to_owned() + &"not(".repeat(n) + "unix" + &")".repeat(n + 1);
+
+        assert!(Lossless::parse(nested(MAX_NESTING - 1)).is_ok());
+        assert!(Lossless::parse(nested(100_000)).is_err());
+    }
+}
@@ -0,0 +1,528 @@
+/// Constructs a predicate from the configuration predicate syntax, without parsing at runtime.
+///
+/// The macro expands directly to the constructor calls,
+/// so it doesn't need the `parsing` feature.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let predicate = cfg_pred!(all(unix, target_os = "linux", not(feature = "x")));
+///
+/// assert_eq!(
+///     predicate,
+///     all(vec![
+///         name("unix"),
+///         name_value("target_os", "linux"),
+///         not(name_value("feature", "x")),
+///     ])
+/// );
+/// ```
+#[macro_export]
+macro_rules! cfg_pred {
+    (true) => {
+        $crate::Predicate::True
+    };
+    (false) => {
+        $crate::Predicate::False
+    };
+    (any($($args:tt)*)) => {
+        $crate::any($crate::cfg_pred!(@list [] $($args)*))
+    };
+    (all($($args:tt)*)) => {
+        $crate::all($crate::cfg_pred!(@list [] $($args)*))
+    };
+    (param($name:ident)) => {
+        $crate::param(stringify!($name))
+    };
+    (not($($args:tt)+)) => {
+        $crate::not($crate::cfg_pred!($($args)+))
+    };
+    ($name:ident = $value:literal) => {
+        $crate::name_value(stringify!($name), $value)
+    };
+    ($name:ident) => {
+        $crate::name(stringify!($name))
+    };
+
+    (@list [$($out:expr),*] $(,)?) => {
+        [$($out),*]
+    };
+    (@list [$($out:expr),*] true $(, $($rest:tt)*)?) => {
+        $crate::cfg_pred!(@list [$($out,)* $crate::Predicate::True] $($($rest)*)?)
+    };
+    (@list [$($out:expr),*] false $(, $($rest:tt)*)?) => {
+        $crate::cfg_pred!(@list [$($out,)* $crate::Predicate::False] $($($rest)*)?)
+    };
+    (@list [$($out:expr),*] $name:ident ($($args:tt)*) $(, $($rest:tt)*)?) => {
+        $crate::cfg_pred!(@list [$($out,)* $crate::cfg_pred!($name($($args)*))] $($($rest)*)?)
+    };
+    (@list [$($out:expr),*] $name:ident = $value:literal $(, $($rest:tt)*)?) => {
+        $crate::cfg_pred!(@list [$($out,)* $crate::cfg_pred!($name = $value)] $($($rest)*)?)
+    };
+    (@list [$($out:expr),*] $name:ident $(, $($rest:tt)*)?) => {
+        $crate::cfg_pred!(@list [$($out,)* $crate::cfg_pred!($name)] $($($rest)*)?)
+    };
+}
+
+/// Selects the first arm whose predicate matches the flags at runtime.
+///
+/// The arms are checked in order, and `_` matches anything;
+/// without a `_` arm, the arms must evaluate to `()`.
+/// Each arm must end with a comma, except the last one.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let flags = vec![("unix", None), ("target_os", Some("linux"))];
+///
+/// let os = runtime_cfg_match! {
+///     flags;
+///     windows => "windows",
+///     all(unix, target_os = "linux") => "linux",
+///     unix => "unix",
+///     _ => "unknown",
+/// };
+///
+/// assert_eq!(os, "linux");
+/// ```
+#[macro_export]
+macro_rules! runtime_cfg_match {
+    ($flags:expr; $($arms:tt)*) => {{
+        let flags = &$flags;
+
+        $crate::runtime_cfg_match!(@arms flags; $($arms)*)
+    }};
+
+    (@arms $flags:ident; $(,)?) => {
+        ()
+    };
+    (@arms $flags:ident; _ => $body:expr $(,)?) => {
+        $body
+    };
+    (@arms $flags:ident; $name:ident ($($args:tt)*) => $body:expr $(, $($rest:tt)*)?) => {
+        if $crate::cfg_pred!($name($($args)*)).matches($flags) {
+            $body
+        } else {
+            $crate::runtime_cfg_match!(@arms $flags; $($($rest)*)?)
+        }
+    };
+    (@arms $flags:ident; $name:ident = $value:literal => $body:expr $(, $($rest:tt)*)?) => {
+        if $crate::cfg_pred!($name = $value).matches($flags) {
+            $body
+        } else {
+            $crate::runtime_cfg_match!(@arms $flags; $($($rest)*)?)
+        }
+    };
+    (@arms $flags:ident; $name:ident => $body:expr $(, $($rest:tt)*)?) => {
+        if $crate::cfg_pred!($name).matches($flags) {
+            $body
+        } else {
+            $crate::runtime_cfg_match!(@arms $flags; $($($rest)*)?)
+        }
+    };
+}
+
+/// Builds a `FlagSet` of the configurations in effect when the calling crate is compiled.
+///
+/// The well-known configuration names and values are probed with `cfg!(..)` in the calling crate,
+/// so the flags describe the binary being built, without a build script.
+/// The Cargo features are not well-known, the features to probe could be listed as `features = [..]`.
+///
+/// The `CARGO_CFG_*` environment variables are only set for the build scripts,
+/// so the flags are limited to the probed names and values,
+/// use `build_helper::target_flags()` in a build script for the complete configuration.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let flags = host_flags!(features = ["std"]);
+///
+/// assert_eq!(flags.has("unix"), cfg!(unix));
+/// assert_eq!(flags.has_value("target_os", "linux"), cfg!(target_os = "linux"));
+/// ```
+#[macro_export]
+macro_rules! host_flags {
+    () => {
+        $crate::host_flags!(features = [])
+    };
+    (features = [$($feature:literal),* $(,)?]) => {{
+        let mut flags = $crate::FlagSet::new();
+
+        $crate::__probe_names!(flags; unix, windows, test, debug_assertions, miri);
+        $crate::__probe_values!(flags; target_os = [
+            "linux", "android", "macos", "ios", "tvos", "watchos", "visionos", "freebsd", "dragonfly",
+            "openbsd", "netbsd", "solaris", "illumos", "windows", "fuchsia", "redox", "haiku",
+            "hermit", "wasi", "emscripten", "none", "uefi", "aix", "espidf", "horizon", "vxworks",
+            "l4re", "nto", "hurd", "cuda"
+        ]);
+        $crate::__probe_values!(flags; target_family = ["unix", "windows", "wasm"]);
+        $crate::__probe_values!(flags; target_arch = [
+            "x86", "x86_64", "arm", "aarch64", "mips", "mips64", "powerpc", "powerpc64", "riscv32",
+            "riscv64", "s390x", "sparc", "sparc64", "wasm32", "wasm64", "loongarch64", "m68k",
+            "csky", "hexagon", "bpf", "avr", "msp430", "nvptx64", "xtensa"
+        ]);
+        $crate::__probe_values!(flags; target_env = [
+            "", "gnu", "musl", "msvc", "sgx", "uclibc", "newlib", "ohos"
+        ]);
+        $crate::__probe_values!(flags; target_vendor = [
+            "apple", "pc", "unknown", "fortanix", "uwp", "nvidia", "wrs"
+        ]);
+        $crate::__probe_values!(flags; target_endian = ["little", "big"]);
+        $crate::__probe_values!(flags; target_pointer_width = ["16", "32", "64"]);
+        $crate::__probe_values!(flags; target_has_atomic = ["8", "16", "32", "64", "128", "ptr"]);
+        $crate::__probe_values!(flags; panic = ["unwind", "abort"]);
+        $crate::__probe_values!(flags; target_feature = [
+            "crt-static", "sse", "sse2", "sse3", "ssse3", "sse4.1", "sse4.2", "avx", "avx2",
+            "fma", "neon", "simd128"
+        ]);
+        $crate::__probe_values!(flags; feature = [$($feature),*]);
+
+        flags
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __probe_names {
+    ($flags:ident; $($name:ident),*) => {
+        $(
+            if cfg!($name) {
+                $flags.insert_name(stringify!($name));
+            }
+        )*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __probe_values {
+    ($flags:ident; $name:ident = [$($value:literal),*]) => {
+        $(
+            if cfg!($name = $value) {
+                $flags.insert(stringify!($name), $value);
+            }
+        )*
+    };
+}
+
+/// Builds a `FlagSet` literal, each flag is a name with an optional value.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let flags = flags![unix, target_os = "linux", feature = "std", feature = "serde"];
+///
+/// assert!(flags.has("unix"));
+/// assert_eq!(flags.values("feature").collect::<Vec<_>>(), vec!["serde", "std"]);
+/// assert!(cfg_pred!(all(unix, target_os = "linux")).matches(&flags));
+/// ```
+#[macro_export]
+macro_rules! flags {
+    () => {
+        $crate::FlagSet::new()
+    };
+    (@insert $flags:ident; $(,)?) => {};
+    (@insert $flags:ident; $name:ident = $value:expr $(, $($rest:tt)*)?) => {
+        $flags.insert(stringify!($name), $value);
+        $crate::flags!(@insert $flags; $($($rest)*)?)
+    };
+    (@insert $flags:ident; $name:ident $(, $($rest:tt)*)?) => {
+        $flags.insert_name(stringify!($name));
+        $crate::flags!(@insert $flags; $($($rest)*)?)
+    };
+    ($($flags:tt)*) => {{
+        let mut flags = $crate::FlagSet::new();
+
+        $crate::flags!(@insert flags; $($flags)*);
+
+        flags
+    }};
+}
+
+/// Asserts that a configuration or predicate matches the pattern.
+///
+/// On failure, the panic message shows the predicate,
+/// and the explanation tree with the result of each node and the answers of the pattern to the leaves.
+/// An optional message in the `format!` syntax is appended, like `assert!`.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let flags = flags![unix, target_os = "linux"];
+///
+/// assert_cfg_matches!(cfg_pred!(all(unix, target_os = "linux")), flags);
+/// assert_cfg_not_matches!(cfg_pred!(windows), flags, "on {}", "linux");
+/// ```
+///
+/// A failed assertion panics with
+///
+/// ```text
+/// assertion failed: `cfg_pred!(all(unix, not(target_os = "linux")))` matches `flags`
+///   cfg: all(unix, not(target_os = "linux"))
+/// ✗ all
+/// ├── ✓ unix
+/// └── ✗ not
+///     └── ✓ target_os = "linux"
+/// ```
+#[cfg(feature = "printing")]
+#[macro_export]
+macro_rules! assert_cfg_matches {
+    ($cfg:expr, $pattern:expr $(,)?) => {
+        $crate::__assert_cfg(
+            ::core::convert::AsRef::<$crate::Predicate>::as_ref(&$cfg),
+            &$pattern,
+            true,
+            (stringify!($cfg), stringify!($pattern)),
+            ::core::option::Option::None,
+        )
+    };
+    ($cfg:expr, $pattern:expr, $($arg:tt)+) => {
+        $crate::__assert_cfg(
+            ::core::convert::AsRef::<$crate::Predicate>::as_ref(&$cfg),
+            &$pattern,
+            true,
+            (stringify!($cfg), stringify!($pattern)),
+            ::core::option::Option::Some(::core::format_args!($($arg)+)),
+        )
+    };
+}
+
+/// Asserts that a configuration or predicate doesn't match the pattern,
+/// panics with the explanation tree like [`assert_cfg_matches!`] on failure.
+#[cfg(feature = "printing")]
+#[macro_export]
+macro_rules! assert_cfg_not_matches {
+    ($cfg:expr, $pattern:expr $(,)?) => {
+        $crate::__assert_cfg(
+            ::core::convert::AsRef::<$crate::Predicate>::as_ref(&$cfg),
+            &$pattern,
+            false,
+            (stringify!($cfg), stringify!($pattern)),
+            ::core::option::Option::None,
+        )
+    };
+    ($cfg:expr, $pattern:expr, $($arg:tt)+) => {
+        $crate::__assert_cfg(
+            ::core::convert::AsRef::<$crate::Predicate>::as_ref(&$cfg),
+            &$pattern,
+            false,
+            (stringify!($cfg), stringify!($pattern)),
+            ::core::option::Option::Some(::core::format_args!($($arg)+)),
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_cfg_pred() {
+        assert_eq!(cfg_pred!(unix), name("unix"));
+        assert_eq!(
+            cfg_pred!(target_os = "macos"),
+            name_value("target_os", "macos")
+        );
+        assert_eq!(cfg_pred!(true), Predicate::True);
+        assert_eq!(cfg_pred!(any()), any(vec![]));
+        assert_eq!(cfg_pred!(not(any)), not(name("any")));
+        assert_eq!(cfg_pred!(all(param(region))), all(vec![param("region")]));
+        assert_eq!(
+            cfg_pred!(any(
+                foo,
+                false,
+                all(unix, target_pointer_width = "32"),
+                not(all()),
+            )),
+            any(vec![
+                name("foo"),
+                Predicate::False,
+                all(vec![name("unix"), name_value("target_pointer_width", "32")]),
+                not(all(vec![])),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_host_flags() {
+        let flags = host_flags!(features = ["std", "printing"]);
+
+        assert_eq!(flags.has("unix"), cfg!(unix));
+        assert_eq!(flags.has("windows"), cfg!(windows));
+        assert!(flags.has("test"));
+        assert_eq!(
+            flags.has_value("target_pointer_width", "64"),
+            cfg!(target_pointer_width = "64")
+        );
+        assert_eq!(flags.has_value("feature", "std"), cfg!(feature = "std"));
+        assert_eq!(
+            flags.has_value("feature", "printing"),
+            cfg!(feature = "printing")
+        );
+        assert_eq!(flags.values("target_endian").count(), 1);
+        assert!(host_flags!().values("feature").next().is_none());
+    }
+
+    #[test]
+    fn test_flags() {
+        let flags = flags![unix, target_pointer_width = "64", feature = "std",];
+
+        assert_eq!(flags.len(), 3);
+        assert!(flags.has("unix"));
+        assert!(flags.has_value("target_pointer_width", "64"));
+        assert!(flags.has_value("feature", "std"));
+        assert!(flags![].is_empty());
+    }
+
+    #[test]
+    fn test_runtime_cfg_match() {
+        let flags = vec![("unix", None), ("feature", Some("beta"))];
+
+        assert_eq!(
+            runtime_cfg_match! {
+                flags;
+                windows => 1,
+                feature = "beta" => 2,
+                unix => 3,
+                _ => 4,
+            },
+            2
+        );
+        assert_eq!(
+            runtime_cfg_match! {
+                flags;
+                not(unix) => 1,
+                _ => 4
+            },
+            4
+        );
+
+        let mut hits = 0;
+
+        runtime_cfg_match! {
+            FlagSet::new();
+            true => hits += 1,
+            false => hits += 10,
+        }
+
+        assert_eq!(hits, 1);
+    }
+
+    #[cfg(feature = "printing")]
+    #[test]
+    fn test_assert_cfg_matches() {
+        let flags = flags![unix, target_os = "linux"];
+
+        assert_cfg_matches!(cfg_pred!(unix), flags);
+        assert_cfg_matches!(Cfg::from(cfg_pred!(target_os = "linux")), &flags, "linux");
+        assert_cfg_not_matches!(cfg_pred!(windows), flags);
+    }
+
+    #[cfg(all(feature = "printing", feature = "std"))]
+    #[test]
+    fn test_assert_cfg_matches_failed() {
+        let flags = flags![unix, target_os = "linux"];
+        let err = std::panic::catch_unwind(|| {
+            assert_cfg_matches!(
+                cfg_pred!(all(unix, not(target_os = "linux"))),
+                flags,
+                "on {}",
+                "linux"
+            )
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<String>().unwrap(),
+            r#"assertion failed: `cfg_pred!(all(unix, not(target_os = "linux")))` matches `flags`: on linux
+  cfg: all(unix, not(target_os = "linux"))
+✗ all
+├── ✓ unix
+└── ✗ not
+    └── ✓ target_os = "linux"
+"#
+        );
+    }
+}
+
+#[cfg(all(test, feature = "macros"))]
+mod proc_macro_tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+            use alloc::vec::Vec;
+        }
+    }
+
+    use crate::*;
+
+    fn flags(beta: bool) -> Vec<(&'static str, Option<&'static str>)> {
+        if beta {
+            vec![("unix", None), ("feature", Some("beta"))]
+        } else {
+            vec![("unix", None)]
+        }
+    }
+
+    #[runtime_cfg(all(unix, feature = "beta"), flags = flags(beta))]
+    fn guarded(beta: bool) -> Option<u32> {
+        Some(42)
+    }
+
+    #[runtime_cfg(feature = "beta", default = Err("beta disabled"), flags = flags(beta))]
+    fn fallible(beta: bool) -> Result<u32, &'static str> {
+        Ok(42)
+    }
+
+    #[test]
+    fn test_runtime_cfg() {
+        assert_eq!(guarded(true), Some(42));
+        assert_eq!(guarded(false), None);
+        assert_eq!(fallible(true), Ok(42));
+        assert_eq!(fallible(false), Err("beta disabled"));
+    }
+
+    #[test]
+    fn test_include_cfg() {
+        assert_eq!(
+            include_cfg!("tests/fixtures/include_cfg.rs", "global"),
+            feature("std").into()
+        );
+        assert_eq!(
+            include_cfg!("tests/fixtures/include_cfg.rs", "borrowed"),
+            Cfg::always()
+        );
+        assert_eq!(
+            include_cfg!("tests/fixtures/include_cfg.rs", "tests::guarded"),
+            all(vec![name("test"), feature("macros")]).into()
+        );
+        assert_eq!(
+            include_cfg!("tests/fixtures/include_cfg.rs", "sys::Socket"),
+            any(vec![name("unix"), name("windows")]).into()
+        );
+        assert_eq!(
+            include_cfg!("tests/fixtures/include_cfg.rs", "sys::Pipe"),
+            all(vec![name("windows"), name_value("target_env", "msvc")]).into()
+        );
+        assert_eq!(
+            include_cfg!("tests/fixtures/include_cfg.rs", "ext"),
+            feature("ext").into()
+        );
+    }
+}
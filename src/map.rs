@@ -0,0 +1,338 @@
+//! A map keyed by configurations, looked up by the matching flags.
+
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::collections::BTreeMap;
+        use std::vec;
+    } else {
+        use alloc::collections::BTreeMap;
+        use alloc::string::{String, ToString};
+        use alloc::vec::{self, Vec};
+    }
+}
+
+use core::cell::RefCell;
+use core::iter::FromIterator;
+use core::slice;
+
+use crate::{Cfg, Pattern, Specificity};
+
+/// A map keyed by configurations, looked up by the flags matching the configurations.
+///
+/// The entries are kept in the insertion order.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let mut map = CfgMap::new();
+///
+/// map.insert(Cfg::always(), "generic");
+/// map.insert(name("unix").into(), "unix");
+/// map.insert(all(vec![name("unix"), name_value("target_os", "linux")]).into(), "linux");
+///
+/// let flags = vec![("unix", None), ("target_os", Some("macos"))];
+///
+/// assert_eq!(map.get_matching(&flags).collect::<Vec<_>>(), vec![&"generic", &"unix"]);
+/// assert_eq!(map.get_best(&flags), Some(&"unix"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CfgMap<T> {
+    entries: Vec<(Cfg, T)>,
+}
+
+impl<T> Default for CfgMap<T> {
+    fn default() -> Self {
+        CfgMap {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T> CfgMap<T> {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a value with the configuration, returns the previous value of the same configuration if any.
+    ///
+    /// The previous entry keeps its position in the insertion order.
+    pub fn insert(&mut self, cfg: Cfg, value: T) -> Option<T> {
+        match self.entries.iter_mut().find(|(key, _)| *key == cfg) {
+            Some((_, prev)) => Some(core::mem::replace(prev, value)),
+            None => {
+                self.entries.push((cfg, value));
+                None
+            }
+        }
+    }
+
+    /// Returns the value of the configuration.
+    pub fn get(&self, cfg: &Cfg) -> Option<&T> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key == cfg)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the mutable value of the configuration.
+    pub fn get_mut(&mut self, cfg: &Cfg) -> Option<&mut T> {
+        self.entries
+            .iter_mut()
+            .find(|(key, _)| key == cfg)
+            .map(|(_, value)| value)
+    }
+
+    /// Removes the configuration, returns its value if any.
+    pub fn remove(&mut self, cfg: &Cfg) -> Option<T> {
+        let idx = self.entries.iter().position(|(key, _)| key == cfg)?;
+
+        Some(self.entries.remove(idx).1)
+    }
+
+    /// Returns `true` if the map contains the configuration.
+    pub fn contains(&self, cfg: &Cfg) -> bool {
+        self.entries.iter().any(|(key, _)| key == cfg)
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns an iterator over the entries, in the insertion order.
+    pub fn iter(&self) -> CfgMapIter<'_, T> {
+        CfgMapIter(self.entries.iter())
+    }
+
+    /// Returns an iterator over the values whose configuration matches the pattern, in the insertion order.
+    ///
+    /// Each flag is looked up from the pattern at most once for all the configurations,
+    /// so an expensive pattern is queried as few times as possible.
+    pub fn get_matching<'a, P: Pattern>(
+        &'a self,
+        pattern: &'a P,
+    ) -> impl Iterator<Item = &'a T> + 'a {
+        let pattern = Memoized::new(pattern);
+
+        self.entries
+            .iter()
+            .filter(move |(cfg, _)| cfg.matches(&pattern))
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the value whose configuration matches the pattern and is the most specific.
+    ///
+    /// The configurations are compared by their [`Specificity`],
+    /// the first inserted one wins when they are equally specific.
+    pub fn get_best<P: Pattern>(&self, pattern: &P) -> Option<&T> {
+        let pattern = Memoized::new(pattern);
+
+        self.entries
+            .iter()
+            .filter(|(cfg, _)| cfg.matches(&pattern))
+            .fold(None, |best: Option<(Specificity, &T)>, (cfg, value)| {
+                let specificity = cfg.specificity();
+
+                match best {
+                    Some((best_specificity, _)) if best_specificity >= specificity => best,
+                    _ => Some((specificity, value)),
+                }
+            })
+            .map(|(_, value)| value)
+    }
+}
+
+/// A pattern caching the results of the looked up flags.
+///
+/// The cache is keyed by the name and then the value, so a hit is looked up by the borrowed flag,
+/// and only a miss allocates the owned key.
+pub(crate) struct Memoized<'a, P> {
+    pattern: &'a P,
+    names: RefCell<BTreeMap<String, bool>>,
+    values: RefCell<BTreeMap<String, BTreeMap<String, bool>>>,
+}
+
+impl<'a, P> Memoized<'a, P> {
+    pub(crate) fn new(pattern: &'a P) -> Self {
+        Memoized {
+            pattern,
+            names: RefCell::new(BTreeMap::new()),
+            values: RefCell::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl<P: Pattern> Pattern for Memoized<'_, P> {
+    fn matches(&self, key: &str, value: Option<&str>) -> bool {
+        match value {
+            None => {
+                if let Some(&matched) = self.names.borrow().get(key) {
+                    return matched;
+                }
+
+                let matched = self.pattern.matches(key, None);
+
+                self.names.borrow_mut().insert(key.to_string(), matched);
+
+                matched
+            }
+            Some(value) => {
+                if let Some(&matched) = self
+                    .values
+                    .borrow()
+                    .get(key)
+                    .and_then(|values| values.get(value))
+                {
+                    return matched;
+                }
+
+                let matched = self.pattern.matches(key, Some(value));
+                let mut values = self.values.borrow_mut();
+
+                match values.get_mut(key) {
+                    Some(values) => values.insert(value.to_string(), matched),
+                    None => values
+                        .entry(key.to_string())
+                        .or_default()
+                        .insert(value.to_string(), matched),
+                };
+
+                matched
+            }
+        }
+    }
+}
+
+/// An iterator over the entries of a `CfgMap`.
+#[derive(Debug, Clone)]
+pub struct CfgMapIter<'a, T>(slice::Iter<'a, (Cfg, T)>);
+
+impl<'a, T> Iterator for CfgMapIter<'a, T> {
+    type Item = (&'a Cfg, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(cfg, value)| (cfg, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a CfgMap<T> {
+    type Item = (&'a Cfg, &'a T);
+    type IntoIter = CfgMapIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for CfgMap<T> {
+    type Item = (Cfg, T);
+    type IntoIter = vec::IntoIter<(Cfg, T)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<T> FromIterator<(Cfg, T)> for CfgMap<T> {
+    fn from_iter<I: IntoIterator<Item = (Cfg, T)>>(iter: I) -> Self {
+        let mut map = CfgMap::new();
+
+        map.extend(iter);
+
+        map
+    }
+}
+
+impl<T> Extend<(Cfg, T)> for CfgMap<T> {
+    fn extend<I: IntoIterator<Item = (Cfg, T)>>(&mut self, iter: I) {
+        for (cfg, value) in iter {
+            self.insert(cfg, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+            use alloc::vec::Vec;
+        }
+    }
+
+    use core::cell::Cell;
+
+    use crate::*;
+
+    struct Counting<'a>(Vec<(&'a str, Option<&'a str>)>, Cell<usize>);
+
+    impl Pattern for Counting<'_> {
+        fn matches(&self, key: &str, value: Option<&str>) -> bool {
+            self.1.set(self.1.get() + 1);
+            self.0.matches(key, value)
+        }
+    }
+
+    #[test]
+    fn test_cfg_map() {
+        let mut map: CfgMap<u32> = vec![
+            (Cfg::from(name("unix")), 1),
+            (Cfg::from(all(vec![name("unix"), feature("beta")])), 2),
+            (Cfg::from(name("windows")), 3),
+            (Cfg::from(feature("beta")), 4),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.insert(name("windows").into(), 5), Some(3));
+        assert_eq!(map.get(&name("windows").into()), Some(&5));
+        assert_eq!(map.remove(&name("windows").into()), Some(5));
+        assert!(!map.contains(&name("windows").into()));
+
+        let flags = Counting(
+            vec![("unix", None), ("feature", Some("beta"))],
+            Cell::new(0),
+        );
+
+        assert_eq!(
+            map.get_matching(&flags).collect::<Vec<_>>(),
+            vec![&1, &2, &4]
+        );
+        assert_eq!(flags.1.get(), 2);
+        assert_eq!(map.get_best(&flags), Some(&2));
+        assert_eq!(map.get_best(&vec![("windows", None::<&str>)]), None);
+        assert_eq!(
+            map.iter().map(|(_, value)| *value).collect::<Vec<_>>(),
+            vec![1, 2, 4]
+        );
+
+        let map: CfgMap<u32> = vec![
+            (Cfg::from(name("feature")), 1),
+            (Cfg::from(any(vec![feature("alpha"), feature("beta")])), 2),
+            (Cfg::from(all(vec![feature("beta"), name("feature")])), 3),
+        ]
+        .into_iter()
+        .collect();
+
+        flags.1.set(0);
+
+        assert_eq!(
+            map.get_matching(&flags).collect::<Vec<_>>(),
+            vec![&1, &2, &3]
+        );
+        assert_eq!(flags.1.get(), 3);
+    }
+}
@@ -2,14 +2,25 @@
 
 cfg_if! {
     if #[cfg(feature = "std")] {
+        use std::borrow::{Borrow, Cow};
+        #[allow(clippy::disallowed_types)]
         use std::collections::HashMap;
-        use std::borrow::Borrow;
+        use std::collections::{BTreeMap, BTreeSet};
         use std::hash::Hash;
-    } else {
+    } else if #[cfg(feature = "alloc")] {
+        use alloc::borrow::Cow;
+        use alloc::boxed::Box;
+        use alloc::collections::{BTreeMap, BTreeSet};
+        use alloc::string::String;
+        use alloc::vec;
         use alloc::vec::Vec;
     }
 }
 
+use core::fmt;
+use core::ops::{Range, RangeFrom, RangeInclusive};
+
+#[cfg(feature = "alloc")]
 use crate::Predicate;
 
 /// A matcher for string matching.
@@ -22,6 +33,53 @@ pub trait Pattern {
     fn matches(&self, key: &str, value: Option<&str>) -> bool;
 }
 
+/// The answer of a pattern about a flag, borrowed from the pattern.
+#[derive(Clone, Copy)]
+pub enum Lookup<'a> {
+    /// The flag isn't set.
+    Absent,
+    /// The flag is set without value.
+    Name,
+    /// The flag is set with the values accepted by the matcher.
+    Values(&'a dyn Matcher),
+}
+
+impl Lookup<'_> {
+    /// Returns `true` if the flag is set.
+    pub fn is_present(&self) -> bool {
+        !matches!(self, Lookup::Absent)
+    }
+
+    /// Answers a query of [`Pattern::matches`] from the lookup.
+    pub fn matches(&self, value: Option<&str>) -> bool {
+        match (self, value) {
+            (Lookup::Absent, _) => false,
+            (_, None) => true,
+            (Lookup::Name, Some(_)) => false,
+            (Lookup::Values(matcher), Some(value)) => matcher.matches(value),
+        }
+    }
+}
+
+impl fmt::Debug for Lookup<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Lookup::Absent => f.write_str("Absent"),
+            Lookup::Name => f.write_str("Name"),
+            Lookup::Values(_) => f.write_str("Values(..)"),
+        }
+    }
+}
+
+/// A pattern answering all the queries about a flag from a single lookup.
+///
+/// The patterns backed by a parsed configuration could find the flag once,
+/// and [`Predicate::matches_lookup`] answers both the `name` and `name = "value"` predicates from it.
+pub trait PatternExt: Pattern {
+    /// Looks up the flag of the key.
+    fn lookup(&self, key: &str) -> Lookup<'_>;
+}
+
 impl Matcher for &str {
     fn matches(&self, value: &str) -> bool {
         *self == value
@@ -30,22 +88,108 @@ impl Matcher for &str {
 
 impl Matcher for &[&str] {
     fn matches(&self, value: &str) -> bool {
-        self.iter().any(|s| *s == value)
+        self.contains(&value)
     }
 }
 
+#[cfg(feature = "alloc")]
 impl Matcher for Vec<&str> {
     fn matches(&self, value: &str) -> bool {
-        self.iter().any(|s| *s == value)
+        self.contains(&value)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Matcher for String {
+    fn matches(&self, value: &str) -> bool {
+        self == value
     }
 }
 
+#[cfg(feature = "alloc")]
+impl Matcher for &String {
+    fn matches(&self, value: &str) -> bool {
+        *self == value
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Matcher for Cow<'_, str> {
+    fn matches(&self, value: &str) -> bool {
+        self == value
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Matcher for &[String] {
+    fn matches(&self, value: &str) -> bool {
+        self.iter().any(|s| s == value)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Matcher for Vec<String> {
+    fn matches(&self, value: &str) -> bool {
+        self.iter().any(|s| s == value)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Matcher for BTreeSet<String> {
+    fn matches(&self, value: &str) -> bool {
+        self.contains(value)
+    }
+}
+
+/// Matches the value `true` or `false`.
+impl Matcher for bool {
+    fn matches(&self, value: &str) -> bool {
+        value.parse::<bool>().is_ok_and(|value| value == *self)
+    }
+}
+
+macro_rules! impl_integer_matcher {
+    ($($ty:ty),*) => {
+        $(
+            /// Matches the value numerically, the value which isn't an integer never matches.
+            impl Matcher for $ty {
+                fn matches(&self, value: &str) -> bool {
+                    value.parse::<$ty>().is_ok_and(|value| value == *self)
+                }
+            }
+
+            /// Matches the value in the range numerically.
+            impl Matcher for Range<$ty> {
+                fn matches(&self, value: &str) -> bool {
+                    value.parse::<$ty>().is_ok_and(|value| self.contains(&value))
+                }
+            }
+
+            /// Matches the value in the range numerically.
+            impl Matcher for RangeInclusive<$ty> {
+                fn matches(&self, value: &str) -> bool {
+                    value.parse::<$ty>().is_ok_and(|value| self.contains(&value))
+                }
+            }
+
+            /// Matches the value in the range numerically.
+            impl Matcher for RangeFrom<$ty> {
+                fn matches(&self, value: &str) -> bool {
+                    value.parse::<$ty>().is_ok_and(|value| self.contains(&value))
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_matcher!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
 impl<T> Matcher for Option<T>
 where
     T: Matcher,
 {
     fn matches(&self, value: &str) -> bool {
-        self.as_ref().map_or(false, |m| m.matches(value))
+        self.as_ref().is_some_and(|m| m.matches(value))
     }
 }
 
@@ -57,13 +201,24 @@ where
     fn matches(&self, key: &str, value: Option<&str>) -> bool {
         if let Some(value) = value {
             self.iter()
-                .any(|(k, v)| k.matches(key) && v.as_ref().map_or(false, |v| v.matches(value)))
+                .any(|(k, v)| k.matches(key) && v.as_ref().is_some_and(|v| v.matches(value)))
         } else {
             self.iter().any(|(k, _)| k.matches(key))
         }
     }
 }
 
+impl<K, V, const N: usize> Pattern for [(K, Option<V>); N]
+where
+    K: Matcher,
+    V: Matcher,
+{
+    fn matches(&self, key: &str, value: Option<&str>) -> bool {
+        self.as_slice().matches(key, value)
+    }
+}
+
+#[cfg(feature = "alloc")]
 impl<K, V> Pattern for Vec<(K, Option<V>)>
 where
     K: Matcher,
@@ -74,7 +229,21 @@ where
     }
 }
 
+impl<P: Pattern + ?Sized> Pattern for &P {
+    fn matches(&self, key: &str, value: Option<&str>) -> bool {
+        (**self).matches(key, value)
+    }
+}
+
+impl<P: PatternExt + ?Sized> PatternExt for &P {
+    fn lookup(&self, key: &str) -> Lookup<'_> {
+        (**self).lookup(key)
+    }
+}
+
+// the lookups don't depend on the iteration order
 #[cfg(feature = "std")]
+#[allow(clippy::disallowed_types)]
 impl<K, V> Pattern for HashMap<K, V>
 where
     K: Eq + Hash + Borrow<str>,
@@ -92,26 +261,158 @@ where
     }
 }
 
+#[cfg(feature = "std")]
+#[allow(clippy::disallowed_types)]
+impl<K, V> PatternExt for HashMap<K, V>
+where
+    K: Eq + Hash + Borrow<str>,
+    V: Matcher,
+{
+    fn lookup(&self, key: &str) -> Lookup<'_> {
+        self.get(key)
+            .map_or(Lookup::Absent, |v| Lookup::Values(v as &dyn Matcher))
+    }
+}
+
+#[cfg(feature = "alloc")]
 impl Predicate {
     /// Returns `true` if configuration matches the predicate
-    pub fn matches<P: Pattern>(&self, pattern: &P) -> bool {
+    ///
+    /// The predicate is evaluated with an explicit stack instead of recursion,
+    /// so the deeply nested predicates can't overflow the call stack.
+    pub fn matches<P: Pattern + ?Sized>(&self, pattern: &P) -> bool {
+        self.eval(|name, value| pattern.matches(name, value))
+    }
+
+    /// Returns `true` if configuration matches the predicate,
+    /// looking up each flag once however many predicates query it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// struct Config(Vec<(String, Vec<String>)>);
+    ///
+    /// impl Pattern for Config {
+    ///     fn matches(&self, key: &str, value: Option<&str>) -> bool {
+    ///         self.lookup(key).matches(value)
+    ///     }
+    /// }
+    ///
+    /// impl PatternExt for Config {
+    ///     fn lookup(&self, key: &str) -> Lookup<'_> {
+    ///         match self.0.iter().find(|(name, _)| name == key) {
+    ///             Some((_, values)) if values.is_empty() => Lookup::Name,
+    ///             Some((_, values)) => Lookup::Values(values),
+    ///             None => Lookup::Absent,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let config = Config(vec![
+    ///     ("unix".to_owned(), vec![]),
+    ///     ("feature".to_owned(), vec!["std".to_owned(), "serde".to_owned()]),
+    /// ]);
+    /// let predicate = all(vec![name("unix"), name("feature"), feature("serde")]);
+    ///
+    /// assert!(predicate.matches_lookup(&config));
+    /// assert!(!not(name("unix")).matches_lookup(&config));
+    /// ```
+    pub fn matches_lookup<P: PatternExt + ?Sized>(&self, pattern: &P) -> bool {
+        let mut lookups = BTreeMap::new();
+
+        self.eval(|name, value| {
+            lookups
+                .entry(name)
+                .or_insert_with(|| pattern.lookup(name))
+                .matches(value)
+        })
+    }
+
+    /// Evaluates the predicate with the answers of the leaves.
+    fn eval<'a, F>(&'a self, mut leaf: F) -> bool
+    where
+        F: FnMut(&'a str, Option<&'a str>) -> bool,
+    {
         use Predicate::*;
 
-        match self {
-            Any(predicates) => predicates
-                .iter()
-                .any(|predicate| predicate.matches(pattern)),
-            All(predicates) => predicates
-                .iter()
-                .all(|predicate| predicate.matches(pattern)),
-            Not(predicate) => !predicate.matches(pattern),
-            Name(name) => pattern.matches(name, None),
-            NameValue(name, value) => pattern.matches(name, Some(value)),
+        enum Frame<'a> {
+            /// An `any` (`all` is `false`) or `all` with its remaining sub-predicates.
+            List {
+                all: bool,
+                rest: core::slice::Iter<'a, Box<Predicate>>,
+            },
+            Not,
+        }
+
+        let mut stack = Vec::new();
+        let mut predicate: &'a Predicate = self;
+
+        'descend: loop {
+            let mut matched = match predicate {
+                Any(predicates) | All(predicates) => {
+                    let all = matches!(predicate, All(_));
+                    let mut rest = predicates.iter();
+
+                    match rest.next() {
+                        Some(first) => {
+                            stack.push(Frame::List { all, rest });
+                            predicate = first;
+                            continue;
+                        }
+                        None => all,
+                    }
+                }
+                Not(inner) => {
+                    stack.push(Frame::Not);
+                    predicate = inner;
+                    continue;
+                }
+                Name(name) => leaf(name, None),
+                NameValue(name, value) => leaf(name, Some(value)),
+                True => true,
+                False | Param(_) => false,
+            };
+
+            while let Some(frame) = stack.last_mut() {
+                match frame {
+                    Frame::Not => matched = !matched,
+                    // `any` stops at the first matched sub-predicate, and `all` at the first unmatched one
+                    Frame::List { all, rest } if matched == *all => {
+                        if let Some(next) = rest.next() {
+                            predicate = next;
+                            continue 'descend;
+                        }
+                    }
+                    Frame::List { .. } => {}
+                }
+
+                stack.pop();
+            }
+
+            return matched;
         }
     }
+
+    /// Returns `true` if the predicate matches the enabled Cargo features.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let predicate = all(vec![feature("serde"), not(feature("std"))]);
+    ///
+    /// assert!(predicate.matches_features(&["serde", "alloc"]));
+    /// assert!(!predicate.matches_features(&["serde", "std"]));
+    /// ```
+    pub fn matches_features(&self, features: &[&str]) -> bool {
+        self.matches(&vec![("feature", Some(features))])
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
     cfg_if! {
         if #[cfg(feature = "std")] {
@@ -169,6 +470,16 @@ mod tests {
                 vec![("unix", None), ("target_pointer_width", Some("32"))],
                 true,
             ),
+            (Cfg(True), vec![], true),
+            (Cfg(False), vec![("unix", None)], false),
+            (
+                Cfg(Any(vec![
+                    Box::new(False),
+                    Box::new(Name("unix".to_owned())),
+                ])),
+                vec![("unix", None)],
+                true,
+            ),
         ];
 
         for (cfg, flags, res) in testcases {
@@ -176,8 +487,183 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_matches_deep() {
+        let mut predicate = Name("unix".to_owned());
+
+        for i in 0..100_000 {
+            predicate = if i % 2 == 0 {
+                Not(Box::new(predicate))
+            } else {
+                All(vec![Box::new(True), Box::new(predicate)])
+            };
+        }
+
+        assert!(predicate.matches(&vec![("unix", None::<&str>)]));
+        assert!(!predicate.matches(&vec![("windows", None::<&str>)]));
+
+        // the derived `Drop` is recursive
+        loop {
+            predicate = match predicate {
+                Not(inner) => *inner,
+                All(mut predicates) => *predicates.pop().unwrap(),
+                _ => break,
+            };
+        }
+    }
+
+    #[test]
+    fn test_matches_lookup() {
+        use core::cell::Cell;
+
+        use crate::{FlagSet, Lookup, Pattern, PatternExt};
+
+        struct Counting(FlagSet, Cell<usize>);
+
+        impl Pattern for Counting {
+            fn matches(&self, key: &str, value: Option<&str>) -> bool {
+                self.lookup(key).matches(value)
+            }
+        }
+
+        impl PatternExt for Counting {
+            fn lookup(&self, key: &str) -> Lookup<'_> {
+                self.1.set(self.1.get() + 1);
+                self.0.lookup(key)
+            }
+        }
+
+        let flags = Counting(
+            vec![("unix", None), ("feature", Some("std"))]
+                .into_iter()
+                .collect::<FlagSet>(),
+            Cell::new(0),
+        );
+        let predicate = All(vec![
+            Box::new(Name("feature".to_owned())),
+            Box::new(Not(Box::new(NameValue(
+                "feature".to_owned(),
+                "serde".to_owned(),
+            )))),
+            Box::new(NameValue("feature".to_owned(), "std".to_owned())),
+            Box::new(Name("unix".to_owned())),
+            Box::new(Not(Box::new(NameValue("unix".to_owned(), "1".to_owned())))),
+        ]);
+
+        assert!(predicate.matches(&flags));
+        assert_eq!(flags.1.replace(0), 5);
+        assert!(predicate.matches_lookup(&flags));
+        assert_eq!(flags.1.get(), 2);
+
+        assert!(!Lookup::Absent.is_present());
+        assert!(!Lookup::Absent.matches(None));
+        assert!(Lookup::Name.matches(None));
+        assert!(!Lookup::Name.matches(Some("std")));
+        assert!(Lookup::Values(&"std").matches(None));
+        assert!(Lookup::Values(&"std").matches(Some("std")));
+    }
+
+    #[test]
+    fn test_matches_array() {
+        use crate::{all, name, name_value};
+
+        let predicate = all(vec![name("unix"), name_value("target_os", "linux")]);
+        let flags = [("unix", None), ("target_os", Some("linux"))];
+
+        assert!(predicate.matches(&flags));
+        assert!(predicate.matches(&&flags));
+        assert!(predicate.matches(&flags[..]));
+        assert!(!predicate.matches(&flags[..1]));
+        assert!(!predicate.matches(&[("unix", None::<&str>)]));
+    }
+
+    #[test]
+    fn test_matches_features() {
+        use crate::{any_features, feature, not};
+
+        let predicate = any_features(vec!["foo", "bar"]);
+
+        assert_eq!(
+            predicate,
+            Any(vec![
+                Box::new(NameValue("feature".to_owned(), "foo".to_owned())),
+                Box::new(NameValue("feature".to_owned(), "bar".to_owned())),
+            ])
+        );
+        assert!(predicate.matches_features(&["bar"]));
+        assert!(!predicate.matches_features(&[]));
+        assert!(not(feature("foo")).matches_features(&["bar"]));
+    }
+
+    #[test]
+    fn test_matches_owned() {
+        cfg_if! {
+            if #[cfg(not(feature = "std"))] {
+                use alloc::borrow::Cow;
+                use alloc::string::String;
+                use alloc::vec::Vec;
+            } else {
+                use std::borrow::Cow;
+            }
+        }
+
+        use crate::{feature, name_value};
+
+        let features: Vec<String> = vec!["std".to_owned(), "serde".to_owned()];
+        let os = "linux".to_owned();
+
+        assert!(feature("serde").matches(&vec![("feature", Some(features.clone()))]));
+        assert!(feature("std").matches(&vec![("feature", Some(features.as_slice()))]));
+        assert!(!feature("alloc").matches(&vec![("feature", Some(features))]));
+        assert!(name_value("target_os", "linux").matches(&vec![("target_os", Some(&os))]));
+        assert!(name_value("target_os", "linux").matches(&vec![
+            ("unix".to_owned(), None),
+            ("target_os".to_owned(), Some(os.clone()))
+        ]));
+        assert!(name_value("target_os", "linux").matches(&vec![(
+            Cow::Borrowed("target_os"),
+            Some(Cow::<str>::Owned(os))
+        )]));
+    }
+
+    #[test]
+    fn test_matches_numeric() {
+        use crate::{name_value, Matcher};
+
+        let flags = [
+            ("target_pointer_width", Some(64u32..=64)),
+            ("target_has_atomic", Some(8..=128)),
+        ];
+
+        assert!(name_value("target_pointer_width", "64").matches(&flags));
+        assert!(!name_value("target_pointer_width", "32").matches(&flags));
+        assert!(!name_value("target_pointer_width", "ptr").matches(&flags));
+        assert!(name_value("target_has_atomic", "16").matches(&flags));
+
+        assert!(Matcher::matches(&true, "true"));
+        assert!(!Matcher::matches(&false, "true"));
+        assert!(!Matcher::matches(&true, "1"));
+        assert!(Matcher::matches(&-1i8, "-1"));
+        assert!(Matcher::matches(&(1u64..), "10"));
+        assert!(!Matcher::matches(&(1usize..4), "4"));
+
+        #[cfg(feature = "std")]
+        #[allow(clippy::disallowed_types)]
+        {
+            use std::collections::HashMap;
+
+            let mut flags = HashMap::new();
+
+            flags.insert("target_pointer_width", 64u32);
+
+            assert!(name_value("target_pointer_width", "64").matches(&flags));
+            assert!(!name_value("target_pointer_width", "064x").matches(&flags));
+        }
+    }
+
     #[cfg(feature = "std")]
     #[test]
+    #[allow(clippy::disallowed_types)]
     fn test_matches_hashmap() {
         use std::collections::HashMap;
 
@@ -0,0 +1,103 @@
+//! Configurations of the fields and variants of a type, available at runtime.
+
+use crate::Cfg;
+
+/// A type records the configurations of its fields or enum variants.
+///
+/// With the `macros` feature, it could be implemented with `#[derive(RuntimeCfg)]`
+/// or `#[cfg_fields]`.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "macros")] fn main() {
+/// use runtime_cfg::*;
+///
+/// #[cfg_fields]
+/// #[allow(dead_code)]
+/// struct Config {
+///     #[cfg(unix)]
+///     socket: String,
+///     #[cfg(windows)]
+///     pipe: String,
+///     port: u16,
+/// }
+///
+/// assert_eq!(
+///     Config::field_cfgs(),
+///     &[
+///         ("socket", Cfg::from(name("unix"))),
+///         ("pipe", Cfg::from(name("windows"))),
+///     ]
+/// );
+/// # }
+/// # #[cfg(not(feature = "macros"))] fn main() {}
+/// ```
+pub trait RuntimeCfg {
+    /// Returns the names of the conditional fields or variants, and their configurations.
+    ///
+    /// The fields of an enum variant are named as `Variant::field`.
+    fn field_cfgs() -> &'static [(&'static str, Cfg)];
+}
+
+#[cfg(all(test, feature = "macros"))]
+mod tests {
+    use crate::*;
+
+    #[derive(RuntimeCfg)]
+    #[allow(dead_code)]
+    struct Derived {
+        #[cfg(test)]
+        active: u8,
+        #[cfg(not(test))]
+        inactive: u8,
+        always: u8,
+    }
+
+    #[cfg_fields]
+    #[allow(dead_code)]
+    enum Attributed {
+        #[cfg(unix)]
+        Unix(#[cfg(target_os = "linux")] u8),
+        Named {
+            #[cfg_attr(feature = "std", cfg(not(windows)))]
+            field: u8,
+            #[cfg(unix)]
+            #[cfg(not(target_env = "musl"))]
+            other: u8,
+        },
+    }
+
+    #[test]
+    fn test_derive() {
+        assert_eq!(
+            Derived::field_cfgs(),
+            &[("active", Cfg::from(name("test")))]
+        );
+    }
+
+    #[test]
+    fn test_cfg_fields() {
+        assert_eq!(
+            Attributed::field_cfgs(),
+            &[
+                ("Unix", Cfg::from(name("unix"))),
+                ("Unix::0", Cfg::from(name_value("target_os", "linux"))),
+                (
+                    "Named::field",
+                    Cfg::from(any(vec![
+                        not(feature("std")),
+                        all(vec![not(name("windows"))])
+                    ]))
+                ),
+                (
+                    "Named::other",
+                    Cfg::from(all(vec![
+                        name("unix"),
+                        not(name_value("target_env", "musl"))
+                    ]))
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Complexity metrics of a predicate tree.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::vec;
+    }
+}
+
+use core::cmp;
+
+use crate::Predicate;
+
+/// The complexity metrics of a predicate.
+///
+/// The metrics may also be used as a complexity budget.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let predicate = all(vec![name("unix"), not(name_value("target_env", "musl"))]);
+/// let metrics = predicate.metrics();
+///
+/// assert_eq!(metrics, Metrics { depth: 3, nodes: 4, leaves: 2 });
+///
+/// let budget = Metrics { depth: 2, ..Metrics::UNLIMITED };
+///
+/// assert!(metrics.exceeds(&budget));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Metrics {
+    /// The depth of the predicate tree, a single leaf has a depth of 1.
+    pub depth: usize,
+    /// The total number of nodes in the predicate tree.
+    pub nodes: usize,
+    /// The number of `Name` and `NameValue` leaves in the predicate tree.
+    pub leaves: usize,
+}
+
+impl Metrics {
+    /// A budget without any limitation.
+    pub const UNLIMITED: Metrics = Metrics {
+        depth: usize::MAX,
+        nodes: usize::MAX,
+        leaves: usize::MAX,
+    };
+
+    /// Returns `true` if any of the metrics exceeds the budget.
+    pub fn exceeds(&self, budget: &Metrics) -> bool {
+        self.depth > budget.depth || self.nodes > budget.nodes || self.leaves > budget.leaves
+    }
+}
+
+impl Predicate {
+    /// Returns the complexity metrics of the predicate.
+    ///
+    /// The predicate is walked with an explicit stack, so it never overflows the stack however deep the predicate is.
+    pub fn metrics(&self) -> Metrics {
+        let mut metrics = Metrics::default();
+        let mut pending = vec![(self, 1)];
+
+        while let Some((predicate, depth)) = pending.pop() {
+            metrics.depth = cmp::max(metrics.depth, depth);
+            metrics.nodes += 1;
+
+            match predicate {
+                Predicate::Any(predicates) | Predicate::All(predicates) => {
+                    pending.extend(predicates.iter().map(|predicate| (&**predicate, depth + 1)))
+                }
+                Predicate::Not(predicate) => pending.push((predicate, depth + 1)),
+                Predicate::Name(_) | Predicate::NameValue(..) => metrics.leaves += 1,
+                Predicate::True | Predicate::False | Predicate::Param(_) => {}
+            }
+        }
+
+        metrics
+    }
+
+    /// Returns `true` if the complexity metrics of the predicate exceeds the budget.
+    pub fn exceeds(&self, budget: &Metrics) -> bool {
+        self.metrics().exceeds(budget)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_metrics() {
+        let testcases = vec![
+            (name("unix"), (1, 1, 1)),
+            (any(vec![]), (1, 1, 0)),
+            (
+                any(vec![
+                    name("foo"),
+                    all(vec![name("bar"), not(name_value("target_os", "macos"))]),
+                ]),
+                (4, 6, 3),
+            ),
+        ];
+
+        for (predicate, (depth, nodes, leaves)) in testcases {
+            assert_eq!(
+                predicate.metrics(),
+                Metrics {
+                    depth,
+                    nodes,
+                    leaves
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_metrics_deep() {
+        let mut predicate = (0..100_000).fold(name("unix"), |predicate, _| not(predicate));
+
+        assert_eq!(
+            predicate.metrics(),
+            Metrics {
+                depth: 100_001,
+                nodes: 100_001,
+                leaves: 1
+            }
+        );
+        assert!(matches!(
+            predicate.check_nesting(),
+            Err(Error::LimitExceeded { .. })
+        ));
+
+        // the derived `Drop` is recursive
+        while let Predicate::Not(inner) = predicate {
+            predicate = *inner;
+        }
+    }
+
+    #[test]
+    fn test_exceeds() {
+        let predicate = all(vec![name("foo"), name("bar")]);
+
+        assert!(!predicate.exceeds(&Metrics::UNLIMITED));
+        assert!(!predicate.exceeds(&Metrics {
+            depth: 2,
+            nodes: 3,
+            leaves: 2
+        }));
+        assert!(predicate.exceeds(&Metrics {
+            leaves: 1,
+            ..Metrics::UNLIMITED
+        }));
+    }
+}
@@ -0,0 +1,81 @@
+//! Predicates keyed by name, shared by the aliases and the parameter bindings.
+
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::collections::btree_map::{self, BTreeMap};
+    } else {
+        use alloc::collections::btree_map::{self, BTreeMap};
+        use alloc::string::String;
+    }
+}
+
+use core::iter::FromIterator;
+
+use crate::Predicate;
+
+/// A map from names to predicates, ordered by name.
+///
+/// It is used as the [`Aliases`](crate::Aliases) of bare names and the [`Bindings`](crate::Bindings) of parameters.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NamedPredicates(pub(crate) BTreeMap<String, Predicate>);
+
+impl NamedPredicates {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a predicate under the name, returns the previous predicate of the name if any.
+    pub fn insert<S: Into<String>>(&mut self, name: S, predicate: Predicate) -> Option<Predicate> {
+        self.0.insert(name.into(), predicate)
+    }
+
+    /// Removes a name, returns its predicate if any.
+    pub fn remove(&mut self, name: &str) -> Option<Predicate> {
+        self.0.remove(name)
+    }
+
+    /// Returns the predicate of a name.
+    pub fn get(&self, name: &str) -> Option<&Predicate> {
+        self.0.get(name)
+    }
+
+    /// Returns `true` if the name has a predicate.
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// Returns the number of names.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if no name has a predicate.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns an iterator over the names and their predicates, ordered by name.
+    pub fn iter(&self) -> btree_map::Iter<'_, String, Predicate> {
+        self.0.iter()
+    }
+}
+
+impl<S: Into<String>> FromIterator<(S, Predicate)> for NamedPredicates {
+    fn from_iter<I: IntoIterator<Item = (S, Predicate)>>(iter: I) -> Self {
+        NamedPredicates(
+            iter.into_iter()
+                .map(|(name, predicate)| (name.into(), predicate))
+                .collect(),
+        )
+    }
+}
+
+impl<S: Into<String>> Extend<(S, Predicate)> for NamedPredicates {
+    fn extend<I: IntoIterator<Item = (S, Predicate)>>(&mut self, iter: I) {
+        self.0.extend(
+            iter.into_iter()
+                .map(|(name, predicate)| (name.into(), predicate)),
+        )
+    }
+}
@@ -0,0 +1,425 @@
+//! Flags from the Nix-style attribute sets, like `{ target_os = "linux"; feature = [ "ssl" "gzip" ]; }`.
+//!
+//! | Attribute                     | Flags                           |
+//! |-------------------------------|---------------------------------|
+//! | `key = "value";`              | `key = "value"`                 |
+//! | `key = 42;`                   | `key = "42"`                    |
+//! | `key = true;`                 | `key`                           |
+//! | `key = false;` / `key = null;`| unset                           |
+//! | `key = [ "a" 1 ];`            | `key = "a"`, `key = "1"`        |
+//! | `a.b = "c";`                  | `a.b = "c"`                     |
+//! | `a = { b = "c"; };`           | `a.b = "c"`                     |
+//!
+//! The surrounding braces are optional, the keys may be quoted, and `#` starts a line comment.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::string::{String, ToString};
+        use alloc::vec::Vec;
+    }
+}
+
+use core::fmt;
+
+use crate::FlagSet;
+
+/// An error when parsing a Nix-style attribute set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NixError {
+    /// The byte offset of the error.
+    pub offset: usize,
+    /// The description of the error.
+    pub message: &'static str,
+}
+
+impl fmt::Display for NixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl core::error::Error for NixError {}
+
+impl FlagSet {
+    /// Parses the flags from a Nix-style attribute set.
+    ///
+    /// With the `no-panic` feature, the attribute sets nested deeper than `MAX_NESTING` are rejected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let flags = FlagSet::from_nix(r#"
+    ///     {
+    ///         target.os = "linux";
+    ///         unix = true;
+    ///         services = { nginx.enable = true; };
+    ///         feature = [ "ssl" "gzip" ];
+    ///     }
+    /// "#)
+    /// .unwrap();
+    ///
+    /// assert!(all(vec![
+    ///     name("unix"),
+    ///     name_value("target.os", "linux"),
+    ///     name("services.nginx.enable"),
+    ///     feature("gzip"),
+    /// ])
+    /// .matches(&flags));
+    /// ```
+    pub fn from_nix(s: &str) -> Result<FlagSet, NixError> {
+        let mut parser = Parser {
+            s,
+            off: 0,
+            depth: 0,
+        };
+        let mut flags = FlagSet::new();
+
+        parser.skip_whitespace();
+
+        if parser.peek() == Some('{') {
+            parser.bump();
+            parser.bindings(&mut flags, "", Some('}'))?;
+        } else {
+            parser.bindings(&mut flags, "", None)?;
+        }
+
+        parser.skip_whitespace();
+
+        if parser.off < s.len() {
+            Err(parser.error("unexpected trailing characters"))
+        } else {
+            Ok(flags)
+        }
+    }
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    off: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &'static str) -> NixError {
+        NixError {
+            offset: self.off,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.s[self.off..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+
+        self.off += c.len_utf8();
+
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('#') => while self.bump().is_some_and(|c| c != '\n') {},
+                _ => break,
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char, message: &'static str) -> Result<(), NixError> {
+        self.skip_whitespace();
+
+        if self.peek() == Some(expected) {
+            self.bump();
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    /// Parses the bindings until the closing character, or the end of input.
+    fn bindings(
+        &mut self,
+        flags: &mut FlagSet,
+        prefix: &str,
+        close: Option<char>,
+    ) -> Result<(), NixError> {
+        loop {
+            self.skip_whitespace();
+
+            match (self.peek(), close) {
+                (None, None) => return Ok(()),
+                (Some(c), Some(close)) if c == close => {
+                    self.bump();
+                    return Ok(());
+                }
+                (None, Some(_)) => return Err(self.error("unterminated attribute set")),
+                _ => {}
+            }
+
+            let mut key = prefix.to_string();
+
+            loop {
+                if !key.is_empty() {
+                    key.push('.');
+                }
+
+                key.push_str(&self.key()?);
+                self.skip_whitespace();
+
+                if self.peek() == Some('.') {
+                    self.bump();
+                } else {
+                    break;
+                }
+            }
+
+            self.expect('=', "expect `=`")?;
+            self.value(flags, &key)?;
+            self.expect(';', "expect `;`")?;
+        }
+    }
+
+    fn key(&mut self) -> Result<String, NixError> {
+        self.skip_whitespace();
+
+        if self.peek() == Some('"') {
+            return self.string();
+        }
+
+        let start = self.off;
+
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '\'')
+        {
+            self.bump();
+        }
+
+        if start == self.off {
+            Err(self.error("expect a key"))
+        } else {
+            Ok(self.s[start..self.off].to_string())
+        }
+    }
+
+    fn value(&mut self, flags: &mut FlagSet, key: &str) -> Result<(), NixError> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('{') => {
+                #[cfg(feature = "no-panic")]
+                {
+                    if self.depth >= crate::MAX_NESTING {
+                        return Err(self.error("nesting too deep"));
+                    }
+                }
+
+                self.bump();
+                self.depth += 1;
+
+                let parsed = self.bindings(flags, key, Some('}'));
+
+                self.depth -= 1;
+
+                parsed
+            }
+            Some('[') => {
+                self.bump();
+
+                let mut values = Vec::new();
+
+                loop {
+                    self.skip_whitespace();
+
+                    if self.peek() == Some(']') {
+                        self.bump();
+                        break;
+                    }
+
+                    match self.scalar()? {
+                        Scalar::Value(value) => values.push(value),
+                        Scalar::Bool(b) => values.push(b.to_string()),
+                        Scalar::Null => {}
+                    }
+                }
+
+                if values.is_empty() {
+                    flags.insert_name(key);
+                }
+
+                for value in values {
+                    flags.insert(key, value);
+                }
+
+                Ok(())
+            }
+            _ => {
+                match self.scalar()? {
+                    Scalar::Value(value) => {
+                        flags.insert(key, value);
+                    }
+                    Scalar::Bool(true) => flags.insert_name(key),
+                    Scalar::Bool(false) | Scalar::Null => {}
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    fn scalar(&mut self) -> Result<Scalar, NixError> {
+        self.skip_whitespace();
+
+        if self.peek() == Some('"') {
+            return self.string().map(Scalar::Value);
+        }
+
+        let start = self.off;
+
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+        {
+            self.bump();
+        }
+
+        match &self.s[start..self.off] {
+            "true" => Ok(Scalar::Bool(true)),
+            "false" => Ok(Scalar::Bool(false)),
+            "null" => Ok(Scalar::Null),
+            number if number.parse::<f64>().is_ok() => Ok(Scalar::Value(number.to_string())),
+            _ => {
+                self.off = start;
+
+                Err(self.error("expect a string, number, boolean, null, list or attribute set"))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, NixError> {
+        self.expect('"', "expect a string")?;
+
+        let mut value = String::new();
+
+        loop {
+            match self
+                .bump()
+                .ok_or_else(|| self.error("unterminated string"))?
+            {
+                '"' => return Ok(value),
+                '\\' => {
+                    let c = match self.bump() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('$') => '$',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        _ => return Err(self.error("invalid escape")),
+                    };
+
+                    value.push(c);
+                }
+                '$' if self.peek() == Some('{') => {
+                    return Err(self.error("string interpolation is not supported"))
+                }
+                c => value.push(c),
+            }
+        }
+    }
+}
+
+enum Scalar {
+    Value(String),
+    Bool(bool),
+    Null,
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_from_nix() {
+        let flags = FlagSet::from_nix(
+            r#"
+            # quoted key
+            "x-y" = "a\"b";
+            level = 3; ratio = 0.5;
+            debug = false; region = null;
+            tags = [ true 2 null "t" ]; empty = [ ];
+            a = { b.c = { d = "e"; }; };
+        "#,
+        )
+        .unwrap();
+        let expected: FlagSet = vec![
+            ("x-y", Some("a\"b")),
+            ("level", Some("3")),
+            ("ratio", Some("0.5")),
+            ("tags", Some("true")),
+            ("tags", Some("2")),
+            ("tags", Some("t")),
+            ("empty", None),
+            ("a.b.c.d", Some("e")),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(flags, expected);
+        assert_eq!(FlagSet::from_nix("{ }"), Ok(FlagSet::new()));
+
+        let testcases = vec![
+            ("{ a = 1;", 8, "unterminated attribute set"),
+            ("a = 1", 5, "expect `;`"),
+            ("a 1;", 2, "expect `=`"),
+            (
+                "a = foo;",
+                4,
+                "expect a string, number, boolean, null, list or attribute set",
+            ),
+            ("a = \"${b}\";", 6, "string interpolation is not supported"),
+            ("{ a = 1; } }", 11, "unexpected trailing characters"),
+        ];
+
+        for (s, offset, message) in testcases {
+            assert_eq!(
+                FlagSet::from_nix(s),
+                Err(NixError { offset, message }),
+                "parse {}",
+                s
+            );
+        }
+    }
+
+    #[cfg(feature = "no-panic")]
+    #[test]
+    fn test_from_nix_too_deep() {
+        let nested = |n| "a = { ".repeat(n) + "b = 1;" + &" };".repeat(n);
+
+        assert!(FlagSet::from_nix(&nested(MAX_NESTING)).is_ok());
+        assert_eq!(
+            FlagSet::from_nix(&nested(MAX_NESTING + 1)),
+            Err(NixError {
+                offset: MAX_NESTING * 6 + 4,
+                message: "nesting too deep",
+            })
+        );
+        assert!(
+            FlagSet::from_nix(&("a = {".repeat(200_000) + "1" + &"; }".repeat(200_000))).is_err()
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! Placeholders in predicates, bound to concrete predicates at evaluation time.
+
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::collections::BTreeSet;
+    } else {
+        use alloc::collections::BTreeSet;
+        use alloc::string::String;
+    }
+}
+
+use crate::{fold::Fold, visit::Visit, NamedPredicates, Pattern, Predicate};
+
+/// The bindings of parameters, mapping a parameter name to a concrete predicate.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let predicate = all(vec![name("unix"), param("region")]);
+///
+/// let mut bindings = Bindings::new();
+/// bindings.insert("region", name_value("region", "eu-west-1"));
+///
+/// let flags = vec![("unix", None), ("region", Some("eu-west-1"))];
+///
+/// assert!(!predicate.matches(&flags));
+/// assert!(predicate.matches_with(&flags, &bindings));
+/// assert_eq!(
+///     predicate.bind(&bindings),
+///     all(vec![name("unix"), name_value("region", "eu-west-1")])
+/// );
+/// ```
+pub type Bindings = NamedPredicates;
+
+impl Predicate {
+    /// Returns a predicate with the bound parameters replaced by their predicates.
+    ///
+    /// The unbound parameters are left as is.
+    pub fn bind(&self, bindings: &Bindings) -> Predicate {
+        self.clone().fold(&mut Bind { bindings })
+    }
+
+    /// Returns `true` if configuration matches the predicate,
+    /// with the parameters resolved from the bindings.
+    ///
+    /// An unbound parameter never matches.
+    pub fn matches_with<P: Pattern>(&self, pattern: &P, bindings: &Bindings) -> bool {
+        use Predicate::*;
+
+        match self {
+            Any(predicates) => predicates
+                .iter()
+                .any(|predicate| predicate.matches_with(pattern, bindings)),
+            All(predicates) => predicates
+                .iter()
+                .all(|predicate| predicate.matches_with(pattern, bindings)),
+            Not(predicate) => !predicate.matches_with(pattern, bindings),
+            Param(name) => bindings
+                .get(name)
+                .is_some_and(|predicate| predicate.matches(pattern)),
+            _ => self.matches(pattern),
+        }
+    }
+
+    /// Returns the names of the parameters referenced by the predicate.
+    pub fn params(&self) -> BTreeSet<&str> {
+        let mut params = Params::default();
+
+        self.walk(&mut params);
+
+        params.0
+    }
+}
+
+struct Bind<'a> {
+    bindings: &'a Bindings,
+}
+
+impl Fold for Bind<'_> {
+    fn fold_param(&mut self, name: String) -> Predicate {
+        match self.bindings.get(&name) {
+            Some(predicate) => predicate.clone(),
+            None => Predicate::Param(name),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Params<'ast>(BTreeSet<&'ast str>);
+
+impl<'ast> Visit<'ast> for Params<'ast> {
+    fn visit_param(&mut self, name: &'ast str) {
+        self.0.insert(name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+            use alloc::vec::Vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_bind() {
+        let predicate = any(vec![
+            param("region"),
+            all(vec![name("staging"), not(param("tier"))]),
+        ]);
+        let bindings: Bindings = vec![("region", name_value("region", "us"))]
+            .into_iter()
+            .collect();
+
+        assert_eq!(
+            predicate.params().into_iter().collect::<Vec<_>>(),
+            vec!["region", "tier"]
+        );
+        assert_eq!(
+            predicate.bind(&bindings),
+            any(vec![
+                name_value("region", "us"),
+                all(vec![name("staging"), not(param("tier"))]),
+            ])
+        );
+        assert_eq!(
+            predicate
+                .bind(&bindings)
+                .params()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["tier"]
+        );
+    }
+
+    #[test]
+    fn test_matches_with() {
+        let predicate = all(vec![name("staging"), not(param("tier"))]);
+        let flags = vec![("staging", None), ("tier", Some("gold"))];
+
+        assert!(predicate.matches(&flags));
+        assert!(predicate.matches_with(&flags, &Bindings::new()));
+        assert!(predicate.matches_with(
+            &flags,
+            &vec![("tier", name_value("tier", "free"))]
+                .into_iter()
+                .collect()
+        ));
+        assert!(!predicate.matches_with(
+            &flags,
+            &vec![("tier", name_value("tier", "gold"))]
+                .into_iter()
+                .collect()
+        ));
+    }
+}
@@ -3,18 +3,29 @@ use core::str::FromStr;
 
 use syn::{bracketed, spanned::Spanned, Token};
 
-use crate::{Cfg, Predicate};
+use crate::{Cfg, Error, Predicate};
 
 impl FromStr for Cfg {
-    type Err = syn::Error;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Cfg::parse(s)
     }
 }
 
+/// Parses a `#[cfg(..)]` attribute, or a bare `cfg(..)` predicate.
+///
+/// The bare form is accepted for the configuration files and command lines, where the attribute syntax is noisy;
+/// any other meta, like `unix` or `any(..)`, is still rejected.
 impl syn::parse::Parse for Cfg {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        #[cfg(feature = "no-panic")]
+        check_tokens_nesting(&input.fork().parse()?)?;
+
+        if !input.peek(Token![#]) {
+            return input.parse::<syn::Meta>().and_then(Cfg::try_from);
+        }
+
         let _: Token![#] = input.parse()?;
         let content;
         let _ = bracketed!(content in input);
@@ -32,6 +43,7 @@ pub fn cfg<T: TryInto<Cfg>>(from: T) -> Result<Cfg, <T as TryInto<Cfg>>::Error>
     from.try_into()
 }
 
+/// Parses the tokens of a `#[cfg(..)]` attribute, or a bare `cfg(..)` predicate.
 impl TryFrom<proc_macro2::TokenStream> for Cfg {
     type Error = syn::Error;
 
@@ -44,6 +56,9 @@ impl<'ast> TryFrom<&'ast syn::Attribute> for Cfg {
     type Error = syn::Error;
 
     fn try_from(attr: &'ast syn::Attribute) -> Result<Self, Self::Error> {
+        #[cfg(feature = "no-panic")]
+        check_tokens_nesting(&attr.tts)?;
+
         attr.parse_meta().and_then(Cfg::try_from)
     }
 }
@@ -52,7 +67,7 @@ impl TryFrom<syn::Attribute> for Cfg {
     type Error = syn::Error;
 
     fn try_from(attr: syn::Attribute) -> Result<Self, Self::Error> {
-        attr.parse_meta().and_then(Cfg::try_from)
+        Cfg::try_from(&attr)
     }
 }
 
@@ -60,6 +75,9 @@ impl TryFrom<syn::Meta> for Cfg {
     type Error = syn::Error;
 
     fn try_from(meta: syn::Meta) -> Result<Self, Self::Error> {
+        #[cfg(feature = "no-panic")]
+        check_meta_nesting(&meta)?;
+
         if meta.name() == "cfg" {
             parse_meta(meta).map(Cfg)
         } else {
@@ -78,8 +96,31 @@ impl Cfg {
     }
 
     /// Parse the `cfg` attribute from `meta`
-    pub fn parse<S: AsRef<str>>(s: S) -> syn::Result<Self> {
-        syn::parse_str(s.as_ref())
+    pub fn parse<S: AsRef<str>>(s: S) -> Result<Self, Error> {
+        #[cfg(feature = "no-panic")]
+        check_nesting(s.as_ref())?;
+
+        syn::parse_str(s.as_ref()).map_err(Error::Parse)
+    }
+
+    /// Returns `true` if the configuration equals the parsed `#[cfg(..)]` attribute or bare `cfg(..)` predicate,
+    /// or `false` if it fails to parse.
+    ///
+    /// The predicates are compared structurally, the order of sub-predicates matters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let cfg = Cfg::from(all(vec![name("unix"), name_value("target_os", "linux")]));
+    ///
+    /// assert!(cfg.is(r#"cfg(all(unix, target_os = "linux"))"#));
+    /// assert!(cfg.is(r#"#[cfg(all(unix, target_os = "linux"))]"#));
+    /// assert!(!cfg.is(r#"cfg(all(target_os = "linux", unix))"#));
+    /// ```
+    pub fn is<S: AsRef<str>>(&self, s: S) -> bool {
+        Cfg::parse(s).is_ok_and(|cfg| cfg == *self)
     }
 }
 
@@ -87,7 +128,7 @@ fn parse_meta(meta: syn::Meta) -> syn::Result<Predicate> {
     match meta {
         syn::Meta::Word(value) => Ok(Predicate::Name(value.to_string())),
         syn::Meta::NameValue(syn::MetaNameValue { ident, lit, .. }) => {
-            Ok(Predicate::NameValue(ident.to_string(), lit_to_string(lit)))
+            Ok(Predicate::NameValue(ident.to_string(), lit_to_string(lit)?))
         }
         syn::Meta::List(meta_list) => parse_meta_list(meta_list),
     }
@@ -128,6 +169,30 @@ fn parse_meta_list(meta_list: syn::MetaList) -> syn::Result<Predicate> {
         } else {
             predicate
         }
+    } else if ident == "param" {
+        let mut predicates = nested.into_iter();
+        let predicate = match predicates.next() {
+            Some(syn::NestedMeta::Meta(syn::Meta::Word(name))) => {
+                Ok(Predicate::Param(name.to_string()))
+            }
+            Some(nested_meta) => Err(syn::Error::new(
+                nested_meta.span(),
+                "#[cfg(param(..))] expect a parameter name",
+            )),
+            None => Err(syn::Error::new(
+                span,
+                "#[cfg(param(..))] parameter can't be empty",
+            )),
+        };
+
+        if let Some(nested_meta) = predicates.next() {
+            Err(syn::Error::new(
+                nested_meta.span(),
+                "#[cfg(param(..))] only support one parameter",
+            ))
+        } else {
+            predicate
+        }
     } else if ident == "cfg" {
         let mut predicates = nested.into_iter();
         let predicate = predicates
@@ -151,31 +216,151 @@ fn parse_meta_list(meta_list: syn::MetaList) -> syn::Result<Predicate> {
     }
 }
 
-fn parse_nested_meta(nested_meta: syn::NestedMeta) -> syn::Result<Predicate> {
+pub(crate) fn parse_nested_meta(nested_meta: syn::NestedMeta) -> syn::Result<Predicate> {
     let span = nested_meta.span();
 
     match nested_meta {
         syn::NestedMeta::Meta(meta) => parse_meta(meta),
+        syn::NestedMeta::Literal(syn::Lit::Bool(lit)) => Ok(if lit.value {
+            Predicate::True
+        } else {
+            Predicate::False
+        }),
         syn::NestedMeta::Literal(lit) => Err(syn::Error::new(
             span,
-            format!("unexpected literal: {:?}", lit_to_string(lit)),
+            format!("unexpected literal: {:?}", lit_to_string(lit)?),
         )),
     }
 }
 
-fn lit_to_string(lit: syn::Lit) -> String {
+fn lit_to_string(lit: syn::Lit) -> syn::Result<String> {
     use syn::Lit::*;
 
-    match lit {
+    Ok(match lit {
         Str(v) => v.value(),
-        ByteStr(v) => String::from_utf8(v.value()).expect("utf-8"),
+        ByteStr(v) => String::from_utf8(v.value())
+            .map_err(|_| syn::Error::new(v.span(), "byte string literal isn't valid UTF-8"))?,
         Byte(v) => (v.value() as char).to_string(),
         Char(v) => v.value().to_string(),
         Int(v) => v.value().to_string(),
         Float(v) => v.value().to_string(),
         Bool(v) => v.value.to_string(),
         Verbatim(v) => v.token.to_string(),
+    })
+}
+
+/// Rejects the parentheses nested deeper than [`MAX_NESTING`](crate::MAX_NESTING),
+/// before the recursive tokenizer and parser see them.
+#[cfg(feature = "no-panic")]
+fn check_nesting(s: &str) -> Result<(), Error> {
+    let bytes = s.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' => {
+                depth += 1;
+
+                if depth > crate::MAX_NESTING {
+                    return Err(Error::Parse(too_deep(proc_macro2::Span::call_site())));
+                }
+            }
+            b')' => depth = depth.saturating_sub(1),
+            b'"' => {
+                i += 1;
+
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'r' if matches!(bytes.get(i + 1), Some(b'"') | Some(b'#')) => {
+                let hashes = bytes[i + 1..].iter().take_while(|&&b| b == b'#').count();
+                let start = i + 1 + hashes;
+
+                if bytes.get(start) == Some(&b'"') {
+                    let mut closing = vec![b'"'];
+                    closing.resize(hashes + 1, b'#');
+
+                    i = bytes[start + 1..]
+                        .windows(closing.len())
+                        .position(|w| w == &closing[..])
+                        .map_or(bytes.len(), |pos| start + pos + closing.len());
+                }
+            }
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    Ok(())
+}
+
+/// Rejects the parenthesized groups nested deeper than [`MAX_NESTING`](crate::MAX_NESTING),
+/// before the recursive parser sees them.
+#[cfg(feature = "no-panic")]
+pub(crate) fn check_tokens_nesting(tokens: &proc_macro2::TokenStream) -> syn::Result<()> {
+    use proc_macro2::{Delimiter, TokenTree};
+
+    let mut groups = vec![(tokens.clone().into_iter(), false)];
+    let mut depth = 0;
+
+    while let Some((tokens, _)) = groups.last_mut() {
+        match tokens.next() {
+            Some(TokenTree::Group(group)) => {
+                let parenthesized = group.delimiter() == Delimiter::Parenthesis;
+
+                if parenthesized {
+                    depth += 1;
+
+                    if depth > crate::MAX_NESTING {
+                        return Err(too_deep(group.span()));
+                    }
+                }
+
+                groups.push((group.stream().into_iter(), parenthesized));
+            }
+            Some(_) => {}
+            None => {
+                if let Some((_, true)) = groups.pop() {
+                    depth -= 1;
+                }
+            }
+        }
     }
+
+    Ok(())
+}
+
+/// Rejects the meta lists nested deeper than [`MAX_NESTING`](crate::MAX_NESTING),
+/// before the recursive parser sees them.
+#[cfg(feature = "no-panic")]
+fn check_meta_nesting(meta: &syn::Meta) -> syn::Result<()> {
+    let mut metas = vec![(meta, 1)];
+
+    while let Some((meta, depth)) = metas.pop() {
+        if let syn::Meta::List(meta_list) = meta {
+            if depth > crate::MAX_NESTING {
+                return Err(too_deep(meta_list.span()));
+            }
+
+            metas.extend(meta_list.nested.iter().filter_map(|nested| match nested {
+                syn::NestedMeta::Meta(meta) => Some((meta, depth + 1)),
+                syn::NestedMeta::Literal(_) => None,
+            }));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "no-panic")]
+fn too_deep(span: proc_macro2::Span) -> syn::Error {
+    syn::Error::new(
+        span,
+        format!("predicate nested deeper than {}", crate::MAX_NESTING),
+    )
 }
 
 #[cfg(test)]
@@ -209,7 +394,9 @@ mod tests {
 
         for (s, l) in testcases {
             assert_eq!(
-                lit_to_string(syn::parse2::<syn::Lit>(s).unwrap()).as_str(),
+                lit_to_string(syn::parse2::<syn::Lit>(s).unwrap())
+                    .unwrap()
+                    .as_str(),
                 l
             );
         }
@@ -244,6 +431,13 @@ mod tests {
                 Cfg(Not(Box::new(Name("foo".to_owned())))),
             ),
             (quote! { #[cfg(test)] }, Cfg(Name("test".to_owned()))),
+            (
+                quote! { #[cfg(all(unix, param(region)))] },
+                Cfg(All(vec![
+                    Box::new(Name("unix".to_owned())),
+                    Box::new(Param("region".to_owned())),
+                ])),
+            ),
         ];
 
         for (ref s, ref cfg) in testcases {
@@ -255,6 +449,136 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_bare() {
+        assert_eq!(
+            Cfg::parse(r#"cfg(all(unix, target_os = "linux"))"#).unwrap(),
+            Cfg(All(vec![
+                Box::new(Name("unix".to_owned())),
+                Box::new(NameValue("target_os".to_owned(), "linux".to_owned())),
+            ]))
+        );
+        assert_eq!(
+            Cfg::parse("unix").unwrap_err().to_string(),
+            "failed to parse cfg, expect #[cfg(..)] attribute"
+        );
+    }
+
+    #[test]
+    fn test_parse_bare_tokens() {
+        let expected = Cfg(Not(Box::new(Name("test".to_owned()))));
+
+        assert_eq!(Cfg::try_from(quote!(cfg(not(test)))).unwrap(), expected);
+        assert_eq!(
+            syn::parse2::<Cfg>(quote!(cfg(not(test)))).unwrap(),
+            expected
+        );
+        assert_eq!(cfg(quote!(#[cfg(not(test))])).unwrap(), expected);
+
+        let testcases = vec![
+            (quote!(unix), "expect #[cfg(..)] attribute"),
+            (quote!(any(unix)), "expect #[cfg(..)] attribute"),
+            (
+                quote!(cfg_attr(unix, allow(dead_code))),
+                "expect #[cfg(..)] attribute",
+            ),
+            (quote!(cfg(unix) cfg(windows)), "unexpected token"),
+            (quote!(#[unix]), "expect #[cfg(..)] attribute"),
+        ];
+
+        for (tokens, message) in testcases {
+            assert_eq!(
+                Cfg::try_from(tokens.clone()).unwrap_err().to_string(),
+                message,
+                "parse {}",
+                tokens
+            );
+        }
+    }
+
+    #[test]
+    fn test_is() {
+        let cfg = Cfg(All(vec![
+            Box::new(Name("unix".to_owned())),
+            Box::new(NameValue("target_os".to_owned(), "linux".to_owned())),
+        ]));
+
+        assert!(cfg.is(r#"cfg(all(unix, target_os = "linux"))"#));
+        assert!(cfg.is(r#"#[cfg(all(unix, target_os="linux"))]"#));
+        assert!(cfg.is(String::from(r#"cfg(all(unix, target_os = "linux"))"#)));
+        assert!(!cfg.is(r#"cfg(all(target_os = "linux", unix))"#));
+        assert!(!cfg.is("cfg(all(unix"));
+        assert!(!cfg.is("all(unix)"));
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        let flags = vec![("unix", None::<&str>)];
+
+        let all = Cfg::parse("cfg(all())").unwrap();
+
+        assert_eq!(all, Cfg(All(vec![])));
+        assert!(all.matches(&flags));
+        assert!(all.matches(&Vec::<(&str, Option<&str>)>::new()));
+        assert_eq!(all.simplify(), True);
+        assert_eq!(all.canonicalize(), True);
+
+        let any = Cfg::parse("cfg(any())").unwrap();
+
+        assert_eq!(any, Cfg(Any(vec![])));
+        assert!(!any.matches(&flags));
+        assert_eq!(any.simplify(), False);
+        assert_eq!(any.canonicalize(), False);
+
+        let nested = Cfg::parse("cfg(any(unix, not(any()), all(any())))").unwrap();
+
+        assert!(nested.matches(&Vec::<(&str, Option<&str>)>::new()));
+        assert_eq!(nested.simplify(), True);
+    }
+
+    #[test]
+    fn test_parse_bool() {
+        let testcases = vec![
+            (quote! { #[cfg(true)] }, Cfg(True)),
+            (quote! { #[cfg(false)] }, Cfg(False)),
+            (
+                quote! { #[cfg(any(false, not(true)))] },
+                Cfg(Any(vec![Box::new(False), Box::new(Not(Box::new(True)))])),
+            ),
+        ];
+
+        for (s, cfg) in testcases {
+            assert_eq!(syn::parse2::<Cfg>(s.clone()).unwrap(), cfg, "parse {}", s);
+        }
+
+        assert_eq!(Cfg::always().to_string(), "#[cfg(all())]");
+        assert_eq!(Cfg::never().to_string(), "#[cfg(any())]");
+    }
+
+    #[test]
+    fn test_roundtrip_escaped() {
+        let values = vec![
+            r#"with "quotes""#,
+            r"back\slash",
+            "new\nline\ttab\0nul",
+            "unicode \u{301}é 中文",
+            "'single'",
+            "\u{1b}[0m\r\u{7f}",
+        ];
+
+        for value in values {
+            let cfg = Cfg(NameValue("target_env".to_owned(), value.to_owned()));
+            let s = cfg.to_string();
+
+            assert_eq!(Cfg::parse(&s).unwrap(), cfg, "parse {}", s);
+        }
+
+        assert_eq!(
+            Cfg(NameValue("foo".to_owned(), r#"a"b\c"#.to_owned())).to_string(),
+            r#"#[cfg(foo = "a\"b\\c")]"#
+        );
+    }
+
     #[test]
     fn test_parse_error() {
         let errcases = vec![
@@ -273,6 +597,10 @@ mod tests {
                 "#[cfg(not(..))] predicate can't be empty",
             ),
             (quote! { #[cfg()] }, "#[cfg(..)] predicate can't be empty"),
+            (
+                quote! { #[cfg(param(foo = "bar"))] },
+                "#[cfg(param(..))] expect a parameter name",
+            ),
             (quote! { #[cfg("hello")] }, "unexpected literal: \"hello\""),
         ];
 
@@ -280,4 +608,84 @@ mod tests {
             assert_eq!(syn::parse2::<Cfg>(s).unwrap_err().to_string(), err,);
         }
     }
+
+    #[test]
+    fn test_parse_never_panics() {
+        assert!(lit_to_string(syn::parse2::<syn::Lit>(quote!(b"\xff")).unwrap()).is_err());
+
+        let inputs = [
+            "",
+            "#",
+            "#[",
+            "#[cfg",
+            "cfg(",
+            "cfg())",
+            "cfg(a = b\"\\xff\")",
+            "cfg(any(a, b\"\\xff\"))",
+            "cfg(a = 'x)",
+            "cfg(a = r#\"x)",
+            "cfg(not(not(not())))",
+            "cfg(param(\"x\"))",
+            "cfg(🦀)",
+            "\0",
+        ];
+
+        for input in &inputs {
+            assert!(Cfg::parse(input).is_err(), "{}", input);
+        }
+    }
+
+    #[cfg(feature = "no-panic")]
+    #[test]
+    fn test_parse_too_deep() {
+        let nested = |n| "cfg(".to_owned() + &"not(".repeat(n) + "unix" + &")".repeat(n + 1);
+
+        assert!(Cfg::parse(nested(crate::MAX_NESTING - 1)).is_ok());
+        assert!(Cfg::parse(nested(crate::MAX_NESTING)).is_err());
+        assert!(Cfg::parse("(".repeat(100_000)).is_err());
+        assert!(Cfg::parse("cfg(".to_owned() + &"not(".repeat(100_000)).is_err());
+        assert!(Cfg::parse(format!(
+            "cfg(all(a = \"{}\", b = r#\"{}\"#))",
+            "(".repeat(100),
+            "(".repeat(100)
+        ))
+        .is_ok());
+    }
+
+    #[cfg(feature = "no-panic")]
+    #[test]
+    fn test_try_from_too_deep() {
+        use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+
+        let nested = |n| {
+            let mut tokens: TokenStream = quote!(unix);
+
+            for _ in 0..n {
+                let group = Group::new(Delimiter::Parenthesis, tokens);
+
+                tokens = quote!(not);
+                tokens.extend(Some(TokenTree::Group(group)));
+            }
+
+            let group = Group::new(Delimiter::Parenthesis, tokens);
+            let mut tokens = quote!(cfg);
+
+            tokens.extend(Some(TokenTree::Group(group)));
+            tokens
+        };
+
+        let tokens = nested(crate::MAX_NESTING - 1);
+        assert!(Cfg::try_from(tokens.clone()).is_ok());
+        let attr: syn::Attribute = syn::parse_quote!(#[#tokens]);
+        assert!(Cfg::try_from(&attr).is_ok());
+        assert!(Cfg::try_from(attr.parse_meta().unwrap()).is_ok());
+
+        let tokens = nested(crate::MAX_NESTING);
+        assert!(Cfg::try_from(tokens.clone()).is_err());
+        let attr: syn::Attribute = syn::parse_quote!(#[#tokens]);
+        assert!(Cfg::try_from(&attr).is_err());
+        assert!(Cfg::try_from(attr.parse_meta().unwrap()).is_err());
+
+        assert!(Cfg::try_from(nested(1_000)).is_err());
+    }
 }
@@ -0,0 +1,153 @@
+//! Partitioning of a predicate by the keys it references.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::vec::Vec;
+    }
+}
+
+use core::fmt;
+
+use crate::{all, Predicate};
+
+/// An error when a predicate can't be partitioned, since some conjuncts reference the keys of both parts.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PartitionError {
+    /// The conjuncts referencing the keys of both parts.
+    pub mixed: Vec<Predicate>,
+}
+
+impl fmt::Display for PartitionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} conjunct(s) reference the keys of both parts",
+            self.mixed.len()
+        )
+    }
+}
+
+impl core::error::Error for PartitionError {}
+
+impl Predicate {
+    /// Splits the predicate into the part referencing only the selected keys, and the residual part,
+    /// the predicate matches when both parts match.
+    ///
+    /// The predicate is split by its [conjuncts](Predicate::conjuncts),
+    /// a conjunct referencing no key, like a parameter, belongs to the residual part.
+    /// If any conjunct references the keys of both parts, there is no such factorization,
+    /// and the offending conjuncts are reported.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let platform = |key: &str| key.starts_with("target_") || key == "unix";
+    /// let predicate = all(vec![
+    ///     name("unix"),
+    ///     any(vec![feature("ssl"), feature("tls")]),
+    ///     not(name_value("target_env", "musl")),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     predicate.partition(platform),
+    ///     Ok((
+    ///         all(vec![name("unix"), not(name_value("target_env", "musl"))]),
+    ///         any(vec![feature("ssl"), feature("tls")]),
+    ///     ))
+    /// );
+    ///
+    /// let mixed = any(vec![name("unix"), feature("ssl")]);
+    ///
+    /// assert_eq!(
+    ///     mixed.partition(platform),
+    ///     Err(PartitionError { mixed: vec![mixed.clone()] })
+    /// );
+    /// ```
+    pub fn partition<F>(&self, mut selected: F) -> Result<(Predicate, Predicate), PartitionError>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let mut selection = Vec::new();
+        let mut residual = Vec::new();
+        let mut mixed = Vec::new();
+
+        for conjunct in self.conjuncts() {
+            let (mut any_selected, mut any_residual) = (false, false);
+
+            for name in conjunct.referenced_names() {
+                if selected(name) {
+                    any_selected = true;
+                } else {
+                    any_residual = true;
+                }
+            }
+
+            match (any_selected, any_residual) {
+                (true, true) => mixed.push(conjunct),
+                (true, false) => selection.push(conjunct),
+                (false, _) => residual.push(conjunct),
+            }
+        }
+
+        if mixed.is_empty() {
+            Ok((join(selection), join(residual)))
+        } else {
+            Err(PartitionError { mixed })
+        }
+    }
+}
+
+/// Joins the conjuncts, without `all` for a single one.
+fn join(mut conjuncts: Vec<Predicate>) -> Predicate {
+    match conjuncts.len() {
+        0 => Predicate::True,
+        1 => conjuncts.pop().unwrap(),
+        _ => all(conjuncts),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_partition() {
+        let is_feature = |key: &str| key == "feature";
+        let predicate = all(vec![
+            feature("ssl"),
+            param("region"),
+            not(any(vec![feature("beta"), name("test")])),
+            any(vec![name("unix"), Predicate::False]),
+        ]);
+
+        assert_eq!(
+            predicate.partition(is_feature),
+            Ok((
+                all(vec![feature("ssl"), not(feature("beta"))]),
+                all(vec![param("region"), not(name("test")), name("unix")]),
+            ))
+        );
+        assert_eq!(
+            Predicate::True.partition(is_feature),
+            Ok((Predicate::True, Predicate::True))
+        );
+        assert_eq!(
+            feature("ssl").partition(is_feature),
+            Ok((feature("ssl"), Predicate::True))
+        );
+
+        let err = any(vec![feature("ssl"), name("unix")])
+            .partition(is_feature)
+            .unwrap_err();
+
+        assert_eq!(err.mixed, vec![any(vec![feature("ssl"), name("unix")])]);
+    }
+}
@@ -0,0 +1,366 @@
+//! Paths addressing the sub-predicates, and the search of the sub-predicates.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::vec::Vec;
+    }
+}
+
+use core::{fmt, mem};
+
+use crate::Predicate;
+
+/// A path of a sub-predicate, as the indexes of the children from the root.
+///
+/// The sub-predicates of `any` and `all` are indexed in order, and the sub-predicate of `not` is indexed as `0`.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let predicate = all(vec![name("unix"), not(feature("beta"))]);
+/// let path = Path::from(vec![1, 0]);
+///
+/// assert_eq!(predicate.get(&path), Some(&feature("beta")));
+/// assert_eq!(predicate.get(&path.parent().unwrap()), Some(&not(feature("beta"))));
+/// assert_eq!(predicate.get(&Path::root()), Some(&predicate));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Path(Vec<usize>);
+
+impl Path {
+    /// Returns the path of the root.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if the path is the root.
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the indexes of the children from the root.
+    pub fn indices(&self) -> &[usize] {
+        &self.0
+    }
+
+    /// Returns the depth of the path, the root is `0`.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the path is the root.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the path of a child.
+    pub fn child(&self, index: usize) -> Path {
+        let mut path = self.clone();
+
+        path.0.push(index);
+        path
+    }
+
+    /// Returns the path of the parent, or `None` for the root.
+    pub fn parent(&self) -> Option<Path> {
+        self.0.split_last().map(|(_, parent)| Path(parent.to_vec()))
+    }
+
+    /// Returns `true` if the path is the other one or a descendant of it.
+    pub fn starts_with(&self, other: &Path) -> bool {
+        self.0.starts_with(&other.0)
+    }
+}
+
+impl From<Vec<usize>> for Path {
+    fn from(indices: Vec<usize>) -> Self {
+        Path(indices)
+    }
+}
+
+impl From<&[usize]> for Path {
+    fn from(indices: &[usize]) -> Self {
+        Path(indices.to_vec())
+    }
+}
+
+/// An error when mutating a predicate at a path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathError {
+    /// No sub-predicate at the path.
+    NotFound(Path),
+    /// The root can't be removed.
+    RemoveRoot,
+    /// The sub-predicate of `not` can't be removed, since `not` requires exactly one sub-predicate.
+    Arity(Path),
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathError::NotFound(path) => write!(f, "no sub-predicate at path {:?}", path.0),
+            PathError::RemoveRoot => f.write_str("the root can't be removed"),
+            PathError::Arity(path) => write!(
+                f,
+                "the sub-predicate of `not` at path {:?} can't be removed",
+                path.0
+            ),
+        }
+    }
+}
+
+impl core::error::Error for PathError {}
+
+impl Predicate {
+    /// Returns the sub-predicate at the path.
+    pub fn get(&self, path: &Path) -> Option<&Predicate> {
+        path.0
+            .iter()
+            .try_fold(self, |predicate, &index| predicate.child(index))
+    }
+
+    /// Returns the mutable sub-predicate at the path.
+    pub fn get_mut(&mut self, path: &Path) -> Option<&mut Predicate> {
+        path.0
+            .iter()
+            .try_fold(self, |predicate, &index| predicate.child_mut(index))
+    }
+
+    /// Replaces the sub-predicate at the path, returns the replaced one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let mut predicate = all(vec![name("unix"), not(feature("beta"))]);
+    ///
+    /// assert_eq!(predicate.replace_at(&Path::from(vec![1, 0]), feature("gamma")), Ok(feature("beta")));
+    /// assert_eq!(predicate, all(vec![name("unix"), not(feature("gamma"))]));
+    /// assert!(predicate.replace_at(&Path::from(vec![2]), name("windows")).is_err());
+    /// ```
+    pub fn replace_at(
+        &mut self,
+        path: &Path,
+        predicate: Predicate,
+    ) -> Result<Predicate, PathError> {
+        self.get_mut(path)
+            .map(|replaced| mem::replace(replaced, predicate))
+            .ok_or_else(|| PathError::NotFound(path.clone()))
+    }
+
+    /// Removes the sub-predicate of `any` or `all` at the path, returns the removed one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let mut predicate = all(vec![name("unix"), not(feature("beta"))]);
+    ///
+    /// assert_eq!(predicate.remove_at(&Path::from(vec![1, 0])), Err(PathError::Arity(Path::from(vec![1, 0]))));
+    /// assert_eq!(predicate.remove_at(&Path::from(vec![1])), Ok(not(feature("beta"))));
+    /// assert_eq!(predicate, all(vec![name("unix")]));
+    /// ```
+    pub fn remove_at(&mut self, path: &Path) -> Result<Predicate, PathError> {
+        let (&index, parent) = path.0.split_last().ok_or(PathError::RemoveRoot)?;
+        let not_found = || PathError::NotFound(path.clone());
+
+        match self.get_mut(&Path::from(parent)).ok_or_else(not_found)? {
+            Predicate::Any(predicates) | Predicate::All(predicates) if index < predicates.len() => {
+                Ok(*predicates.remove(index))
+            }
+            Predicate::Not(_) if index == 0 => Err(PathError::Arity(path.clone())),
+            _ => Err(not_found()),
+        }
+    }
+
+    /// Returns `true` if the predicate is or contains the sub-predicate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let predicate = any(vec![name("windows"), all(vec![name("unix"), feature("ssl")])]);
+    ///
+    /// assert!(predicate.contains(&feature("ssl")));
+    /// assert!(predicate.contains(&predicate));
+    /// assert!(!predicate.contains(&all(vec![name("unix")])));
+    /// ```
+    pub fn contains(&self, other: &Predicate) -> bool {
+        self == other || self.children().any(|child| child.contains(other))
+    }
+
+    /// Returns the first leaf with the name in the pre-order, with or without value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let predicate = all(vec![name("unix"), not(feature("beta")), feature("ssl")]);
+    ///
+    /// assert_eq!(predicate.find_leaf("feature"), Some(&feature("beta")));
+    /// assert_eq!(predicate.find_leaf("windows"), None);
+    /// ```
+    pub fn find_leaf(&self, name: &str) -> Option<&Predicate> {
+        match self {
+            Predicate::Name(n) | Predicate::NameValue(n, _) if n == name => Some(self),
+            _ => self.children().find_map(|child| child.find_leaf(name)),
+        }
+    }
+
+    /// Returns the paths of the leaves with the name in the pre-order, with or without value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let predicate = all(vec![name("unix"), not(feature("beta")), feature("ssl")]);
+    ///
+    /// assert_eq!(
+    ///     predicate.positions_of("feature"),
+    ///     vec![Path::from(vec![1, 0]), Path::from(vec![2])]
+    /// );
+    /// ```
+    pub fn positions_of(&self, name: &str) -> Vec<Path> {
+        let mut paths = Vec::new();
+
+        self.collect_positions(name, &mut Path::root(), &mut paths);
+
+        paths
+    }
+
+    fn collect_positions(&self, name: &str, path: &mut Path, paths: &mut Vec<Path>) {
+        match self {
+            Predicate::Name(n) | Predicate::NameValue(n, _) if n == name => {
+                paths.push(path.clone())
+            }
+            _ => {
+                for (index, child) in self.children().enumerate() {
+                    path.0.push(index);
+                    child.collect_positions(name, path, paths);
+                    path.0.pop();
+                }
+            }
+        }
+    }
+
+    fn children(&self) -> impl Iterator<Item = &Predicate> {
+        let (predicates, predicate): (&[_], _) = match self {
+            Predicate::Any(predicates) | Predicate::All(predicates) => (predicates, None),
+            Predicate::Not(predicate) => (&[], Some(&**predicate)),
+            _ => (&[], None),
+        };
+
+        predicates
+            .iter()
+            .map(|predicate| &**predicate)
+            .chain(predicate)
+    }
+
+    fn child(&self, index: usize) -> Option<&Predicate> {
+        match self {
+            Predicate::Any(predicates) | Predicate::All(predicates) => {
+                predicates.get(index).map(|predicate| &**predicate)
+            }
+            Predicate::Not(predicate) if index == 0 => Some(predicate),
+            _ => None,
+        }
+    }
+
+    fn child_mut(&mut self, index: usize) -> Option<&mut Predicate> {
+        match self {
+            Predicate::Any(predicates) | Predicate::All(predicates) => {
+                predicates.get_mut(index).map(|predicate| &mut **predicate)
+            }
+            Predicate::Not(predicate) if index == 0 => Some(predicate),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_search() {
+        let mut predicate = any(vec![
+            name_value("target_os", "linux"),
+            not(all(vec![name("unix"), name_value("target_os", "macos")])),
+            param("target_os"),
+        ]);
+
+        assert_eq!(
+            predicate.positions_of("target_os"),
+            vec![Path::from(vec![0]), Path::from(vec![1, 0, 1])]
+        );
+        assert!(predicate.positions_of("windows").is_empty());
+        assert_eq!(
+            predicate.find_leaf("unix"),
+            predicate.get(&Path::from(&[1, 0, 0][..]))
+        );
+        assert!(predicate.contains(&param("target_os")));
+        assert!(!predicate.contains(&name("target_os")));
+        assert_eq!(predicate.get(&Path::from(vec![1, 1])), None);
+        assert_eq!(predicate.get(&Path::from(vec![0, 0])), None);
+
+        let path = Path::root().child(1).child(0).child(1);
+
+        assert_eq!(path.len(), 3);
+        assert!(path.starts_with(&Path::from(vec![1])));
+        assert!(!path.starts_with(&Path::from(vec![0])));
+
+        *predicate.get_mut(&path).unwrap() = name_value("target_os", "ios");
+
+        assert!(predicate.contains(&name_value("target_os", "ios")));
+        assert!(Path::root().parent().is_none());
+    }
+
+    #[test]
+    fn test_mutate() {
+        let mut predicate = any(vec![name("unix"), not(name("test")), feature("ssl")]);
+
+        assert_eq!(
+            predicate.replace_at(&Path::root(), name("wasm")),
+            Ok(any(vec![name("unix"), not(name("test")), feature("ssl")]))
+        );
+        assert_eq!(predicate, name("wasm"));
+        assert_eq!(
+            predicate.replace_at(&Path::from(vec![0]), name("unix")),
+            Err(PathError::NotFound(Path::from(vec![0])))
+        );
+
+        let mut predicate = any(vec![name("unix"), not(name("test")), feature("ssl")]);
+
+        assert_eq!(
+            predicate.remove_at(&Path::root()),
+            Err(PathError::RemoveRoot)
+        );
+        assert_eq!(
+            predicate.remove_at(&Path::from(vec![3])),
+            Err(PathError::NotFound(Path::from(vec![3])))
+        );
+        assert_eq!(
+            predicate.remove_at(&Path::from(vec![0, 0])),
+            Err(PathError::NotFound(Path::from(vec![0, 0])))
+        );
+        assert_eq!(
+            predicate.remove_at(&Path::from(vec![1, 0])),
+            Err(PathError::Arity(Path::from(vec![1, 0])))
+        );
+        assert_eq!(predicate.remove_at(&Path::from(vec![0])), Ok(name("unix")));
+        assert_eq!(predicate, any(vec![not(name("test")), feature("ssl")]));
+    }
+}
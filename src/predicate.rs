@@ -0,0 +1,272 @@
+//! The configurations and their predicates.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::boxed::Box;
+        use alloc::string::String;
+        use alloc::vec::Vec;
+    }
+}
+
+use core::convert::{AsMut, AsRef};
+use core::ops::{Deref, DerefMut};
+
+/// Boolean evaluation of configuration flags, at runtime-time.
+///
+/// The configurations are ordered as their predicates.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cfg(pub(crate) Predicate);
+
+impl Deref for Cfg {
+    type Target = Predicate;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Cfg {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl AsRef<Predicate> for Predicate {
+    fn as_ref(&self) -> &Predicate {
+        self
+    }
+}
+
+impl AsRef<Predicate> for Cfg {
+    fn as_ref(&self) -> &Predicate {
+        &self.0
+    }
+}
+
+impl AsMut<Predicate> for Cfg {
+    fn as_mut(&mut self) -> &mut Predicate {
+        &mut self.0
+    }
+}
+
+impl Cfg {
+    /// A configuration always success.
+    pub const fn always() -> Self {
+        Cfg(Predicate::True)
+    }
+
+    /// A configuration always fails.
+    pub const fn never() -> Self {
+        Cfg(Predicate::False)
+    }
+}
+
+impl From<Predicate> for Cfg {
+    fn from(predicate: Predicate) -> Self {
+        Cfg(predicate)
+    }
+}
+
+impl From<Cfg> for Predicate {
+    fn from(cfg: Cfg) -> Self {
+        cfg.0
+    }
+}
+
+/// A configuration predicate.
+///
+/// # Ordering
+///
+/// The predicates are ordered by their variants first, in the order of
+/// `Any`, `All`, `Not`, `Name`, `NameValue`, `True`, `False` and `Param`;
+/// then the predicates of the same variant are ordered by their contents,
+/// the sub-predicates are compared lexicographically, and the names and values are compared as strings.
+///
+/// This ordering is stable, and is used to sort the sub-predicates in the canonical form.
+///
+/// # Recursion
+///
+/// The matching, printing and [metrics](Predicate::metrics) walk the sub-predicates with an explicit stack,
+/// the other operations, including the derived traits and `Drop`, recurse into them;
+/// reject the predicates nested too deep with [`Predicate::check_nesting`] before using them.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(::serde::Serialize, ::serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[cfg_attr(feature = "schemars", derive(::schemars::JsonSchema))]
+pub enum Predicate {
+    /// A configuration predicate success when `any` of sub-predicates success.
+    ///
+    /// Like rustc, an empty `any()` never matches, it is equivalent to [`Predicate::False`].
+    Any(Vec<Box<Predicate>>),
+    /// A configuration predicate success when `all` of sub-predicates success.
+    ///
+    /// Like rustc, an empty `all()` always matches, it is equivalent to [`Predicate::True`].
+    All(Vec<Box<Predicate>>),
+    /// A configuration predicate apply `not` operator to a predicate.
+    Not(Box<Predicate>),
+    /// A configuration predicate with name.
+    Name(String),
+    /// A configuration predicate with name and value.
+    NameValue(String, String),
+    /// A configuration predicate always success, printed as `all()`.
+    True,
+    /// A configuration predicate always fails, printed as `any()`.
+    False,
+    /// A placeholder bound to a concrete predicate at evaluation time, printed as `param(name)`.
+    ///
+    /// An unbound parameter never matches.
+    Param(String),
+}
+
+/// A configuration predicate success when `any` of sub-predicates success.
+pub fn any<I: IntoIterator<Item = Predicate>>(predicates: I) -> Predicate {
+    Predicate::Any(predicates.into_iter().map(Box::new).collect())
+}
+
+/// A configuration predicate success when `all` of sub-predicates success.
+pub fn all<I: IntoIterator<Item = Predicate>>(predicates: I) -> Predicate {
+    Predicate::All(predicates.into_iter().map(Box::new).collect())
+}
+
+/// A configuration predicate success when `any` of sub-predicates success,
+/// the nested `any` sub-predicates are flattened and the duplicated ones are removed.
+///
+/// The sub-predicates keep the order of their first occurrences.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// assert_eq!(
+///     any_flat(vec![any(vec![name("unix"), name("windows")]), name("unix"), name("wasm")]),
+///     any(vec![name("unix"), name("windows"), name("wasm")])
+/// );
+/// ```
+pub fn any_flat<I: IntoIterator<Item = Predicate>>(predicates: I) -> Predicate {
+    let mut flattened = Vec::new();
+
+    for predicate in predicates {
+        flatten(predicate, true, &mut flattened);
+    }
+
+    Predicate::Any(flattened)
+}
+
+/// A configuration predicate success when `all` of sub-predicates success,
+/// the nested `all` sub-predicates are flattened and the duplicated ones are removed.
+///
+/// The sub-predicates keep the order of their first occurrences.
+pub fn all_flat<I: IntoIterator<Item = Predicate>>(predicates: I) -> Predicate {
+    let mut flattened = Vec::new();
+
+    for predicate in predicates {
+        flatten(predicate, false, &mut flattened);
+    }
+
+    Predicate::All(flattened)
+}
+
+#[allow(clippy::vec_box)]
+fn flatten(predicate: Predicate, is_any: bool, flattened: &mut Vec<Box<Predicate>>) {
+    match predicate {
+        Predicate::Any(predicates) if is_any => {
+            for predicate in predicates {
+                flatten(*predicate, is_any, flattened)
+            }
+        }
+        Predicate::All(predicates) if !is_any => {
+            for predicate in predicates {
+                flatten(*predicate, is_any, flattened)
+            }
+        }
+        _ => {
+            if !flattened.iter().any(|p| **p == predicate) {
+                flattened.push(Box::new(predicate))
+            }
+        }
+    }
+}
+
+/// A configuration predicate apply `not` operator to a sub-predicate.
+pub fn not(predicate: Predicate) -> Predicate {
+    Predicate::Not(Box::new(predicate))
+}
+
+/// A configuration predicate with name.
+pub fn name<S: Into<String>>(name: S) -> Predicate {
+    Predicate::Name(name.into())
+}
+
+/// A configuration predicate with name and value.
+pub fn name_value<S: Into<String>>(name: S, value: S) -> Predicate {
+    Predicate::NameValue(name.into(), value.into())
+}
+
+/// A placeholder bound to a concrete predicate at evaluation time.
+pub fn param<S: Into<String>>(name: S) -> Predicate {
+    Predicate::Param(name.into())
+}
+
+/// A configuration predicate for the Cargo feature, as `feature = "name"`.
+pub fn feature<S: Into<String>>(name: S) -> Predicate {
+    Predicate::NameValue("feature".into(), name.into())
+}
+
+/// A configuration predicate success when `any` of the Cargo features enabled.
+pub fn any_features<I, S>(names: I) -> Predicate
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    any(names.into_iter().map(feature))
+}
+
+/// A configuration predicate success when `all` of the Cargo features enabled.
+pub fn all_features<I, S>(names: I) -> Predicate
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    all(names.into_iter().map(feature))
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_flat() {
+        assert_eq!(any_flat(vec![]), any(vec![]));
+        assert_eq!(
+            all_flat(vec![
+                all(vec![name("unix"), all(vec![feature("ssl"), name("unix")])]),
+                any(vec![name("a"), any(vec![name("b")])]),
+                feature("ssl"),
+                any(vec![name("a"), any(vec![name("b")])]),
+            ]),
+            all(vec![
+                name("unix"),
+                feature("ssl"),
+                any(vec![name("a"), any(vec![name("b")])]),
+            ])
+        );
+        assert_eq!(
+            any_flat(vec![
+                all(vec![name("a")]),
+                any(vec![name("b"), name("c")]),
+                name("b")
+            ]),
+            any(vec![all(vec![name("a")]), name("b"), name("c")])
+        );
+    }
+}
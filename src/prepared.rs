@@ -0,0 +1,172 @@
+//! Evaluation of a configuration against the answers of its flags, queried once from a pattern.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::vec::Vec;
+    }
+}
+
+use crate::{Cfg, Pattern};
+
+/// A configuration prepared for evaluation, with the answers of all the flags it references.
+///
+/// Each referenced flag is queried from the pattern once when preparing,
+/// then the configuration is evaluated in memory however many times,
+/// so an expensive pattern is queried as few times as possible.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let cfg = Cfg::from(any(vec![
+///     all(vec![name("unix"), not(name_value("target_os", "macos"))]),
+///     not(any(vec![name("unix"), name("windows")])),
+/// ]));
+/// let flags = Recording::new(vec![("unix", None), ("target_os", Some("linux"))]);
+///
+/// let mut prepared = cfg.prepare(&flags);
+///
+/// assert!(prepared.eval());
+/// assert_eq!(flags.queries().len(), 3);
+///
+/// assert_eq!(prepared.set("target_os", Some("macos"), true), Some(false));
+/// assert!(!prepared.eval());
+/// assert_eq!(flags.queries().len(), 3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PreparedEval<'a> {
+    cfg: &'a Cfg,
+    answers: Vec<((&'a str, Option<&'a str>), bool)>,
+}
+
+impl Cfg {
+    /// Queries all the flags referenced by the configuration from the pattern,
+    /// returns the configuration prepared for evaluation with the answers.
+    pub fn prepare<P: Pattern + ?Sized>(&self, pattern: &P) -> PreparedEval<'_> {
+        let answers = self
+            .referenced_flags()
+            .into_iter()
+            .map(|(key, value)| ((key, value), pattern.matches(key, value)))
+            .collect();
+
+        PreparedEval { cfg: self, answers }
+    }
+}
+
+impl<'a> PreparedEval<'a> {
+    /// Returns the prepared configuration.
+    pub fn cfg(&self) -> &'a Cfg {
+        self.cfg
+    }
+
+    /// Returns `true` if the configuration matches the answers.
+    pub fn eval(&self) -> bool {
+        self.cfg.matches(self)
+    }
+
+    /// Returns the answer of the flag, or `None` if it isn't referenced by the configuration.
+    pub fn answer(&self, key: &str, value: Option<&str>) -> Option<bool> {
+        self.position(key, value)
+            .ok()
+            .map(|idx| self.answers[idx].1)
+    }
+
+    /// Overrides the answer of the flag, to evaluate the configuration in another case.
+    ///
+    /// Returns the previous answer, or `None` if the flag isn't referenced by the configuration.
+    pub fn set(&mut self, key: &str, value: Option<&str>, matched: bool) -> Option<bool> {
+        let idx = self.position(key, value).ok()?;
+
+        Some(core::mem::replace(&mut self.answers[idx].1, matched))
+    }
+
+    /// Queries all the referenced flags from the pattern again.
+    pub fn refresh<P: Pattern + ?Sized>(&mut self, pattern: &P) {
+        for ((key, value), matched) in &mut self.answers {
+            *matched = pattern.matches(key, *value);
+        }
+    }
+
+    /// Returns an iterator over the referenced flags and their answers, in the order of the flags.
+    pub fn iter(&self) -> impl Iterator<Item = ((&'a str, Option<&'a str>), bool)> + '_ {
+        self.answers.iter().copied()
+    }
+
+    /// Returns the number of referenced flags.
+    pub fn len(&self) -> usize {
+        self.answers.len()
+    }
+
+    /// Returns `true` if the configuration doesn't reference any flag.
+    pub fn is_empty(&self) -> bool {
+        self.answers.is_empty()
+    }
+
+    fn position(&self, key: &str, value: Option<&str>) -> Result<usize, usize> {
+        self.answers
+            .binary_search_by(|(flag, _)| flag.cmp(&(key, value)))
+    }
+}
+
+/// Answers the referenced flags, the other flags never match.
+impl Pattern for PreparedEval<'_> {
+    fn matches(&self, key: &str, value: Option<&str>) -> bool {
+        self.answer(key, value).unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+            use alloc::vec::Vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_prepare() {
+        let cfg = Cfg::from(all(vec![
+            name("unix"),
+            any(vec![feature("std"), not(name("unix"))]),
+            not(feature("serde")),
+        ]));
+        let mut flags = FlagSet::new();
+
+        flags.insert_name("unix");
+        flags.insert("feature", "serde");
+
+        let mut prepared = cfg.prepare(&flags);
+
+        assert!(!prepared.eval());
+        assert_eq!(prepared.len(), 3);
+        assert_eq!(
+            prepared.iter().collect::<Vec<_>>(),
+            vec![
+                (("feature", Some("serde")), true),
+                (("feature", Some("std")), false),
+                (("unix", None), true),
+            ]
+        );
+        assert_eq!(prepared.answer("unix", None), Some(true));
+        assert_eq!(prepared.answer("windows", None), None);
+        assert!(!Pattern::matches(&prepared, "windows", None));
+
+        assert_eq!(prepared.set("feature", Some("serde"), false), Some(true));
+        assert!(!prepared.eval());
+        assert_eq!(prepared.set("feature", Some("std"), true), Some(false));
+        assert!(prepared.eval());
+        assert_eq!(prepared.set("windows", None, true), None);
+
+        prepared.refresh(&flags);
+        assert!(!prepared.eval());
+
+        let cfg = Cfg::always();
+
+        assert!(cfg.prepare(&flags).is_empty());
+        assert!(cfg.prepare(&flags).eval());
+    }
+}
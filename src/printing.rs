@@ -1,30 +1,350 @@
-use core::fmt;
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::boxed::Box;
+        use alloc::format;
+        use alloc::string::{String, ToString};
+        use alloc::vec::Vec;
+    }
+}
 
-use crate::{Cfg, Predicate};
+use core::fmt::{self, Write};
+
+use crate::escape::Quoted;
+use crate::{Cfg, CfgLint, Predicate, PredicateRef, RouteExplanation, SharedCfg};
 
 impl fmt::Display for Cfg {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "#[cfg({})]", self.0)
+        if f.alternate() {
+            write!(f, "#[cfg({:#})]", self.0)
+        } else {
+            write!(f, "#[cfg({})]", self.0)
+        }
     }
 }
 
+impl fmt::Display for SharedCfg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "#[cfg({:#})]", **self)
+        } else {
+            write!(f, "#[cfg({})]", **self)
+        }
+    }
+}
+
+/// The alternate `{:#}` form prints `any` and `all` in multiple lines,
+/// with one sub-predicate per line.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let predicate = all(vec![name("unix"), not(any(vec![name("foo"), name("bar")]))]);
+///
+/// assert_eq!(
+///     format!("{:#}", predicate),
+///     r#"all(
+///     unix,
+///     not(any(
+///         foo,
+///         bar,
+///     )),
+/// )"#
+/// );
+/// ```
 impl fmt::Display for Predicate {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use Predicate::*;
+        if f.alternate() {
+            fmt_pretty(self, f)
+        } else {
+            fmt_compact(self, f)
+        }
+    }
+}
 
-        match self {
-            Any(predicates) => {
-                f.write_str("any(")?;
-                for (i, predicate) in predicates.iter().enumerate() {
-                    if i > 0 {
-                        f.write_str(", ")?;
+/// A partially printed `any`, `all` or `not`, to print the predicates with an explicit stack instead of recursion,
+/// so the deeply nested predicates can't overflow the call stack.
+enum Frame<'a> {
+    List {
+        rest: core::slice::Iter<'a, Box<Predicate>>,
+        indent: usize,
+    },
+    Not,
+}
+
+fn fmt_compact(mut predicate: &Predicate, f: &mut fmt::Formatter) -> fmt::Result {
+    use Predicate::*;
+
+    let mut stack = Vec::new();
+
+    'descend: loop {
+        match predicate {
+            Any(predicates) | All(predicates) => {
+                f.write_str(if let Any(_) = predicate {
+                    "any("
+                } else {
+                    "all("
+                })?;
+
+                let mut rest = predicates.iter();
+
+                if let Some(first) = rest.next() {
+                    stack.push(Frame::List { rest, indent: 0 });
+                    predicate = first;
+                    continue;
+                }
+
+                f.write_str(")")?;
+            }
+            Not(inner) => {
+                f.write_str("not(")?;
+                stack.push(Frame::Not);
+                predicate = inner;
+                continue;
+            }
+            Name(name) => f.write_str(name)?,
+            NameValue(name, value) => write!(f, "{} = {}", name, Quoted(value))?,
+            True => f.write_str("all()")?,
+            False => f.write_str("any()")?,
+            Param(name) => write!(f, "param({})", name)?,
+        }
+
+        while let Some(frame) = stack.last_mut() {
+            if let Frame::List { rest, .. } = frame {
+                if let Some(next) = rest.next() {
+                    f.write_str(", ")?;
+                    predicate = next;
+                    continue 'descend;
+                }
+            }
+
+            f.write_str(")")?;
+            stack.pop();
+        }
+
+        return Ok(());
+    }
+}
+
+/// Displays a predicate in the Cargo target-key form, like `cfg(all(unix, target_env = "musl"))`.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let cfg = Cfg::from(all(vec![name("unix"), name_value("target_env", "musl")]));
+///
+/// assert_eq!(cfg.cargo_key().to_string(), r#"cfg(all(unix, target_env = "musl"))"#);
+/// assert_eq!(cfg.to_cargo_key(), r#"'cfg(all(unix, target_env = "musl"))'"#);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct CargoKey<'a>(&'a Predicate);
+
+impl fmt::Display for CargoKey<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cfg({})", self.0)
+    }
+}
+
+/// Displays a predicate as a `cfg!(..)` macro expression, like `cfg!(all(unix, target_env = "musl"))`.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let cfg = Cfg::from(all(vec![name("unix"), name_value("target_env", "musl")]));
+///
+/// assert_eq!(cfg.cfg_macro().to_string(), r#"cfg!(all(unix, target_env = "musl"))"#);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct CfgMacro<'a>(&'a Predicate);
+
+impl fmt::Display for CfgMacro<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cfg!({})", self.0)
+    }
+}
+
+/// Displays a lint as its message followed by the offending sub-predicate.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let predicate = not(not(name("unix")));
+///
+/// assert_eq!(
+///     predicate.lint()[0].to_string(),
+///     "double negation is redundant: `not(not(unix))`"
+/// );
+/// ```
+impl fmt::Display for CfgLint<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: `{}`", self.kind.message(), self.predicate)
+    }
+}
+
+/// Displays the evaluated rules of a route, with the failed leaves of the rejected rules.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let mut router = CfgRouter::with_default("generic");
+///
+/// router.push(all(vec![name("unix"), feature("beta")]).into(), "unix-beta");
+/// router.push(name("windows").into(), "windows");
+///
+/// assert_eq!(
+///     router.explain_route(&vec![("unix", None::<&str>)]).to_string(),
+///     r#"✗ rule #0: all(unix, feature = "beta")
+///     failed: feature = "beta"
+/// ✗ rule #1: windows
+///     failed: windows
+/// ✓ default
+/// "#
+/// );
+/// ```
+impl<T> fmt::Display for RouteExplanation<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, explanation) in self.rejected.iter().enumerate() {
+            writeln!(f, "✗ rule #{}: {}", index, explanation.predicate)?;
+
+            for leaf in explanation.failed_leaves() {
+                writeln!(f, "    failed: {}", leaf)?;
+            }
+        }
+
+        match (self.index, &self.fired) {
+            (Some(index), Some(explanation)) => {
+                writeln!(f, "✓ rule #{}: {}", index, explanation.predicate)
+            }
+            _ if self.value.is_some() => writeln!(f, "✓ default"),
+            _ => writeln!(f, "✗ no rule matched"),
+        }
+    }
+}
+
+impl Predicate {
+    /// Returns a wrapper to display the predicate as a `cfg!(..)` macro expression.
+    pub fn cfg_macro(&self) -> CfgMacro<'_> {
+        CfgMacro(self)
+    }
+
+    /// Returns a wrapper to display the predicate in the Cargo target-key form.
+    pub fn cargo_key(&self) -> CargoKey<'_> {
+        CargoKey(self)
+    }
+
+    /// Returns the Cargo target-key of the predicate, quoted as a TOML key.
+    ///
+    /// The key is ready to be used in the `[target.<key>]` table of `Cargo.toml`,
+    /// it is quoted as a literal string when possible, otherwise as a basic string with escapes.
+    pub fn to_cargo_key(&self) -> String {
+        let key = self.cargo_key().to_string();
+
+        if key.contains(|c: char| c == '\'' || c.is_control()) {
+            let mut quoted = String::with_capacity(key.len() + 2);
+
+            quoted.push('"');
+            for c in key.chars() {
+                match c {
+                    '"' => quoted.push_str("\\\""),
+                    '\\' => quoted.push_str("\\\\"),
+                    '\n' => quoted.push_str("\\n"),
+                    '\t' => quoted.push_str("\\t"),
+                    '\r' => quoted.push_str("\\r"),
+                    c if c.is_control() => {
+                        let _ = write!(quoted, "\\u{:04X}", c as u32);
                     }
-                    predicate.fmt(f)?;
+                    c => quoted.push(c),
                 }
-                f.write_str(")")
             }
-            All(predicates) => {
-                f.write_str("all(")?;
+            quoted.push('"');
+
+            quoted
+        } else {
+            format!("'{}'", key)
+        }
+    }
+}
+
+const INDENT: &str = "    ";
+
+fn fmt_pretty(mut predicate: &Predicate, f: &mut fmt::Formatter) -> fmt::Result {
+    use Predicate::*;
+
+    let write_indent =
+        |f: &mut fmt::Formatter, indent| (0..indent).try_for_each(|_| f.write_str(INDENT));
+    let mut stack = Vec::new();
+    let mut indent = 0;
+
+    'descend: loop {
+        match predicate {
+            Any(predicates) | All(predicates) if !predicates.is_empty() => {
+                f.write_str(if let Any(_) = predicate {
+                    "any(\n"
+                } else {
+                    "all(\n"
+                })?;
+
+                let mut rest = predicates.iter();
+
+                if let Some(first) = rest.next() {
+                    write_indent(f, indent + 1)?;
+                    stack.push(Frame::List { rest, indent });
+                    predicate = first;
+                    indent += 1;
+                    continue;
+                }
+            }
+            Not(inner) => {
+                f.write_str("not(")?;
+                stack.push(Frame::Not);
+                predicate = inner;
+                continue;
+            }
+            _ => fmt_compact(predicate, f)?,
+        }
+
+        while let Some(frame) = stack.last_mut() {
+            if let Frame::List {
+                rest,
+                indent: outer,
+            } = frame
+            {
+                f.write_str(",\n")?;
+
+                if let Some(next) = rest.next() {
+                    write_indent(f, *outer + 1)?;
+                    predicate = next;
+                    indent = *outer + 1;
+                    continue 'descend;
+                }
+
+                write_indent(f, *outer)?;
+            }
+
+            f.write_str(")")?;
+            stack.pop();
+        }
+
+        return Ok(());
+    }
+}
+
+impl fmt::Display for PredicateRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use PredicateRef::*;
+
+        match self {
+            Any(predicates) | All(predicates) => {
+                f.write_str(if let Any(_) = self { "any(" } else { "all(" })?;
                 for (i, predicate) in predicates.iter().enumerate() {
                     if i > 0 {
                         f.write_str(", ")?;
@@ -34,8 +354,90 @@ impl fmt::Display for Predicate {
                 f.write_str(")")
             }
             Not(predicate) => write!(f, "not({})", predicate),
-            Name(name) => f.write_str(&name),
-            NameValue(name, value) => write!(f, "{} = \"{}\"", name, value),
+            Name(name) => f.write_str(name),
+            NameValue(name, value) => write!(f, "{} = {}", name, Quoted(value)),
+            True => f.write_str("all()"),
+            False => f.write_str("any()"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::format;
+            use alloc::string::ToString;
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_cargo_key() {
+        let predicate = all(vec![name("unix"), name_value("target_env", "musl")]);
+
+        assert_eq!(
+            predicate.cargo_key().to_string(),
+            r#"cfg(all(unix, target_env = "musl"))"#
+        );
+        assert_eq!(
+            Cfg::from(predicate).to_cargo_key(),
+            r#"'cfg(all(unix, target_env = "musl"))'"#
+        );
+        assert_eq!(
+            name_value("vendor", "o'reilly\t").to_cargo_key(),
+            r#""cfg(vendor = \"o'reilly\\t\")""#
+        );
+    }
+
+    #[test]
+    fn test_cfg_macro() {
+        assert_eq!(not(name("test")).cfg_macro().to_string(), "cfg!(not(test))");
+    }
+
+    #[test]
+    fn test_display_deep() {
+        let mut predicate = name("unix");
+
+        for _ in 0..100_000 {
+            predicate = not(predicate);
+        }
+
+        let s = "not(".repeat(100_000) + "unix" + &")".repeat(100_000);
+
+        assert_eq!(predicate.to_string(), s);
+        assert_eq!(format!("{:#}", predicate), s);
+
+        // the derived `Drop` is recursive
+        while let Predicate::Not(inner) = predicate {
+            predicate = *inner;
+        }
+    }
+
+    #[test]
+    fn test_pretty() {
+        let cfg = Cfg::from(any(vec![
+            all(vec![]),
+            all(vec![name("unix"), name_value("target_os", "linux")]),
+            not(name("test")),
+        ]));
+
+        assert_eq!(
+            format!("{}", cfg),
+            r#"#[cfg(any(all(), all(unix, target_os = "linux"), not(test)))]"#
+        );
+        assert_eq!(
+            format!("{:#}", cfg),
+            r#"#[cfg(any(
+    all(),
+    all(
+        unix,
+        target_os = "linux",
+    ),
+    not(test),
+))]"#
+        );
+    }
+}
@@ -0,0 +1,512 @@
+//! Flags fetched from the remote sources, like a HTTP/JSON endpoint or a mounted Kubernetes ConfigMap,
+//! and refreshed periodically.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::future::Future;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+use crate::{serde_loose, FlagSet, Pattern};
+
+/// An error when fetching the flags.
+#[derive(Debug)]
+pub enum SourceError {
+    /// The source can't be read.
+    Io(io::Error),
+    /// The URL is malformed or unsupported.
+    Url(String),
+    /// The HTTP response has a failure status.
+    Status(u16),
+    /// The content is malformed.
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SourceError::Io(err) => write!(f, "failed to read flags, {}", err),
+            SourceError::Url(url) => write!(f, "unsupported URL `{}`", url),
+            SourceError::Status(status) => {
+                write!(f, "failed to fetch flags, HTTP status {}", status)
+            }
+            SourceError::Parse(err) => write!(f, "failed to parse flags, {}", err),
+        }
+    }
+}
+
+impl Error for SourceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SourceError::Io(err) => Some(err),
+            SourceError::Parse(err) => Some(err),
+            SourceError::Url(_) | SourceError::Status(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for SourceError {
+    fn from(err: io::Error) -> Self {
+        SourceError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SourceError {
+    fn from(err: serde_json::Error) -> Self {
+        SourceError::Parse(err)
+    }
+}
+
+/// A source of flags, fetched asynchronously.
+///
+/// Implement it with the HTTP client or the etcd client of your async runtime.
+///
+/// The provided sources perform the blocking I/O when [`FlagSource::fetch`] is called, not when the future is polled,
+/// and the future is ready when first polled. [`RemoteFlags`] calls them on its own thread;
+/// don't call them on the thread of an async runtime, or wrap the call with its `spawn_blocking`.
+pub trait FlagSource: Send + Sync {
+    /// Fetches the current flags.
+    fn fetch(&self) -> impl Future<Output = Result<FlagSet, SourceError>> + Send;
+}
+
+/// A source fetching the flags from a HTTP endpoint responding a JSON object.
+///
+/// The JSON object is converted with the coercion rules of [`serde_loose`].
+/// Only the plain `http://` URLs are supported, use a TLS-terminating sidecar or implement [`FlagSource`] for HTTPS.
+///
+/// The connection, each read and each write time out after [`HttpSource::DEFAULT_TIMEOUT`],
+/// so a stalled endpoint can't hang the refresh; the host name is resolved by the system without a timeout.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HttpSource {
+    host: String,
+    port: u16,
+    path: String,
+    timeout: Duration,
+}
+
+impl HttpSource {
+    /// The default timeout of connecting, and of each read or write.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Creates a source of the `http://host[:port]/path` URL.
+    pub fn new(url: &str) -> Result<Self, SourceError> {
+        let invalid = || SourceError::Url(url.to_string());
+        let rest = url.strip_prefix("http://").ok_or_else(invalid)?;
+        let (authority, path) = match rest.find('/') {
+            Some(idx) => rest.split_at(idx),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| invalid())?),
+            None => (authority, 80),
+        };
+
+        if host.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(HttpSource {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+            timeout: Self::DEFAULT_TIMEOUT,
+        })
+    }
+
+    /// Sets the timeout of connecting, and of each read or write.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn connect(&self) -> io::Result<TcpStream> {
+        let mut last_err = None;
+
+        for addr in (self.host.as_str(), self.port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, self.timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(self.timeout))?;
+                    stream.set_write_timeout(Some(self.timeout))?;
+
+                    return Ok(stream);
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "host resolved to no address")
+        }))
+    }
+
+    fn get(&self) -> Result<FlagSet, SourceError> {
+        let mut stream = self.connect()?;
+
+        stream.write_all(
+            format!(
+                "GET {} HTTP/1.0\r\nHost: {}\r\nAccept: application/json\r\nConnection: close\r\n\r\n",
+                self.path, self.host
+            )
+            .as_bytes(),
+        )?;
+
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+
+        reader.read_line(&mut line)?;
+
+        let status = line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse::<u16>().ok())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "malformed HTTP status line")
+            })?;
+
+        if !(200..300).contains(&status) {
+            return Err(SourceError::Status(status));
+        }
+
+        // skip the headers
+        loop {
+            line.clear();
+
+            if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+                break;
+            }
+        }
+
+        let mut body = Vec::new();
+
+        reader.read_to_end(&mut body)?;
+
+        Ok(serde_loose::deserialize(
+            &mut serde_json::Deserializer::from_slice(&body),
+        )?)
+    }
+}
+
+/// Connects and reads the response on the calling thread, see [`FlagSource`].
+impl FlagSource for HttpSource {
+    fn fetch(&self) -> impl Future<Output = Result<FlagSet, SourceError>> + Send {
+        let flags = self.get();
+
+        async move { flags }
+    }
+}
+
+/// A source reading the flags from a mounted Kubernetes ConfigMap, a directory with a file per key.
+///
+/// The content of each file is trimmed, `true` sets the key without value,
+/// `false` and the empty content leave it unset, and the other contents set the key with the value.
+/// The hidden files, like `..data` of the mounted volume, are skipped.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConfigMapSource {
+    dir: PathBuf,
+}
+
+impl ConfigMapSource {
+    /// Creates a source of the mounted directory.
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        ConfigMapSource {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn read(&self) -> Result<FlagSet, SourceError> {
+        let mut flags = FlagSet::new();
+
+        let mut entries = fs::read_dir(&self.dir)?.collect::<io::Result<Vec<_>>>()?;
+
+        // read in the order of the keys, so the first error doesn't depend on the directory order
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let key = entry.file_name().to_string_lossy().into_owned();
+
+            if key.starts_with('.') || !entry.path().is_file() {
+                continue;
+            }
+
+            match fs::read_to_string(entry.path())?.trim() {
+                "true" => flags.insert_name(key),
+                "false" | "" => {}
+                value => {
+                    flags.insert(key, value);
+                }
+            }
+        }
+
+        Ok(flags)
+    }
+}
+
+/// Reads the directory on the calling thread, see [`FlagSource`].
+impl FlagSource for ConfigMapSource {
+    fn fetch(&self) -> impl Future<Output = Result<FlagSet, SourceError>> + Send {
+        let flags = self.read();
+
+        async move { flags }
+    }
+}
+
+/// The flags fetched from a source, and refreshed periodically on a background thread.
+///
+/// When a refresh fails, the previous flags are kept.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use runtime_cfg::*;
+/// use runtime_cfg::remote::{ConfigMapSource, RemoteFlags};
+///
+/// let flags = RemoteFlags::spawn(
+///     ConfigMapSource::new("/etc/config/flags"),
+///     Duration::from_secs(30),
+///     |flags| println!("flags changed: {:?}", flags),
+/// )
+/// .unwrap();
+///
+/// if feature("beta").matches(&flags) {
+///     // ..
+/// }
+/// ```
+#[derive(Debug)]
+pub struct RemoteFlags {
+    flags: Arc<RwLock<Arc<FlagSet>>>,
+    // the refreshing thread stops when the sender is dropped
+    _stop: Sender<()>,
+}
+
+impl RemoteFlags {
+    /// Fetches the flags from the source, and refreshes them every interval,
+    /// the callback is called with the new flags after each change.
+    ///
+    /// The first fetch blocks the calling thread, bounded by the timeouts of the source.
+    pub fn spawn<S, F>(source: S, interval: Duration, mut on_change: F) -> Result<Self, SourceError>
+    where
+        S: FlagSource + 'static,
+        F: FnMut(&FlagSet) + Send + 'static,
+    {
+        let flags = Arc::new(RwLock::new(Arc::new(block_on(source.fetch())?)));
+        let (stop, stopped) = mpsc::channel();
+
+        {
+            let flags = flags.clone();
+
+            thread::spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    if let Ok(fetched) = block_on(source.fetch()) {
+                        let changed = {
+                            let mut flags = flags.write().unwrap_or_else(PoisonError::into_inner);
+
+                            (**flags != fetched).then(|| {
+                                *flags = Arc::new(fetched);
+                                flags.clone()
+                            })
+                        };
+
+                        // the lock is released, so the lookups don't wait for the callback
+                        if let Some(changed) = changed {
+                            on_change(&changed);
+                        }
+                    }
+                }
+            });
+        }
+
+        Ok(RemoteFlags { flags, _stop: stop })
+    }
+
+    /// Returns the current flags.
+    pub fn snapshot(&self) -> Arc<FlagSet> {
+        self.flags
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Returns the generation of the current flags, which changes when the fetched flags are different.
+    pub fn generation(&self) -> u64 {
+        self.snapshot().generation()
+    }
+}
+
+impl Pattern for RemoteFlags {
+    fn matches(&self, key: &str, value: Option<&str>) -> bool {
+        self.snapshot().matches(key, value)
+    }
+}
+
+/// Runs the future to completion on the current thread.
+fn block_on<F: Future>(future: F) -> F::Output {
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark()
+        }
+    }
+
+    let waker = Waker::from(Arc::new(Unpark(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::{self, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::remote::*;
+    use crate::*;
+
+    #[test]
+    fn test_http_source() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/flags", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            for body in ["", r#"{"unix": true, "feature": ["ssl"]}"#] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).unwrap();
+
+                    assert_ne!(n, 0);
+
+                    request.extend_from_slice(&buf[..n]);
+                }
+
+                assert!(request.starts_with(b"GET /flags HTTP/1.0\r\n"));
+
+                let status = if body.is_empty() {
+                    "503 Service Unavailable"
+                } else {
+                    "200 OK"
+                };
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\n\r\n{}",
+                    status, body
+                )
+                .unwrap();
+            }
+        });
+        let source = HttpSource::new(&url).unwrap();
+
+        assert!(matches!(
+            block_on(source.fetch()),
+            Err(SourceError::Status(503))
+        ));
+        assert_eq!(
+            block_on(source.fetch()).unwrap(),
+            vec![("unix", None), ("feature", Some("ssl"))]
+                .into_iter()
+                .collect()
+        );
+
+        server.join().unwrap();
+
+        assert!(HttpSource::new("https://example.com/").is_err());
+        assert!(HttpSource::new("http://:80/").is_err());
+        assert!(HttpSource::new("http://example.com:http/").is_err());
+        assert_eq!(
+            HttpSource::new("http://example.com").unwrap(),
+            HttpSource {
+                host: "example.com".to_string(),
+                port: 80,
+                path: "/".to_string(),
+                timeout: HttpSource::DEFAULT_TIMEOUT,
+            }
+        );
+    }
+
+    #[test]
+    fn test_http_source_timeout() {
+        // the connection is queued by the backlog, but never answered
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/flags", listener.local_addr().unwrap());
+        let source = HttpSource::new(&url)
+            .unwrap()
+            .with_timeout(Duration::from_millis(100));
+
+        assert!(matches!(
+            block_on(source.fetch()),
+            Err(SourceError::Io(err)) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+        ));
+
+        drop(listener);
+    }
+
+    #[test]
+    fn test_config_map_source() {
+        let dir = std::env::temp_dir().join(format!("runtime_cfg-remote-{}", std::process::id()));
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("unix"), "true\n").unwrap();
+        fs::write(dir.join("debug"), "false").unwrap();
+        fs::write(dir.join("target_os"), " linux\n").unwrap();
+        fs::write(dir.join("..data"), "ignored").unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let flags = RemoteFlags::spawn(
+            ConfigMapSource::new(&dir),
+            Duration::from_millis(10),
+            move |flags| {
+                let _ = tx.send(flags.clone());
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            *flags.snapshot(),
+            vec![("unix", None), ("target_os", Some("linux"))]
+                .into_iter()
+                .collect()
+        );
+
+        let generation = flags.generation();
+
+        fs::write(dir.join("debug"), "true").unwrap();
+
+        assert_eq!(
+            rx.recv_timeout(Duration::from_secs(10)).unwrap(),
+            vec![
+                ("unix", None),
+                ("debug", None),
+                ("target_os", Some("linux"))
+            ]
+            .into_iter()
+            .collect()
+        );
+        assert!(name("debug").matches(&flags));
+        assert_ne!(flags.generation(), generation);
+
+        drop(flags);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,258 @@
+//! A report of the code regions guarded by `#[cfg(..)]` in the source files, evaluated against a set of targets.
+//!
+//! A region is active for a target when its configuration and the configurations of all the enclosing regions match,
+//! so the regions nested in a region which never matches are dead too.
+//!
+//! The report is displayed as text, or serialized as JSON with the `serde` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use runtime_cfg::report::{Report, Status};
+//! use runtime_cfg::*;
+//!
+//! let mut index = CfgIndex::new();
+//!
+//! index.add_file("src/lib.rs", r#"
+//! #[cfg(unix)]
+//! mod unix {
+//!     #[cfg(target_os = "linux")]
+//!     pub fn linux() {}
+//! }
+//!
+//! #[cfg(target_os = "none")]
+//! fn bare_metal() {}
+//! "#);
+//!
+//! let linux: FlagSet = vec![("unix", None), ("target_os", Some("linux"))].into_iter().collect();
+//! let macos: FlagSet = vec![("unix", None), ("target_os", Some("macos"))].into_iter().collect();
+//! let report = Report::new(&index, &[("linux", linux), ("macos", macos)]);
+//!
+//! assert_eq!(
+//!     report.regions.iter().map(|region| region.status).collect::<Vec<_>>(),
+//!     vec![Status::Active, Status::Partial, Status::Dead]
+//! );
+//! assert_eq!(report.regions[1].active, vec!["linux"]);
+//!
+//! println!("{}", report);
+//! ```
+
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::{CfgIndex, Pattern};
+
+/// The status of a region over the targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(::serde::Serialize, ::serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Status {
+    /// The region is active for all the targets.
+    Active,
+    /// The region is active for some of the targets.
+    Partial,
+    /// The region is active for none of the targets.
+    Dead,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Status::Active => "active",
+            Status::Partial => "partial",
+            Status::Dead => "dead",
+        })
+    }
+}
+
+/// A region guarded by a `#[cfg(..)]` attribute.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct RegionReport {
+    /// The path of the source file.
+    pub path: PathBuf,
+    /// The 1-based line of the start of the region.
+    pub start_line: usize,
+    /// The 1-based line of the end of the region.
+    pub end_line: usize,
+    /// The attribute, like `#[cfg(unix)]`.
+    pub cfg: String,
+    /// The status of the region over the targets.
+    pub status: Status,
+    /// The names of the targets for which the region is active.
+    pub active: Vec<String>,
+}
+
+/// A report of the regions in the indexed source files, in the order of their paths and positions.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct Report {
+    /// The names of the targets.
+    pub targets: Vec<String>,
+    /// The regions guarded by the attributes.
+    pub regions: Vec<RegionReport>,
+}
+
+impl Report {
+    /// Evaluates the regions of the index against the named targets,
+    /// like the flags of [`FlagSet::from_builtin_target`](crate::FlagSet) with the enabled features.
+    pub fn new<S: AsRef<str>, P: Pattern>(index: &CfgIndex, targets: &[(S, P)]) -> Report {
+        let regions = index
+            .iter()
+            .map(|(path, cfg)| {
+                let last = cfg.region.end.saturating_sub(1).max(cfg.region.start);
+                let active = targets
+                    .iter()
+                    .filter(|(_, pattern)| {
+                        index
+                            .guards(path, last)
+                            .all(|guard| guard.cfg.matches(pattern))
+                    })
+                    .map(|(name, _)| name.as_ref().to_string())
+                    .collect::<Vec<_>>();
+                let status = if active.is_empty() {
+                    Status::Dead
+                } else if active.len() == targets.len() {
+                    Status::Active
+                } else {
+                    Status::Partial
+                };
+
+                RegionReport {
+                    path: path.to_path_buf(),
+                    start_line: cfg.start_line,
+                    end_line: cfg.end_line,
+                    cfg: cfg.cfg.to_string(),
+                    status,
+                    active,
+                }
+            })
+            .collect();
+
+        Report {
+            targets: targets
+                .iter()
+                .map(|(name, _)| name.as_ref().to_string())
+                .collect(),
+            regions,
+        }
+    }
+
+    /// Returns the regions of the status.
+    pub fn regions_of(&self, status: Status) -> impl Iterator<Item = &RegionReport> {
+        self.regions
+            .iter()
+            .filter(move |region| region.status == status)
+    }
+}
+
+/// Displays a line per region, like `src/lib.rs:2-6: partial #[cfg(unix)] [linux]`, followed by a summary.
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for region in &self.regions {
+            writeln!(
+                f,
+                "{}:{}-{}: {} {} [{}]",
+                region.path.display(),
+                region.start_line,
+                region.end_line,
+                region.status,
+                region.cfg,
+                region.active.join(", ")
+            )?;
+        }
+
+        write!(
+            f,
+            "{} regions over {} targets: {} active, {} partial, {} dead",
+            self.regions.len(),
+            self.targets.len(),
+            self.regions_of(Status::Active).count(),
+            self.regions_of(Status::Partial).count(),
+            self.regions_of(Status::Dead).count()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::*;
+    use crate::*;
+
+    const SOURCE: &str = r#"
+#[cfg(feature = "std")]
+mod std_impl {
+    #[cfg(windows)]
+    fn windows() {}
+
+    #[cfg(unix)]
+    fn unix() {}
+}
+
+#[cfg(test)]
+mod tests {}
+"#;
+
+    #[test]
+    fn test_report() {
+        let mut index = CfgIndex::new();
+
+        index.add_file("src/lib.rs", SOURCE);
+
+        let std_unix: FlagSet = vec![("unix", None), ("feature", Some("std"))]
+            .into_iter()
+            .collect();
+        let no_std_windows: FlagSet = vec![("windows", None::<&str>)].into_iter().collect();
+        let report = Report::new(
+            &index,
+            &[("std-unix", std_unix), ("no-std-windows", no_std_windows)],
+        );
+
+        assert_eq!(report.targets, vec!["std-unix", "no-std-windows"]);
+        assert_eq!(
+            report
+                .regions
+                .iter()
+                .map(|region| (
+                    region.start_line,
+                    region.end_line,
+                    region.status,
+                    region.active.clone()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (2, 9, Status::Partial, vec!["std-unix".to_string()]),
+                (4, 5, Status::Dead, vec![]),
+                (7, 8, Status::Partial, vec!["std-unix".to_string()]),
+                (11, 12, Status::Dead, vec![]),
+            ]
+        );
+        assert_eq!(
+            report.to_string(),
+            r#"src/lib.rs:2-9: partial #[cfg(feature = "std")] [std-unix]
+src/lib.rs:4-5: dead #[cfg(windows)] []
+src/lib.rs:7-8: partial #[cfg(unix)] [std-unix]
+src/lib.rs:11-12: dead #[cfg(test)] []
+4 regions over 2 targets: 0 active, 2 partial, 2 dead"#
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json() {
+        let mut index = CfgIndex::new();
+
+        index.add_file("lib.rs", "#[cfg(unix)] fn f() {}");
+
+        let unix: FlagSet = vec![("unix", None::<&str>)].into_iter().collect();
+        let report = Report::new(&index, &[("unix", unix)]);
+
+        assert_eq!(
+            serde_json::to_string(&report).unwrap(),
+            r##"{"targets":["unix"],"regions":[{"path":"lib.rs","start_line":1,"end_line":1,"cfg":"#[cfg(unix)]","status":"active","active":["unix"]}]}"##
+        );
+    }
+}
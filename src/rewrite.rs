@@ -0,0 +1,331 @@
+//! Rule-based rewriting of predicates, to normalize them to the conventions.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::boxed::Box;
+        use alloc::vec::Vec;
+    }
+}
+
+use core::iter::FromIterator;
+
+use crate::{Bindings, Predicate};
+
+/// The maximum number of passes of [`Predicate::rewrite`], to stop the rules rewriting each other forever.
+const MAX_PASSES: usize = 64;
+
+/// A rewriting rule, replacing the sub-predicates matching the pattern with the replacement.
+///
+/// The parameters of the pattern, like `param("x")`, match any sub-predicate,
+/// a parameter used more than once must match the same sub-predicate,
+/// and the parameters of the replacement are bound to the matched sub-predicates.
+///
+/// An `any` or `all` pattern without parameter matches the sub-predicates in any order,
+/// and also matches a part of the sub-predicates of the same kind,
+/// then only that part is replaced, in place of its first sub-predicate.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rule {
+    pattern: Predicate,
+    replacement: Predicate,
+}
+
+impl Rule {
+    /// Creates a rule replacing the pattern with the replacement.
+    pub fn new(pattern: Predicate, replacement: Predicate) -> Self {
+        Rule {
+            pattern,
+            replacement,
+        }
+    }
+
+    /// Returns the pattern of the rule.
+    pub fn pattern(&self) -> &Predicate {
+        &self.pattern
+    }
+
+    /// Returns the replacement of the rule.
+    pub fn replacement(&self) -> &Predicate {
+        &self.replacement
+    }
+
+    /// Returns the rewritten predicate if the rule applies to the predicate itself.
+    pub fn apply(&self, predicate: &Predicate) -> Option<Predicate> {
+        let mut bindings = Bindings::new();
+
+        if unify(&self.pattern, predicate, &mut bindings) {
+            return Some(self.replacement.bind(&bindings));
+        }
+
+        match (&self.pattern, predicate) {
+            (Predicate::Any(patterns), Predicate::Any(predicates)) => {
+                self.replace_part(patterns, predicates).map(Predicate::Any)
+            }
+            (Predicate::All(patterns), Predicate::All(predicates)) => {
+                self.replace_part(patterns, predicates).map(Predicate::All)
+            }
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::vec_box)]
+    fn replace_part(
+        &self,
+        patterns: &[Box<Predicate>],
+        predicates: &[Box<Predicate>],
+    ) -> Option<Vec<Box<Predicate>>> {
+        if patterns.is_empty() || !self.pattern.params().is_empty() {
+            return None;
+        }
+
+        let matched = find_all(patterns, predicates)?;
+        let first = matched.iter().min().copied()?;
+
+        Some(
+            predicates
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, predicate)| {
+                    if idx == first {
+                        Some(Box::new(self.replacement.clone()))
+                    } else if matched.contains(&idx) {
+                        None
+                    } else {
+                        Some(predicate.clone())
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+/// A set of rewriting rules, applied in the order they were added.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let rules: Rules = vec![
+///     (
+///         any(vec![name_value("target_os", "ios"), name_value("target_os", "macos")]),
+///         name_value("target_vendor", "apple"),
+///     ),
+///     (not(not(param("x"))), param("x")),
+/// ]
+/// .into_iter()
+/// .collect();
+///
+/// let predicate = all(vec![
+///     name("unix"),
+///     any(vec![
+///         name_value("target_os", "macos"),
+///         name_value("target_os", "linux"),
+///         not(not(name_value("target_os", "ios"))),
+///     ]),
+/// ]);
+///
+/// assert_eq!(
+///     predicate.rewrite(&rules),
+///     all(vec![
+///         name("unix"),
+///         any(vec![name_value("target_vendor", "apple"), name_value("target_os", "linux")]),
+///     ])
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Rules(Vec<Rule>);
+
+impl Rules {
+    /// Creates an empty set of rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule replacing the pattern with the replacement.
+    pub fn add(&mut self, pattern: Predicate, replacement: Predicate) -> &mut Self {
+        self.0.push(Rule::new(pattern, replacement));
+        self
+    }
+
+    /// Returns the number of rules.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if there is no rule.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns an iterator over the rules.
+    pub fn iter(&self) -> core::slice::Iter<'_, Rule> {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<Rule> for Rules {
+    fn from_iter<I: IntoIterator<Item = Rule>>(iter: I) -> Self {
+        Rules(iter.into_iter().collect())
+    }
+}
+
+impl FromIterator<(Predicate, Predicate)> for Rules {
+    fn from_iter<I: IntoIterator<Item = (Predicate, Predicate)>>(iter: I) -> Self {
+        Rules(
+            iter.into_iter()
+                .map(|(pattern, replacement)| Rule::new(pattern, replacement))
+                .collect(),
+        )
+    }
+}
+
+impl Extend<Rule> for Rules {
+    fn extend<I: IntoIterator<Item = Rule>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+impl Predicate {
+    /// Returns a predicate rewritten with the rules to fixpoint.
+    ///
+    /// Each pass rewrites the sub-predicates from the bottom up, with the first applicable rule;
+    /// the passes are repeated until nothing changes, or at most 64 passes for the rules rewriting each other.
+    pub fn rewrite(&self, rules: &Rules) -> Predicate {
+        let mut predicate = self.clone();
+
+        for _ in 0..MAX_PASSES {
+            let mut changed = false;
+
+            predicate = rewrite(rules, predicate, &mut changed);
+
+            if !changed {
+                break;
+            }
+        }
+
+        predicate
+    }
+}
+
+fn rewrite(rules: &Rules, predicate: Predicate, changed: &mut bool) -> Predicate {
+    let predicate = match predicate {
+        Predicate::Any(predicates) => Predicate::Any(rewrite_all(rules, predicates, changed)),
+        Predicate::All(predicates) => Predicate::All(rewrite_all(rules, predicates, changed)),
+        Predicate::Not(predicate) => Predicate::Not(Box::new(rewrite(rules, *predicate, changed))),
+        predicate => predicate,
+    };
+
+    match rules.iter().find_map(|rule| rule.apply(&predicate)) {
+        Some(rewritten) if rewritten != predicate => {
+            *changed = true;
+            rewritten
+        }
+        _ => predicate,
+    }
+}
+
+#[allow(clippy::vec_box)]
+fn rewrite_all(
+    rules: &Rules,
+    predicates: Vec<Box<Predicate>>,
+    changed: &mut bool,
+) -> Vec<Box<Predicate>> {
+    predicates
+        .into_iter()
+        .map(|predicate| Box::new(rewrite(rules, *predicate, changed)))
+        .collect()
+}
+
+/// Matches the predicate with the pattern, binding its parameters.
+fn unify(pattern: &Predicate, predicate: &Predicate, bindings: &mut Bindings) -> bool {
+    match (pattern, predicate) {
+        (Predicate::Param(name), _) => match bindings.get(name) {
+            Some(bound) => bound == predicate,
+            None => {
+                bindings.insert(name.as_str(), predicate.clone());
+                true
+            }
+        },
+        (Predicate::Any(patterns), Predicate::Any(predicates))
+        | (Predicate::All(patterns), Predicate::All(predicates)) => {
+            if patterns.len() != predicates.len() {
+                false
+            } else if pattern.params().is_empty() {
+                find_all(patterns, predicates).is_some()
+            } else {
+                patterns
+                    .iter()
+                    .zip(predicates)
+                    .all(|(pattern, predicate)| unify(pattern, predicate, bindings))
+            }
+        }
+        (Predicate::Not(pattern), Predicate::Not(predicate)) => unify(pattern, predicate, bindings),
+        _ => pattern == predicate,
+    }
+}
+
+/// Finds the distinct sub-predicates equal to the patterns, returns their indexes.
+fn find_all(patterns: &[Box<Predicate>], predicates: &[Box<Predicate>]) -> Option<Vec<usize>> {
+    let mut matched = Vec::with_capacity(patterns.len());
+
+    for pattern in patterns {
+        let idx = predicates
+            .iter()
+            .enumerate()
+            .position(|(idx, predicate)| predicate == pattern && !matched.contains(&idx))?;
+
+        matched.push(idx);
+    }
+
+    Some(matched)
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_rewrite() {
+        let mut rules = Rules::new();
+
+        rules
+            .add(all(vec![param("x"), param("x")]), param("x"))
+            .add(any(vec![name("unix"), name("windows")]), Predicate::True)
+            .add(not(Predicate::True), Predicate::False);
+
+        assert_eq!(rules.len(), 3);
+        assert_eq!(
+            all(vec![feature("ssl"), feature("ssl")]).rewrite(&rules),
+            feature("ssl")
+        );
+        assert_eq!(
+            all(vec![feature("ssl"), feature("tls")]).rewrite(&rules),
+            all(vec![feature("ssl"), feature("tls")])
+        );
+        assert_eq!(
+            not(any(vec![name("windows"), name("wasm"), name("unix")])).rewrite(&rules),
+            not(any(vec![Predicate::True, name("wasm")]))
+        );
+        assert_eq!(
+            all(vec![
+                not(any(vec![name("windows"), name("unix")])),
+                not(any(vec![name("unix"), name("windows")])),
+            ])
+            .rewrite(&rules),
+            Predicate::False
+        );
+
+        let cyclic: Rules = vec![(name("a"), name("b")), (name("b"), name("a"))]
+            .into_iter()
+            .collect();
+
+        assert_eq!(name("a").rewrite(&cyclic), name("a"));
+        assert_eq!(name("c").rewrite(&Rules::new()), name("c"));
+    }
+}
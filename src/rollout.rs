@@ -0,0 +1,165 @@
+//! Percentage rollouts, as the `rollout = "25"` leaves evaluated by a stable hash of the caller ID.
+//!
+//! The leaves remain valid `cfg` syntax, only the [`Rollout`] pattern evaluates them,
+//! so the other patterns treat them as the ordinary flags.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::format;
+        use alloc::string::String;
+    }
+}
+
+use crate::{Pattern, Predicate};
+
+/// The key of the rollout leaves.
+pub const ROLLOUT_KEY: &str = "rollout";
+
+/// The number of buckets, for the percentages with up to two decimals.
+const BUCKETS: u64 = 10_000;
+
+/// A pattern evaluating the rollout leaves for a stable ID, and delegating the other flags to the wrapped pattern.
+///
+/// A rollout leaf is `rollout = "<percentage>"` or `rollout = "<name>:<percentage>"`,
+/// the ID matches if its bucket, hashed from the name and the ID, is under the percentage.
+/// The same ID always lands in the same bucket of a rollout, and the buckets of the rollouts with different names
+/// are independent; a malformed rollout never matches.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let predicate = all(vec![name("unix"), rollout("new-ui", 25)]);
+///
+/// let enabled = (0..1000)
+///     .filter(|id| predicate.matches(&Rollout::new(vec![("unix", None::<&str>)], id.to_string())))
+///     .count();
+///
+/// assert!(200 < enabled && enabled < 300);
+/// assert!(rollout("new-ui", 100).matches(&Rollout::new(FlagSet::new(), "user-42")));
+/// assert!(!rollout("new-ui", 0).matches(&Rollout::new(FlagSet::new(), "user-42")));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Rollout<P> {
+    pattern: P,
+    id: String,
+}
+
+impl<P> Rollout<P> {
+    /// Creates a pattern evaluating the rollouts for the stable ID.
+    pub fn new<S: Into<String>>(pattern: P, id: S) -> Self {
+        Rollout {
+            pattern,
+            id: id.into(),
+        }
+    }
+
+    /// Returns the stable ID.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Consumes the adapter, returns the wrapped pattern.
+    pub fn into_inner(self) -> P {
+        self.pattern
+    }
+}
+
+impl<P: Pattern> Pattern for Rollout<P> {
+    fn matches(&self, key: &str, value: Option<&str>) -> bool {
+        match value {
+            Some(value) if key == ROLLOUT_KEY => in_rollout(value, &self.id),
+            _ => self.pattern.matches(key, value),
+        }
+    }
+}
+
+/// Returns `true` if the ID is in the rollout of `<percentage>` or `<name>:<percentage>`.
+pub(crate) fn in_rollout(rollout: &str, id: &str) -> bool {
+    let (name, percentage) = rollout.rsplit_once(':').unwrap_or(("", rollout));
+
+    match percentage.trim().parse::<f64>() {
+        Ok(percentage) if (0.0..=100.0).contains(&percentage) => {
+            bucket(name, id) < (percentage * (BUCKETS / 100) as f64) as u64
+        }
+        _ => false,
+    }
+}
+
+/// Hashes the name and the ID with the 64-bit FNV-1a algorithm, which is stable across processes and platforms.
+fn bucket(name: &str, id: &str) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let hash = name
+        .bytes()
+        .chain(Some(b':'))
+        .chain(id.bytes())
+        .fold(FNV_OFFSET_BASIS, |hash, b| {
+            (hash ^ u64::from(b)).wrapping_mul(FNV_PRIME)
+        });
+
+    hash % BUCKETS
+}
+
+/// A rollout predicate of the percentage of IDs, as `rollout = "<name>:<percentage>"`.
+///
+/// The percentages over 100 are clamped to 100, so they match every ID.
+pub fn rollout(name: &str, percentage: u8) -> Predicate {
+    Predicate::NameValue(
+        ROLLOUT_KEY.into(),
+        format!("{}:{}", name, percentage.min(100)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::string::ToString;
+            use alloc::vec;
+            use alloc::vec::Vec;
+        }
+    }
+
+    use crate::rollout::in_rollout;
+    use crate::*;
+
+    #[test]
+    fn test_rollout() {
+        assert_eq!(rollout("beta", 25), name_value("rollout", "beta:25"));
+        assert_eq!(rollout("beta", 101), name_value("rollout", "beta:100"));
+        assert!(rollout("beta", u8::MAX).matches(&Rollout::new(FlagSet::new(), "user-1")));
+
+        let ids = (0..10_000).map(|id| id.to_string()).collect::<Vec<_>>();
+        let count = |rollout: &str| ids.iter().filter(|id| in_rollout(rollout, id)).count();
+
+        assert_eq!(count("0"), 0);
+        assert_eq!(count("100"), 10_000);
+        assert!((900..1100).contains(&count("a:10")));
+        assert!((0..30).contains(&count("a:0.1")));
+        assert_eq!(count("a:101"), 0);
+        assert_eq!(count("a:-1"), 0);
+        assert_eq!(count("a:many"), 0);
+
+        // the smaller rollout of the same name is a subset of the larger one
+        assert!(ids
+            .iter()
+            .all(|id| !in_rollout("a:10", id) || in_rollout("a:20", id)));
+        // the rollouts of different names are independent
+        assert!(ids
+            .iter()
+            .any(|id| in_rollout("a:10", id) != in_rollout("b:10", id)));
+
+        let flags = Rollout::new(vec![("rollout", Some("beta:25"))], "user-1");
+
+        assert_eq!(flags.id(), "user-1");
+        assert!(name("rollout").matches(&flags));
+        assert!(!name_value("rollout", "gamma").matches(&flags));
+        assert_eq!(
+            name_value("rollout", "beta:25").matches(&flags),
+            in_rollout("beta:25", "user-1")
+        );
+    }
+}
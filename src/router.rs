@@ -0,0 +1,209 @@
+//! An ordered rule engine, routing the flags to the first matching rule.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::vec::Vec;
+    }
+}
+
+use core::iter::FromIterator;
+
+use crate::{Cfg, Explanation, Pattern};
+
+/// An ordered list of rules with an optional default,
+/// the first rule whose configuration matches the flags wins.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let mut router = CfgRouter::with_default("generic");
+///
+/// router.push(all(vec![name("unix"), feature("beta")]).into(), "unix-beta");
+/// router.push(name("unix").into(), "unix");
+///
+/// let flags = vec![("unix", None), ("feature", Some("stable"))];
+///
+/// assert_eq!(router.route(&flags), Some(&"unix"));
+///
+/// let route = router.explain_route(&flags);
+///
+/// assert_eq!(route.index, Some(1));
+/// assert_eq!(route.rejected.len(), 1);
+/// assert_eq!(
+///     route.rejected[0].failed_leaves().collect::<Vec<_>>(),
+///     vec![&feature("beta")]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CfgRouter<T> {
+    rules: Vec<(Cfg, T)>,
+    default: Option<T>,
+}
+
+impl<T> Default for CfgRouter<T> {
+    fn default() -> Self {
+        CfgRouter {
+            rules: Vec::new(),
+            default: None,
+        }
+    }
+}
+
+/// The result of routing, with the explanations of the evaluated rules.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct RouteExplanation<'a, T> {
+    /// The index of the fired rule, or `None` if no rule matches.
+    pub index: Option<usize>,
+    /// The value of the fired rule, or the default value if no rule matches.
+    pub value: Option<&'a T>,
+    /// The explanations of the rules before the fired one, which don't match.
+    pub rejected: Vec<Explanation<'a>>,
+    /// The explanation of the fired rule.
+    pub fired: Option<Explanation<'a>>,
+}
+
+impl<T> CfgRouter<T> {
+    /// Creates a router without any rule or default.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a router without any rule, routing to the default value.
+    pub fn with_default(value: T) -> Self {
+        CfgRouter {
+            rules: Vec::new(),
+            default: Some(value),
+        }
+    }
+
+    /// Appends a rule after the existing rules.
+    pub fn push(&mut self, cfg: Cfg, value: T) -> &mut Self {
+        self.rules.push((cfg, value));
+        self
+    }
+
+    /// Sets the default value when no rule matches, returns the previous default value if any.
+    pub fn set_default(&mut self, value: T) -> Option<T> {
+        self.default.replace(value)
+    }
+
+    /// Returns the default value.
+    pub fn default_value(&self) -> Option<&T> {
+        self.default.as_ref()
+    }
+
+    /// Returns the rules in order.
+    pub fn rules(&self) -> &[(Cfg, T)] {
+        &self.rules
+    }
+
+    /// Returns the number of rules.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Returns `true` if there is no rule.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns the value of the first rule matching the pattern, or the default value.
+    pub fn route<P: Pattern>(&self, pattern: &P) -> Option<&T> {
+        self.rules
+            .iter()
+            .find(|(cfg, _)| cfg.matches(pattern))
+            .map(|(_, value)| value)
+            .or(self.default.as_ref())
+    }
+
+    /// Routes the pattern, and explains why the rules before the fired one don't match.
+    pub fn explain_route<P: Pattern>(&self, pattern: &P) -> RouteExplanation<'_, T> {
+        let mut rejected = Vec::new();
+
+        for (index, (cfg, value)) in self.rules.iter().enumerate() {
+            let explanation = cfg.explain(pattern);
+
+            if explanation.matched {
+                return RouteExplanation {
+                    index: Some(index),
+                    value: Some(value),
+                    rejected,
+                    fired: Some(explanation),
+                };
+            }
+
+            rejected.push(explanation);
+        }
+
+        RouteExplanation {
+            index: None,
+            value: self.default.as_ref(),
+            rejected,
+            fired: None,
+        }
+    }
+}
+
+impl<T> FromIterator<(Cfg, T)> for CfgRouter<T> {
+    fn from_iter<I: IntoIterator<Item = (Cfg, T)>>(iter: I) -> Self {
+        CfgRouter {
+            rules: iter.into_iter().collect(),
+            default: None,
+        }
+    }
+}
+
+impl<T> Extend<(Cfg, T)> for CfgRouter<T> {
+    fn extend<I: IntoIterator<Item = (Cfg, T)>>(&mut self, iter: I) {
+        self.rules.extend(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_route() {
+        let mut router: CfgRouter<u32> = vec![
+            (Cfg::from(name("windows")), 1),
+            (Cfg::from(name("unix")), 2),
+            (Cfg::from(name("unix")), 3),
+        ]
+        .into_iter()
+        .collect();
+
+        let unix = vec![("unix", None::<&str>)];
+        let wasm = vec![("target_family", Some("wasm"))];
+
+        assert_eq!(router.route(&unix), Some(&2));
+        assert_eq!(router.route(&wasm), None);
+
+        let route = router.explain_route(&wasm);
+
+        assert_eq!(route.index, None);
+        assert_eq!(route.value, None);
+        assert_eq!(route.rejected.len(), 3);
+        assert!(route.fired.is_none());
+
+        assert_eq!(router.set_default(0), None);
+        assert_eq!(router.route(&wasm), Some(&0));
+        assert_eq!(router.explain_route(&wasm).value, Some(&0));
+
+        let route = router.explain_route(&unix);
+
+        assert_eq!(route.index, Some(1));
+        assert_eq!(route.value, Some(&2));
+        assert_eq!(route.rejected.len(), 1);
+        assert!(route.fired.unwrap().matched);
+    }
+}
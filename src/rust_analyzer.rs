@@ -0,0 +1,265 @@
+//! The `cfg` arrays of `rust-project.json` accepted by rust-analyzer.
+//!
+//! Each flag is a string, like `"unix"` or `"feature=\"serde\""`.
+//! The `"` and `\` in the quoted values are escaped with `\`,
+//! and the unquoted values, like `"feature=serde"`, are accepted as written.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::format;
+        use alloc::string::{String, ToString};
+        use alloc::vec;
+        use alloc::vec::Vec;
+    }
+}
+
+use core::fmt;
+
+use crate::FlagSet;
+
+/// An error when parsing a rust-analyzer `cfg` string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InvalidCfgError(String);
+
+impl fmt::Display for InvalidCfgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid cfg: {:?}", self.0)
+    }
+}
+
+impl core::error::Error for InvalidCfgError {}
+
+impl FlagSet {
+    /// Returns the flags as the `cfg` array of `rust-project.json`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let flags: FlagSet = vec![("unix", None), ("feature", Some("serde"))]
+    ///     .into_iter()
+    ///     .collect();
+    ///
+    /// assert_eq!(flags.to_rust_analyzer_cfgs(), vec!["feature=\"serde\"", "unix"]);
+    /// assert_eq!(
+    ///     FlagSet::from_rust_analyzer_cfgs(flags.to_rust_analyzer_cfgs()),
+    ///     Ok(flags)
+    /// );
+    /// ```
+    pub fn to_rust_analyzer_cfgs(&self) -> Vec<String> {
+        self.iter()
+            .flat_map(|(name, values)| {
+                if values.is_empty() {
+                    vec![name.clone()]
+                } else {
+                    values
+                        .iter()
+                        .map(|value| format!("{}=\"{}\"", name, escape(value)))
+                        .collect()
+                }
+            })
+            .collect()
+    }
+
+    /// Parses the flags from the `cfg` array of `rust-project.json`.
+    ///
+    /// The quoted values are unescaped, and the unquoted values are taken as written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let flags = FlagSet::from_rust_analyzer_cfgs(vec![r#"feature="a\"b""#, "target_os=linux"]).unwrap();
+    ///
+    /// assert!(flags.has_value("feature", "a\"b"));
+    /// assert!(flags.has_value("target_os", "linux"));
+    /// ```
+    pub fn from_rust_analyzer_cfgs<I, S>(cfgs: I) -> Result<FlagSet, InvalidCfgError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut flags = FlagSet::new();
+
+        for cfg in cfgs {
+            let cfg = cfg.as_ref();
+
+            match cfg.split_once('=') {
+                Some((name, value)) => {
+                    let name = valid_name(name, cfg)?;
+
+                    if value.starts_with('"') {
+                        let value =
+                            unescape(value).ok_or_else(|| InvalidCfgError(cfg.to_string()))?;
+
+                        flags.insert(name, value);
+                    } else if value.contains('"') {
+                        return Err(InvalidCfgError(cfg.to_string()));
+                    } else {
+                        flags.insert(name, value);
+                    }
+                }
+                None => flags.insert_name(valid_name(cfg, cfg)?),
+            }
+        }
+
+        Ok(flags)
+    }
+}
+
+fn valid_name<'a>(name: &'a str, cfg: &str) -> Result<&'a str, InvalidCfgError> {
+    if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '"') {
+        Err(InvalidCfgError(cfg.to_string()))
+    } else {
+        Ok(name)
+    }
+}
+
+/// Escapes the `"` and `\` of a value to be quoted.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            escaped.push('\\');
+        }
+
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Unescapes a quoted value, or returns `None` if it isn't properly quoted and escaped.
+fn unescape(quoted: &str) -> Option<String> {
+    let mut chars = quoted.strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut value = String::with_capacity(quoted.len());
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                c @ ('"' | '\\') => value.push(c),
+                _ => return None,
+            },
+            '"' => return None,
+            c => value.push(c),
+        }
+    }
+
+    Some(value)
+}
+
+/// Serializes and deserializes a `FlagSet` as the `cfg` array of `rust-project.json`,
+/// with `#[serde(with = "runtime_cfg::rust_analyzer::cfgs")]`.
+#[cfg(feature = "serde")]
+pub mod cfgs {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::string::String;
+            use alloc::vec::Vec;
+        }
+    }
+
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::FlagSet;
+
+    /// Serializes the flags as the `cfg` array.
+    pub fn serialize<S: Serializer>(flags: &FlagSet, serializer: S) -> Result<S::Ok, S::Error> {
+        flags.to_rust_analyzer_cfgs().serialize(serializer)
+    }
+
+    /// Deserializes the flags from the `cfg` array.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<FlagSet, D::Error> {
+        FlagSet::from_rust_analyzer_cfgs(Vec::<String>::deserialize(deserializer)?)
+            .map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    use super::*;
+
+    #[test]
+    fn test_from_rust_analyzer_cfgs() {
+        let flags = FlagSet::from_rust_analyzer_cfgs(vec![
+            "unix",
+            "feature=\"std\"",
+            "feature=\"serde\"",
+            "target_os=\"\"",
+        ])
+        .unwrap();
+
+        assert!(all(vec![
+            name("unix"),
+            feature("std"),
+            feature("serde"),
+            name_value("target_os", "")
+        ])
+        .matches(&flags));
+
+        let flags =
+            FlagSet::from_rust_analyzer_cfgs(vec!["feature=serde", "target_os=", "panic=un=wind"])
+                .unwrap();
+
+        assert!(flags.has_value("feature", "serde"));
+        assert!(flags.has_value("target_os", ""));
+        assert!(flags.has_value("panic", "un=wind"));
+
+        for cfg in &[
+            "",
+            "=\"x\"",
+            "a b",
+            "feature=\"serde",
+            "feature=\"",
+            "feature=se\"rde",
+            r#"feature="a"b""#,
+            r#"feature="a\nb""#,
+            r#"feature="a\""#,
+        ] {
+            assert_eq!(
+                FlagSet::from_rust_analyzer_cfgs([cfg]),
+                Err(InvalidCfgError(cfg.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_escape() {
+        let mut flags = FlagSet::new();
+
+        flags.insert("feature", r#"a"b"#);
+        flags.insert("target_env", r"c:\d\");
+
+        let cfgs = flags.to_rust_analyzer_cfgs();
+
+        assert_eq!(cfgs, vec![r#"feature="a\"b""#, r#"target_env="c:\\d\\""#]);
+        assert_eq!(FlagSet::from_rust_analyzer_cfgs(cfgs), Ok(flags));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        #[derive(::serde::Serialize, ::serde::Deserialize, PartialEq, Debug)]
+        struct Crate {
+            #[serde(with = "cfgs")]
+            cfg: FlagSet,
+        }
+
+        let json = r#"{"cfg":["feature=\"serde\"","unix"]}"#;
+        let krate: Crate = serde_json::from_str(json).unwrap();
+
+        assert!(krate.cfg.has("unix"));
+        assert_eq!(serde_json::to_string(&krate).unwrap(), json);
+    }
+}
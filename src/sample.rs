@@ -0,0 +1,221 @@
+//! Random sampling of flag sets that satisfy, or don't satisfy, a predicate.
+
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::collections::BTreeMap;
+    } else {
+        use alloc::collections::BTreeMap;
+        use alloc::vec::Vec;
+    }
+}
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{FlagSet, Predicate};
+
+/// The maximum attempts to sample an assignment before giving up.
+const MAX_ATTEMPTS: usize = 64;
+
+/// The well-known flags which have at most one value.
+const SINGLE_VALUED: &[&str] = &[
+    "panic",
+    "target_arch",
+    "target_endian",
+    "target_env",
+    "target_os",
+    "target_pointer_width",
+    "target_vendor",
+];
+
+type Constraints<'a> = BTreeMap<(&'a str, Option<&'a str>), bool>;
+
+impl Predicate {
+    /// Samples a random flag set that matches the predicate if `want` is `true`,
+    /// or doesn't match the predicate otherwise.
+    ///
+    /// The flags are drawn from the flags referenced by the predicate,
+    /// and the well-known single-valued flags, like `target_os`, never get more than one value.
+    /// Returns `None` if no such flag set was found, e.g. the predicate is unsatisfiable.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rand::{rngs::SmallRng, SeedableRng};
+    /// use runtime_cfg::*;
+    ///
+    /// let mut rng = SmallRng::seed_from_u64(42);
+    /// let predicate = all(vec![name("unix"), not(name_value("target_os", "macos"))]);
+    ///
+    /// let flags = predicate.sample_assignment(&mut rng, true).unwrap();
+    /// assert!(predicate.matches(&flags));
+    ///
+    /// let flags = predicate.sample_assignment(&mut rng, false).unwrap();
+    /// assert!(!predicate.matches(&flags));
+    ///
+    /// assert_eq!(Predicate::False.sample_assignment(&mut rng, true), None);
+    /// ```
+    pub fn sample_assignment<R: Rng + ?Sized>(&self, rng: &mut R, want: bool) -> Option<FlagSet> {
+        let flags = self.referenced_flags();
+
+        (0..MAX_ATTEMPTS).find_map(|_| {
+            let mut constraints = Constraints::new();
+
+            if !constrain(self, want, rng, &mut constraints) {
+                return None;
+            }
+
+            let mut by_name = BTreeMap::<&str, Vec<Option<&str>>>::new();
+
+            for &(name, value) in &flags {
+                by_name.entry(name).or_default().push(value);
+            }
+
+            let mut assignment = FlagSet::new();
+
+            for (name, values) in by_name {
+                if SINGLE_VALUED.contains(&name) {
+                    let forced = values
+                        .iter()
+                        .copied()
+                        .flatten()
+                        .find(|&value| constraints.get(&(name, Some(value))) == Some(&true));
+                    let allowed = values
+                        .iter()
+                        .copied()
+                        .flatten()
+                        .filter(|&value| constraints.get(&(name, Some(value))) != Some(&false))
+                        .collect::<Vec<_>>();
+
+                    let value = forced.or_else(|| {
+                        if rng.gen() {
+                            allowed.choose(rng).copied()
+                        } else {
+                            None
+                        }
+                    });
+                    let name_only = match constraints.get(&(name, None)) {
+                        Some(&set) => set,
+                        None => values.contains(&None) && rng.gen(),
+                    };
+
+                    match value {
+                        Some(value) => {
+                            assignment.insert(name, value);
+                        }
+                        None if name_only => assignment.insert_name(name),
+                        None => {}
+                    }
+                } else {
+                    for value in values {
+                        let set = match constraints.get(&(name, value)) {
+                            Some(&set) => set,
+                            None => rng.gen(),
+                        };
+
+                        if set {
+                            match value {
+                                Some(value) => {
+                                    assignment.insert(name, value);
+                                }
+                                None => assignment.insert_name(name),
+                            }
+                        }
+                    }
+                }
+            }
+
+            if self.matches(&assignment) == want {
+                Some(assignment)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Collects the constraints on flags to make the predicate evaluate to `want`,
+/// returns `false` if the constraints are conflicted.
+fn constrain<'a, R: Rng + ?Sized>(
+    predicate: &'a Predicate,
+    want: bool,
+    rng: &mut R,
+    constraints: &mut Constraints<'a>,
+) -> bool {
+    use Predicate::*;
+
+    match predicate {
+        Any(predicates) | All(predicates) => {
+            // `any` is true or `all` is false if one of the sub-predicates is so,
+            // otherwise all the sub-predicates must agree.
+            let one = if let Any(_) = predicate { want } else { !want };
+
+            if one {
+                predicates
+                    .choose(rng)
+                    .is_some_and(|predicate| constrain(predicate, want, rng, constraints))
+            } else {
+                predicates
+                    .iter()
+                    .all(|predicate| constrain(predicate, want, rng, constraints))
+            }
+        }
+        Not(predicate) => constrain(predicate, !want, rng, constraints),
+        Name(name) => *constraints.entry((name, None)).or_insert(want) == want,
+        NameValue(name, value) => *constraints.entry((name, Some(value))).or_insert(want) == want,
+        True => want,
+        False | Param(_) => !want,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use crate::*;
+
+    #[test]
+    fn test_sample_assignment() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let predicates = vec![
+            name("unix"),
+            any(vec![
+                name_value("target_os", "linux"),
+                name_value("target_os", "macos"),
+            ]),
+            all(vec![
+                feature("std"),
+                not(all(vec![feature("alloc"), name("test")])),
+            ]),
+            Predicate::True,
+        ];
+
+        for predicate in predicates {
+            for _ in 0..16 {
+                let flags = predicate.sample_assignment(&mut rng, true).unwrap();
+
+                assert!(predicate.matches(&flags), "{:?} {:?}", predicate, flags);
+                assert!(flags.values("target_os").count() <= 1);
+
+                if let Some(flags) = predicate.sample_assignment(&mut rng, false) {
+                    assert!(!predicate.matches(&flags), "{:?} {:?}", predicate, flags);
+                    assert_ne!(predicate, Predicate::True);
+                }
+            }
+        }
+
+        assert_eq!(
+            all(vec![
+                name_value("target_os", "linux"),
+                name_value("target_os", "macos"),
+            ])
+            .sample_assignment(&mut rng, true),
+            None
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! JSON schema of the serialized configurations.
+
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{Metadata, Schema, SchemaObject, SubschemaValidation},
+    JsonSchema,
+};
+
+use crate::{Cfg, Predicate};
+
+/// A configuration is either a predicate tree, or a `#[cfg(..)]` or `cfg(..)` string.
+impl JsonSchema for Cfg {
+    fn schema_name() -> String {
+        "Cfg".to_owned()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let mut string = gen.subschema_for::<String>().into_object();
+
+        string.metadata = Some(Box::new(Metadata {
+            description: Some("A `#[cfg(..)]` or `cfg(..)` string.".to_owned()),
+            ..Default::default()
+        }));
+
+        SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                description: Some("A configuration predicate.".to_owned()),
+                ..Default::default()
+            })),
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![gen.subschema_for::<Predicate>(), string.into()]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::schema_for;
+    use serde_json::json;
+
+    use crate::*;
+
+    #[test]
+    fn test_schema() {
+        let schema = serde_json::to_value(schema_for!(Cfg)).unwrap();
+
+        assert_eq!(
+            schema["anyOf"][0],
+            json!({ "$ref": "#/definitions/Predicate" })
+        );
+        assert_eq!(schema["anyOf"][1]["type"], "string");
+
+        let variants = schema["definitions"]["Predicate"]["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|variant| {
+                variant["enum"]
+                    .as_array()
+                    .map(|values| {
+                        values
+                            .iter()
+                            .map(|v| v.as_str().unwrap().to_owned())
+                            .collect()
+                    })
+                    .unwrap_or_else(|| {
+                        variant["required"]
+                            .as_array()
+                            .unwrap()
+                            .iter()
+                            .map(|v| v.as_str().unwrap().to_owned())
+                            .collect::<Vec<_>>()
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            variants,
+            vec![
+                "any",
+                "all",
+                "not",
+                "name",
+                "name_value",
+                "true",
+                "false",
+                "param"
+            ]
+        );
+
+        let schema = serde_json::to_value(schema_for!(FlagSet)).unwrap();
+
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["additionalProperties"]["type"], "array");
+    }
+}
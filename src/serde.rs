@@ -0,0 +1,234 @@
+//! Serialization of configurations.
+//!
+//! A predicate is serialized as a tagged tree, like `{"all": [{"name": "unix"}, "true"]}`.
+//!
+//! A configuration is serialized as the tree of its predicate,
+//! and could be deserialized from either the tree or, with the `parsing` feature,
+//! the `#[cfg(..)]` or `cfg(..)` string.
+//!
+//! # Example
+//!
+//! ```
+//! use runtime_cfg::*;
+//!
+//! let cfg = Cfg::from(all(vec![name("unix"), name_value("target_os", "linux")]));
+//! let json = r#"{"all":[{"name":"unix"},{"name_value":["target_os","linux"]}]}"#;
+//!
+//! assert_eq!(serde_json::to_string(&cfg).unwrap(), json);
+//! assert_eq!(serde_json::from_str::<Cfg>(json).unwrap(), cfg);
+//! # #[cfg(feature = "parsing")]
+//! assert_eq!(
+//!     serde_json::from_str::<Cfg>(r#""cfg(all(unix, target_os = \"linux\"))""#).unwrap(),
+//!     cfg
+//! );
+//! ```
+//!
+//! With the `no-panic` feature, a configuration nested deeper than `MAX_NESTING` is rejected in either form.
+//! The recursion of deserializing the tree form is bounded by the format, like the recursion limit of `serde_json`.
+//!
+//! # Results
+//!
+//! The results of the evaluations and lints are serialized for the tools, like the CI systems and editors:
+//!
+//! - an [`Explanation`] as `{"predicate": <tree>, "matched": <bool>, "children": [<explanation>, ..]}`;
+//! - a [`CfgLint`] as `{"kind": <kind>, "message": <string>, "predicate": <tree>}`,
+//!   the kind is the snake case name of the [`LintKind`], like `"double_negation"`;
+//! - a [`RouteExplanation`] as `{"index": <int or null>, "value": <value or null>,
+//!   "rejected": [<explanation>, ..], "fired": <explanation or null>}`;
+//! - an [`UnknownCfg`] as `{"kind": <kind>, "predicate": <tree>}`,
+//!   the kind is the snake case name of the [`UnknownKind`], like `"missing_value"`.
+//!
+//! ```
+//! use runtime_cfg::*;
+//!
+//! let predicate = all(vec![name("unix"), not(not(name("unix")))]);
+//!
+//! assert_eq!(
+//!     serde_json::to_string(&predicate.lint()).unwrap(),
+//!     r#"[{"kind":"double_negation","message":"double negation is redundant","predicate":{"not":{"not":{"name":"unix"}}}}]"#
+//! );
+//! assert_eq!(
+//!     serde_json::to_string(&name("unix").explain(&vec![("unix", None::<&str>)])).unwrap(),
+//!     r#"{"predicate":{"name":"unix"},"matched":true,"children":[]}"#
+//! );
+//! ```
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::string::String;
+    }
+}
+
+use serde::{de, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Cfg, CfgLint, Predicate};
+
+impl Serialize for Cfg {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cfg {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Tree(Predicate),
+            Str(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Tree(predicate) => {
+                #[cfg(feature = "no-panic")]
+                predicate
+                    .check_metrics(&crate::Metrics {
+                        depth: crate::MAX_NESTING,
+                        ..crate::Metrics::UNLIMITED
+                    })
+                    .map_err(de::Error::custom)?;
+
+                Ok(Cfg(predicate))
+            }
+            Repr::Str(s) => parse_str(&s),
+        }
+    }
+}
+
+impl Serialize for CfgLint<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut lint = serializer.serialize_struct("CfgLint", 3)?;
+
+        lint.serialize_field("kind", &self.kind)?;
+        lint.serialize_field("message", self.kind.message())?;
+        lint.serialize_field("predicate", self.predicate)?;
+        lint.end()
+    }
+}
+
+#[cfg(feature = "parsing")]
+fn parse_str<E: de::Error>(s: &str) -> Result<Cfg, E> {
+    Cfg::parse(s).map_err(E::custom)
+}
+
+#[cfg(not(feature = "parsing"))]
+fn parse_str<E: de::Error>(s: &str) -> Result<Cfg, E> {
+    Err(E::invalid_value(
+        de::Unexpected::Str(s),
+        &"a predicate tree, parsing feature is required for the string form",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_serde() {
+        let predicate = any(vec![
+            not(name("test")),
+            Predicate::True,
+            Predicate::False,
+            param("region"),
+        ]);
+        let json = r#"{"any":[{"not":{"name":"test"}},"true","false",{"param":"region"}]}"#;
+
+        assert_eq!(serde_json::to_string(&predicate).unwrap(), json);
+        assert_eq!(serde_json::from_str::<Predicate>(json).unwrap(), predicate);
+        assert_eq!(
+            serde_json::from_str::<Cfg>(json).unwrap(),
+            Cfg::from(predicate)
+        );
+        assert_eq!(
+            serde_json::from_str::<Cfg>(r#""true""#).unwrap(),
+            Cfg::always()
+        );
+        assert!(serde_json::from_str::<Cfg>(r#"{"unknown":"test"}"#).is_err());
+    }
+
+    #[cfg(feature = "parsing")]
+    #[test]
+    fn test_deserialize_str() {
+        let cfg = Cfg::from(all(vec![name("unix"), not(name("test"))]));
+
+        assert_eq!(
+            serde_json::from_str::<Cfg>(r##""#[cfg(all(unix, not(test)))]""##).unwrap(),
+            cfg
+        );
+        assert_eq!(
+            serde_json::from_str::<Cfg>(r#""cfg(all(unix, not(test)))""#).unwrap(),
+            cfg
+        );
+        assert!(serde_json::from_str::<Cfg>(r#""all(unix""#).is_err());
+    }
+
+    #[test]
+    fn test_results() {
+        let mut router = CfgRouter::new();
+
+        router
+            .push(Cfg::from(name("windows")), "win")
+            .push(Cfg::from(name("unix")), "unix");
+
+        let explanation = router.explain_route(&vec![("unix", None::<&str>)]);
+
+        assert_eq!(
+            serde_json::to_value(&explanation).unwrap(),
+            serde_json::json!({
+                "index": 1,
+                "value": "unix",
+                "rejected": [{"predicate": {"name": "windows"}, "matched": false, "children": []}],
+                "fired": {"predicate": {"name": "unix"}, "matched": true, "children": []},
+            })
+        );
+        assert_eq!(
+            serde_json::to_string(&LintKind::EmptyAny).unwrap(),
+            r#""empty_any""#
+        );
+        assert_eq!(
+            serde_json::from_str::<LintKind>(r#""single_child""#).unwrap(),
+            LintKind::SingleChild
+        );
+
+        let predicate = all(vec![name("unix"), name_value("target_os", "linx")]);
+
+        assert_eq!(
+            serde_json::to_value(predicate.check_known(&KnownCfgs::rustc())).unwrap(),
+            serde_json::json!([{
+                "kind": "value",
+                "predicate": {"name_value": ["target_os", "linx"]},
+            }])
+        );
+        assert_eq!(
+            serde_json::from_str::<UnknownKind>(r#""missing_value""#).unwrap(),
+            UnknownKind::MissingValue
+        );
+    }
+
+    #[test]
+    fn test_flag_set() {
+        let flags: FlagSet = vec![("unix", None), ("feature", Some("std"))]
+            .into_iter()
+            .collect();
+        let json = r#"{"feature":["std"],"unix":[]}"#;
+
+        assert_eq!(serde_json::to_string(&flags).unwrap(), json);
+        assert_eq!(serde_json::from_str::<FlagSet>(json).unwrap(), flags);
+    }
+
+    #[cfg(feature = "no-panic")]
+    #[test]
+    fn test_deserialize_too_deep() {
+        let nested = |n| r#"{"not":"#.repeat(n) + r#"{"name":"unix"}"# + &"}".repeat(n);
+
+        assert!(serde_json::from_str::<Cfg>(&nested(MAX_NESTING - 1)).is_ok());
+        assert!(serde_json::from_str::<Cfg>(&nested(MAX_NESTING)).is_err());
+        assert!(serde_json::from_str::<Cfg>(&nested(100_000)).is_err());
+    }
+}
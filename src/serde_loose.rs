@@ -0,0 +1,251 @@
+//! Tolerant deserialization of the flags from the loosely typed data, like the JSON objects from the web dashboards.
+//!
+//! The data must be a map from the flag names, the values are coerced as:
+//!
+//! - a string sets the flag with the value, `"log": "info"` as `log = "info"`;
+//! - a number sets the flag with the value in decimal, `"level": 3` as `level = "3"`;
+//! - `true` sets the flag without value, `"unix": true` as `unix`;
+//! - `false` and `null` leave the flag unset;
+//! - an array sets the flag with each element as a value, the strings and numbers as above,
+//!   the booleans as `"true"` or `"false"`, and the `null` elements are skipped,
+//!   an empty array sets the flag without value.
+//!
+//! The nested arrays and maps are rejected.
+//!
+//! # Example
+//!
+//! ```
+//! use runtime_cfg::*;
+//! use serde_json::json;
+//!
+//! let value = json!({
+//!     "unix": true,
+//!     "debug": false,
+//!     "target_os": "linux",
+//!     "level": 3,
+//!     "feature": ["ssl", "gzip", null],
+//!     "region": null,
+//! });
+//! let flags = serde_loose::deserialize(value).unwrap();
+//!
+//! assert!(all(vec![
+//!     name("unix"),
+//!     name_value("target_os", "linux"),
+//!     name_value("level", "3"),
+//!     feature("ssl"),
+//!     feature("gzip"),
+//! ])
+//! .matches(&flags));
+//! assert!(!any(vec![name("debug"), name("region")]).matches(&flags));
+//! ```
+//!
+//! Use it with `#[serde(deserialize_with = "runtime_cfg::serde_loose::deserialize")]` on a `FlagSet` field.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::string::{String, ToString};
+        use alloc::vec;
+        use alloc::vec::Vec;
+    }
+}
+
+use core::fmt;
+
+use ::serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::FlagSet;
+
+/// Deserializes the flags from a loosely typed map.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<FlagSet, D::Error> {
+    deserializer.deserialize_map(FlagsVisitor)
+}
+
+struct FlagsVisitor;
+
+impl<'de> Visitor<'de> for FlagsVisitor {
+    type Value = FlagSet;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of flags")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut flags = FlagSet::new();
+
+        while let Some((name, Flag(values))) = map.next_entry::<String, Flag>()? {
+            match values {
+                Some(values) if values.is_empty() => flags.insert_name(name),
+                Some(values) => {
+                    for value in values {
+                        flags.insert(name.as_str(), value);
+                    }
+                }
+                None => {}
+            }
+        }
+
+        Ok(flags)
+    }
+}
+
+/// The values of a flag, or `None` if the flag is unset.
+struct Flag(Option<Vec<String>>);
+
+impl<'de> Deserialize<'de> for Flag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(FlagVisitor).map(Flag)
+    }
+}
+
+struct FlagVisitor;
+
+impl<'de> Visitor<'de> for FlagVisitor {
+    type Value = Option<Vec<String>>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string, number, boolean, null or array")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(if v { Some(Vec::new()) } else { None })
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(Some(vec![v.to_string()]))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(Some(vec![v.to_string()]))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(Some(vec![v.to_string()]))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(Some(vec![v.to_string()]))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut values = Vec::new();
+
+        while let Some(Scalar(value)) = seq.next_element()? {
+            values.extend(value);
+        }
+
+        Ok(Some(values))
+    }
+}
+
+/// An element of the array, or `None` if it is `null`.
+struct Scalar(Option<String>);
+
+impl<'de> Deserialize<'de> for Scalar {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ScalarVisitor).map(Scalar)
+    }
+}
+
+struct ScalarVisitor;
+
+impl<'de> Visitor<'de> for ScalarVisitor {
+    type Value = Option<String>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string, number, boolean or null")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::string::String;
+            use alloc::vec;
+        }
+    }
+
+    use ::serde::Deserialize;
+    use serde_json::{json, Value};
+
+    use crate::*;
+
+    #[test]
+    fn test_deserialize() {
+        let map: serde_json::Map<String, Value> = serde_json::from_str(
+            r#"{"unix": true, "windows": false, "ratio": 0.5, "offset": -1, "feature": [], "tags": [true, 2, "x", null]}"#,
+        )
+        .unwrap();
+        let flags = serde_loose::deserialize(Value::Object(map)).unwrap();
+        let expected: FlagSet = vec![
+            ("unix", None),
+            ("ratio", Some("0.5")),
+            ("offset", Some("-1")),
+            ("feature", None),
+            ("tags", Some("true")),
+            ("tags", Some("2")),
+            ("tags", Some("x")),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(flags, expected);
+        assert!(serde_loose::deserialize(json!({"nested": {"a": 1}})).is_err());
+        assert!(serde_loose::deserialize(json!({"nested": [[1]]})).is_err());
+        assert!(serde_loose::deserialize(json!(["unix"])).is_err());
+
+        #[derive(Deserialize)]
+        struct Config {
+            #[serde(deserialize_with = "crate::serde_loose::deserialize")]
+            flags: FlagSet,
+        }
+
+        let config: Config = serde_json::from_str(r#"{"flags": {"log": "debug"}}"#).unwrap();
+
+        assert!(config.flags.has_value("log", "debug"));
+    }
+}
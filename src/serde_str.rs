@@ -0,0 +1,88 @@
+//! Serialization of configurations in the string form, like `cfg(all(unix, feature = "ssl"))`.
+//!
+//! The string form is more readable than the predicate tree for the hand-edited configuration files,
+//! use it with `#[serde(with = "runtime_cfg::serde_str")]` on a field.
+//!
+//! # Example
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//!
+//! use runtime_cfg::{all, feature, name, Cfg};
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Plugin {
+//!     name: String,
+//!     #[serde(with = "runtime_cfg::serde_str")]
+//!     cfg: Cfg,
+//! }
+//!
+//! let plugin = Plugin {
+//!     name: "tls".to_owned(),
+//!     cfg: Cfg::from(all(vec![name("unix"), feature("ssl")])),
+//! };
+//! let json = r#"{"name":"tls","cfg":"cfg(all(unix, feature = \"ssl\"))"}"#;
+//!
+//! assert_eq!(serde_json::to_string(&plugin).unwrap(), json);
+//! assert_eq!(serde_json::from_str::<Plugin>(json).unwrap(), plugin);
+//! ```
+
+use ::serde::{de, Deserialize, Deserializer, Serializer};
+
+use crate::Cfg;
+
+/// Serializes the configuration as the `cfg(..)` string.
+pub fn serialize<S: Serializer>(cfg: &Cfg, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_args!("cfg({})", cfg.0))
+}
+
+/// Deserializes the configuration from the `cfg(..)` or `#[cfg(..)]` string.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Cfg, D::Error> {
+    let s = String::deserialize(deserializer)?;
+
+    Cfg::parse(&s).map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use ::serde::{Deserialize, Serialize};
+
+    use crate::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Rule {
+        #[serde(with = "crate::serde_str")]
+        cfg: Cfg,
+    }
+
+    #[test]
+    fn test_serde_str() {
+        let rule = Rule {
+            cfg: Cfg::from(any(vec![
+                not(name("test")),
+                name_value("target_os", "linux"),
+            ])),
+        };
+        let json = r#"{"cfg":"cfg(any(not(test), target_os = \"linux\"))"}"#;
+
+        assert_eq!(serde_json::to_string(&rule).unwrap(), json);
+        assert_eq!(serde_json::from_str::<Rule>(json).unwrap(), rule);
+        assert_eq!(
+            serde_json::from_str::<Rule>(
+                r##"{"cfg":"#[cfg(any(not(test), target_os = \"linux\"))]"}"##
+            )
+            .unwrap(),
+            rule
+        );
+        assert!(serde_json::from_str::<Rule>(r#"{"cfg":"any(unix"}"#).is_err());
+        assert!(serde_json::from_str::<Rule>(r#"{"cfg":{"name":"unix"}}"#).is_err());
+    }
+
+    #[cfg(feature = "no-panic")]
+    #[test]
+    fn test_deserialize_too_deep() {
+        let json = r#"{"cfg":"cfg("#.to_owned() + &"not(".repeat(100_000) + r#"unix"}"#;
+
+        assert!(serde_json::from_str::<Rule>(&json).is_err());
+    }
+}
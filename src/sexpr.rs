@@ -0,0 +1,397 @@
+//! S-expression form of predicates, like `(all unix (= target_os "linux"))`.
+//!
+//! | Predicate          | S-expression            |
+//! |--------------------|-------------------------|
+//! | `any(a, b)`        | `(any a b)`             |
+//! | `all(a, b)`        | `(all a b)`             |
+//! | `not(a)`           | `(not a)`               |
+//! | `name`             | `name`                  |
+//! | `name = "value"`   | `(= name "value")`      |
+//! | `True` / `False`   | `(all)` / `(any)`       |
+//! | `param(name)`      | `(param name)`          |
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::boxed::Box;
+        use alloc::string::{String, ToString};
+        use alloc::vec::Vec;
+    }
+}
+
+use core::fmt;
+
+use crate::{escape::Quoted, Predicate};
+
+/// Displays a predicate in the s-expression form.
+#[derive(Debug, Clone, Copy)]
+pub struct Sexpr<'a>(&'a Predicate);
+
+impl fmt::Display for Sexpr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Predicate::*;
+
+        match self.0 {
+            Any(predicates) | All(predicates) => {
+                f.write_str(if let Any(_) = self.0 { "(any" } else { "(all" })?;
+                for predicate in predicates {
+                    write!(f, " {}", Sexpr(predicate))?;
+                }
+                f.write_str(")")
+            }
+            Not(predicate) => write!(f, "(not {})", Sexpr(predicate)),
+            Name(name) => f.write_str(name),
+            NameValue(name, value) => write!(f, "(= {} {})", name, Quoted(value)),
+            True => f.write_str("(all)"),
+            False => f.write_str("(any)"),
+            Param(name) => write!(f, "(param {})", name),
+        }
+    }
+}
+
+/// An error when parsing an s-expression.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SexprError {
+    /// The byte offset of the error.
+    pub offset: usize,
+    /// The description of the error.
+    pub message: &'static str,
+}
+
+impl fmt::Display for SexprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl core::error::Error for SexprError {}
+
+impl Predicate {
+    /// Returns a wrapper to display the predicate in the s-expression form.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let predicate = all(vec![name("unix"), name_value("target_os", "linux")]);
+    /// let sexpr = predicate.sexpr().to_string();
+    ///
+    /// assert_eq!(sexpr, r#"(all unix (= target_os "linux"))"#);
+    /// assert_eq!(Predicate::from_sexpr(&sexpr), Ok(predicate));
+    /// ```
+    pub fn sexpr(&self) -> Sexpr<'_> {
+        Sexpr(self)
+    }
+
+    /// Parses a predicate from the s-expression form.
+    pub fn from_sexpr(s: &str) -> Result<Predicate, SexprError> {
+        let mut parser = Parser {
+            s,
+            off: 0,
+            depth: 0,
+        };
+        let predicate = parser.predicate()?;
+
+        parser.skip_whitespace();
+
+        if parser.off < s.len() {
+            Err(parser.error("unexpected trailing characters"))
+        } else {
+            Ok(predicate)
+        }
+    }
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    off: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &'static str) -> SexprError {
+        SexprError {
+            offset: self.off,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.s[self.off..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+
+        self.off += c.len_utf8();
+
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, expected: char, message: &'static str) -> Result<(), SexprError> {
+        self.skip_whitespace();
+
+        if self.peek() == Some(expected) {
+            self.bump();
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    fn ident(&mut self) -> Result<&'a str, SexprError> {
+        self.skip_whitespace();
+
+        let start = self.off;
+
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        {
+            self.bump();
+        }
+
+        if start == self.off {
+            Err(self.error("expect an identifier"))
+        } else {
+            Ok(&self.s[start..self.off])
+        }
+    }
+
+    fn string(&mut self) -> Result<String, SexprError> {
+        self.expect('"', "expect a string")?;
+
+        let mut value = String::new();
+
+        loop {
+            match self
+                .bump()
+                .ok_or_else(|| self.error("unterminated string"))?
+            {
+                '"' => return Ok(value),
+                '\\' => {
+                    let c = match self.bump() {
+                        Some('"') => '"',
+                        Some('\'') => '\'',
+                        Some('\\') => '\\',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('0') => '\0',
+                        Some('u') => self.unicode()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+
+                    value.push(c);
+                }
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn unicode(&mut self) -> Result<char, SexprError> {
+        self.expect('{', "expect `{` of unicode escape")?;
+
+        let start = self.off;
+
+        while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+            self.bump();
+        }
+
+        let c = u32::from_str_radix(&self.s[start..self.off], 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+
+        self.expect('}', "expect `}` of unicode escape")?;
+
+        Ok(c)
+    }
+
+    fn predicate(&mut self) -> Result<Predicate, SexprError> {
+        self.skip_whitespace();
+
+        #[cfg(feature = "no-panic")]
+        {
+            if self.depth >= crate::MAX_NESTING {
+                return Err(self.error("nesting too deep"));
+            }
+        }
+
+        self.depth += 1;
+
+        let predicate = self.operand();
+
+        self.depth -= 1;
+
+        predicate
+    }
+
+    fn operand(&mut self) -> Result<Predicate, SexprError> {
+        if self.peek() != Some('(') {
+            return self.ident().map(|name| Predicate::Name(name.to_string()));
+        }
+
+        self.bump();
+        self.skip_whitespace();
+
+        let predicate = if self.peek() == Some('=') {
+            self.bump();
+
+            let name = self.ident()?.to_string();
+            let value = self.string()?;
+
+            Predicate::NameValue(name, value)
+        } else {
+            let off = self.off;
+
+            match self.ident()? {
+                op @ "any" | op @ "all" => {
+                    let mut predicates = Vec::new();
+
+                    loop {
+                        self.skip_whitespace();
+
+                        if self.peek() == Some(')') || self.peek().is_none() {
+                            break;
+                        }
+
+                        predicates.push(Box::new(self.predicate()?));
+                    }
+
+                    match (op, predicates.is_empty()) {
+                        ("any", true) => Predicate::False,
+                        ("all", true) => Predicate::True,
+                        ("any", false) => Predicate::Any(predicates),
+                        _ => Predicate::All(predicates),
+                    }
+                }
+                "not" => Predicate::Not(Box::new(self.predicate()?)),
+                "param" => Predicate::Param(self.ident()?.to_string()),
+                _ => {
+                    return Err(SexprError {
+                        offset: off,
+                        message: "unexpected operator",
+                    })
+                }
+            }
+        };
+
+        self.expect(')', "expect `)`")?;
+
+        Ok(predicate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::string::ToString;
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let predicates = vec![
+            name("unix"),
+            any(vec![
+                not(name_value("target_env", "with \"quotes\" and \\")),
+                param("region"),
+                name_value("foo", "new\nline \u{301}"),
+            ]),
+            Predicate::True,
+            Predicate::False,
+        ];
+
+        for predicate in predicates {
+            let sexpr = predicate.sexpr().to_string();
+
+            assert_eq!(Predicate::from_sexpr(&sexpr), Ok(predicate), "{}", sexpr);
+        }
+    }
+
+    #[test]
+    fn test_from_sexpr() {
+        assert_eq!(
+            Predicate::from_sexpr(" ( all\n unix ( not (= feature \"std\" ) ) ) "),
+            Ok(all(vec![name("unix"), not(feature("std"))]))
+        );
+
+        let errors = vec![
+            (
+                "(all unix",
+                SexprError {
+                    offset: 9,
+                    message: "expect `)`",
+                },
+            ),
+            (
+                "(foo unix)",
+                SexprError {
+                    offset: 1,
+                    message: "unexpected operator",
+                },
+            ),
+            (
+                "(= foo bar)",
+                SexprError {
+                    offset: 7,
+                    message: "expect a string",
+                },
+            ),
+            (
+                "(= foo \"bar)",
+                SexprError {
+                    offset: 12,
+                    message: "unterminated string",
+                },
+            ),
+            (
+                "unix unix",
+                SexprError {
+                    offset: 5,
+                    message: "unexpected trailing characters",
+                },
+            ),
+            (
+                "(not)",
+                SexprError {
+                    offset: 4,
+                    message: "expect an identifier",
+                },
+            ),
+        ];
+
+        for (s, err) in errors {
+            assert_eq!(Predicate::from_sexpr(s), Err(err), "{}", s);
+        }
+    }
+
+    #[cfg(feature = "no-panic")]
+    #[test]
+    fn test_from_sexpr_too_deep() {
+        let nested = |n| "(not ".repeat(n) + "unix" + &")".repeat(n);
+
+        assert!(Predicate::from_sexpr(&nested(MAX_NESTING - 1)).is_ok());
+        assert_eq!(
+            Predicate::from_sexpr(&nested(MAX_NESTING)),
+            Err(SexprError {
+                offset: MAX_NESTING * 5,
+                message: "nesting too deep",
+            })
+        );
+        assert!(Predicate::from_sexpr(&"(all ".repeat(100_000)).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! A shared configuration, cloned cheaply across threads.
+
+cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::sync::Arc;
+    } else {
+        use alloc::sync::Arc;
+    }
+}
+
+use core::ops::Deref;
+
+use crate::{Cfg, Predicate};
+
+/// A configuration shared by reference counting, cloning it doesn't copy the predicate tree.
+///
+/// The predicate is copied on write, only when it is shared with the other clones.
+///
+/// # Example
+///
+/// ```
+/// use std::thread;
+///
+/// use runtime_cfg::*;
+///
+/// let cfg = SharedCfg::from(all(vec![name("unix"), feature("beta")]));
+///
+/// let workers = (0..4)
+///     .map(|_| {
+///         let cfg = cfg.clone();
+///
+///         thread::spawn(move || cfg.matches(&vec![("unix", None), ("feature", Some("beta"))]))
+///     })
+///     .collect::<Vec<_>>();
+///
+/// assert!(workers.into_iter().all(|worker| worker.join().unwrap()));
+///
+/// let mut local = cfg.clone();
+///
+/// *local.make_mut() = name("windows");
+///
+/// assert!(!SharedCfg::ptr_eq(&cfg, &local));
+/// assert_eq!(*cfg, all(vec![name("unix"), feature("beta")]));
+/// assert_eq!(*local, name("windows"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SharedCfg(Arc<Predicate>);
+
+impl SharedCfg {
+    /// Creates a shared configuration.
+    pub fn new(cfg: Cfg) -> Self {
+        SharedCfg(Arc::new(cfg.0))
+    }
+
+    /// Returns a mutable predicate, which is cloned first if it is shared with the other clones.
+    pub fn make_mut(&mut self) -> &mut Predicate {
+        Arc::make_mut(&mut self.0)
+    }
+
+    /// Returns a mutable predicate if it isn't shared with the other clones.
+    pub fn get_mut(&mut self) -> Option<&mut Predicate> {
+        Arc::get_mut(&mut self.0)
+    }
+
+    /// Returns `true` if the two configurations share the same predicate.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+
+    /// Returns the number of the clones sharing the predicate.
+    pub fn share_count(this: &Self) -> usize {
+        Arc::strong_count(&this.0)
+    }
+
+    /// Converts to an owned configuration, which is cloned only if it is shared.
+    pub fn into_cfg(self) -> Cfg {
+        Cfg(Arc::try_unwrap(self.0).unwrap_or_else(|predicate| (*predicate).clone()))
+    }
+}
+
+impl Deref for SharedCfg {
+    type Target = Predicate;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<Predicate> for SharedCfg {
+    fn as_ref(&self) -> &Predicate {
+        &self.0
+    }
+}
+
+impl From<Cfg> for SharedCfg {
+    fn from(cfg: Cfg) -> Self {
+        SharedCfg::new(cfg)
+    }
+}
+
+impl From<Predicate> for SharedCfg {
+    fn from(predicate: Predicate) -> Self {
+        SharedCfg(Arc::new(predicate))
+    }
+}
+
+impl From<SharedCfg> for Cfg {
+    fn from(cfg: SharedCfg) -> Self {
+        cfg.into_cfg()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_make_mut() {
+        let mut cfg = SharedCfg::from(Cfg::from(any(vec![name("unix"), name("windows")])));
+        let shared = cfg.clone();
+
+        assert!(SharedCfg::ptr_eq(&cfg, &shared));
+        assert_eq!(SharedCfg::share_count(&cfg), 2);
+        assert!(cfg.get_mut().is_none());
+
+        if let Predicate::Any(predicates) = cfg.make_mut() {
+            predicates.pop();
+        }
+
+        assert!(!SharedCfg::ptr_eq(&cfg, &shared));
+        assert_eq!(SharedCfg::share_count(&shared), 1);
+        assert_eq!(*cfg, any(vec![name("unix")]));
+        assert_eq!(*shared, any(vec![name("unix"), name("windows")]));
+
+        *cfg.get_mut().unwrap() = name("wasm");
+
+        assert_eq!(cfg.into_cfg(), Cfg::from(name("wasm")));
+        assert_eq!(
+            Cfg::from(shared),
+            Cfg::from(any(vec![name("unix"), name("windows")]))
+        );
+    }
+}
@@ -0,0 +1,266 @@
+//! Boolean simplification of a predicate tree.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::boxed::Box;
+        use alloc::vec::Vec;
+    }
+}
+
+use crate::{fold::Fold, Predicate};
+
+impl Predicate {
+    /// Returns a logically equivalent predicate, simplified with the boolean laws.
+    ///
+    /// - the constants are folded, `not(all())` is `any()` and `any(x, all())` is `all()`;
+    /// - the double negations are removed, `not(not(x))` is `x`;
+    /// - the nested `any` or `all` are flattened into their parent of the same kind;
+    /// - the duplicated sub-predicates are removed, keeping the first one;
+    /// - the complements are folded, `any(x, not(x))` is `all()` and `all(x, not(x))` is `any()`;
+    /// - `any` or `all` with a single sub-predicate is replaced by the sub-predicate.
+    ///
+    /// Unlike [`Predicate::canonicalize`], the order of the sub-predicates is preserved.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let predicate = all(vec![
+    ///     name("unix"),
+    ///     not(not(name("unix"))),
+    ///     any(vec![Predicate::False, name_value("target_os", "linux")]),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     predicate.simplify(),
+    ///     all(vec![name("unix"), name_value("target_os", "linux")])
+    /// );
+    /// ```
+    pub fn simplify(&self) -> Predicate {
+        self.clone().fold(&mut Simplify)
+    }
+
+    /// Returns the top-level factors of the simplified predicate, which matches when all of them match.
+    ///
+    /// The negated `any` is split with De Morgan's laws, `not(any(a, b))` as `[not(a), not(b)]`;
+    /// `all()` has no factor, and a predicate without top-level `all` is the single factor.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// let predicate = all(vec![
+    ///     name("unix"),
+    ///     any(vec![feature("ssl"), feature("tls")]),
+    ///     not(any(vec![name("test"), name("unix")])),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     predicate.conjuncts(),
+    ///     vec![name("unix"), any(vec![feature("ssl"), feature("tls")]), not(name("test")), not(name("unix"))]
+    /// );
+    /// ```
+    pub fn conjuncts(&self) -> Vec<Predicate> {
+        let mut factors = Vec::new();
+
+        split(self.simplify(), true, &mut factors);
+
+        factors
+    }
+
+    /// Returns the top-level terms of the simplified predicate, which matches when any of them matches.
+    ///
+    /// The negated `all` is split with De Morgan's laws, `not(all(a, b))` as `[not(a), not(b)]`;
+    /// `any()` has no term, and a predicate without top-level `any` is the single term.
+    pub fn disjuncts(&self) -> Vec<Predicate> {
+        let mut terms = Vec::new();
+
+        split(self.simplify(), false, &mut terms);
+
+        terms
+    }
+}
+
+/// Splits the predicate into the factors of `all` when `conjunction`, otherwise the terms of `any`.
+fn split(predicate: Predicate, conjunction: bool, parts: &mut Vec<Predicate>) {
+    match predicate {
+        Predicate::All(predicates) if conjunction => {
+            for predicate in predicates {
+                split(*predicate, conjunction, parts)
+            }
+        }
+        Predicate::Any(predicates) if !conjunction => {
+            for predicate in predicates {
+                split(*predicate, conjunction, parts)
+            }
+        }
+        Predicate::Not(predicate) => match *predicate {
+            Predicate::Any(predicates) if conjunction => {
+                for predicate in predicates {
+                    split(Predicate::Not(predicate).simplify(), conjunction, parts)
+                }
+            }
+            Predicate::All(predicates) if !conjunction => {
+                for predicate in predicates {
+                    split(Predicate::Not(predicate).simplify(), conjunction, parts)
+                }
+            }
+            predicate => push_unique(parts, Predicate::Not(Box::new(predicate))),
+        },
+        Predicate::True if conjunction => {}
+        Predicate::False if !conjunction => {}
+        predicate => push_unique(parts, predicate),
+    }
+}
+
+fn push_unique(parts: &mut Vec<Predicate>, predicate: Predicate) {
+    if !parts.contains(&predicate) {
+        parts.push(predicate)
+    }
+}
+
+struct Simplify;
+
+impl Fold for Simplify {
+    fn fold_any(&mut self, predicates: Vec<Box<Predicate>>) -> Predicate {
+        self.fold_children(predicates, false)
+    }
+
+    fn fold_all(&mut self, predicates: Vec<Box<Predicate>>) -> Predicate {
+        self.fold_children(predicates, true)
+    }
+
+    fn fold_not(&mut self, predicate: Predicate) -> Predicate {
+        match self.fold_predicate(predicate) {
+            Predicate::True => Predicate::False,
+            Predicate::False => Predicate::True,
+            Predicate::Not(predicate) => *predicate,
+            predicate => Predicate::Not(Box::new(predicate)),
+        }
+    }
+}
+
+impl Simplify {
+    /// Folds the sub-predicates of `all` when `conjunction`, otherwise the sub-predicates of `any`.
+    #[allow(clippy::vec_box)]
+    fn fold_children(&mut self, predicates: Vec<Box<Predicate>>, conjunction: bool) -> Predicate {
+        let (identity, absorbing) = if conjunction {
+            (Predicate::True, Predicate::False)
+        } else {
+            (Predicate::False, Predicate::True)
+        };
+
+        let mut children: Vec<Box<Predicate>> = Vec::with_capacity(predicates.len());
+        let mut pending = predicates
+            .into_iter()
+            .rev()
+            .map(|predicate| self.fold_predicate(*predicate))
+            .collect::<Vec<_>>();
+
+        while let Some(predicate) = pending.pop() {
+            match predicate {
+                Predicate::All(predicates) if conjunction => {
+                    pending.extend(predicates.into_iter().rev().map(|predicate| *predicate))
+                }
+                Predicate::Any(predicates) if !conjunction => {
+                    pending.extend(predicates.into_iter().rev().map(|predicate| *predicate))
+                }
+                ref predicate if *predicate == identity => {}
+                ref predicate if *predicate == absorbing => return absorbing,
+                predicate => {
+                    let complemented = children.iter().any(|child| match (&**child, &predicate) {
+                        (Predicate::Not(lhs), rhs) | (rhs, Predicate::Not(lhs)) => **lhs == *rhs,
+                        _ => false,
+                    });
+
+                    if complemented {
+                        return absorbing;
+                    }
+
+                    if !children.iter().any(|child| **child == predicate) {
+                        children.push(Box::new(predicate));
+                    }
+                }
+            }
+        }
+
+        match children.len() {
+            0 => identity,
+            1 => *children.pop().unwrap(),
+            _ if conjunction => Predicate::All(children),
+            _ => Predicate::Any(children),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_simplify() {
+        assert_eq!(not(Predicate::True).simplify(), Predicate::False);
+        assert_eq!(not(not(name("foo"))).simplify(), name("foo"));
+        assert_eq!(any(vec![]).simplify(), Predicate::False);
+        assert_eq!(all(vec![]).simplify(), Predicate::True);
+        assert_eq!(all(vec![name("foo")]).simplify(), name("foo"));
+        assert_eq!(
+            any(vec![name("foo"), Predicate::True]).simplify(),
+            Predicate::True
+        );
+        assert_eq!(
+            all(vec![name("foo"), not(name("foo"))]).simplify(),
+            Predicate::False
+        );
+        assert_eq!(
+            any(vec![not(name("foo")), name("bar"), name("foo")]).simplify(),
+            Predicate::True
+        );
+        assert_eq!(
+            any(vec![
+                name("foo"),
+                any(vec![name("bar"), all(vec![name("baz")])]),
+                name("foo"),
+                Predicate::False,
+            ])
+            .simplify(),
+            any(vec![name("foo"), name("bar"), name("baz")])
+        );
+    }
+
+    #[test]
+    fn test_conjuncts() {
+        let predicate = all(vec![
+            name("a"),
+            all(vec![any(vec![name("b"), name("c")]), name("a")]),
+            not(any(vec![not(name("d")), all(vec![name("e"), name("f")])])),
+        ]);
+
+        assert_eq!(
+            predicate.conjuncts(),
+            vec![
+                name("a"),
+                any(vec![name("b"), name("c")]),
+                name("d"),
+                not(all(vec![name("e"), name("f")])),
+            ]
+        );
+        assert_eq!(predicate.disjuncts(), vec![predicate.simplify()]);
+        assert_eq!(
+            not(all(vec![name("a"), not(name("b"))])).disjuncts(),
+            vec![not(name("a")), name("b")]
+        );
+        assert!(Predicate::True.conjuncts().is_empty());
+        assert!(any(vec![]).disjuncts().is_empty());
+        assert_eq!(Predicate::False.conjuncts(), vec![Predicate::False]);
+        assert_eq!(name("a").disjuncts(), vec![name("a")]);
+    }
+}
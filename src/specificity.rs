@@ -0,0 +1,146 @@
+//! Specificity of a predicate, ordering the predicates from the generic to the specific.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::boxed::Box;
+    }
+}
+
+use core::cmp::{self, Ordering};
+
+use crate::Predicate;
+
+/// The specificity of a predicate, a more specific predicate constrains more flags.
+///
+/// # Ordering
+///
+/// The specificities are compared by `required` first, then by `maximum`.
+///
+/// - `required` is the minimum number of flag conditions decided when the predicate matches,
+///   each sub-predicate of `all` counts, while only the least constrained one of `any` counts.
+/// - `maximum` is the maximum number of flag conditions decided when the predicate matches,
+///   only the most constrained sub-predicate of `any` counts, to break the ties.
+///
+/// The conditions under `not` count the same way, with `any` and `all` swapped.
+///
+/// This ordering is stable, and is used to choose the best match of a `CfgMap`.
+///
+/// # Example
+///
+/// ```
+/// use std::cmp::Ordering;
+///
+/// use runtime_cfg::*;
+///
+/// let generic = Predicate::True;
+/// let os = name_value("target_os", "linux");
+/// let arch = all(vec![name_value("target_os", "linux"), name_value("target_arch", "x86_64")]);
+///
+/// assert!(os.is_more_specific_than(&generic));
+/// assert!(arch.is_more_specific_than(&os));
+/// assert_eq!(arch.specificity(), Specificity { required: 2, maximum: 2 });
+///
+/// let either = any(vec![name_value("target_os", "linux"), name_value("target_os", "macos")]);
+///
+/// assert_eq!(either.specificity(), Specificity { required: 1, maximum: 1 });
+/// assert_eq!(either.cmp_specificity(&os), Ordering::Equal);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Specificity {
+    /// The minimum number of flag conditions decided when the predicate matches.
+    pub required: usize,
+    /// The maximum number of flag conditions decided when the predicate matches.
+    pub maximum: usize,
+}
+
+impl Predicate {
+    /// Returns the specificity of the predicate.
+    pub fn specificity(&self) -> Specificity {
+        Specificity {
+            required: count(self, false, cmp::min),
+            maximum: count(self, false, cmp::max),
+        }
+    }
+
+    /// Compares the specificities of the predicates, the more specific one is greater.
+    pub fn cmp_specificity(&self, other: &Predicate) -> Ordering {
+        self.specificity().cmp(&other.specificity())
+    }
+
+    /// Returns `true` if the predicate is strictly more specific than the other one.
+    pub fn is_more_specific_than(&self, other: &Predicate) -> bool {
+        self.cmp_specificity(other) == Ordering::Greater
+    }
+}
+
+/// Counts the conditions, `pick` chooses a sub-predicate of `any`, or of `all` when negated.
+fn count(predicate: &Predicate, negated: bool, pick: fn(usize, usize) -> usize) -> usize {
+    match predicate {
+        Predicate::Any(predicates) if negated => counts(predicates, negated, pick).sum(),
+        Predicate::All(predicates) if !negated => counts(predicates, negated, pick).sum(),
+        Predicate::Any(predicates) | Predicate::All(predicates) => {
+            counts(predicates, negated, pick).reduce(pick).unwrap_or(0)
+        }
+        Predicate::Not(predicate) => count(predicate, !negated, pick),
+        Predicate::Name(_) | Predicate::NameValue(_, _) => 1,
+        Predicate::True | Predicate::False | Predicate::Param(_) => 0,
+    }
+}
+
+fn counts(
+    predicates: &[Box<Predicate>],
+    negated: bool,
+    pick: fn(usize, usize) -> usize,
+) -> impl Iterator<Item = usize> + '_ {
+    predicates
+        .iter()
+        .map(move |predicate| count(predicate, negated, pick))
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+        }
+    }
+
+    use core::cmp::Ordering;
+
+    use crate::*;
+
+    #[test]
+    fn test_specificity() {
+        let testcases = vec![
+            (Predicate::True, (0, 0)),
+            (any(vec![]), (0, 0)),
+            (name("unix"), (1, 1)),
+            (not(name("windows")), (1, 1)),
+            (all(vec![name("unix"), not(feature("beta"))]), (2, 2)),
+            (not(all(vec![name("unix"), name("windows")])), (1, 1)),
+            (not(any(vec![name("unix"), name("windows")])), (2, 2)),
+            (
+                any(vec![
+                    all(vec![name("unix"), name_value("target_os", "linux")]),
+                    all(vec![name("windows"), Predicate::True]),
+                ]),
+                (1, 2),
+            ),
+        ];
+
+        for (predicate, (required, maximum)) in testcases {
+            assert_eq!(
+                predicate.specificity(),
+                Specificity { required, maximum },
+                "specificity of {:?}",
+                predicate
+            );
+        }
+
+        assert_eq!(
+            name("unix").cmp_specificity(&not(name("windows"))),
+            Ordering::Equal
+        );
+        assert!(!name("unix").is_more_specific_than(&name("windows")));
+    }
+}
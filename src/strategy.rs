@@ -0,0 +1,165 @@
+//! `proptest` strategies to generate predicates.
+//!
+//! # Example
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use runtime_cfg::strategy;
+//!
+//! proptest!(|((predicate, flags) in strategy::satisfiable_predicate(vec![
+//!     ("unix", None),
+//!     ("target_os", Some("linux")),
+//!     ("target_os", Some("macos")),
+//! ]))| {
+//!     let flags = flags
+//!         .iter()
+//!         .map(|(name, value)| (name.as_str(), value.as_deref()))
+//!         .collect::<Vec<_>>();
+//!
+//!     prop_assert!(predicate.matches(&flags));
+//! });
+//! ```
+
+use ::proptest::{
+    collection::vec,
+    prelude::*,
+    sample::{select, subsequence},
+};
+
+use crate::keywords::KEYWORDS;
+use crate::{Cfg, Predicate};
+
+const MAX_DEPTH: u32 = 4;
+const MAX_NODES: u32 = 32;
+const MAX_CHILDREN: usize = 4;
+
+const NAME_REGEX: &str = "[a-z][a-z0-9_]{0,15}";
+const VALUE_REGEX: &str = "[ -~]{0,16}";
+
+/// Generates arbitrary predicates, the names of flags are valid identifiers.
+pub fn any_predicate() -> impl Strategy<Value = Predicate> {
+    let name = NAME_REGEX.prop_filter("keyword", |name| !KEYWORDS.contains(&name.as_str()));
+    let leaf = prop_oneof![
+        name.clone().prop_map(Predicate::Name),
+        (name, VALUE_REGEX).prop_map(|(name, value)| Predicate::NameValue(name, value)),
+        Just(Predicate::True),
+        Just(Predicate::False),
+    ];
+
+    recursive(leaf)
+}
+
+/// Generates arbitrary configurations, the names of flags are valid identifiers.
+pub fn any_cfg() -> impl Strategy<Value = Cfg> {
+    any_predicate().prop_map(Cfg::from)
+}
+
+/// Generates predicates over a fixed vocabulary of flags.
+///
+/// A flag without value is generated as `Name`, otherwise as `NameValue`;
+/// the leaves over an empty vocabulary are `True` and `False`.
+pub fn predicate_over<I, K, V>(flags: I) -> impl Strategy<Value = Predicate>
+where
+    I: IntoIterator<Item = (K, Option<V>)>,
+    K: Into<String>,
+    V: Into<String>,
+{
+    let mut leaves = flags
+        .into_iter()
+        .map(|(name, value)| match value {
+            Some(value) => Predicate::NameValue(name.into(), value.into()),
+            None => Predicate::Name(name.into()),
+        })
+        .collect::<Vec<_>>();
+
+    if leaves.is_empty() {
+        leaves = vec![Predicate::True, Predicate::False];
+    }
+
+    recursive(select(leaves))
+}
+
+/// Generates satisfiable predicates over a fixed vocabulary of flags,
+/// together with the flags satisfying the predicate.
+///
+/// The flags are a subset of the vocabulary.
+pub fn satisfiable_predicate<I, K, V>(
+    flags: I,
+) -> impl Strategy<Value = (Predicate, Vec<(String, Option<String>)>)>
+where
+    I: IntoIterator<Item = (K, Option<V>)>,
+    K: Into<String>,
+    V: Into<String>,
+{
+    let flags = flags
+        .into_iter()
+        .map(|(name, value)| (name.into(), value.map(Into::into)))
+        .collect::<Vec<(String, Option<String>)>>();
+    let len = flags.len();
+
+    (predicate_over(flags.clone()), subsequence(flags, 0..=len)).prop_map(|(predicate, flags)| {
+        let flags_ref = flags
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_deref()))
+            .collect::<Vec<_>>();
+
+        if predicate.matches(&flags_ref) {
+            (predicate, flags)
+        } else {
+            (crate::not(predicate), flags)
+        }
+    })
+}
+
+fn recursive<S>(leaf: S) -> impl Strategy<Value = Predicate>
+where
+    S: Strategy<Value = Predicate> + 'static,
+{
+    leaf.prop_recursive(MAX_DEPTH, MAX_NODES, MAX_CHILDREN as u32, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..=MAX_CHILDREN).prop_map(crate::any),
+            vec(inner.clone(), 0..=MAX_CHILDREN).prop_map(crate::all),
+            inner.prop_map(crate::not),
+        ]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn test_any_predicate(predicate in any_predicate()) {
+            prop_assert!(predicate.metrics().depth <= MAX_DEPTH as usize + 1);
+        }
+
+        #[test]
+        fn test_predicate_over(predicate in predicate_over(vec![("unix", None), ("target_os", Some("linux"))])) {
+            prop_assert!(predicate
+                .referenced_flags()
+                .is_subset(&vec![("unix", None), ("target_os", Some("linux"))].into_iter().collect()));
+        }
+
+        #[test]
+        fn test_predicate_over_empty(predicate in predicate_over(Vec::<(&str, Option<&str>)>::new())) {
+            prop_assert!(predicate.referenced_flags().is_empty());
+        }
+
+        #[test]
+        fn test_satisfiable_predicate_empty((predicate, flags) in satisfiable_predicate(Vec::<(&str, Option<&str>)>::new())) {
+            prop_assert!(flags.is_empty());
+            prop_assert!(predicate.matches(&Vec::<(&str, Option<&str>)>::new()));
+        }
+
+        #[test]
+        fn test_satisfiable_predicate((predicate, flags) in satisfiable_predicate(vec![("unix", None), ("target_os", Some("linux"))])) {
+            let flags = flags
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_deref()))
+                .collect::<Vec<_>>();
+
+            prop_assert!(predicate.matches(&flags));
+        }
+    }
+}
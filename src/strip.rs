@@ -0,0 +1,748 @@
+//! Stripping a syntax tree by the configurations, as the compiler does for a target.
+//!
+//! # Example
+//!
+//! ```
+//! use quote::quote;
+//! use runtime_cfg::strip;
+//!
+//! let mut file: syn::File = syn::parse_str(r#"
+//!     #[cfg(unix)]
+//!     fn unix() {}
+//!
+//!     #[cfg(windows)]
+//!     fn windows() {}
+//!
+//!     #[cfg_attr(unix, derive(Debug))]
+//!     struct Handle {
+//!         #[cfg(windows)]
+//!         handle: usize,
+//!         #[cfg(unix)]
+//!         fd: i32,
+//!     }
+//! "#).unwrap();
+//!
+//! strip::filter_file(&mut file, &vec![("unix", None::<&str>)]);
+//!
+//! let expected: syn::File = syn::parse_str(r#"
+//!     fn unix() {}
+//!
+//!     #[derive(Debug)]
+//!     struct Handle {
+//!         fd: i32,
+//!     }
+//! "#).unwrap();
+//!
+//! assert_eq!(quote!(#file).to_string(), quote!(#expected).to_string());
+//! ```
+
+use core::convert::TryFrom;
+use core::mem;
+
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use quote::quote;
+use syn::{
+    parse::Parser,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit_mut::{self, VisitMut},
+    Attribute,
+};
+
+use crate::{parsing::parse_nested_meta, Cfg, Error, Pattern, Predicate};
+
+/// Removes the items, fields, variants, impl members, statements, match arms
+/// and struct fields of expressions or patterns, whose `#[cfg(..)]` doesn't match the pattern.
+///
+/// The `#[cfg(..)]` of the retained nodes are removed,
+/// and the `#[cfg_attr(..)]` are replaced by their attributes when matched, or removed otherwise.
+/// The file is emptied when its inner `#![cfg(..)]` doesn't match.
+///
+/// The attributes which can't be parsed as configurations are kept as-is.
+pub fn filter_file<P: Pattern>(file: &mut syn::File, pattern: &P) {
+    if configure(&mut file.attrs, pattern) {
+        Strip(pattern).visit_file_mut(file)
+    } else {
+        file.attrs.clear();
+        file.items.clear();
+    }
+}
+
+/// Removes the variants whose `#[cfg(..)]` doesn't match the pattern, and the unmatched fields of the retained variants.
+///
+/// Only the variants and their fields are configured, the attributes of the enum itself are kept as-is.
+///
+/// # Example
+///
+/// ```
+/// use quote::quote;
+/// use runtime_cfg::strip;
+///
+/// let mut item: syn::ItemEnum = syn::parse_str(r#"
+///     enum Handle {
+///         #[cfg(unix)]
+///         Fd(i32),
+///         #[cfg(windows)]
+///         Raw(usize),
+///     }
+/// "#).unwrap();
+///
+/// strip::filter_variants(&mut item, &vec![("unix", None::<&str>)]);
+///
+/// assert_eq!(quote!(#item).to_string(), quote!(enum Handle { Fd(i32), }).to_string());
+/// ```
+pub fn filter_variants<P: Pattern>(item: &mut syn::ItemEnum, pattern: &P) {
+    retain_punctuated(&mut item.variants, pattern, |variant| {
+        Some(&mut variant.attrs)
+    });
+
+    for variant in &mut item.variants {
+        filter_fields(&mut variant.fields, pattern);
+    }
+}
+
+/// Removes the fields whose `#[cfg(..)]` doesn't match the pattern.
+///
+/// The types of the retained fields are not configured.
+///
+/// # Example
+///
+/// ```
+/// use quote::quote;
+/// use runtime_cfg::strip;
+///
+/// let mut item: syn::ItemStruct = syn::parse_str(r#"
+///     struct Handle {
+///         #[cfg(unix)]
+///         fd: i32,
+///         #[cfg_attr(unix, doc = "raw handle")]
+///         raw: usize,
+///     }
+/// "#).unwrap();
+///
+/// strip::filter_fields(&mut item.fields, &vec![("windows", None::<&str>)]);
+///
+/// assert_eq!(quote!(#item).to_string(), quote!(struct Handle { raw: usize, }).to_string());
+/// ```
+pub fn filter_fields<P: Pattern>(fields: &mut syn::Fields, pattern: &P) {
+    match fields {
+        syn::Fields::Named(fields) => {
+            retain_punctuated(&mut fields.named, pattern, |field| Some(&mut field.attrs))
+        }
+        syn::Fields::Unnamed(fields) => {
+            retain_punctuated(&mut fields.unnamed, pattern, |field| Some(&mut field.attrs))
+        }
+        syn::Fields::Unit => {}
+    }
+}
+
+/// Evaluates all the `#[cfg(..)]` and `#![cfg(..)]` of the attributes,
+/// returns `true` if all of them match the pattern.
+///
+/// The `#[cfg_attr(..)]` are expanded when matched, so their nested `#[cfg(..)]` are evaluated too.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let item: syn::ItemFn = syn::parse_str(r#"
+///     #[cfg(unix)]
+///     #[cfg_attr(feature = "strict", cfg(target_os = "linux"))]
+///     #[inline]
+///     fn unix() {}
+/// "#).unwrap();
+///
+/// assert!(evaluate_attrs(&item.attrs, &vec![("unix", None), ("target_os", Some("macos"))]).unwrap());
+/// assert!(!evaluate_attrs(
+///     &item.attrs,
+///     &vec![("unix", None), ("target_os", Some("macos")), ("feature", Some("strict"))]
+/// ).unwrap());
+///
+/// let item: syn::ItemFn = syn::parse_str("#[cfg(foo(bar))] fn foo() {}").unwrap();
+///
+/// assert!(evaluate_attrs(&item.attrs, &vec![("foo", None::<&str>)]).is_err());
+/// ```
+pub fn evaluate_attrs<P: Pattern>(attrs: &[Attribute], pattern: &P) -> Result<bool, Error> {
+    let mut predicates = Vec::new();
+
+    collect_cfgs(attrs, pattern, &mut predicates)?;
+
+    Ok(crate::all(predicates).matches(pattern))
+}
+
+fn collect_cfgs<P: Pattern>(
+    attrs: &[Attribute],
+    pattern: &P,
+    predicates: &mut Vec<Predicate>,
+) -> Result<(), Error> {
+    for attr in attrs {
+        if attr.path.is_ident("cfg") {
+            predicates.push(Cfg::try_from(attr).map_err(Error::Parse)?.into());
+        } else if attr.path.is_ident("cfg_attr") {
+            let (predicate, expanded) = parse_cfg_attr(attr).map_err(Error::Parse)?;
+
+            if predicate.matches(pattern) {
+                collect_cfgs(&expanded, pattern, predicates)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Expands the `#[cfg_attr(..)]` and evaluates the `#[cfg(..)]` of a node.
+///
+/// Returns `false` if the node should be removed, otherwise the `#[cfg(..)]` are removed.
+pub(crate) fn configure<P: Pattern>(attrs: &mut Vec<Attribute>, pattern: &P) -> bool {
+    let mut configured = Vec::with_capacity(attrs.len());
+
+    for attr in attrs.drain(..) {
+        expand_cfg_attr(attr, pattern, &mut configured);
+    }
+
+    let mut matched = true;
+
+    configured.retain(|attr| {
+        if attr.path.is_ident("cfg") {
+            if let Ok(cfg) = Cfg::try_from(attr) {
+                matched &= cfg.matches(pattern);
+
+                return false;
+            }
+        }
+
+        true
+    });
+
+    *attrs = configured;
+
+    matched
+}
+
+fn expand_cfg_attr<P: Pattern>(attr: Attribute, pattern: &P, attrs: &mut Vec<Attribute>) {
+    if !attr.path.is_ident("cfg_attr") {
+        attrs.push(attr);
+        return;
+    }
+
+    match parse_cfg_attr(&attr) {
+        Ok((predicate, expanded)) => {
+            if predicate.matches(pattern) {
+                for attr in expanded {
+                    expand_cfg_attr(attr, pattern, attrs);
+                }
+            }
+        }
+        Err(_) => attrs.push(attr),
+    }
+}
+
+fn parse_cfg_attr(attr: &Attribute) -> syn::Result<(Predicate, Vec<Attribute>)> {
+    #[cfg(feature = "no-panic")]
+    crate::parsing::check_tokens_nesting(&attr.tts)?;
+
+    let mut tts = attr.tts.clone().into_iter();
+    let group = match (tts.next(), tts.next()) {
+        (Some(TokenTree::Group(group)), None) if group.delimiter() == Delimiter::Parenthesis => {
+            group
+        }
+        _ => {
+            return Err(syn::Error::new(
+                attr.span(),
+                "#[cfg_attr(..)] expect a predicate and attributes",
+            ))
+        }
+    };
+
+    let mut segments = split_commas(group.stream()).into_iter();
+    let predicate = segments
+        .next()
+        .ok_or_else(|| syn::Error::new(group.span(), "#[cfg_attr(..)] predicate can't be empty"))
+        .and_then(syn::parse2)
+        .and_then(parse_nested_meta)?;
+    let attrs = segments
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            let parsed = Attribute::parse_outer
+                .parse2(quote! { #[#segment] })?
+                .pop()
+                .ok_or_else(|| syn::Error::new(group.span(), "expect an attribute"))?;
+
+            Ok(Attribute {
+                style: attr.style,
+                ..parsed
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok((predicate, attrs))
+}
+
+fn split_commas(tokens: TokenStream) -> Vec<TokenStream> {
+    let mut segments = vec![];
+    let mut segment = vec![];
+
+    for tt in tokens {
+        match tt {
+            TokenTree::Punct(ref punct) if punct.as_char() == ',' => {
+                segments.push(segment.drain(..).collect())
+            }
+            tt => segment.push(tt),
+        }
+    }
+
+    segments.push(segment.into_iter().collect());
+    segments
+}
+
+macro_rules! attrs_mut {
+    ($value:expr, $ty:ident { $($variant:ident),* }) => {
+        match $value {
+            $(syn::$ty::$variant(ref mut node) => Some(&mut node.attrs),)*
+            _ => None,
+        }
+    };
+}
+
+fn item_attrs_mut(item: &mut syn::Item) -> Option<&mut Vec<Attribute>> {
+    attrs_mut!(
+        item,
+        Item {
+            ExternCrate,
+            Use,
+            Static,
+            Const,
+            Fn,
+            Mod,
+            ForeignMod,
+            Type,
+            Existential,
+            Struct,
+            Enum,
+            Union,
+            Trait,
+            TraitAlias,
+            Impl,
+            Macro,
+            Macro2
+        }
+    )
+}
+
+fn impl_item_attrs_mut(item: &mut syn::ImplItem) -> Option<&mut Vec<Attribute>> {
+    attrs_mut!(
+        item,
+        ImplItem {
+            Const,
+            Method,
+            Type,
+            Existential,
+            Macro
+        }
+    )
+}
+
+fn trait_item_attrs_mut(item: &mut syn::TraitItem) -> Option<&mut Vec<Attribute>> {
+    attrs_mut!(
+        item,
+        TraitItem {
+            Const,
+            Method,
+            Type,
+            Macro
+        }
+    )
+}
+
+fn foreign_item_attrs_mut(item: &mut syn::ForeignItem) -> Option<&mut Vec<Attribute>> {
+    attrs_mut!(
+        item,
+        ForeignItem {
+            Fn,
+            Static,
+            Type,
+            Macro
+        }
+    )
+}
+
+fn expr_attrs_mut(expr: &mut syn::Expr) -> Option<&mut Vec<Attribute>> {
+    attrs_mut!(
+        expr,
+        Expr {
+            Box,
+            InPlace,
+            Array,
+            Call,
+            MethodCall,
+            Tuple,
+            Binary,
+            Unary,
+            Lit,
+            Cast,
+            Type,
+            Let,
+            If,
+            While,
+            ForLoop,
+            Loop,
+            Match,
+            Closure,
+            Unsafe,
+            Block,
+            Assign,
+            AssignOp,
+            Field,
+            Index,
+            Range,
+            Path,
+            Reference,
+            Break,
+            Continue,
+            Return,
+            Macro,
+            Struct,
+            Repeat,
+            Paren,
+            Group,
+            Try,
+            Async,
+            TryBlock,
+            Yield
+        }
+    )
+}
+
+fn stmt_attrs_mut(stmt: &mut syn::Stmt) -> Option<&mut Vec<Attribute>> {
+    match stmt {
+        syn::Stmt::Local(local) => Some(&mut local.attrs),
+        syn::Stmt::Item(item) => item_attrs_mut(item),
+        syn::Stmt::Expr(expr) | syn::Stmt::Semi(expr, _) => expr_attrs_mut(expr),
+    }
+}
+
+/// Retains the nodes whose `#[cfg(..)]` match the pattern.
+pub(crate) fn retain<T, P, F>(nodes: &mut Vec<T>, pattern: &P, mut attrs_mut: F)
+where
+    P: Pattern,
+    F: FnMut(&mut T) -> Option<&mut Vec<Attribute>>,
+{
+    nodes.retain_mut(|node| attrs_mut(node).is_none_or(|attrs| configure(attrs, pattern)))
+}
+
+/// Retains the punctuated nodes whose `#[cfg(..)]` match the pattern.
+pub(crate) fn retain_punctuated<T, U, P, F>(nodes: &mut Punctuated<T, U>, pattern: &P, attrs_mut: F)
+where
+    U: Default,
+    P: Pattern,
+    F: FnMut(&mut T) -> Option<&mut Vec<Attribute>>,
+{
+    let trailing = nodes.trailing_punct();
+    let mut retained = mem::take(nodes).into_iter().collect::<Vec<_>>();
+
+    retain(&mut retained, pattern, attrs_mut);
+
+    *nodes = retained.into_iter().collect();
+
+    if trailing && !nodes.is_empty() {
+        nodes.push_punct(U::default());
+    }
+}
+
+struct Strip<'a, P>(&'a P);
+
+impl<P: Pattern> VisitMut for Strip<'_, P> {
+    fn visit_file_mut(&mut self, file: &mut syn::File) {
+        retain(&mut file.items, self.0, item_attrs_mut);
+        visit_mut::visit_file_mut(self, file)
+    }
+
+    fn visit_item_mod_mut(&mut self, item: &mut syn::ItemMod) {
+        if let Some((_, ref mut items)) = item.content {
+            retain(items, self.0, item_attrs_mut);
+        }
+        visit_mut::visit_item_mod_mut(self, item)
+    }
+
+    fn visit_item_foreign_mod_mut(&mut self, item: &mut syn::ItemForeignMod) {
+        retain(&mut item.items, self.0, foreign_item_attrs_mut);
+        visit_mut::visit_item_foreign_mod_mut(self, item)
+    }
+
+    fn visit_item_impl_mut(&mut self, item: &mut syn::ItemImpl) {
+        retain(&mut item.items, self.0, impl_item_attrs_mut);
+        visit_mut::visit_item_impl_mut(self, item)
+    }
+
+    fn visit_item_trait_mut(&mut self, item: &mut syn::ItemTrait) {
+        retain(&mut item.items, self.0, trait_item_attrs_mut);
+        visit_mut::visit_item_trait_mut(self, item)
+    }
+
+    fn visit_item_enum_mut(&mut self, item: &mut syn::ItemEnum) {
+        retain_punctuated(&mut item.variants, self.0, |variant| {
+            Some(&mut variant.attrs)
+        });
+        visit_mut::visit_item_enum_mut(self, item)
+    }
+
+    fn visit_fields_named_mut(&mut self, fields: &mut syn::FieldsNamed) {
+        retain_punctuated(&mut fields.named, self.0, |field| Some(&mut field.attrs));
+        visit_mut::visit_fields_named_mut(self, fields)
+    }
+
+    fn visit_fields_unnamed_mut(&mut self, fields: &mut syn::FieldsUnnamed) {
+        retain_punctuated(&mut fields.unnamed, self.0, |field| Some(&mut field.attrs));
+        visit_mut::visit_fields_unnamed_mut(self, fields)
+    }
+
+    fn visit_block_mut(&mut self, block: &mut syn::Block) {
+        retain(&mut block.stmts, self.0, stmt_attrs_mut);
+        visit_mut::visit_block_mut(self, block)
+    }
+
+    fn visit_expr_match_mut(&mut self, expr: &mut syn::ExprMatch) {
+        retain(&mut expr.arms, self.0, |arm| Some(&mut arm.attrs));
+        visit_mut::visit_expr_match_mut(self, expr)
+    }
+
+    fn visit_expr_struct_mut(&mut self, expr: &mut syn::ExprStruct) {
+        retain_punctuated(&mut expr.fields, self.0, |field| Some(&mut field.attrs));
+        visit_mut::visit_expr_struct_mut(self, expr)
+    }
+
+    fn visit_pat_struct_mut(&mut self, pat: &mut syn::PatStruct) {
+        retain_punctuated(&mut pat.fields, self.0, |field| Some(&mut field.attrs));
+        visit_mut::visit_pat_struct_mut(self, pat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(source: &str, flags: &[(&str, Option<&str>)]) -> String {
+        let mut file: syn::File = syn::parse_str(source).unwrap();
+
+        filter_file(&mut file, &flags.to_vec());
+
+        quote!(#file).to_string()
+    }
+
+    fn tokens(source: &str) -> String {
+        let file: syn::File = syn::parse_str(source).unwrap();
+
+        quote!(#file).to_string()
+    }
+
+    #[test]
+    fn test_filter_file() {
+        let flags = [("unix", None), ("target_os", Some("linux"))];
+
+        assert_eq!(
+            strip(
+                r#"
+                #[cfg(unix)]
+                mod sys {
+                    #[cfg(target_os = "linux")]
+                    pub fn linux() {}
+                    #[cfg(target_os = "macos")]
+                    pub fn macos() {}
+                }
+
+                #[cfg_attr(windows, derive(Debug))]
+                #[cfg_attr(unix, cfg_attr(target_os = "linux", derive(Clone, Copy), doc = "linux"))]
+                enum Fd {
+                    #[cfg(windows)]
+                    Handle(usize),
+                    Raw(#[cfg(unix)] i32),
+                }
+
+                impl Fd {
+                    #[cfg(not(unix))]
+                    const INVALID: usize = 0;
+
+                    fn new() -> Self {
+                        #[cfg(windows)]
+                        let fd = 0usize;
+                        #[cfg(unix)]
+                        let fd = -1;
+
+                        match fd {
+                            #[cfg(unix)]
+                            -1 => Fd::Raw(fd),
+                            _ => unreachable!(),
+                        }
+                    }
+                }
+                "#,
+                &flags
+            ),
+            tokens(
+                r#"
+                mod sys {
+                    pub fn linux() {}
+                }
+
+                #[derive(Clone, Copy)]
+                #[doc = "linux"]
+                enum Fd {
+                    Raw(i32),
+                }
+
+                impl Fd {
+                    fn new() -> Self {
+                        let fd = -1;
+
+                        match fd {
+                            -1 => Fd::Raw(fd),
+                            _ => unreachable!(),
+                        }
+                    }
+                }
+                "#
+            )
+        );
+    }
+
+    #[test]
+    fn test_filter_file_inner_cfg() {
+        assert_eq!(
+            strip("#![cfg(windows)]\nfn main() {}", &[("unix", None)]),
+            tokens("")
+        );
+        assert_eq!(
+            strip(
+                "#![cfg(unix)]\n#![cfg_attr(unix, allow(dead_code))]\nfn main() {}",
+                &[("unix", None)]
+            ),
+            tokens("#![allow(dead_code)]\nfn main() {}")
+        );
+    }
+
+    #[test]
+    fn test_filter_variants() {
+        let mut item: syn::ItemEnum = syn::parse_str(
+            r#"
+            #[cfg_attr(unix, derive(Debug))]
+            enum Event {
+                #[cfg(unix)]
+                Signal {
+                    #[cfg(target_os = "linux")]
+                    fd: i32,
+                    #[cfg(not(target_os = "linux"))]
+                    kq: i32,
+                },
+                #[cfg_attr(unix, doc = "unix")]
+                Exit(#[cfg(windows)] u32),
+                #[cfg(windows)]
+                Console,
+            }
+            "#,
+        )
+        .unwrap();
+
+        filter_variants(
+            &mut item,
+            &vec![("unix", None), ("target_os", Some("linux"))],
+        );
+
+        assert_eq!(
+            quote!(#item).to_string(),
+            quote! {
+                #[cfg_attr(unix, derive(Debug))]
+                enum Event {
+                    Signal {
+                        fd: i32,
+                    },
+                    #[doc = "unix"]
+                    Exit(),
+                }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_filter_fields() {
+        let mut fields = syn::Fields::Unit;
+
+        filter_fields(&mut fields, &vec![("unix", None::<&str>)]);
+
+        assert_eq!(quote!(#fields).to_string(), "");
+
+        let mut item: syn::ItemStruct =
+            syn::parse_str("struct Pair(#[cfg(unix)] i32, #[cfg(windows)] u32);").unwrap();
+
+        filter_fields(&mut item.fields, &vec![("windows", None::<&str>)]);
+
+        assert_eq!(
+            quote!(#item).to_string(),
+            quote!(
+                struct Pair(u32);
+            )
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_evaluate_attrs() {
+        let file: syn::File = syn::parse_str(
+            r#"
+            #![cfg(unix)]
+            #![cfg_attr(feature = "std", cfg(not(target_os = "none")))]
+            #![allow(dead_code)]
+            "#,
+        )
+        .unwrap();
+
+        assert!(evaluate_attrs(&file.attrs, &vec![("unix", None::<&str>)]).unwrap());
+        assert!(!evaluate_attrs(&file.attrs, &vec![("windows", None::<&str>)]).unwrap());
+        assert!(!evaluate_attrs(
+            &file.attrs,
+            &vec![
+                ("unix", None),
+                ("feature", Some("std")),
+                ("target_os", Some("none"))
+            ]
+        )
+        .unwrap());
+        assert!(evaluate_attrs(&[], &vec![("unix", None::<&str>)]).unwrap());
+
+        let file: syn::File = syn::parse_str("#![cfg_attr(unix, cfg(not(foo, bar)))]").unwrap();
+
+        assert!(evaluate_attrs(&file.attrs, &vec![("windows", None::<&str>)]).unwrap());
+        assert_eq!(
+            evaluate_attrs(&file.attrs, &vec![("unix", None::<&str>)])
+                .unwrap_err()
+                .to_string(),
+            "failed to parse cfg, #[cfg(not(..))] only support one predicate"
+        );
+    }
+
+    #[cfg(feature = "no-panic")]
+    #[test]
+    fn test_evaluate_attrs_too_deep() {
+        let nested = |n| "not(".repeat(n) + "unix" + &")".repeat(n);
+        let flags = vec![("unix", None::<&str>)];
+
+        let file: syn::File = syn::parse_str(&format!(
+            "#![cfg_attr(unix, cfg({}))]",
+            nested(crate::MAX_NESTING - 2)
+        ))
+        .unwrap();
+        assert!(evaluate_attrs(&file.attrs, &flags).is_ok());
+
+        let file: syn::File = syn::parse_str(&format!(
+            "#![cfg_attr(unix, cfg({}))]",
+            nested(crate::MAX_NESTING)
+        ))
+        .unwrap();
+        assert!(evaluate_attrs(&file.attrs, &flags).is_err());
+
+        let file: syn::File =
+            syn::parse_str(&format!("#![cfg({})]", nested(crate::MAX_NESTING))).unwrap();
+        assert!(evaluate_attrs(&file.attrs, &flags).is_err());
+    }
+}
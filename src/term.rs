@@ -0,0 +1,161 @@
+//! Terminal rendering of the evaluation results, colored with the `term` feature.
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::string::String;
+    }
+}
+
+use core::fmt;
+
+use crate::{Explanation, Predicate};
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders the explanation as a tree in the plain text.
+///
+/// # Example
+///
+/// ```
+/// use runtime_cfg::*;
+///
+/// let predicate = all(vec![name("unix"), not(name_value("target_env", "musl"))]);
+/// let explanation = predicate.explain(&vec![("unix", None), ("target_env", Some("musl"))]);
+///
+/// assert_eq!(
+///     explanation.to_string(),
+///     r#"✗ all
+/// ├── ✓ unix
+/// └── ✗ not
+///     └── ✓ target_env = "musl"
+/// "#
+/// );
+/// ```
+impl fmt::Display for Explanation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        render(self, f, false)
+    }
+}
+
+/// Renders the explanation as a tree with ANSI colors,
+/// the matched nodes in green, and the failed nodes in red.
+#[cfg(feature = "term")]
+#[derive(Debug, Clone, Copy)]
+pub struct Colored<'a, 'b>(&'b Explanation<'a>);
+
+#[cfg(feature = "term")]
+impl fmt::Display for Colored<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        render(self.0, f, true)
+    }
+}
+
+#[cfg(feature = "term")]
+impl<'a> Explanation<'a> {
+    /// Returns a wrapper to render the explanation with ANSI colors.
+    pub fn colored(&self) -> Colored<'a, '_> {
+        Colored(self)
+    }
+}
+
+fn render(explanation: &Explanation, f: &mut fmt::Formatter, colored: bool) -> fmt::Result {
+    render_node(explanation, f, colored, &mut String::new(), None)
+}
+
+fn render_node(
+    explanation: &Explanation,
+    f: &mut fmt::Formatter,
+    colored: bool,
+    prefix: &mut String,
+    last: Option<bool>,
+) -> fmt::Result {
+    use Predicate::*;
+
+    f.write_str(prefix)?;
+
+    match last {
+        Some(true) => f.write_str("└── ")?,
+        Some(false) => f.write_str("├── ")?,
+        None => {}
+    }
+
+    let mark = if explanation.matched { "✓" } else { "✗" };
+
+    if colored {
+        f.write_str(if explanation.matched { GREEN } else { RED })?;
+    }
+
+    match explanation.predicate {
+        Any(_) => write!(f, "{} any", mark)?,
+        All(_) => write!(f, "{} all", mark)?,
+        Not(_) => write!(f, "{} not", mark)?,
+        predicate => write!(f, "{} {}", mark, predicate)?,
+    }
+
+    if colored {
+        f.write_str(RESET)?;
+    }
+
+    f.write_str("\n")?;
+
+    let len = prefix.len();
+
+    match last {
+        Some(true) => prefix.push_str("    "),
+        Some(false) => prefix.push_str("│   "),
+        None => {}
+    }
+
+    for (i, child) in explanation.children.iter().enumerate() {
+        render_node(
+            child,
+            f,
+            colored,
+            prefix,
+            Some(i + 1 == explanation.children.len()),
+        )?;
+    }
+
+    prefix.truncate(len);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::format;
+            use alloc::vec;
+        }
+    }
+
+    use crate::*;
+
+    #[test]
+    fn test_render() {
+        let predicate = any(vec![all(vec![name("unix"), name("test")]), Predicate::True]);
+        let explanation = predicate.explain(&vec![("unix", None::<&str>)]);
+
+        assert_eq!(
+            format!("{}", explanation),
+            "✓ any\n├── ✗ all\n│   ├── ✓ unix\n│   └── ✗ test\n└── ✓ all()\n"
+        );
+    }
+
+    #[cfg(feature = "term")]
+    #[test]
+    fn test_render_colored() {
+        assert_eq!(
+            format!(
+                "{}",
+                not(name("test"))
+                    .explain(&vec![("unix", None::<&str>)])
+                    .colored()
+            ),
+            "\x1b[32m✓ not\x1b[0m\n└── \x1b[31m✗ test\x1b[0m\n"
+        );
+    }
+}
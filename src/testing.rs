@@ -0,0 +1,432 @@
+//! A conformance suite of the parser, the printer and the patterns,
+//! for the crates implementing their own [`Pattern`] to check it behaves like the patterns of this crate.
+//!
+//! The [`CORPUS`] lists the configurations with the flags and the expected results.
+//! A flag set with a value answers the queries of its bare name too, as [`FlagSet`] does,
+//! while a flag set without value never answers the queries with a value.
+//!
+//! # Example
+//!
+//! ```
+//! use runtime_cfg::testing;
+//! use runtime_cfg::*;
+//!
+//! assert_eq!(testing::check_roundtrip(testing::CORPUS), Ok(()));
+//! assert_eq!(
+//!     testing::check_pattern(|flags| flags.iter().cloned().collect::<FlagSet>()),
+//!     Ok(())
+//! );
+//!
+//! // a pattern ignoring the values fails the suite
+//! let failures = testing::check_pattern(|flags| {
+//!     flags.iter().map(|&(name, _)| (name, None::<&str>)).collect::<Vec<_>>()
+//! })
+//! .unwrap_err();
+//!
+//! assert!(!failures.is_empty());
+//! ```
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+use std::process::Command;
+
+use crate::{Cfg, FlagSet, Pattern, Predicate};
+
+/// A configuration with the flags, and whether the configuration matches them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Case {
+    /// The configuration, like `cfg(all(unix, target_os = "linux"))`.
+    pub cfg: &'static str,
+    /// The flags set, with their values if any.
+    pub flags: &'static [(&'static str, Option<&'static str>)],
+    /// Whether the configuration matches the flags.
+    pub matches: bool,
+}
+
+/// The conformance cases of the configuration matching.
+pub const CORPUS: &[Case] = &[
+    case("cfg(unix)", &[("unix", None)], true),
+    case("cfg(unix)", &[], false),
+    case("cfg(unix)", &[("windows", None)], false),
+    case("cfg(Unix)", &[("unix", None)], false),
+    case(
+        r#"cfg(target_os = "linux")"#,
+        &[("target_os", Some("linux"))],
+        true,
+    ),
+    case(
+        r#"cfg(target_os = "linux")"#,
+        &[("target_os", Some("macos"))],
+        false,
+    ),
+    case(r#"cfg(target_os = "linux")"#, &[("target_os", None)], false),
+    case(r#"cfg(target_os = "linux")"#, &[("linux", None)], false),
+    case("cfg(target_os)", &[("target_os", Some("linux"))], true),
+    case(
+        r#"cfg(feature = "std")"#,
+        &[("feature", Some("std")), ("feature", Some("serde"))],
+        true,
+    ),
+    case(
+        r#"cfg(feature = "serde")"#,
+        &[("feature", Some("std")), ("feature", Some("serde"))],
+        true,
+    ),
+    case(
+        r#"cfg(feature = "alloc")"#,
+        &[("feature", Some("std")), ("feature", Some("serde"))],
+        false,
+    ),
+    case(r#"cfg(target_env = "")"#, &[("target_env", Some(""))], true),
+    case(
+        r#"cfg(target_env = "")"#,
+        &[("target_env", Some("gnu"))],
+        false,
+    ),
+    case(
+        r#"cfg(vendor = "o\"reilly")"#,
+        &[("vendor", Some("o\"reilly"))],
+        true,
+    ),
+    case(r#"cfg(lang = "中文")"#, &[("lang", Some("中文"))], true),
+    case("cfg(any())", &[("unix", None)], false),
+    case("cfg(all())", &[], true),
+    case("cfg(true)", &[], true),
+    case("cfg(false)", &[("false", None)], false),
+    case("cfg(not(unix))", &[("unix", None)], false),
+    case("cfg(not(unix))", &[], true),
+    case("cfg(not(not(unix)))", &[("unix", None)], true),
+    case("cfg(any(unix, windows))", &[("windows", None)], true),
+    case("cfg(any(unix, windows))", &[("wasm", None)], false),
+    case(
+        r#"cfg(all(unix, target_pointer_width = "64"))"#,
+        &[("unix", None), ("target_pointer_width", Some("32"))],
+        false,
+    ),
+    case(
+        r#"cfg(all(unix, not(target_env = "musl")))"#,
+        &[("unix", None), ("target_env", Some("gnu"))],
+        true,
+    ),
+    case(
+        r#"cfg(any(all(unix, target_os = "macos"), not(any(unix, windows))))"#,
+        &[("unix", None), ("target_os", Some("linux"))],
+        false,
+    ),
+    case("cfg(param(region))", &[("region", None)], false),
+];
+
+const fn case(
+    cfg: &'static str,
+    flags: &'static [(&'static str, Option<&'static str>)],
+    matches: bool,
+) -> Case {
+    Case {
+        cfg,
+        flags,
+        matches,
+    }
+}
+
+/// A failed check of the conformance suite.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Failure {
+    /// The configuration can't be parsed.
+    Parse {
+        /// The configuration.
+        cfg: String,
+        /// The parsing error.
+        error: String,
+    },
+    /// The configuration doesn't parse back to an equivalent one from its printed form.
+    Roundtrip {
+        /// The configuration.
+        cfg: String,
+        /// The printed form.
+        printed: String,
+    },
+    /// The pattern answers a configuration differently.
+    Mismatch {
+        /// The configuration.
+        cfg: String,
+        /// The flags of the case, or `None` if compared to a reference pattern.
+        flags: Option<String>,
+        /// The expected result.
+        expected: bool,
+    },
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Failure::Parse { cfg, error } => write!(f, "`{}` can't be parsed, {}", cfg, error),
+            Failure::Roundtrip { cfg, printed } => {
+                write!(
+                    f,
+                    "`{}` is printed as `{}`, which doesn't parse back",
+                    cfg, printed
+                )
+            }
+            Failure::Mismatch {
+                cfg,
+                flags,
+                expected,
+            } => {
+                let op = if *expected { "match" } else { "not match" };
+
+                match flags {
+                    Some(flags) => write!(f, "`{}` should {} the flags [{}]", cfg, op, flags),
+                    None => write!(f, "`{}` should {} as the reference pattern", cfg, op),
+                }
+            }
+        }
+    }
+}
+
+impl StdError for Failure {}
+
+/// Checks the configurations of the cases parse back to themselves
+/// from the printed, the s-expression and the binary forms.
+///
+/// The configurations are compared in their [canonical](Predicate::canonicalize) forms,
+/// since `true` and `false` are printed as the empty `all()` and `any()`, which are parsed back as they are.
+pub fn check_roundtrip(cases: &[Case]) -> Result<(), Vec<Failure>> {
+    collect(cases.iter().filter_map(|case| {
+        let cfg = match parse(case.cfg) {
+            Ok(cfg) => cfg,
+            Err(failure) => return Some(failure),
+        };
+        let canonical = cfg.canonicalize();
+        let roundtrip = |printed: String, parsed: Option<Predicate>| {
+            if parsed.map(|parsed| parsed.canonicalize()).as_ref() == Some(&canonical) {
+                None
+            } else {
+                Some(Failure::Roundtrip {
+                    cfg: case.cfg.to_string(),
+                    printed,
+                })
+            }
+        };
+
+        let printed = cfg.to_string();
+        let parsed = Cfg::parse(&printed).ok().map(|cfg| cfg.0);
+        if let Some(failure) = roundtrip(printed, parsed) {
+            return Some(failure);
+        }
+
+        let sexpr = cfg.sexpr().to_string();
+        let parsed = Predicate::from_sexpr(&sexpr).ok();
+        if let Some(failure) = roundtrip(sexpr, parsed) {
+            return Some(failure);
+        }
+
+        let encoded = cfg.encode();
+        let parsed = Predicate::decode(&encoded).ok();
+        roundtrip(format!("{:?}", encoded), parsed)
+    }))
+}
+
+/// Checks the patterns built from the flags of the [`CORPUS`] cases answer as expected.
+pub fn check_pattern<P, F>(new: F) -> Result<(), Vec<Failure>>
+where
+    P: Pattern,
+    F: FnMut(&[(&'static str, Option<&'static str>)]) -> P,
+{
+    check_cases(CORPUS, new)
+}
+
+/// Checks the patterns built from the flags of the cases answer as expected.
+pub fn check_cases<P, F>(cases: &[Case], mut new: F) -> Result<(), Vec<Failure>>
+where
+    P: Pattern,
+    F: FnMut(&[(&'static str, Option<&'static str>)]) -> P,
+{
+    collect(cases.iter().filter_map(|case| {
+        let cfg = match parse(case.cfg) {
+            Ok(cfg) => cfg,
+            Err(failure) => return Some(failure),
+        };
+
+        if cfg.matches(&new(case.flags)) == case.matches {
+            None
+        } else {
+            Some(Failure::Mismatch {
+                cfg: case.cfg.to_string(),
+                flags: Some(format_flags(case.flags)),
+                expected: case.matches,
+            })
+        }
+    }))
+}
+
+/// Checks the pattern answers the configurations, and each flag they reference, as the reference pattern does.
+pub fn compare<P, Q>(pattern: &P, reference: &Q, cfgs: &[Cfg]) -> Result<(), Vec<Failure>>
+where
+    P: Pattern + ?Sized,
+    Q: Pattern + ?Sized,
+{
+    collect(cfgs.iter().flat_map(|cfg| {
+        let leaves = cfg
+            .referenced_flags()
+            .into_iter()
+            .map(|(name, value)| match value {
+                Some(value) => Predicate::NameValue(name.to_string(), value.to_string()),
+                None => Predicate::Name(name.to_string()),
+            });
+
+        Some(cfg.0.clone())
+            .into_iter()
+            .chain(leaves)
+            .filter_map(|predicate| {
+                let expected = predicate.matches(reference);
+
+                if predicate.matches(pattern) == expected {
+                    None
+                } else {
+                    Some(Failure::Mismatch {
+                        cfg: Cfg(predicate).to_string(),
+                        flags: None,
+                        expected,
+                    })
+                }
+            })
+            .collect::<Vec<_>>()
+    }))
+}
+
+/// Returns the flags printed by `rustc --print cfg` with the extra arguments, like `--target` or `--cfg`.
+///
+/// The compiler is the `RUSTC` environment variable if set, or `rustc`.
+pub fn rustc_cfg<I, S>(args: I) -> io::Result<FlagSet>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
+    let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let output = Command::new(rustc)
+        .args(["--print", "cfg"])
+        .args(args)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    let stdout = String::from_utf8(output.stdout)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    FlagSet::from_rust_analyzer_cfgs(stdout.lines().filter(|line| !line.is_empty()))
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn parse(cfg: &str) -> Result<Cfg, Failure> {
+    Cfg::parse(cfg).map_err(|err| Failure::Parse {
+        cfg: cfg.to_string(),
+        error: err.to_string(),
+    })
+}
+
+fn format_flags(flags: &[(&str, Option<&str>)]) -> String {
+    flags
+        .iter()
+        .map(|(name, value)| match value {
+            Some(value) => format!("{} = {:?}", name, value),
+            None => name.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn collect<I: IntoIterator<Item = Failure>>(failures: I) -> Result<(), Vec<Failure>> {
+    let failures = failures.into_iter().collect::<Vec<_>>();
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    use super::*;
+
+    #[test]
+    fn test_corpus() {
+        assert_eq!(check_roundtrip(CORPUS), Ok(()));
+        assert_eq!(
+            check_pattern(|flags| flags.iter().cloned().collect::<FlagSet>()),
+            Ok(())
+        );
+        assert_eq!(check_pattern(|flags| flags.to_vec()), Ok(()));
+    }
+
+    #[test]
+    fn test_failures() {
+        let cases = [
+            case("cfg(unix", &[], false),
+            case(r#"cfg(target_os = "linux")"#, &[("target_os", None)], true),
+        ];
+
+        assert_eq!(
+            check_cases(&cases, |flags| flags.to_vec()),
+            Err(vec![
+                Failure::Parse {
+                    cfg: "cfg(unix".to_string(),
+                    error: Cfg::parse("cfg(unix").unwrap_err().to_string(),
+                },
+                Failure::Mismatch {
+                    cfg: r#"cfg(target_os = "linux")"#.to_string(),
+                    flags: Some("target_os".to_string()),
+                    expected: true,
+                },
+            ])
+        );
+        assert_eq!(
+            check_cases(&cases[1..], |flags| flags.to_vec()).unwrap_err()[0].to_string(),
+            r#"`cfg(target_os = "linux")` should match the flags [target_os]"#
+        );
+    }
+
+    #[test]
+    fn test_compare() {
+        let cfgs = [Cfg::from(all(vec![name("unix"), feature("std")]))];
+        let reference = flags![unix, feature = "std"];
+
+        assert_eq!(compare(&reference.clone(), &reference, &cfgs), Ok(()));
+        assert_eq!(
+            compare(
+                &vec![("unix", None::<&str>), ("feature", None)],
+                &reference,
+                &cfgs
+            ),
+            Err(vec![
+                Failure::Mismatch {
+                    cfg: r#"#[cfg(all(unix, feature = "std"))]"#.to_string(),
+                    flags: None,
+                    expected: true,
+                },
+                Failure::Mismatch {
+                    cfg: r#"#[cfg(feature = "std")]"#.to_string(),
+                    flags: None,
+                    expected: true,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_rustc_cfg() {
+        let flags = rustc_cfg(["--cfg", "runtime_cfg_test"]).unwrap();
+
+        assert!(flags.has("runtime_cfg_test"));
+        assert_eq!(flags.has("unix"), cfg!(unix));
+        assert!(flags.has_value("target_os", std::env::consts::OS));
+        assert!(rustc_cfg(["--target", "no-such-target"]).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::{quote, ToTokens};
+
+use crate::{Cfg, Predicate};
+
+/// # Panics
+///
+/// Panics like the tokens of [`Predicate`] if a name isn't an identifier.
+impl ToTokens for Cfg {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let predicate = &self.0;
+
+        tokens.extend(quote! { #[cfg(#predicate)] })
+    }
+}
+
+impl Predicate {
+    /// Returns the tokens of a `cfg!(..)` macro expression with the predicate.
+    ///
+    /// It could be used by the code generators to evaluate the predicate in the compiled program.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a name isn't an identifier or is a keyword, see [`Predicate::try_to_tokens`].
+    pub fn to_cfg_macro_tokens(&self) -> TokenStream {
+        quote! { cfg!(#self) }
+    }
+
+    /// Returns the tokens of an expression evaluating the predicate against a runtime flag source.
+    ///
+    /// The flag source is expected to have the `has(name)` and `has_value(name, value)` methods,
+    /// like `FlagSet`, so the generated code doesn't depend on this crate at runtime.
+    /// The flags expression is evaluated once and bound to a reference, before the leaves are evaluated.
+    /// An unbound parameter never matches.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quote::quote;
+    /// use runtime_cfg::*;
+    ///
+    /// let cfg = Cfg::from(all(vec![name("unix"), name_value("target_os", "linux")]));
+    ///
+    /// assert_eq!(
+    ///     cfg.to_match_fn_tokens(&quote! { load_flags() }).to_string(),
+    ///     quote! {{
+    ///         let __flags = &(load_flags());
+    ///
+    ///         (__flags.has("unix") && __flags.has_value("target_os", "linux"))
+    ///     }}
+    ///     .to_string()
+    /// );
+    /// ```
+    pub fn to_match_fn_tokens(&self, flags_expr: &TokenStream) -> TokenStream {
+        let predicate = self.match_tokens(&quote! { __flags });
+
+        quote! {{
+            let __flags = &(#flags_expr);
+
+            #predicate
+        }}
+    }
+
+    fn match_tokens(&self, flags: &TokenStream) -> TokenStream {
+        use Predicate::*;
+
+        match self {
+            Any(predicates) if predicates.is_empty() => quote! { false },
+            All(predicates) if predicates.is_empty() => quote! { true },
+            Any(predicates) => {
+                let predicates = predicates.iter().map(|p| p.match_tokens(flags));
+
+                quote! { (#(#predicates)||*) }
+            }
+            All(predicates) => {
+                let predicates = predicates.iter().map(|p| p.match_tokens(flags));
+
+                quote! { (#(#predicates)&&*) }
+            }
+            Not(predicate) => {
+                let predicate = predicate.match_tokens(flags);
+
+                quote! { !#predicate }
+            }
+            Name(name) => quote! { #flags.has(#name) },
+            NameValue(name, value) => quote! { #flags.has_value(#name, #value) },
+            True => quote! { true },
+            False | Param(_) => quote! { false },
+        }
+    }
+}
+
+/// # Panics
+///
+/// Panics if a name isn't an identifier, like `a.b`, or is a keyword, like `true`,
+/// which neither rustc nor [`Cfg::parse`] accepts; use [`Predicate::try_to_tokens`] to get an error instead.
+impl ToTokens for Predicate {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self.try_to_tokens() {
+            Ok(predicate) => tokens.extend(predicate),
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
+
+impl Predicate {
+    /// Returns the tokens of the predicate, or an error if a name isn't an identifier or is a keyword.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runtime_cfg::*;
+    ///
+    /// assert_eq!(
+    ///     not(name_value("target_os", "linux")).try_to_tokens().unwrap().to_string(),
+    ///     r#"not ( target_os = "linux" )"#
+    /// );
+    /// assert_eq!(
+    ///     all(vec![name("unix"), name("a.b")]).try_to_tokens().unwrap_err().to_string(),
+    ///     "`a.b` isn't an identifier"
+    /// );
+    /// ```
+    pub fn try_to_tokens(&self) -> syn::Result<TokenStream> {
+        use Predicate::*;
+
+        Ok(match self {
+            Any(predicates) => {
+                let predicates = predicates
+                    .iter()
+                    .map(|predicate| predicate.try_to_tokens())
+                    .collect::<syn::Result<Vec<_>>>()?;
+
+                quote! { any(#(#predicates),*) }
+            }
+            All(predicates) => {
+                let predicates = predicates
+                    .iter()
+                    .map(|predicate| predicate.try_to_tokens())
+                    .collect::<syn::Result<Vec<_>>>()?;
+
+                quote! { all(#(#predicates),*) }
+            }
+            Not(predicate) => {
+                let predicate = predicate.try_to_tokens()?;
+
+                quote! { not(#predicate) }
+            }
+            Name(name) => ident(name)?.into_token_stream(),
+            NameValue(name, value) => {
+                let name = ident(name)?;
+                let value = Literal::string(value);
+
+                quote! { #name = #value }
+            }
+            True => quote! { all() },
+            False => quote! { any() },
+            Param(name) => {
+                let name = ident(name)?;
+
+                quote! { param(#name) }
+            }
+        })
+    }
+}
+
+/// Returns the name as an identifier, or an error if it isn't an identifier or is a keyword,
+/// since `Ident::new` panics on the former, and `true` or `false` would be parsed back as the literals.
+fn ident(name: &str) -> syn::Result<Ident> {
+    syn::parse_str::<Ident>(name)
+        .map_err(|_| syn::Error::new(Span::call_site(), format!("`{}` isn't an identifier", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+
+    use crate::*;
+
+    #[test]
+    fn test_to_tokens() {
+        let testcases = vec![
+            quote! { #[cfg(any(foo, bar))] },
+            quote! { #[cfg(all(unix, target_os = "macos", not(param(region))))] },
+            quote! { #[cfg(any(all(), not(any())))] },
+            quote! { #[cfg(target_env = "with \"quotes\"")] },
+        ];
+
+        for tokens in testcases {
+            let cfg = syn::parse2::<Cfg>(tokens.clone()).unwrap();
+
+            assert_eq!(quote! { #cfg }.to_string(), tokens.to_string());
+            assert_eq!(syn::parse2::<Cfg>(quote! { #cfg }).unwrap(), cfg);
+        }
+
+        let cfg = Cfg::from(all(vec![
+            name("unix"),
+            not(name_value("target_os", "macos")),
+        ]));
+
+        assert_eq!(
+            cfg.to_cfg_macro_tokens().to_string(),
+            quote! { cfg!(all(unix, not(target_os = "macos"))) }.to_string()
+        );
+
+        assert_eq!(
+            any(vec![
+                all(vec![]),
+                not(any(vec![name("test")])),
+                param("region")
+            ])
+            .to_match_fn_tokens(&quote! { self.flags })
+            .to_string(),
+            quote! {{
+                let __flags = &(self.flags);
+
+                (true || !(__flags.has("test")) || false)
+            }}
+            .to_string()
+        );
+
+        let tokens = all(vec![name("unix"), any(vec![feature("a"), feature("b")])])
+            .to_match_fn_tokens(&quote! { load_flags() })
+            .to_string();
+
+        assert_eq!(str::matches(&tokens, "load_flags").count(), 1);
+        assert_eq!(str::matches(&tokens, "__flags .").count(), 3);
+
+        let item = quote! { fn foo() {} };
+
+        assert_eq!(
+            quote! { #cfg #item }.to_string(),
+            quote! { #[cfg(all(unix, not(target_os = "macos")))] fn foo() {} }.to_string()
+        );
+    }
+
+    #[test]
+    fn test_to_tokens_non_ident() {
+        for &name in &["a.b", "foo-bar", "1st", "", "_", "true", "false", "fn"] {
+            let message = format!("`{}` isn't an identifier", name);
+
+            for predicate in &[
+                crate::name(name),
+                name_value(name, "x"),
+                param(name),
+                all(vec![any(vec![not(crate::name(name))])]),
+            ] {
+                assert_eq!(predicate.try_to_tokens().unwrap_err().to_string(), message);
+            }
+        }
+
+        let predicate = all(vec![name("é"), name("r#fn"), name("_a1")]);
+
+        assert_eq!(
+            predicate.try_to_tokens().unwrap().to_string(),
+            quote! { all(é, r#fn, _a1) }.to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "`a.b` isn't an identifier")]
+    fn test_to_tokens_panic() {
+        let cfg = Cfg::from(name("a.b"));
+
+        quote! { #cfg };
+    }
+}
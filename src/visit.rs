@@ -0,0 +1,173 @@
+//! Syntax tree traversal to walk a shared borrow of a predicate tree.
+//!
+//! Each method of the [`Visit`] trait is a hook that can be overridden to customize the behavior
+//! when visiting the corresponding type of node. By default, every method recursively visits the
+//! sub-predicates of the node by calling the free function of the same name in this module.
+//!
+//! # Example
+//!
+//! ```
+//! use runtime_cfg::{visit::Visit, *};
+//!
+//! #[derive(Default)]
+//! struct Names<'ast>(Vec<&'ast str>);
+//!
+//! impl<'ast> Visit<'ast> for Names<'ast> {
+//!     fn visit_name(&mut self, name: &'ast str) {
+//!         self.0.push(name)
+//!     }
+//! }
+//!
+//! let predicate = all(vec![name("unix"), not(name("test"))]);
+//!
+//! let mut names = Names::default();
+//! predicate.walk(&mut names);
+//! assert_eq!(names.0, vec!["unix", "test"]);
+//! ```
+
+cfg_if! {
+    if #[cfg(not(feature = "std"))] {
+        use alloc::boxed::Box;
+    }
+}
+
+use crate::Predicate;
+
+/// Syntax tree traversal to walk a shared borrow of a predicate tree.
+pub trait Visit<'ast> {
+    fn visit_predicate(&mut self, predicate: &'ast Predicate) {
+        visit_predicate(self, predicate)
+    }
+
+    fn visit_any(&mut self, predicates: &'ast [Box<Predicate>]) {
+        visit_any(self, predicates)
+    }
+
+    fn visit_all(&mut self, predicates: &'ast [Box<Predicate>]) {
+        visit_all(self, predicates)
+    }
+
+    fn visit_not(&mut self, predicate: &'ast Predicate) {
+        visit_not(self, predicate)
+    }
+
+    fn visit_name(&mut self, _name: &'ast str) {}
+
+    fn visit_name_value(&mut self, _name: &'ast str, _value: &'ast str) {}
+
+    fn visit_true(&mut self) {}
+
+    fn visit_false(&mut self) {}
+
+    fn visit_param(&mut self, _name: &'ast str) {}
+}
+
+/// Visit the predicate by dispatching on its variant.
+pub fn visit_predicate<'ast, V>(v: &mut V, predicate: &'ast Predicate)
+where
+    V: Visit<'ast> + ?Sized,
+{
+    use Predicate::*;
+
+    match predicate {
+        Any(predicates) => v.visit_any(predicates),
+        All(predicates) => v.visit_all(predicates),
+        Not(predicate) => v.visit_not(predicate),
+        Name(name) => v.visit_name(name),
+        NameValue(name, value) => v.visit_name_value(name, value),
+        True => v.visit_true(),
+        False => v.visit_false(),
+        Param(name) => v.visit_param(name),
+    }
+}
+
+/// Visit the sub-predicates of `any`.
+pub fn visit_any<'ast, V>(v: &mut V, predicates: &'ast [Box<Predicate>])
+where
+    V: Visit<'ast> + ?Sized,
+{
+    for predicate in predicates {
+        v.visit_predicate(predicate)
+    }
+}
+
+/// Visit the sub-predicates of `all`.
+pub fn visit_all<'ast, V>(v: &mut V, predicates: &'ast [Box<Predicate>])
+where
+    V: Visit<'ast> + ?Sized,
+{
+    for predicate in predicates {
+        v.visit_predicate(predicate)
+    }
+}
+
+/// Visit the sub-predicate of `not`.
+pub fn visit_not<'ast, V>(v: &mut V, predicate: &'ast Predicate)
+where
+    V: Visit<'ast> + ?Sized,
+{
+    v.visit_predicate(predicate)
+}
+
+impl Predicate {
+    /// Walk the predicate tree with the visitor.
+    pub fn walk<'ast, V: Visit<'ast>>(&'ast self, visitor: &mut V) {
+        visitor.visit_predicate(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    cfg_if! {
+        if #[cfg(not(feature = "std"))] {
+            use alloc::vec;
+            use alloc::vec::Vec;
+        }
+    }
+
+    use crate::*;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Leaves<'ast> {
+        depth: usize,
+        leaves: Vec<(usize, &'ast str, Option<&'ast str>)>,
+    }
+
+    impl<'ast> Visit<'ast> for Leaves<'ast> {
+        fn visit_not(&mut self, predicate: &'ast Predicate) {
+            self.depth += 1;
+            visit_not(self, predicate);
+            self.depth -= 1;
+        }
+
+        fn visit_name(&mut self, name: &'ast str) {
+            self.leaves.push((self.depth, name, None))
+        }
+
+        fn visit_name_value(&mut self, name: &'ast str, value: &'ast str) {
+            self.leaves.push((self.depth, name, Some(value)))
+        }
+    }
+
+    #[test]
+    fn test_visit() {
+        let predicate = any(vec![
+            name("foo"),
+            all(vec![not(name("bar")), name_value("target_os", "macos")]),
+        ]);
+
+        let mut leaves = Leaves::default();
+        predicate.walk(&mut leaves);
+
+        assert_eq!(
+            leaves.leaves,
+            vec![
+                (0, "foo", None),
+                (1, "bar", None),
+                (0, "target_os", Some("macos"))
+            ]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! JavaScript bindings with `wasm-bindgen`.
+//!
+//! The flags are passed as a plain JavaScript object, a flag is enabled with `true`,
+//! set with a string or number value, or set with an array of values;
+//! the flags with `false`, `null` or `undefined` are disabled.
+//!
+//! ```js
+//! import { Cfg } from "runtime_cfg";
+//!
+//! const cfg = new Cfg('cfg(all(unix, target_pointer_width = "64"))');
+//! const flags = { unix: true, target_pointer_width: 64, feature: ["std", "serde"] };
+//!
+//! cfg.matches(flags);  // true
+//! cfg.explain(flags);  // "✓ all\n├── ✓ unix\n└── ✓ target_pointer_width = \"64\"\n"
+//! ```
+
+use js_sys::{Array, Object};
+use wasm_bindgen::prelude::*;
+
+use crate::{Cfg, FlagSet};
+
+/// A configuration exposed to JavaScript as `Cfg`.
+#[wasm_bindgen(js_name = Cfg)]
+#[derive(Debug, Clone)]
+pub struct JsCfg(Cfg);
+
+#[wasm_bindgen(js_class = Cfg)]
+impl JsCfg {
+    /// Parses the configuration, like `cfg(unix)` or `#[cfg(unix)]`.
+    #[wasm_bindgen(constructor)]
+    pub fn parse(s: &str) -> Result<JsCfg, JsError> {
+        Cfg::parse(s)
+            .map(JsCfg)
+            .map_err(|err| JsError::new(&err.to_string()))
+    }
+
+    /// Returns `true` if the flags match the configuration.
+    pub fn matches(&self, flags: &Object) -> Result<bool, JsError> {
+        Ok(self.0.matches(&flags_from_object(flags)?))
+    }
+
+    /// Returns the evaluation result of each node with the flags, rendered as a tree.
+    pub fn explain(&self, flags: &Object) -> Result<String, JsError> {
+        Ok(self.0.explain(&flags_from_object(flags)?).to_string())
+    }
+
+    /// Returns the configuration as `#[cfg(..)]`.
+    #[wasm_bindgen(js_name = toString)]
+    #[allow(clippy::inherent_to_string)]
+    pub fn to_string(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl From<Cfg> for JsCfg {
+    fn from(cfg: Cfg) -> Self {
+        JsCfg(cfg)
+    }
+}
+
+impl From<JsCfg> for Cfg {
+    fn from(cfg: JsCfg) -> Self {
+        cfg.0
+    }
+}
+
+/// Converts a JavaScript object of flags to a flag set.
+pub fn flags_from_object(obj: &Object) -> Result<FlagSet, JsError> {
+    let mut flags = FlagSet::new();
+
+    for entry in Object::entries(obj).iter() {
+        let entry = Array::from(&entry);
+        let name = entry
+            .get(0)
+            .as_string()
+            .ok_or_else(|| JsError::new("the name of flag should be a string"))?;
+        let value = entry.get(1);
+
+        if Array::is_array(&value) {
+            for value in Array::from(&value).iter() {
+                flags.insert(name.clone(), flag_value(&name, &value)?);
+            }
+        } else if let Some(enabled) = value.as_bool() {
+            if enabled {
+                flags.insert_name(name);
+            }
+        } else if !value.is_null() && !value.is_undefined() {
+            let value = flag_value(&name, &value)?;
+
+            flags.insert(name, value);
+        }
+    }
+
+    Ok(flags)
+}
+
+fn flag_value(name: &str, value: &JsValue) -> Result<String, JsError> {
+    value
+        .as_string()
+        .or_else(|| value.as_f64().map(|n| n.to_string()))
+        .ok_or_else(|| {
+            JsError::new(&format!(
+                "the value of flag `{}` should be a string or number",
+                name
+            ))
+        })
+}
@@ -0,0 +1,310 @@
+//! Flags loaded from a file, and reloaded automatically when the file changes.
+
+use std::error::Error;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use arc_swap::ArcSwap;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{rust_analyzer::InvalidCfgError, FlagSet, Pattern};
+
+/// An error when loading or watching the flags file.
+#[derive(Debug)]
+pub enum WatchError {
+    /// The flags file can't be read.
+    Io(io::Error),
+    /// The flags file contains an invalid flag.
+    Parse(InvalidCfgError),
+    /// The flags file can't be watched.
+    Notify(notify::Error),
+}
+
+impl fmt::Display for WatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WatchError::Io(err) => write!(f, "failed to read flags, {}", err),
+            WatchError::Parse(err) => write!(f, "failed to parse flags, {}", err),
+            WatchError::Notify(err) => write!(f, "failed to watch flags, {}", err),
+        }
+    }
+}
+
+impl Error for WatchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WatchError::Io(err) => Some(err),
+            WatchError::Parse(err) => Some(err),
+            WatchError::Notify(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for WatchError {
+    fn from(err: io::Error) -> Self {
+        WatchError::Io(err)
+    }
+}
+
+impl From<InvalidCfgError> for WatchError {
+    fn from(err: InvalidCfgError) -> Self {
+        WatchError::Parse(err)
+    }
+}
+
+impl From<notify::Error> for WatchError {
+    fn from(err: notify::Error) -> Self {
+        WatchError::Notify(err)
+    }
+}
+
+/// The flags loaded from a file, and reloaded automatically when the file changes.
+///
+/// The file contains a flag per line, like `unix` or `feature="serde"`;
+/// the empty lines and the lines starting with `#` are ignored.
+///
+/// When the reloaded file can't be read or parsed, the previous flags are kept.
+/// The file should be replaced atomically, like writing a temporary file and renaming it,
+/// otherwise a partially written file may be loaded.
+///
+/// Each lookup of the `Pattern` reads the latest flags,
+/// use [`WatchedFlags::snapshot`] to evaluate a predicate with a consistent view.
+///
+/// # Example
+///
+/// ```no_run
+/// use runtime_cfg::*;
+///
+/// let flags = WatchedFlags::with_callback("/etc/my-service/flags", |flags| {
+///     println!("flags changed: {:?}", flags);
+/// })
+/// .unwrap();
+///
+/// let cfg = Cfg::parse(r#"cfg(feature = "beta")"#).unwrap();
+///
+/// if cfg.matches(&flags) {
+///     // ..
+/// }
+/// ```
+pub struct WatchedFlags {
+    shared: Arc<Shared>,
+    _watcher: RecommendedWatcher,
+}
+
+type Callback = Box<dyn FnMut(&FlagSet) + Send>;
+
+/// The state shared by the flags and the watcher.
+struct Shared {
+    path: PathBuf,
+    flags: ArcSwap<FlagSet>,
+    on_change: Mutex<Callback>,
+}
+
+impl Shared {
+    /// Reloads the flags file, and calls the callback if the flags changed.
+    ///
+    /// The lock of the callback serializes the reloads of the watcher and [`WatchedFlags::reload`],
+    /// so the comparison and the store can't interleave, while the lookups never wait for it.
+    fn reload(&self) -> Result<(), WatchError> {
+        let mut on_change = self
+            .on_change
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let reloaded = load(&self.path)?;
+
+        if **self.flags.load() != reloaded {
+            let reloaded = Arc::new(reloaded);
+
+            self.flags.store(reloaded.clone());
+            on_change(&reloaded);
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for WatchedFlags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WatchedFlags")
+            .field("path", &self.shared.path)
+            .field("flags", &self.shared.flags.load())
+            .finish()
+    }
+}
+
+impl WatchedFlags {
+    /// Loads the flags file, and watches it for changes.
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, WatchError> {
+        Self::with_callback(path, |_| ())
+    }
+
+    /// Loads the flags file, and watches it for changes,
+    /// the callback is called with the new flags after each reload which changes them,
+    /// either by the watcher or by [`WatchedFlags::reload`].
+    pub fn with_callback<P, F>(path: P, on_change: F) -> Result<Self, WatchError>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&FlagSet) + Send + 'static,
+    {
+        let path = path.as_ref().to_path_buf();
+        let shared = Arc::new(Shared {
+            flags: ArcSwap::from_pointee(load(&path)?),
+            path,
+            on_change: Mutex::new(Box::new(on_change)),
+        });
+
+        // The parent directory is watched, since the editors and deployment tools
+        // may replace the file instead of writing it in place.
+        let dir = match shared.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let file_name = shared.path.file_name().map(OsString::from);
+
+        let mut watcher = {
+            let shared = shared.clone();
+
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let changed = event.is_ok_and(|event| {
+                    !event.kind.is_access()
+                        && event
+                            .paths
+                            .iter()
+                            .any(|changed| changed.file_name().map(OsString::from) == file_name)
+                });
+
+                if changed {
+                    let _ = shared.reload();
+                }
+            })?
+        };
+
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+        Ok(WatchedFlags {
+            shared,
+            _watcher: watcher,
+        })
+    }
+
+    /// Returns the path of the flags file.
+    pub fn path(&self) -> &Path {
+        &self.shared.path
+    }
+
+    /// Returns the current flags.
+    pub fn snapshot(&self) -> Arc<FlagSet> {
+        self.shared.flags.load_full()
+    }
+
+    /// Returns the generation of the current flags, which changes when the reloaded flags are different.
+    pub fn generation(&self) -> u64 {
+        self.shared.flags.load().generation()
+    }
+
+    /// Reloads the flags file immediately, and calls the callback if the flags changed.
+    pub fn reload(&self) -> Result<(), WatchError> {
+        self.shared.reload()
+    }
+}
+
+impl Pattern for WatchedFlags {
+    fn matches(&self, key: &str, value: Option<&str>) -> bool {
+        self.shared.flags.load().matches(key, value)
+    }
+}
+
+fn load(path: &Path) -> Result<FlagSet, WatchError> {
+    let content = fs::read_to_string(path)?;
+
+    Ok(FlagSet::from_rust_analyzer_cfgs(
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#')),
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::*;
+
+    fn replace(path: &Path, content: &str) {
+        let tmp = path.with_extension("tmp");
+
+        fs::write(&tmp, content).unwrap();
+        fs::rename(&tmp, path).unwrap();
+    }
+
+    #[test]
+    fn test_watched_flags() {
+        let dir = std::env::temp_dir().join(format!("runtime_cfg-watch-{}", std::process::id()));
+        let path = dir.join("flags");
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "# comment\nunix\n\nfeature=\"beta\"\n").unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let flags = WatchedFlags::with_callback(&path, move |flags| {
+            let _ = tx.send(flags.clone());
+        })
+        .unwrap();
+        let cfg = Cfg::from(all(vec![name("unix"), feature("beta")]));
+
+        assert!(cfg.matches(&flags));
+
+        let generation = flags.generation();
+
+        flags.reload().unwrap();
+        assert_eq!(flags.generation(), generation);
+
+        replace(&path, "unix\n");
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        while cfg.matches(&flags) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        assert!(!cfg.matches(&flags));
+        assert!(flags.snapshot().has_changed_since(generation));
+        assert_eq!(
+            rx.recv_timeout(Duration::from_secs(10)).unwrap(),
+            vec![("unix", None::<&str>)].into_iter().collect()
+        );
+
+        replace(&path, "unix\nfeature=\"gamma\"\n");
+        flags.reload().unwrap();
+
+        // the reload waits for the watcher, so either of them has called the callback
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            vec![("unix", None), ("feature", Some("gamma"))]
+                .into_iter()
+                .collect()
+        );
+
+        replace(&path, "unix\n");
+        flags.reload().unwrap();
+        assert!(rx.try_recv().is_ok());
+
+        replace(&path, "unix\nfeature=\"beta\n");
+        assert!(flags.reload().is_err());
+        assert_eq!(
+            *flags.snapshot(),
+            vec![("unix", None::<&str>)].into_iter().collect()
+        );
+
+        drop(flags);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,28 @@
+//! The items of the `include_cfg!` tests, parsed by the macro but never compiled.
+
+#[cfg(feature = "std")]
+pub mod global {}
+
+pub mod borrowed {}
+
+#[cfg(unix)]
+mod sys {
+    pub struct Socket;
+}
+
+#[cfg(windows)]
+mod sys {
+    pub struct Socket;
+
+    #[cfg(target_env = "msvc")]
+    pub struct Pipe;
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "macros")]
+    fn guarded() {}
+}
+
+#[cfg(feature = "ext")]
+mod ext;